@@ -0,0 +1,40 @@
+// compares the default sha1_smol backend against the sha1 crate,
+// run with "cargo bench --features fast-sha1" to include the latter
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: [usize; 3] = [64 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+
+fn sha1_smol(data: &[u8]) -> [u8; 20] {
+    sha1_smol::Sha1::from(data).digest().bytes()
+}
+
+#[cfg(feature = "fast-sha1")]
+fn sha1_fast(data: &[u8]) -> [u8; 20] {
+    use sha1::Digest;
+
+    sha1::Sha1::digest(data).into()
+}
+
+fn hash_backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sha1");
+
+    for size in SIZES {
+        let data = (0..size).map(|i| i as u8).collect::<Vec<u8>>();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("sha1_smol", size), &data, |b, data| {
+            b.iter(|| sha1_smol(data))
+        });
+
+        #[cfg(feature = "fast-sha1")]
+        group.bench_with_input(BenchmarkId::new("sha1", size), &data, |b, data| {
+            b.iter(|| sha1_fast(data))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, hash_backends);
+criterion_main!(benches);
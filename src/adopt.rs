@@ -0,0 +1,219 @@
+use crate::dat::DatFile;
+use crate::game::{Part, RomSource};
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+// files hashed from each directory when looking for its DAT
+const SAMPLE_SIZE: usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Extra,
+    Nointro,
+    Redump,
+}
+
+impl Category {
+    fn configured(self) -> Box<dyn ExactSizeIterator<Item = (String, PathBuf)>> {
+        match self {
+            Self::Extra => crate::dirs::extra_dirs(),
+            Self::Nointro => crate::dirs::nointro_dirs(),
+            Self::Redump => crate::dirs::redump_dirs(),
+        }
+    }
+
+    fn set_dir(self, name: &str, dir: &Path) -> Result<(), Error> {
+        match self {
+            Self::Extra => crate::dirs::set_extra_dir(name, dir),
+            Self::Nointro => crate::dirs::set_nointro_dir(name, dir),
+            Self::Redump => crate::dirs::set_redump_dir(name, dir),
+        }
+    }
+}
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Extra => crate::EXTRA.fmt(f),
+            Self::Nointro => crate::NOINTRO.fmt(f),
+            Self::Redump => crate::REDUMP.fmt(f),
+        }
+    }
+}
+
+// a DAT which some of a directory's sampled files belong to
+pub struct Candidate {
+    pub category: Category,
+    pub name: String,
+    pub matched: usize,
+    pub sampled: usize,
+}
+
+impl Candidate {
+    // most of the sampled files belong to the DAT
+    #[inline]
+    pub fn is_likely(&self) -> bool {
+        self.matched * 2 > self.sampled
+    }
+
+    #[inline]
+    pub fn adopt(&self, dir: &Path) -> Result<(), Error> {
+        self.category.set_dir(&self.name, dir)
+    }
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} \"{}\" ({}/{} sampled files match)",
+            self.category, self.name, self.matched, self.sampled
+        )
+    }
+}
+
+pub enum Proposal {
+    // nothing sampled from the directory is in any DAT
+    Unmatched {
+        dir: PathBuf,
+    },
+    // the directory is already assigned to one of its candidates
+    Assigned {
+        dir: PathBuf,
+        candidate: Candidate,
+    },
+    // candidates ordered from best to worst
+    Candidates {
+        dir: PathBuf,
+        candidates: Vec<Candidate>,
+    },
+}
+
+// samples files from each immediate subdirectory of root
+// and matches their hashes against every initialized DAT
+pub fn propose(root: &Path) -> Result<Vec<Proposal>, Error> {
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
+
+    let mut dirs = std::fs::read_dir(root)
+        .map_err(|_| Error::NoSuchDirectory(root.to_owned()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| path.canonicalize().ok())
+        .collect::<Vec<_>>();
+    dirs.sort_unstable();
+
+    let dats: [(Category, BTreeMap<String, DatFile>); 3] = [
+        (Category::Extra, crate::read_collected_dbs(crate::DIR_EXTRA)),
+        (
+            Category::Nointro,
+            crate::read_collected_dbs(crate::DIR_NOINTRO),
+        ),
+        (
+            Category::Redump,
+            crate::read_collected_dbs(crate::DIR_REDUMP),
+        ),
+    ];
+
+    let mut lookup: HashMap<&Part, BTreeSet<(Category, &str)>> = HashMap::default();
+    for (category, datfiles) in &dats {
+        for (name, datfile) in datfiles {
+            for (_, _, part) in datfile.all_parts() {
+                if !part.is_placeholder() {
+                    lookup
+                        .entry(part)
+                        .or_default()
+                        .insert((*category, name.as_str()));
+                }
+            }
+        }
+    }
+
+    let configured = [Category::Extra, Category::Nointro, Category::Redump]
+        .into_iter()
+        .flat_map(|category| {
+            category
+                .configured()
+                .map(move |(name, dir)| ((category, name), dir))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let pbar = crate::progress::bar(dirs.len() as u64)
+        .with_style(crate::game::verify_style())
+        .with_message("sampling directories");
+
+    let proposals = dirs
+        .into_par_iter()
+        .progress_with(pbar.clone())
+        .map(|dir| {
+            // how many sampled files each DAT has,
+            // where an archive counts as a single file
+            let samples = sample(&dir);
+            let sampled = samples.len();
+            let mut matches: BTreeMap<(Category, &str), usize> = BTreeMap::new();
+
+            for parts in &samples {
+                parts
+                    .iter()
+                    .filter_map(|part| lookup.get(part))
+                    .flatten()
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .for_each(|dat| *matches.entry(*dat).or_default() += 1);
+            }
+
+            let mut candidates = matches
+                .into_iter()
+                .map(|((category, name), matched)| Candidate {
+                    category,
+                    name: name.to_owned(),
+                    matched,
+                    sampled,
+                })
+                .collect::<Vec<_>>();
+            candidates.sort_by_key(|c| std::cmp::Reverse(c.matched));
+
+            let is_assigned = |c: &Candidate| {
+                configured
+                    .get(&(c.category, c.name.clone()))
+                    .and_then(|configured| configured.canonicalize().ok())
+                    .is_some_and(|configured| configured == dir)
+            };
+
+            match candidates.iter().position(is_assigned) {
+                Some(index) => Proposal::Assigned {
+                    dir,
+                    candidate: candidates.swap_remove(index),
+                },
+                None if candidates.is_empty() => Proposal::Unmatched { dir },
+                None => Proposal::Candidates { dir, candidates },
+            }
+        })
+        .collect();
+
+    pbar.finish_and_clear();
+
+    Ok(proposals)
+}
+
+// the parts of the first few files in the directory,
+// one entry per file
+fn sample(dir: &Path) -> Vec<Vec<Part>> {
+    crate::symlinks::walk(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .take(SAMPLE_SIZE)
+        .filter_map(|e| RomSource::from_path(e.into_path()).ok())
+        .map(|sources| {
+            sources
+                .into_iter()
+                .map(|(part, _)| part)
+                .collect::<Vec<_>>()
+        })
+        .filter(|parts| !parts.is_empty())
+        .collect()
+}
@@ -0,0 +1,573 @@
+use crate::dat::VerifyResults;
+use crate::game::{Part, RepairPolicy, RomSources, VerifyFailure, VerifyResultsSummary};
+use crate::progress::Log;
+use crate::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+const ZIP_EXTENSION: &str = "zip";
+
+// a game stored as a single Zip file of its parts
+pub struct ArchivedGame<'s> {
+    pub name: String,
+    pub parts: Vec<(&'s str, &'s Part)>,
+}
+
+impl ArchivedGame<'_> {
+    #[inline]
+    fn path(&self, root: &Path) -> PathBuf {
+        root.join(format!("{}.{ZIP_EXTENSION}", self.name))
+    }
+}
+
+// what's needed to repair games as they're verified
+pub struct Repair<'r, 'u> {
+    pub sources: &'r RomSources<'u>,
+    pub policy: &'r RepairPolicy,
+    pub progress_bar: &'r indicatif::ProgressBar,
+}
+
+// where each entry of a rebuilt Zip file comes from
+enum Plan<'s> {
+    // copied as-is from the existing Zip file, possibly renamed
+    Keep { index: usize },
+    // added from a ROM source
+    Add { part: &'s Part },
+}
+
+// verifies games stored one Zip file per game in the root directory,
+// rebuilding any Zip files which can be repaired
+pub fn process<'s>(
+    games: Vec<ArchivedGame<'s>>,
+    root: &Path,
+    keep_extra: bool,
+    increment_progress: impl Fn(&Part) + Send + Sync,
+    repair: Option<Repair>,
+) -> Result<VerifyResults<'s>, Error> {
+    use rayon::prelude::*;
+    use std::collections::HashSet;
+
+    let _span = tracing::info_span!("archived", root = %root.display()).entered();
+
+    let total = games.len();
+    let archives = games.iter().map(|g| g.path(root)).collect::<HashSet<_>>();
+
+    // anything in the root which isn't one of our Zip files is extra
+    let mut failures = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| !archives.contains(path))
+        .map(|path| {
+            tracing::debug!(path = %path.display(), "extra: not an archive in DAT");
+            if path.is_dir() {
+                VerifyFailure::extra_dir(path)
+            } else {
+                VerifyFailure::Extra {
+                    part: Part::from_path(&path),
+                    path,
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // loose files in the root, such as those of a collection
+    // being switched to this layout, are sources for the games
+    // so that nothing is deleted before it's been zipped
+    let loose = match &repair {
+        Some(repair) => add_loose_sources(repair.sources, &failures),
+        None => Vec::new(),
+    };
+
+    let results = games
+        .par_iter()
+        .map(|game| {
+            game.parts
+                .iter()
+                .for_each(|(_, part)| increment_progress(part));
+            process_game(game, root, repair.as_ref())
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    if let Some(repair) = &repair {
+        use crate::game::RomSource;
+
+        // once zipped, the loose files are no longer sources,
+        // so any left over can be removed as extras
+        for (part, path) in loose {
+            repair.sources.remove_if(
+                &part,
+                |_, source| matches!(source, RomSource::File { file, .. } if file.as_ref() == path),
+            );
+        }
+
+        let mut unfixed = Vec::new();
+        for failure in failures {
+            // sources moved into an archive are already gone
+            if !failure.path().exists() {
+                continue;
+            }
+            match failure.try_fix(repair.sources, repair.policy)? {
+                Ok(fixed) => repair.progress_bar.log(fixed),
+                Err(failure) => unfixed.push(failure),
+            }
+        }
+        failures = unfixed;
+    }
+
+    let mut successes = 0;
+    for game_failures in results {
+        if game_failures.iter().all(|f| f.is_ignored(keep_extra)) {
+            successes += 1;
+        }
+        failures.extend(game_failures);
+    }
+
+    failures.sort_unstable_by(|x, y| x.path().cmp(y.path()));
+
+    let (ignored, failures) = failures.into_iter().partition(|f| f.is_ignored(keep_extra));
+
+    Ok(VerifyResults {
+        failures,
+        ignored,
+        summary: VerifyResultsSummary { successes, total },
+    })
+}
+
+// returns the parts added and the files they're in,
+// leaving alone parts which already had sources
+fn add_loose_sources(
+    sources: &RomSources<'_>,
+    extras: &[VerifyFailure<'_>],
+) -> Vec<(Part, PathBuf)> {
+    use crate::game::RomSource;
+    use dashmap::mapref::entry::Entry;
+    use rayon::prelude::*;
+
+    // the contents of extra directories, such as multi-part games
+    // stored as directories, count as loose files too
+    let found = extras
+        .iter()
+        .flat_map(|failure| {
+            crate::symlinks::walk(failure.path())
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .filter_map(|path| Some((RomSource::from_path(path.clone()).ok()?, path)))
+        .flat_map_iter(|(found, path)| {
+            found
+                .into_iter()
+                .map(move |(part, source)| (part, source, path.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut added = Vec::new();
+    for (part, source, path) in found {
+        if let Entry::Vacant(entry) = sources.entry(part.clone()) {
+            entry.insert(source);
+            added.push((part, path));
+        }
+    }
+    added
+}
+
+fn process_game<'s>(
+    game: &ArchivedGame<'s>,
+    root: &Path,
+    repair: Option<&Repair>,
+) -> Result<Vec<VerifyFailure<'s>>, Error> {
+    let zip_path = game.path(root);
+
+    // the name and contents of each existing entry,
+    // along with the CRC32 and size its header records
+    let entries: Vec<(String, Option<Part>, Part)> = if zip_path.is_file() {
+        match read_entries(&zip_path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                // a Zip file we can't read is never overwritten
+                tracing::debug!(path = %zip_path.display(), %err, "error: unreadable archive");
+                return Ok(vec![VerifyFailure::error(
+                    zip_path,
+                    std::io::Error::other(err.to_string()),
+                )]);
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    // parts known only by their CRC32 are checked against the header's,
+    // rather than against the entry's contents
+    let entry_holds = |index: usize, part: &Part| match part {
+        Part::Crc { .. } => part.matches(&entries[index].2),
+        _ => entries[index].1.as_ref() == Some(part),
+    };
+    let entry_named = |name: &str| entries.iter().position(|(n, _, _)| n == name);
+    let entry_matching = |part: &Part| (0..entries.len()).find(|index| entry_holds(*index, part));
+
+    let mut plan = Vec::new();
+    let mut failures = Vec::new();
+    let mut changed = false;
+    let mut used = vec![false; entries.len()];
+
+    for (name, part) in game.parts.iter().copied() {
+        let path = zip_path.join(name);
+
+        match entry_named(name) {
+            Some(index) if entry_holds(index, part) => {
+                used[index] = true;
+                plan.push((name, Plan::Keep { index }));
+            }
+            Some(index) => {
+                tracing::debug!(path = %path.display(), "bad: archive entry doesn't match");
+                used[index] = true;
+                failures.push((
+                    Some(index),
+                    VerifyFailure::Bad {
+                        path,
+                        name,
+                        expected: part,
+                        actual: match part {
+                            Part::Crc { .. } => Some(entries[index].2.clone()),
+                            _ => entries[index].1.clone(),
+                        },
+                    },
+                ));
+            }
+            None => {
+                tracing::debug!(path = %path.display(), "missing: no archive entry");
+                failures.push((None, VerifyFailure::Missing { path, name, part }));
+            }
+        }
+    }
+
+    let mut extras = entries
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !used[*index])
+        .map(|(index, (name, part, _))| {
+            let path = zip_path.join(name);
+            tracing::debug!(path = %path.display(), "extra: archive entry not in DAT");
+            (
+                index,
+                VerifyFailure::Extra {
+                    path,
+                    part: part
+                        .clone()
+                        .ok_or_else(|| std::io::ErrorKind::InvalidData.into()),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let Some(repair) = repair else {
+        if failures.is_empty() && extras.is_empty() {
+            crate::protect::protect(&zip_path);
+        }
+
+        return Ok(failures
+            .into_iter()
+            .map(|(_, failure)| failure)
+            .chain(extras.into_iter().map(|(_, failure)| failure))
+            .collect());
+    };
+
+    let policy = repair.policy;
+    let mut unfixed = Vec::new();
+
+    for (bad, failure) in failures {
+        let (name, part) = failure.needed().unwrap();
+
+        if bad.is_none() || !policy.add_only {
+            crate::game::add_crc_source(repair.sources, part);
+        }
+
+        if let Some(index) = bad.filter(|_| policy.add_only) {
+            // bad entries are left alone when only adding
+            plan.push((name, Plan::Keep { index }));
+            unfixed.push(failure);
+        } else if let Some(index) = entry_matching(part) {
+            // another entry has what we need under the wrong name
+            plan.push((name, Plan::Keep { index }));
+            changed = true;
+        } else if repair.sources.contains_key(part) {
+            plan.push((name, Plan::Add { part }));
+            changed = true;
+        } else {
+            // without a replacement, bad entries are kept as they are
+            if let Some(index) = bad {
+                plan.push((name, Plan::Keep { index }));
+            }
+            unfixed.push(failure);
+        }
+    }
+
+    // extras are kept unless they're being deleted,
+    // except those which have been renamed into place
+    extras.retain(|(index, _)| {
+        !plan
+            .iter()
+            .any(|(_, p)| matches!(p, Plan::Keep { index: i } if i == index))
+    });
+    for (index, failure) in extras {
+        if policy.keep_extra || policy.add_only {
+            plan.push((&entries[index].0, Plan::Keep { index }));
+            unfixed.push(failure);
+        } else {
+            changed = true;
+            if !policy.dry_run {
+                repair
+                    .progress_bar
+                    .log(crate::game::Repaired::Deleted(failure.path().to_owned()));
+            }
+        }
+    }
+
+    if changed && policy.dry_run {
+        unfixed.extend(preview(&zip_path, &entries, &plan, repair));
+    } else if changed {
+        crate::protect::unprotect(&zip_path);
+        rebuild(&zip_path, &plan, repair)?;
+    }
+
+    if unfixed.is_empty() {
+        crate::protect::protect(&zip_path);
+    }
+
+    Ok(unfixed)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<(String, Option<Part>, Part)>, Error> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+
+    (0..zip.len())
+        .map(|index| {
+            let entry = zip.by_index(index)?;
+            let name = entry.name().to_owned();
+            let crc = Part::Crc {
+                crc32: entry.crc32().to_be_bytes(),
+                size: Some(entry.size()),
+            };
+            Ok((name, Part::from_reader(entry).ok(), crc))
+        })
+        .collect()
+}
+
+// reads a freshly written archive back from its device,
+// where each entry must pass its CRC32 check
+// and each added one must hold the part it was written from
+fn verify_archive(tmp_path: &Path, plan: &[(&str, Plan)]) -> Result<bool, Error> {
+    crate::game::evict(tmp_path)?;
+
+    let entries = read_entries(tmp_path)?;
+
+    Ok(entries.len() == plan.len()
+        && entries
+            .iter()
+            .zip(plan)
+            .all(|((name, part, crc), (planned, plan))| {
+                name == planned
+                    && match plan {
+                        Plan::Keep { .. } => part.is_some(),
+                        Plan::Add {
+                            part: added @ Part::Crc { .. },
+                        } => part.is_some() && added.matches(crc),
+                        Plan::Add { part: added } => part.as_ref() == Some(*added),
+                    }
+            }))
+}
+
+// the entries a rebuild would add, rename or drop, without writing anything,
+// each of which is still a failure until the rebuild's made
+fn preview<'s>(
+    zip_path: &Path,
+    entries: &[(String, Option<Part>, Part)],
+    plan: &[(&str, Plan)],
+    repair: &Repair,
+) -> Vec<VerifyFailure<'s>> {
+    use crate::game::Repaired;
+
+    let mut repairable = Vec::new();
+
+    for (name, plan) in plan {
+        let path = zip_path.join(name);
+        match plan {
+            Plan::Add { part } => {
+                if let Some(source) = repair.sources.get(*part) {
+                    repairable.push(VerifyFailure::Repairable {
+                        repair: format!(
+                            "{} {} {}",
+                            source.value(),
+                            crate::glyphs::copied(),
+                            path.display()
+                        ),
+                        path,
+                    });
+                }
+            }
+            Plan::Keep { index } if entries[*index].0 != *name => {
+                let source = zip_path.join(&entries[*index].0);
+                repairable.push(VerifyFailure::Repairable {
+                    repair: Repaired::Moved {
+                        source,
+                        destination: path.clone(),
+                    }
+                    .to_string(),
+                    path,
+                });
+            }
+            Plan::Keep { .. } => {}
+        }
+    }
+
+    repairable.extend(
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                !plan
+                    .iter()
+                    .any(|(_, p)| matches!(p, Plan::Keep { index: i } if i == index))
+            })
+            .map(|(_, (name, _, _))| {
+                let path = zip_path.join(name);
+                VerifyFailure::Repairable {
+                    repair: Repaired::Deleted(path.clone()).to_string(),
+                    path,
+                }
+            }),
+    );
+
+    repairable
+}
+
+// writes a new Zip file according to the plan,
+// replacing the old one only once it's complete
+fn rebuild(zip_path: &Path, plan: &[(&str, Plan)], repair: &Repair) -> Result<(), Error> {
+    use crate::game::{Compression, RomSource};
+    use std::sync::Arc;
+
+    let mut old = match zip_path.is_file() {
+        true => Some(zip::ZipArchive::new(BufReader::new(File::open(zip_path)?))?),
+        false => None,
+    };
+
+    let tmp_path = zip_path.with_extension(format!("{ZIP_EXTENSION}.tmp"));
+
+    // a partly written Zip file is never left behind
+    let moved =
+        match write_archive(&tmp_path, zip_path, old.as_mut(), plan, repair).and_then(|moved| {
+            match !repair.policy.verify_writes || verify_archive(&tmp_path, plan)? {
+                true => Ok(moved),
+                false => Err(Error::WriteVerifyFailed(zip_path.to_owned())),
+            }
+        }) {
+            Ok(moved) => moved,
+            Err(err) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(err);
+            }
+        };
+
+    // an archive losing any of its entries is kept whole
+    if let (Some(old), Some(dir)) = (&old, &repair.policy.backup_dir) {
+        let kept = plan
+            .iter()
+            .filter_map(|(_, plan)| match plan {
+                Plan::Keep { index } => Some(*index),
+                Plan::Add { .. } => None,
+            })
+            .collect::<std::collections::HashSet<_>>();
+        if kept.len() < old.len() {
+            crate::quarantine::preserve(dir, zip_path)?;
+        }
+    }
+    std::fs::rename(&tmp_path, zip_path)?;
+
+    // moved sources are removed only once their copies are in place,
+    // and later repairs take the part from the new archive instead
+    let zip_path: Arc<Path> = Arc::from(zip_path);
+    for (index, part, source) in moved {
+        match std::fs::remove_file(&source) {
+            Ok(()) => {
+                repair.sources.insert(
+                    part.clone(),
+                    RomSource::File {
+                        file: Arc::clone(&zip_path),
+                        has_xattr: false,
+                        zip_parts: vec![Compression::Zip { index }],
+                    },
+                );
+            }
+            Err(err) => {
+                tracing::debug!(source = %source.display(), %err, "unable to remove moved source")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// writes the planned entries to a temporary file,
+// returning the new index, part and path of each plain file source
+// which is to be moved into the archive
+fn write_archive<'s>(
+    tmp_path: &Path,
+    zip_path: &Path,
+    mut old: Option<&mut zip::ZipArchive<BufReader<File>>>,
+    plan: &[(&str, Plan<'s>)],
+    repair: &Repair,
+) -> Result<Vec<(usize, &'s Part, PathBuf)>, Error> {
+    use crate::game::RomSource;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let mut new = zip::ZipWriter::new(std::io::BufWriter::new(File::create(tmp_path)?));
+    let mut moved = Vec::new();
+
+    for (index, (name, plan)) in plan.iter().enumerate() {
+        match plan {
+            Plan::Keep { index } => {
+                let old = old.as_mut().expect("kept entries require an archive");
+                let entry = old.by_index_raw(*index)?;
+                if entry.name() == *name {
+                    new.raw_copy_file(entry)?;
+                } else {
+                    new.raw_copy_file_rename(entry, name)?;
+                }
+            }
+            Plan::Add { part } => {
+                let source = repair
+                    .sources
+                    .get(*part)
+                    .expect("added parts require a source");
+                new.start_file(*name, SimpleFileOptions::default())?;
+                source.copy_to(&mut new)?;
+                repair.progress_bar.log(format!(
+                    "{} {} {}",
+                    source.value(),
+                    crate::glyphs::copied(),
+                    zip_path.join(name).display()
+                ));
+                if let RomSource::File {
+                    file, zip_parts, ..
+                } = source.value()
+                {
+                    if repair.policy.move_sources && zip_parts.is_empty() {
+                        moved.push((index, *part, file.to_path_buf()));
+                    }
+                }
+            }
+        }
+    }
+
+    new.finish()?.flush()?;
+
+    Ok(moved)
+}
@@ -0,0 +1,138 @@
+use crate::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+// everything in the data directory that makes up a working setup,
+// which may not be everything in the data directory itself
+// (such as when it's also the portable collection root)
+fn entries() -> [&'static str; 15] {
+    [
+        crate::DB_MAME,
+        crate::DB_MAME_META,
+        crate::DB_MESS_SPLIT,
+        crate::DB_REDUMP_SPLIT,
+        crate::DIR_SL,
+        crate::DIR_EXTRA,
+        crate::DIR_REDUMP,
+        crate::DIR_NOINTRO,
+        crate::dirs::DIR_CONFIG_FILE,
+        crate::index::SOURCE_INDEX_FILE,
+        crate::http::VALIDATORS_FILE,
+        crate::tags::TAGS_FILE,
+        crate::wish::WISHLIST_FILE,
+        crate::update::UPDATE_SOURCES_FILE,
+        crate::sbi::SBI_INDEX_FILE,
+    ]
+}
+
+#[inline]
+fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz") || ext.eq_ignore_ascii_case("tgz"))
+}
+
+// writes databases and configuration to a tar file,
+// which is gzipped if the file ends in .gz or .tgz,
+// returning the number of entries backed up
+pub fn backup(target: &Path) -> Result<usize, Error> {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn append<W: Write>(data_dir: &Path, w: W) -> Result<(W, usize), Error> {
+        let mut builder = tar::Builder::new(w);
+        let mut backed_up = 0;
+
+        for entry in entries() {
+            let path = data_dir.join(entry);
+
+            if path.is_dir() {
+                builder.append_dir_all(entry, &path)?;
+                backed_up += 1;
+            } else if path.is_file() {
+                builder.append_path_with_name(&path, entry)?;
+                backed_up += 1;
+            }
+        }
+
+        Ok((builder.into_inner()?, backed_up))
+    }
+
+    let data_dir = crate::dirs::data_dir();
+    let w = BufWriter::new(File::create(target)?);
+
+    let backed_up = if is_compressed(target) {
+        let (w, backed_up) = append(&data_dir, GzEncoder::new(w, flate2::Compression::default()))?;
+        w.finish()?.flush()?;
+        backed_up
+    } else {
+        let (mut w, backed_up) = append(&data_dir, w)?;
+        w.flush()?;
+        backed_up
+    };
+
+    Ok(backed_up)
+}
+
+// replaces databases and configuration with those from a backup,
+// returning the number of entries restored
+pub fn restore(source: &Path) -> Result<usize, Error> {
+    use flate2::read::MultiGzDecoder;
+    use std::collections::BTreeSet;
+    use std::io::Read;
+
+    fn open(source: &Path) -> Result<tar::Archive<Box<dyn Read>>, Error> {
+        let mut r = BufReader::new(File::open(source)?);
+
+        Ok(tar::Archive::new(if crate::is_gzip(&mut r)? {
+            Box::new(MultiGzDecoder::new(r))
+        } else {
+            Box::new(r)
+        }))
+    }
+
+    // the known entry an archived path belongs to, if any
+    fn known_entry(path: &Path) -> Option<&'static str> {
+        let top = path.components().next()?.as_os_str().to_str()?;
+        entries().into_iter().find(|entry| *entry == top)
+    }
+
+    // read the whole backup once before touching anything
+    // so that a bad archive doesn't leave us with nothing
+    let mut restored = BTreeSet::new();
+    for entry in open(source)?.entries()? {
+        if let Some(known) = known_entry(&entry?.path()?) {
+            restored.insert(known);
+        }
+    }
+
+    if restored.is_empty() {
+        return Err(Error::InvalidBackup(source.to_owned()));
+    }
+
+    let data_dir = crate::dirs::data_dir();
+
+    // clear out the old setup entirely so that databases
+    // not in the backup don't linger after restoring
+    for entry in entries() {
+        let path = data_dir.join(entry);
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else if path.is_file() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    std::fs::create_dir_all(&data_dir)?;
+
+    for entry in open(source)?.entries()? {
+        let mut entry = entry?;
+
+        // anything we don't know about is skipped
+        if known_entry(&entry.path()?).is_some() {
+            entry.unpack_in(&data_dir)?;
+        }
+    }
+
+    Ok(restored.len())
+}
@@ -0,0 +1,385 @@
+use crate::game::{Part, RomSource, VerifyResultsSummary};
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Copy, Clone)]
+pub enum Format {
+    Sha1,
+    Md5,
+    Sfv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "sha1" => Ok(Format::Sha1),
+            "md5" => Ok(Format::Md5),
+            "sfv" => Ok(Format::Sfv),
+            _ => Err("invalid checksum format".to_string()),
+        }
+    }
+}
+
+impl Format {
+    fn checksum(self, path: &Path) -> Result<String, std::io::Error> {
+        match self {
+            Format::Sha1 => crate::game::file_sha1(path).map(hex::encode),
+            Format::Md5 => file_md5(path).map(hex::encode),
+            Format::Sfv => file_crc32(path).map(|crc| format!("{crc:08X}")),
+        }
+    }
+
+    // the checksum of a ROM source, which may be within an archive
+    pub fn source_checksum(self, part: &Part, source: &RomSource) -> Result<String, crate::Error> {
+        match self {
+            Format::Sha1 => Ok(part.digest().to_string()),
+            Format::Md5 => {
+                use md5::{Digest, Md5};
+
+                let mut w = Md5Writer(Md5::new());
+                source.copy_to(&mut w)?;
+                Ok(hex::encode(w.0.finalize()))
+            }
+            Format::Sfv => {
+                let mut w = Crc32Writer(crc32fast::Hasher::new());
+                source.copy_to(&mut w)?;
+                Ok(format!("{:08X}", w.0.finalize()))
+            }
+        }
+    }
+
+    // writes a single entry in the same layout as sha1sum or cksfv
+    pub fn write_line<W: Write>(
+        self,
+        mut w: W,
+        path: &str,
+        checksum: &str,
+    ) -> Result<(), std::io::Error> {
+        match self {
+            Format::Sha1 | Format::Md5 => writeln!(w, "{checksum}  {path}"),
+            Format::Sfv => writeln!(w, "{path} {checksum}"),
+        }
+    }
+}
+
+// returns checksums of all files under root,
+// keyed by their "/"-separated paths relative to root
+// and sorted by those paths
+pub fn generate(root: &Path, format: Format) -> Vec<(String, Result<String, std::io::Error>)> {
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
+
+    let files = relative_files(root);
+
+    let pbar = crate::progress::bar(files.len().try_into().unwrap())
+        .with_style(crate::game::verify_style())
+        .with_message("generating checksums");
+
+    let mut checksums = files
+        .into_par_iter()
+        .progress_with(pbar.clone())
+        .map(|(relative, path)| (relative, format.checksum(&path)))
+        .collect::<Vec<_>>();
+
+    pbar.finish_and_clear();
+
+    checksums.sort_unstable_by(|(x, _), (y, _)| x.cmp(y));
+    checksums
+}
+
+// all files under root, along with their "/"-separated paths relative to root
+fn relative_files(root: &Path) -> Vec<(String, PathBuf)> {
+    crate::symlinks::walk(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let relative = e
+                .path()
+                .strip_prefix(root)
+                .ok()?
+                .iter()
+                .map(|c| c.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((relative, e.into_path()))
+        })
+        .collect()
+}
+
+pub struct Crc32Writer(pub crc32fast::Hasher);
+
+impl Write for Crc32Writer {
+    fn write(&mut self, data: &[u8]) -> Result<usize, std::io::Error> {
+        self.0.update(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+struct Md5Writer(md5::Md5);
+
+impl Write for Md5Writer {
+    fn write(&mut self, data: &[u8]) -> Result<usize, std::io::Error> {
+        md5::Digest::update(&mut self.0, data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+pub fn file_crc32(path: &Path) -> Result<u32, std::io::Error> {
+    let mut r = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; crate::game::buffer_size()];
+
+    loop {
+        match r.read(&mut buf)? {
+            0 => break Ok(hasher.finalize()),
+            bytes => hasher.update(&buf[0..bytes]),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Expected {
+    Sha1([u8; 20]),
+    Md5([u8; 16]),
+    Crc32(u32),
+}
+
+impl Expected {
+    fn matches(&self, path: &Path) -> Result<bool, std::io::Error> {
+        match self {
+            Expected::Sha1(sha1) => crate::game::file_sha1(path).map(|s| &s == sha1),
+            Expected::Md5(md5) => file_md5(path).map(|s| &s == md5),
+            Expected::Crc32(crc) => file_crc32(path).map(|s| &s == crc),
+        }
+    }
+
+    // writes an entry in the same layout it was parsed from
+    fn write_line<W: Write>(&self, mut w: W, path: &str) -> Result<(), std::io::Error> {
+        match self {
+            Expected::Sha1(sha1) => writeln!(w, "{}  {path}", hex::encode(sha1)),
+            Expected::Md5(md5) => writeln!(w, "{}  {path}", hex::encode(md5)),
+            Expected::Crc32(crc) => writeln!(w, "{path} {crc:08X}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    line: usize,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid checksum entry on line {}", self.line)
+    }
+}
+
+// parses sha1sum, md5sum or SFV style checksum files
+// into (path, checksum) pairs
+pub fn parse(data: &str) -> Result<Vec<(String, Expected)>, ParseError> {
+    fn parse_hash(line: &str) -> Option<(String, Expected)> {
+        let (hash, path) = line.split_once(' ')?;
+        // sha1sum and md5sum mark binary mode files with a "*"
+        let path = path.strip_prefix([' ', '*'])?;
+
+        match hash.len() {
+            40 => {
+                let mut sha1 = [0; 20];
+                hex::decode_to_slice(hash, &mut sha1).ok()?;
+                Some((path.to_owned(), Expected::Sha1(sha1)))
+            }
+            32 => {
+                let mut md5 = [0; 16];
+                hex::decode_to_slice(hash, &mut md5).ok()?;
+                Some((path.to_owned(), Expected::Md5(md5)))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_sfv(line: &str) -> Option<(String, Expected)> {
+        let (path, crc) = line.rsplit_once(' ')?;
+        (crc.len() == 8)
+            .then(|| u32::from_str_radix(crc, 16).ok())
+            .flatten()
+            .map(|crc| (path.trim_end().to_owned(), Expected::Crc32(crc)))
+    }
+
+    data.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with(';'))
+        .map(|(line, entry)| {
+            parse_hash(entry)
+                .or_else(|| parse_sfv(entry))
+                .ok_or(ParseError { line })
+        })
+        .collect()
+}
+
+// converts checksum entries to SHA-1 parts
+// if every entry has a SHA-1 checksum
+pub fn into_parts(entries: &[(String, Expected)]) -> Option<Vec<(String, Part)>> {
+    entries
+        .iter()
+        .map(|(path, expected)| match expected {
+            Expected::Sha1(sha1) => Some((
+                path.clone(),
+                Part::Rom {
+                    sha1: *sha1,
+                    size: None,
+                },
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+pub enum Failure {
+    Missing { path: PathBuf },
+    Bad { path: PathBuf },
+    Extra { path: PathBuf },
+    Error { path: PathBuf, err: std::io::Error },
+}
+
+impl Failure {
+    #[inline]
+    fn path(&self) -> &Path {
+        match self {
+            Failure::Missing { path }
+            | Failure::Bad { path }
+            | Failure::Extra { path }
+            | Failure::Error { path, .. } => path,
+        }
+    }
+
+    // the path of a file which is missing or bad
+    #[inline]
+    fn needed(&self) -> Option<&Path> {
+        match self {
+            Failure::Missing { path } | Failure::Bad { path } => Some(path),
+            Failure::Extra { .. } | Failure::Error { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Missing { path } => write!(f, " MISSING : {}", path.display()),
+            Failure::Bad { path } => write!(f, "     BAD : {}", path.display()),
+            Failure::Extra { path } => write!(f, "   EXTRA : {}", path.display()),
+            Failure::Error { path, err } => write!(f, "   ERROR : {} : {}", path.display(), err),
+        }
+    }
+}
+
+// a lightweight verification for checksums which can't be
+// turned into parts, which checks for missing or bad files
+// and, unless they're kept, files with no entry
+pub fn verify(
+    root: &Path,
+    entries: &[(String, Expected)],
+    keep_extra: bool,
+) -> (Vec<Failure>, VerifyResultsSummary) {
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
+
+    let pbar = crate::progress::bar(entries.len().try_into().unwrap())
+        .with_style(crate::game::verify_style())
+        .with_message("verifying files");
+
+    let mut failures = entries
+        .par_iter()
+        .progress_with(pbar.clone())
+        .filter_map(|(name, expected)| {
+            let path = root.join(name);
+            if !path.is_file() {
+                Some(Failure::Missing { path })
+            } else {
+                match expected.matches(&path) {
+                    Ok(true) => None,
+                    Ok(false) => Some(Failure::Bad { path }),
+                    Err(err) => Some(Failure::Error { path, err }),
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    pbar.finish_and_clear();
+
+    let summary = VerifyResultsSummary {
+        successes: entries.len() - failures.len(),
+        total: entries.len(),
+    };
+
+    if !keep_extra {
+        let expected = entries
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<std::collections::HashSet<_>>();
+
+        failures.extend(
+            relative_files(root)
+                .into_iter()
+                .filter(|(relative, _)| !expected.contains(relative.as_str()))
+                .map(|(_, path)| Failure::Extra { path }),
+        );
+    }
+
+    failures.sort_unstable_by(|x, y| x.path().cmp(y.path()));
+
+    (failures, summary)
+}
+
+// writes the entries of missing or bad files,
+// in the same layout as the checksum file they're from
+pub fn export_needed(
+    path: &Path,
+    root: &Path,
+    entries: &[(String, Expected)],
+    failures: &[Failure],
+) -> Result<(), std::io::Error> {
+    let needed = failures
+        .iter()
+        .filter_map(Failure::needed)
+        .collect::<std::collections::HashSet<_>>();
+
+    let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for (name, expected) in entries {
+        if needed.contains(root.join(name).as_path()) {
+            expected.write_line(&mut w, name)?;
+        }
+    }
+    w.flush()
+}
+
+fn file_md5(path: &Path) -> Result<[u8; 16], std::io::Error> {
+    use md5::{Digest, Md5};
+
+    let mut r = std::fs::File::open(path)?;
+    let mut hasher = Md5::new();
+    let mut buf = vec![0; crate::game::buffer_size()];
+
+    loop {
+        match r.read(&mut buf)? {
+            0 => break Ok(hasher.finalize().into()),
+            bytes => hasher.update(&buf[0..bytes]),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use super::{Error, ResourceError};
-use crate::game::{ExtendOne, FileSize, GameParts, Part, RomSources, VerifyFailure};
+use crate::game::{
+    ExtendOne, FileSize, GameParts, Part, Preflight, RepairPolicy, RomSources, Search, SetLists,
+    VerifyFailure,
+};
+use crate::progress::Log;
 use crate::Resource;
-use comfy_table::Table;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
@@ -9,7 +12,9 @@ use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize)]
 pub struct Datafile {
-    header: Header,
+    // MAME's -listxml output has no header, only a build attribute
+    header: Option<Header>,
+    build: Option<String>,
     game: Option<Vec<Game>>,
     machine: Option<Vec<Game>>,
 }
@@ -17,7 +22,30 @@ pub struct Datafile {
 impl Datafile {
     #[inline]
     pub fn name(&self) -> &str {
-        self.header.name.as_str()
+        Header::name_of(&self.header)
+    }
+
+    // the header, games and MAME machines, where only a header-less
+    // -listxml document has machines handled as MAME's;
+    // Logiqx DATs using <machine> for their games have them as games
+    fn into_header(self) -> (Header, Vec<Game>, Vec<Game>) {
+        let mut games = self.game.unwrap_or_default();
+
+        match self.header {
+            Some(header) => {
+                games.extend(self.machine.unwrap_or_default());
+                (header, games, Vec::new())
+            }
+            None => (
+                Header {
+                    name: Header::name_of(&None).to_owned(),
+                    version: self.build.unwrap_or_default(),
+                    date: None,
+                },
+                games,
+                self.machine.unwrap_or_default(),
+            ),
+        }
     }
 
     #[inline]
@@ -30,6 +58,14 @@ impl Datafile {
 pub struct Header {
     name: String,
     version: String,
+    date: Option<String>,
+}
+
+impl Header {
+    #[inline]
+    fn name_of(header: &Option<Header>) -> &str {
+        header.as_ref().map(|h| h.name.as_str()).unwrap_or("MAME")
+    }
 }
 
 type Flattened = Result<(String, Part), (String, GameParts)>;
@@ -37,8 +73,10 @@ type Flattened = Result<(String, Part), (String, GameParts)>;
 #[derive(Debug, Deserialize)]
 pub struct Game {
     name: String,
+    isdevice: Option<String>,
     rom: Option<Vec<Rom>>,
     disk: Option<Vec<Disk>>,
+    device_ref: Option<Vec<DeviceRef>>,
 }
 
 impl std::fmt::Display for Game {
@@ -58,6 +96,11 @@ impl Game {
         self.rom.iter().flatten()
     }
 
+    #[inline]
+    fn is_device(&self) -> bool {
+        matches!(self.isdevice.as_deref(), Some("yes"))
+    }
+
     #[inline]
     fn into_parts(self) -> Result<(String, GameParts), hex::FromHexError> {
         Ok((
@@ -82,18 +125,23 @@ impl Game {
                 name: game_name,
                 rom: Some(roms),
                 disk: None,
+                ..
             } => match &roms[..] {
                 [Rom {
                     name: rom_name,
-                    sha1: Some(_),
+                    sha1,
+                    crc,
                     ..
-                }] if rom_name.starts_with(game_name) => rom_name.as_str().into(),
+                }] if rom_name.starts_with(game_name) && (sha1.is_some() || crc.is_some()) => {
+                    rom_name.as_str().into()
+                }
                 _ => game_name.as_str().into(),
             },
             Game {
                 name: game_name,
                 rom: None,
                 disk: Some(disks),
+                ..
             } => match &disks[..] {
                 [Disk {
                     name: disk_name,
@@ -117,13 +165,23 @@ impl Game {
                 name: game_name,
                 rom: Some(roms),
                 disk: None,
+                ..
             } => match &roms[..] {
                 [Rom {
                     name: rom_name,
                     sha1: Some(sha1),
+                    size,
                     ..
                 }] if rom_name.starts_with(game_name) => {
-                    Part::new_rom(sha1).map(|part| Ok((rom_name.clone(), part)))
+                    Part::new_rom(sha1).map(|part| Ok((rom_name.clone(), part.with_size(*size))))
+                }
+                [Rom {
+                    name: rom_name,
+                    sha1: None,
+                    crc: Some(crc),
+                    size,
+                }] if rom_name.starts_with(game_name) => {
+                    Part::new_crc(crc).map(|part| Ok((rom_name.clone(), part.with_size(*size))))
                 }
                 _ => self.into_parts().map(Err),
             },
@@ -131,6 +189,7 @@ impl Game {
                 name: game_name,
                 rom: None,
                 disk: Some(disks),
+                ..
             } => match &disks[..] {
                 [Disk {
                     name: disk_name,
@@ -151,6 +210,7 @@ pub struct Rom {
     name: String,
     size: Option<u64>,
     sha1: Option<String>,
+    crc: Option<String>,
 }
 
 impl Rom {
@@ -175,9 +235,10 @@ impl Rom {
             Self {
                 sha1: Some(sha1),
                 name,
+                size,
                 ..
             } => Some(match Part::new_rom(&sha1) {
-                Ok(part) => Ok((name, part)),
+                Ok(part) => Ok((name, part.with_size(size))),
                 Err(err) => Err(err),
             }),
 
@@ -185,8 +246,20 @@ impl Rom {
                 sha1: None,
                 size: Some(0),
                 name,
+                ..
             } => Some(Ok((name, Part::new_empty()))),
 
+            // many DATs give only a CRC32 for some ROMs
+            Self {
+                sha1: None,
+                crc: Some(crc),
+                name,
+                size,
+            } => Some(match Part::new_crc(&crc) {
+                Ok(part) => Ok((name, part.with_size(size))),
+                Err(err) => Err(err),
+            }),
+
             _ => None,
         }
     }
@@ -211,6 +284,36 @@ impl Disk {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct DeviceRef {
+    name: String,
+}
+
+// MAME machines as games of their own, since unlike DAT games
+// they're never flattened to loose files, where devices
+// are kept as their own sets only if some machine refers to them
+// and machines with nothing to verify are dropped
+fn machine_parts(
+    machines: Vec<Game>,
+) -> Result<impl Iterator<Item = (String, GameParts)>, hex::FromHexError> {
+    use std::collections::HashSet;
+
+    let referenced = machines
+        .iter()
+        .filter(|machine| !machine.is_device())
+        .flat_map(|machine| machine.device_ref.iter().flatten())
+        .map(|device| device.name.clone())
+        .collect::<HashSet<_>>();
+
+    Ok(machines
+        .into_iter()
+        .filter(|machine| !machine.is_device() || referenced.contains(&machine.name))
+        .map(Game::into_parts)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|(_, parts)| !parts.is_empty()))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatFile {
     name: String,
@@ -219,6 +322,27 @@ pub struct DatFile {
     flat: GameParts,
     // games with multiple ROMs
     tree: BTreeMap<String, GameParts>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    imported: Option<Imported>,
+    // the date the DAT declares in its header, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    // how entries are stored, such as in subdirectories by their first letter,
+    // which is a property of the directory rather than the DAT
+    #[serde(skip)]
+    layout: Layout,
+    // the extension of files which may sit in a game's directory
+    // named after the game without being in the DAT,
+    // such as the .sbi files of libcrypt-protected PSX games
+    #[serde(skip)]
+    sidecar: Option<&'static str>,
+}
+
+// when and where a DAT was imported from
+#[derive(Debug, Serialize, Deserialize)]
+struct Imported {
+    time: std::time::SystemTime,
+    source: String,
 }
 
 impl std::fmt::Display for DatFile {
@@ -228,20 +352,48 @@ impl std::fmt::Display for DatFile {
 }
 
 impl DatFile {
+    // builds a DAT from paths and parts, such as from a checksum file,
+    // where files in subdirectories become games of their own
+    pub fn from_parts(name: String, parts: impl IntoIterator<Item = (String, Part)>) -> Self {
+        // parts are gathered first and sorted once,
+        // rather than inserted into their sorted places one by one
+        let mut flat = Vec::new();
+        let mut tree = BTreeMap::<String, Vec<(String, Part)>>::default();
+
+        for (path, part) in parts {
+            match path.split_once('/') {
+                Some((game, rom)) => {
+                    tree.entry(game.to_owned())
+                        .or_default()
+                        .push((rom.to_owned(), part));
+                }
+                None => flat.push((path, part)),
+            }
+        }
+
+        Self {
+            name,
+            version: String::default(),
+            flat: flat.into_iter().collect(),
+            tree: tree
+                .into_iter()
+                .map(|(game, parts)| (game, parts.into_iter().collect()))
+                .collect(),
+            imported: None,
+            date: None,
+            layout: Layout::Files,
+            sidecar: None,
+        }
+    }
+
     pub fn new_flattened(datafile: Datafile) -> Result<Self, hex::FromHexError> {
-        let mut flat = GameParts::default();
-        let mut tree = BTreeMap::default();
+        let (header, games, machines) = datafile.into_header();
+        let mut flat = Vec::new();
+        let mut tree = machine_parts(machines)?.collect::<BTreeMap<_, _>>();
 
-        for game in datafile
-            .game
-            .into_iter()
-            .flatten()
-            .chain(datafile.machine.into_iter().flatten())
-        {
+        for game in games {
             match game.try_flatten()? {
-                Ok((name, part)) => {
-                    flat.insert(name, part);
-                }
+                Ok((name, part)) => flat.push((name, part)),
                 Err((name, parts)) => {
                     tree.insert(name, parts);
                 }
@@ -249,31 +401,35 @@ impl DatFile {
         }
 
         Ok(Self {
-            name: datafile.header.name,
-            version: datafile.header.version,
-            flat,
+            name: header.name,
+            version: header.version,
+            flat: flat.into_iter().collect(),
             tree,
+            imported: None,
+            date: header.date,
+            layout: Layout::Files,
+            sidecar: None,
         })
     }
 
     pub fn new_unflattened(datafile: Datafile) -> Result<Self, hex::FromHexError> {
-        let mut tree = BTreeMap::default();
+        let (header, games, machines) = datafile.into_header();
+        let mut tree = machine_parts(machines)?.collect::<BTreeMap<_, _>>();
 
-        for game in datafile
-            .game
-            .into_iter()
-            .flatten()
-            .chain(datafile.machine.into_iter().flatten())
-        {
+        for game in games {
             let (name, parts) = game.into_parts()?;
             tree.insert(name, parts);
         }
 
         Ok(Self {
-            name: datafile.header.name,
-            version: datafile.header.version,
+            name: header.name,
+            version: header.version,
             flat: GameParts::default(),
             tree,
+            imported: None,
+            date: header.date,
+            layout: Layout::Files,
+            sidecar: None,
         })
     }
 
@@ -285,10 +441,186 @@ impl DatFile {
         self.version.as_str()
     }
 
+    #[inline]
+    pub fn set_imported(&mut self, source: String) {
+        self.imported = Some(Imported {
+            time: std::time::SystemTime::now(),
+            source,
+        });
+    }
+
+    // the UTC date the DAT was imported, if known
+    pub fn imported(&self) -> String {
+        match &self.imported {
+            Some(Imported { time, .. }) => chrono::DateTime::<chrono::Utc>::from(*time)
+                .format("%Y-%m-%d")
+                .to_string(),
+            None => String::default(),
+        }
+    }
+
+    // the DAT's declared date as a time, such as "2024-01-31"
+    // or the "20240131-123456" versions No-Intro uses instead
+    pub fn date(&self) -> Option<std::time::SystemTime> {
+        use chrono::{NaiveDate, NaiveDateTime};
+
+        fn parse(s: &str) -> Option<NaiveDateTime> {
+            let s = s.trim();
+            ["%Y%m%d-%H%M%S", "%Y-%m-%d %H-%M-%S", "%Y-%m-%d %H:%M:%S"]
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+                .or_else(|| {
+                    ["%Y-%m-%d", "%Y%m%d"]
+                        .iter()
+                        .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+                        .and_then(|d| d.and_hms_opt(0, 0, 0))
+                })
+        }
+
+        self.date
+            .as_deref()
+            .and_then(parse)
+            .or_else(|| parse(&self.version))
+            .map(|time| time.and_utc().into())
+    }
+
+    #[inline]
+    pub fn imported_from(&self) -> Option<&str> {
+        self.imported.as_ref().map(|i| i.source.as_str())
+    }
+
     pub fn games(&self) -> impl Iterator<Item = &str> {
         self.flat.keys().chain(self.tree.keys()).map(|s| s.as_str())
     }
 
+    #[inline]
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    #[inline]
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    #[inline]
+    pub fn set_sidecar(&mut self, extension: &'static str) {
+        self.sidecar = Some(extension);
+    }
+
+    // whether the failure is a sidecar file in its game's directory,
+    // which is never treated as extra
+    fn is_sidecar(&self, failure: &VerifyFailure) -> bool {
+        let (Some(extension), VerifyFailure::Extra { path, .. }) = (self.sidecar, failure) else {
+            return false;
+        };
+
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+            && path
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .is_some_and(|game| Some(game) == path.file_stem())
+            && path
+                .file_stem()
+                .and_then(|game| game.to_str())
+                .is_some_and(|game| self.tree.contains_key(game))
+    }
+
+    fn skip_sidecars<'d, E>(
+        &'d self,
+        handle_failure: impl Fn(VerifyFailure) -> Result<Result<Option<PathBuf>, VerifyFailure>, E>
+            + Send
+            + Sync
+            + 'd,
+    ) -> impl Fn(VerifyFailure) -> Result<Result<Option<PathBuf>, VerifyFailure>, E> + Send + Sync + 'd
+    {
+        move |failure| match self.is_sidecar(&failure) {
+            true => Ok(Err(failure)),
+            false => handle_failure(failure),
+        }
+    }
+
+    // where a single-ROM game's file or a game's directory belongs
+    fn entry_path(&self, root: &Path, name: &str) -> PathBuf {
+        match self.layout == Layout::Letters {
+            true => root.join(letter_dir(name)).join(name),
+            false => root.join(name),
+        }
+    }
+
+    // moves the files and directories of games in the DAT
+    // into or out of subdirectories by their first letter,
+    // returning the number of entries moved and the paths of
+    // any entries left in place because they didn't verify
+    pub fn set_lettered_files(
+        &mut self,
+        root: &Path,
+        lettered: bool,
+    ) -> Result<(usize, Vec<PathBuf>), Error> {
+        use crate::game::ExtendSink;
+        use rayon::prelude::*;
+
+        let layout = match lettered {
+            true => Layout::Letters,
+            false => Layout::Files,
+        };
+
+        let entries = self
+            .games()
+            .map(|name| (name, self.entry_path(root, name)))
+            .filter(|(_, old)| old.symlink_metadata().is_ok())
+            .collect::<Vec<_>>();
+
+        // only entries whose files all verify are moved,
+        // though a game's directory may still be missing some
+        let (verified, unverified): (Vec<_>, Vec<_>) =
+            entries.into_par_iter().partition(|(name, old)| {
+                match (self.flat.get(name), self.tree.get(*name)) {
+                    (Some(part), _) => part.verify(name, old.clone()).is_ok(),
+                    (None, Some(parts)) => {
+                        let (_, failures): (ExtendSink<_>, Vec<_>) = parts.verify(old);
+                        failures.iter().all(|f| {
+                            matches!(f, VerifyFailure::Missing { .. }) || self.is_sidecar(f)
+                        })
+                    }
+                    (None, None) => false,
+                }
+            });
+
+        let mut moved = 0;
+
+        for (name, old) in verified {
+            let new = match lettered {
+                true => root.join(letter_dir(name)).join(name),
+                false => root.join(name),
+            };
+
+            if old != new && new.symlink_metadata().is_err() {
+                std::fs::create_dir_all(new.parent().unwrap())?;
+                std::fs::rename(&old, &new)?;
+                moved += 1;
+            }
+        }
+
+        // letter directories left empty are no longer needed
+        if !lettered {
+            let letters = self
+                .games()
+                .map(letter_dir)
+                .collect::<std::collections::BTreeSet<_>>();
+            for letter in letters {
+                let _ = std::fs::remove_dir(root.join(letter));
+            }
+        }
+
+        let skipped = unverified.into_iter().map(|(_, old)| old).collect();
+
+        self.layout = layout;
+
+        Ok((moved, skipped))
+    }
+
     // un-flattens the DAT into (game_name, parts) tuples
     pub fn into_game_parts(self) -> impl Iterator<Item = (String, GameParts)> {
         self.flat
@@ -297,11 +629,71 @@ impl DatFile {
             .chain(self.tree)
     }
 
+    // where a game's ROM belongs, given a game name from game_parts()
+    pub fn part_path(&self, root: &Path, game: &str, rom: &str) -> PathBuf {
+        match game {
+            "" => self.entry_path(root, rom),
+            game => self.entry_path(root, game).join(rom),
+        }
+    }
+
+    // adds a ROM the DAT doesn't list to one of its multi-ROM games,
+    // if the DAT has such a game
+    pub fn add_part(&mut self, game: &str, rom: String, part: Part) {
+        if let Some(parts) = self.tree.get_mut(game) {
+            parts.insert(rom, part);
+        }
+    }
+
     pub fn game_parts(&self) -> impl Iterator<Item = (&str, &GameParts)> {
         std::iter::once(("", &self.flat))
             .chain(self.tree.iter().map(|(game, parts)| (game.as_str(), parts)))
     }
 
+    // what changed from this DAT to a newer one,
+    // where single-ROM games are named after their ROM
+    pub fn changes(&self, new: &DatFile) -> crate::game::Changes {
+        fn games(dat: &DatFile) -> crate::game::GameContents<'_> {
+            dat.flat
+                .iter()
+                .map(|(rom, part)| (rom.as_str(), vec![(rom.as_str(), part)]))
+                .chain(dat.tree.iter().map(|(game, parts)| {
+                    (
+                        game.as_str(),
+                        parts.iter().map(|(n, p)| (n.as_str(), p)).collect(),
+                    )
+                }))
+                .collect()
+        }
+
+        crate::game::Changes::new(games(self), games(new))
+    }
+
+    // every part of every game as (game, part name, part) triples,
+    // where single-ROM games are named after their ROM
+    pub fn all_parts(&self) -> impl Iterator<Item = (&str, &str, &Part)> {
+        self.flat
+            .iter()
+            .map(|(rom, part)| (rom.as_str(), rom.as_str(), part))
+            .chain(self.tree.iter().flat_map(|(game, parts)| {
+                parts
+                    .iter()
+                    .map(move |(rom, part)| (game.as_str(), rom.as_str(), part))
+            }))
+    }
+
+    // whether nearly all the parts of both DATs are shared,
+    // such as a DAT renamed between versions
+    pub fn is_near_identical(&self, other: &DatFile) -> bool {
+        use std::collections::HashSet;
+
+        let parts = self.all_parts().map(|(_, _, p)| p).collect::<HashSet<_>>();
+        let other = other.all_parts().map(|(_, _, p)| p).collect::<HashSet<_>>();
+        let shared = parts.intersection(&other).count();
+
+        shared > 0 && shared * 10 >= parts.len().max(other.len()) * 9
+    }
+
     pub fn remove_game(&mut self, name: &str) -> Option<GameParts> {
         self.flat
             .remove(name)
@@ -313,37 +705,30 @@ impl DatFile {
     where
         I: IntoIterator<Item = (T, Self)>,
     {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Version", "DAT Name"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Version", "DAT Name", "Imported", "Source"]);
 
         for (_, datfile) in iter {
-            table.add_row(vec![datfile.version(), datfile.name()]);
+            table.add_row(vec![
+                datfile.version(),
+                datfile.name(),
+                &datfile.imported(),
+                datfile.imported_from().unwrap_or_default(),
+            ]);
         }
 
         println!("{table}");
     }
 
-    pub fn list(&self, search: Option<&str>) {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-
+    pub fn list(&self, search: Option<&Search>) {
         let mut games: Vec<_> = match search {
-            Some(search) => self.games().filter(|game| game.contains(search)).collect(),
+            Some(search) => self.games().filter(|game| search.is_match(game)).collect(),
             None => self.games().collect(),
         };
         games.sort_unstable();
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Game"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Game"]);
 
         for game in games {
             table.add_row(vec![game]);
@@ -355,24 +740,39 @@ impl DatFile {
     fn process<E>(
         &self,
         root: &Path,
-        increment_progress: impl Fn() + Send + Sync,
+        keep_extra: bool,
+        increment_progress: impl Fn(&Part) + Send + Sync,
         handle_failure: impl Fn(VerifyFailure) -> Result<Result<Option<PathBuf>, VerifyFailure>, E>
             + Send
             + Sync,
-    ) -> Result<VerifyResults, E>
+    ) -> Result<VerifyResults<'_>, E>
     where
         E: Send,
     {
         use crate::game::{ExtendCounter, ExtendSink, GameDir};
         use dashmap::DashMap;
         use rayon::prelude::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
         use std::sync::Mutex;
 
+        let _span =
+            tracing::info_span!("dat", name = self.name(), root = %root.display()).entered();
+
+        // sidecar files are left alone and don't count against their games
+        let is_ignored = |f: &VerifyFailure| f.is_ignored(keep_extra) || self.is_sidecar(f);
+        let handle_failure = self.skip_sidecars(handle_failure);
+
         let GameDir {
             files,
             dirs,
             mut failures,
-        }: GameDir<DashMap<_, _>, DashMap<_, _>, Vec<_>> = GameDir::open(root);
+        }: GameDir<DashMap<_, _>, DashMap<_, _>, Vec<_>> = match self.layout == Layout::Letters {
+            true => open_lettered(root),
+            false => GameDir::open(root),
+        };
+
+        // the loose files tested before any interruption
+        let flat_tested = AtomicUsize::new(0);
 
         // first, handle loose files not in subdirectories
         let ExtendCounter {
@@ -380,53 +780,83 @@ impl DatFile {
         } = self.flat.process(
             files,
             &mut failures,
-            |name| root.join(name),
-            &increment_progress,
+            |name| self.entry_path(root, name),
+            |part| {
+                flat_tested.fetch_add(1, Ordering::Relaxed);
+                increment_progress(part)
+            },
             &handle_failure,
         )?;
 
         let successes = Mutex::new(successes);
         let failures = Mutex::new(failures);
+        let skipped = AtomicUsize::new(0);
 
         // then handle everything with a subdirectory
         self.tree.par_iter().try_for_each(|(name, parts)| {
+            if crate::interrupt::requested() {
+                skipped.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+
+            let tested = AtomicUsize::new(0);
             let (_, game_failures): (ExtendSink<_>, Vec<_>) = parts.process_parts(
                 &dirs
                     .remove(name)
                     .map(|(_, v)| v)
-                    .unwrap_or_else(|| root.join(name)),
-                &increment_progress,
+                    .unwrap_or_else(|| self.entry_path(root, name)),
+                |part| {
+                    tested.fetch_add(1, Ordering::Relaxed);
+                    increment_progress(part)
+                },
                 &handle_failure,
             )?;
 
-            if game_failures.is_empty() {
+            // a game only partly tested when interrupted counts as skipped
+            if tested.into_inner() < parts.len() {
+                skipped.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+
+            if game_failures.iter().all(is_ignored) {
                 *successes.lock().unwrap() += 1;
-            } else {
-                failures.lock().unwrap().extend(game_failures);
             }
+            failures.lock().unwrap().extend(game_failures);
 
             Ok(())
         })?;
 
         let successes = successes.into_inner().unwrap();
         let mut failures = failures.into_inner().unwrap();
-
-        // mark any leftover directories as extras
-        failures.extend(dirs.into_iter().map(|(_, v)| VerifyFailure::extra_dir(v)));
+        let skipped = skipped.into_inner();
+
+        // mark any leftover directories as extras,
+        // unless they may belong to games skipped by an interruption
+        if skipped == 0 {
+            for (_, v) in dirs {
+                tracing::debug!(path = %v.display(), "extra: directory name not in DAT");
+                if let Err(failure) = handle_failure(VerifyFailure::extra_dir(v))? {
+                    failures.push(failure);
+                }
+            }
+        }
 
         failures.sort_unstable_by(|x, y| x.path().cmp(y.path()));
 
+        let (ignored, failures) = failures.into_iter().partition(is_ignored);
+
         Ok(VerifyResults {
             failures,
+            ignored,
             summary: crate::game::VerifyResultsSummary {
                 successes,
-                total: self.flat.len() + self.tree.len(),
+                total: flat_tested.into_inner() + self.tree.len() - skipped,
             },
         })
     }
 
     pub fn progress_bar(&self) -> indicatif::ProgressBar {
-        indicatif::ProgressBar::new(
+        crate::progress::bar(
             (self.flat.len() + self.tree.values().map(|g| g.len()).sum::<usize>())
                 .try_into()
                 .unwrap(),
@@ -435,32 +865,122 @@ impl DatFile {
         .with_message(format!("{} ({})", self.name, self.version))
     }
 
-    pub fn verify(&self, root: &Path, progress_bar: &indicatif::ProgressBar) -> VerifyResults {
+    #[inline]
+    pub fn verify(
+        &self,
+        root: &Path,
+        keep_extra: bool,
+        progress_bar: &indicatif::ProgressBar,
+    ) -> VerifyResults<'_> {
+        self.verify_with_progress(root, keep_extra, |_| progress_bar.inc(1))
+    }
+
+    // the files of every game which verifies OK, as pairs of
+    // their paths relative to the root and their actual paths
+    pub fn verified_files(
+        &self,
+        root: &Path,
+        progress_bar: &indicatif::ProgressBar,
+    ) -> Vec<(PathBuf, PathBuf)> {
+        self.verified_games(root, progress_bar)
+            .into_iter()
+            .flat_map(|(_, files)| files)
+            .collect()
+    }
+
+    // every game which verifies OK by name, along with its files
+    // as pairs of their paths relative to the root and their actual paths,
+    // where a single-ROM game is named by its file
+    pub fn verified_games(
+        &self,
+        root: &Path,
+        progress_bar: &indicatif::ProgressBar,
+    ) -> Vec<(String, Vec<(PathBuf, PathBuf)>)> {
+        use std::collections::HashSet;
+
+        let results = self.verify(root, true, progress_bar);
+        let failed = results
+            .failures
+            .iter()
+            .map(|f| f.path())
+            .collect::<HashSet<_>>();
+
+        self.flat
+            .keys()
+            .filter(|rom| !failed.contains(self.entry_path(root, rom).as_path()))
+            .map(|rom| {
+                (
+                    rom.clone(),
+                    vec![(PathBuf::from(rom), self.entry_path(root, rom))],
+                )
+            })
+            .chain(
+                self.tree
+                    .iter()
+                    .filter(|(game, _)| {
+                        let dir = self.entry_path(root, game);
+                        !failed.iter().any(|path| path.starts_with(&dir))
+                    })
+                    .map(|(game, parts)| {
+                        let dir = self.entry_path(root, game);
+                        (
+                            game.clone(),
+                            parts
+                                .keys()
+                                .map(|rom| (Path::new(game).join(rom), dir.join(rom)))
+                                .collect(),
+                        )
+                    }),
+            )
+            .collect()
+    }
+
+    pub fn verify_with_progress(
+        &self,
+        root: &Path,
+        keep_extra: bool,
+        increment_progress: impl Fn(&Part) + Send + Sync,
+    ) -> VerifyResults<'_> {
         use crate::game::Never;
 
         let results = self
-            .process(
-                root,
-                || progress_bar.inc(1),
-                |failure| Ok::<_, Never>(Err(failure)),
-            )
+            .process(root, keep_extra, increment_progress, |failure| {
+                Ok::<_, Never>(Err(failure))
+            })
             .unwrap();
 
         results
     }
 
+    #[inline]
     pub fn add_and_verify(
         &self,
-        roms: &mut RomSources,
+        roms: &RomSources,
+        policy: &RepairPolicy,
+        root: &Path,
+        progress_bar: &indicatif::ProgressBar,
+    ) -> Result<VerifyResults<'_>, Error> {
+        self.add_and_verify_with_progress(roms, policy, root, progress_bar, |_| progress_bar.inc(1))
+    }
+
+    // progress_bar is used for displaying repairs
+    pub fn add_and_verify_with_progress(
+        &self,
+        roms: &RomSources,
+        policy: &RepairPolicy,
         root: &Path,
         progress_bar: &indicatif::ProgressBar,
-    ) -> Result<VerifyResults, Error> {
+        increment_progress: impl Fn(&Part) + Send + Sync,
+    ) -> Result<VerifyResults<'_>, Error> {
+        let policy = &policy.dated(self.date());
+
         self.process(
             root,
-            || progress_bar.inc(1),
-            |failure| match failure.try_fix(roms) {
+            policy.keep_extra,
+            increment_progress,
+            |failure| match failure.try_fix(roms, policy) {
                 Ok(Ok(fix)) => {
-                    progress_bar.println(fix.to_string());
+                    progress_bar.log(fix.to_string());
                     Ok(Ok(fix.into_fixed_pathbuf()))
                 }
                 Ok(Err(f)) => Ok(Err(f)),
@@ -469,21 +989,360 @@ impl DatFile {
         )
     }
 
+    // games as they'd be stored one Zip file per game,
+    // where single-ROM games are named after their ROM
+    fn archived_games(&self) -> Vec<crate::archive::ArchivedGame<'_>> {
+        use crate::archive::ArchivedGame;
+
+        self.flat
+            .iter()
+            .map(|(rom, part)| ArchivedGame {
+                name: Path::new(rom)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| rom.clone()),
+                parts: vec![(rom.as_str(), part)],
+            })
+            .chain(self.tree.iter().map(|(game, parts)| {
+                ArchivedGame {
+                    name: game.clone(),
+                    parts: parts
+                        .iter()
+                        .map(|(rom, part)| (rom.as_str(), part))
+                        .collect(),
+                }
+            }))
+            .collect()
+    }
+
+    // records which of each game's parts are needed, given the failures
+    // from verifying it either as loose files or as Zip archives
+    pub fn record_sets(&self, root: &Path, failures: &[VerifyFailure], sets: &SetLists) {
+        use std::collections::HashSet;
+
+        // once interrupted, games left untested or partly tested
+        // have no failures to go by, so none are listed
+        if crate::interrupt::requested() {
+            return;
+        }
+
+        let needed = failures
+            .iter()
+            .filter(|f| f.needed().is_some())
+            .map(|f| f.path())
+            .collect::<HashSet<_>>();
+
+        for game in self.archived_games() {
+            let loose = match self.tree.contains_key(&game.name) {
+                true => self.entry_path(root, &game.name),
+                false => self
+                    .entry_path(root, &game.name)
+                    .parent()
+                    .unwrap()
+                    .to_owned(),
+            };
+            let zip = root.join(format!("{}.zip", game.name));
+
+            sets.record_set(
+                game.name,
+                game.parts.into_iter().filter(|(rom, _)| {
+                    needed.contains(loose.join(rom).as_path())
+                        || needed.contains(zip.join(rom).as_path())
+                }),
+            );
+        }
+    }
+
+    // records how many of each game's needed parts the sources can supply,
+    // given the failures from verifying it either as loose files or as Zip archives,
+    // with each game named within its DAT, if given
+    pub fn preflight(
+        &self,
+        root: &Path,
+        failures: &[VerifyFailure],
+        sources: &RomSources,
+        preflight: &Preflight,
+        dat: Option<&str>,
+    ) {
+        use std::collections::HashMap;
+
+        // failures by the file they'd put in place
+        let failures = failures
+            .iter()
+            .map(|f| match f {
+                VerifyFailure::Rename { destination, .. }
+                | VerifyFailure::Interleaved { destination, .. } => (destination.as_path(), f),
+                f => (f.path(), f),
+            })
+            .collect::<HashMap<_, _>>();
+
+        for game in self.archived_games() {
+            let loose = match self.tree.contains_key(&game.name) {
+                true => self.entry_path(root, &game.name),
+                false => self
+                    .entry_path(root, &game.name)
+                    .parent()
+                    .unwrap()
+                    .to_owned(),
+            };
+            let zip = root.join(format!("{}.zip", game.name));
+
+            preflight.record(
+                match dat {
+                    Some(dat) => format!("{dat}/{}", game.name),
+                    None => game.name.clone(),
+                },
+                game.parts.iter().filter_map(|(rom, _)| {
+                    failures
+                        .get(loose.join(rom).as_path())
+                        .or_else(|| failures.get(zip.join(rom).as_path()))
+                        .copied()
+                }),
+                sources,
+            );
+        }
+    }
+
+    pub fn verify_archived(
+        &self,
+        root: &Path,
+        keep_extra: bool,
+        increment_progress: impl Fn(&Part) + Send + Sync,
+    ) -> VerifyResults<'_> {
+        crate::archive::process(
+            self.archived_games(),
+            root,
+            keep_extra,
+            increment_progress,
+            None,
+        )
+        .expect("verifying alone writes nothing which could fail")
+    }
+
+    // progress_bar is used for displaying repairs
+    pub fn add_and_verify_archived(
+        &self,
+        roms: &RomSources,
+        policy: &RepairPolicy,
+        root: &Path,
+        progress_bar: &indicatif::ProgressBar,
+        increment_progress: impl Fn(&Part) + Send + Sync,
+    ) -> Result<VerifyResults<'_>, Error> {
+        crate::archive::process(
+            self.archived_games(),
+            root,
+            policy.keep_extra,
+            increment_progress,
+            Some(crate::archive::Repair {
+                sources: roms,
+                policy,
+                progress_bar,
+            }),
+        )
+    }
+
     pub fn size(&self, root: &Path) -> FileSize {
-        self.flat.size(root)
+        self.paths(root)
+            .map(|pb| FileSize::new(&pb).unwrap_or_default())
+            .sum()
+    }
+
+    pub fn paths<'r>(&'r self, root: &'r Path) -> impl Iterator<Item = PathBuf> + 'r {
+        self.flat
+            .keys()
+            .map(|rom| self.entry_path(root, rom))
+            .chain(self.tree.iter().flat_map(|(name, parts)| {
+                let dir = self.entry_path(root, name);
+                parts.keys().map(move |rom| dir.join(rom))
+            }))
+    }
+
+    pub fn expected_size(&self) -> u64 {
+        self.flat.expected_size()
             + self
                 .tree
-                .iter()
-                .map(|(name, parts)| parts.size(&root.join(name)))
-                .sum::<FileSize>()
+                .values()
+                .map(|parts| parts.expected_size())
+                .sum::<u64>()
+    }
+}
+
+// how a DAT's games are stored on disk
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    // loose files, with multi-ROM games in subdirectories
+    #[default]
+    Files,
+    // loose files in subdirectories by first letter, like A/ B/ ... 0-9/
+    Letters,
+    // one Zip file per game
+    Archives,
+}
+
+impl std::str::FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "files" => Ok(Layout::Files),
+            "letter" => Ok(Layout::Letters),
+            "zip" => Ok(Layout::Archives),
+            _ => Err("invalid layout, use \"files\", \"letter\" or \"zip\"".to_string()),
+        }
+    }
+}
+
+// the subdirectory a game belongs in when sorted by letter,
+// where names not starting with a letter or digit go in "#"
+pub fn letter_dir(name: &str) -> String {
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase().to_string(),
+        Some(c) if c.is_ascii_digit() => "0-9".to_owned(),
+        _ => "#".to_owned(),
+    }
+}
+
+// like GameDir::open, but gathering files and directories
+// from each letter's subdirectory, where anything outside
+// of the subdirectory it belongs in is extra
+fn open_lettered<'s>(
+    root: &Path,
+) -> crate::game::GameDir<
+    dashmap::DashMap<String, PathBuf>,
+    dashmap::DashMap<String, PathBuf>,
+    Vec<VerifyFailure<'s>>,
+> {
+    use crate::game::GameDir;
+
+    let GameDir {
+        files: root_files,
+        dirs: letters,
+        mut failures,
+    }: GameDir<Vec<_>, Vec<_>, Vec<_>> = GameDir::open(root);
+
+    failures.extend(
+        root_files
+            .into_iter()
+            .map(|(_, path)| VerifyFailure::extra(path)),
+    );
+
+    let files = dashmap::DashMap::new();
+    let dirs = dashmap::DashMap::new();
+
+    for (letter, dir) in letters {
+        let GameDir {
+            files: letter_files,
+            dirs: letter_dirs,
+            failures: letter_failures,
+        }: GameDir<Vec<_>, Vec<_>, Vec<_>> = GameDir::open(&dir);
+
+        failures.extend(letter_failures);
+
+        for (name, path) in letter_files {
+            match letter_dir(&name) == letter {
+                true => {
+                    files.insert(name, path);
+                }
+                false => failures.push(VerifyFailure::extra(path)),
+            }
+        }
+
+        for (name, path) in letter_dirs {
+            match letter_dir(&name) == letter {
+                true => {
+                    dirs.insert(name, path);
+                }
+                false => failures.push(VerifyFailure::extra_dir(path)),
+            }
+        }
+    }
+
+    GameDir {
+        files,
+        dirs,
+        failures,
     }
 }
 
 pub struct VerifyResults<'v> {
     pub failures: Vec<VerifyFailure<'v>>,
+    // failures which don't count against verification
+    pub ignored: Vec<VerifyFailure<'v>>,
     pub summary: crate::game::VerifyResultsSummary,
 }
 
+// writes games and their parts as a Logiqx XML DAT,
+// such as a fixdat of only those parts a collection lacks
+pub fn write_dat<'p, W: std::io::Write>(
+    mut w: W,
+    name: &str,
+    games: impl IntoIterator<Item = (&'p str, &'p BTreeMap<String, Part>)>,
+) -> Result<(), std::io::Error> {
+    use quick_xml::escape::escape;
+
+    let version =
+        chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::now()).format("%Y%m%d-%H%M%S");
+
+    writeln!(w, r#"<?xml version="1.0"?>"#)?;
+    writeln!(
+        w,
+        r#"<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">"#
+    )?;
+    writeln!(w, "<datafile>")?;
+    writeln!(w, "  <header>")?;
+    writeln!(w, "    <name>{}</name>", escape(name))?;
+    writeln!(w, "    <description>{}</description>", escape(name))?;
+    writeln!(w, "    <version>{version}</version>")?;
+    writeln!(w, "  </header>")?;
+    for (game, parts) in games {
+        writeln!(w, r#"  <game name="{}">"#, escape(game))?;
+        writeln!(w, "    <description>{}</description>", escape(game))?;
+        for (part_name, part) in parts {
+            match part {
+                Part::Rom {
+                    size: Some(size), ..
+                } => writeln!(
+                    w,
+                    r#"    <rom name="{}" size="{size}" sha1="{}"/>"#,
+                    escape(part_name),
+                    part.digest()
+                )?,
+                Part::Rom { size: None, .. } => writeln!(
+                    w,
+                    r#"    <rom name="{}" sha1="{}"/>"#,
+                    escape(part_name),
+                    part.digest()
+                )?,
+                Part::Crc {
+                    size: Some(size), ..
+                } => writeln!(
+                    w,
+                    r#"    <rom name="{}" size="{size}" crc="{}"/>"#,
+                    escape(part_name),
+                    part.digest()
+                )?,
+                Part::Crc { size: None, .. } => writeln!(
+                    w,
+                    r#"    <rom name="{}" crc="{}"/>"#,
+                    escape(part_name),
+                    part.digest()
+                )?,
+                // disks are named without their .chd extension
+                Part::Disk { .. } => writeln!(
+                    w,
+                    r#"    <disk name="{}" sha1="{}"/>"#,
+                    escape(part_name.strip_suffix(".chd").unwrap_or(part_name)),
+                    part.digest()
+                )?,
+            }
+        }
+        writeln!(w, "  </game>")?;
+    }
+    writeln!(w, "</datafile>")?;
+
+    Ok(())
+}
+
 pub fn edit_file(dat: Datafile, old_dat: Option<DatFile>) -> Result<Datafile, Error> {
     use crate::terminal_height;
     use inquire::list_option::ListOption;
@@ -492,6 +1351,7 @@ pub fn edit_file(dat: Datafile, old_dat: Option<DatFile>) -> Result<Datafile, Er
     match dat {
         Datafile {
             header,
+            build,
             game: Some(mut game),
             machine,
         } if !game.is_empty() => {
@@ -511,7 +1371,7 @@ pub fn edit_file(dat: Datafile, old_dat: Option<DatFile>) -> Result<Datafile, Er
 
             Ok(Datafile {
                 game: Some(
-                    inquire::MultiSelect::new(&header.name, game)
+                    inquire::MultiSelect::new(Header::name_of(&header), game)
                         .with_default(&defaults)
                         .with_page_size(terminal_height())
                         .with_formatter(&|opts: &[ListOption<&Game>]| {
@@ -527,6 +1387,7 @@ pub fn edit_file(dat: Datafile, old_dat: Option<DatFile>) -> Result<Datafile, Er
                         .prompt()?,
                 ),
                 header,
+                build,
                 machine,
             })
         }
@@ -534,68 +1395,198 @@ pub fn edit_file(dat: Datafile, old_dat: Option<DatFile>) -> Result<Datafile, Er
     }
 }
 
+#[inline]
 pub fn fetch_and_parse<R, D>(
     dats: R,
-    mut convert: impl FnMut(Resource, Datafile) -> Result<DatFile, Error>,
+    convert: impl FnMut(Resource, Datafile) -> Result<DatFile, Error>,
 ) -> Result<D, Error>
+where
+    R: IntoIterator<Item = Resource>,
+    D: Default + ExtendOne<DatFile>,
+{
+    fetch_modified_and_parse(dats, None::<fn(&str) -> bool>, convert).map(|(datfiles, _)| datfiles)
+}
+
+// if is_stored is given, URLs whose servers say they haven't changed
+// since they were last imported are skipped rather than imported again,
+// as long as is_stored says each DAT imported from them is still stored,
+// returning the validators to save once the DATs have been written
+pub fn fetch_modified_and_parse<R, D>(
+    dats: R,
+    is_stored: Option<impl Fn(&str) -> bool>,
+    mut convert: impl FnMut(Resource, Datafile) -> Result<DatFile, Error>,
+) -> Result<(D, crate::http::Imported), Error>
 where
     R: IntoIterator<Item = Resource>,
     D: Default + ExtendOne<DatFile>,
 {
     type Dats = Vec<(Resource, Box<[u8]>)>;
 
-    fn read_dats(resource: Resource) -> Result<Dats, Error> {
-        use super::is_zip;
+    // bundles may contain Zip files of DATs, which are unpacked recursively
+    fn read_zip_dats<R>(resource: &Resource, r: R, dats: &mut Dats) -> Result<(), Error>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
         use std::io::Read;
 
-        let mut f = resource.open()?;
+        let mut zip = zip::ZipArchive::new(r)?;
+
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+            let name = entry.name().to_ascii_lowercase();
+
+            if name.ends_with(".zip") {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                read_zip_dats(resource, std::io::Cursor::new(data), dats)?;
+            } else if name.ends_with(".dat") || name.ends_with(".xml") {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                dats.push((resource.clone(), data.into_boxed_slice()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_dats(resource: Resource, mut f: crate::ResourceFile) -> Result<Dats, Error> {
+        use super::{is_gzip, is_zip};
+        use std::io::Read;
 
         match is_zip(&mut f) {
             Ok(true) => {
-                let mut zip = zip::ZipArchive::new(f)?;
-
-                let dats = zip
-                    .file_names()
-                    .filter(|s| s.ends_with(".dat"))
-                    .map(|s| s.to_owned())
-                    .collect::<Vec<String>>();
-
-                dats.into_iter()
-                    .map(|name| {
-                        let mut data = Vec::new();
-                        zip.by_name(&name)?.read_to_end(&mut data)?;
-                        Ok((resource.clone(), data.into_boxed_slice()))
-                    })
-                    .collect()
+                let mut dats = Dats::default();
+                read_zip_dats(&resource, f, &mut dats)?;
+                Ok(dats)
             }
             Ok(false) => {
                 let mut data = Vec::new();
-                f.read_to_end(&mut data)?;
+                if is_gzip(&mut f)? {
+                    flate2::read::MultiGzDecoder::new(f).read_to_end(&mut data)?;
+                } else {
+                    f.read_to_end(&mut data)?;
+                }
                 Ok(vec![(resource, data.into_boxed_slice())])
             }
             Err(err) => Err(Error::IO(err)),
         }
     }
 
+    // DAT files found in directories are imported on a best-effort basis
+    // while explicitly given files must all import successfully
+    fn expand(resource: Resource) -> Vec<(Resource, bool)> {
+        match resource {
+            Resource::File(dir) if dir.is_dir() => {
+                let mut files = crate::symlinks::walk(dir)
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| e.into_path())
+                    .filter(|path| {
+                        // DATs may also be gzipped or zipped,
+                        // where Zip files without any DATs import nothing
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .map(|name| name.to_ascii_lowercase())
+                            .is_some_and(|name| {
+                                [".dat", ".xml", ".dat.gz", ".xml.gz", ".zip"]
+                                    .iter()
+                                    .any(|ext| name.ends_with(ext))
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                files.sort_unstable();
+                files
+                    .into_iter()
+                    .map(|path| (Resource::File(path), true))
+                    .collect()
+            }
+            resource => vec![(resource, false)],
+        }
+    }
+
+    let resources = dats.into_iter().flat_map(expand).collect::<Vec<_>>();
+    let from_dirs = resources.iter().any(|(_, in_dir)| *in_dir);
+
+    let pbar = crate::progress::bar(resources.len().try_into().unwrap())
+        .with_style(crate::game::verify_style())
+        .with_message("importing DAT files");
+
     let mut datfiles = D::default();
+    let mut validated = crate::http::Imported::default();
+    let mut imported = 0;
+    let mut failed = 0;
+
+    for (resource, in_dir) in resources {
+        let opened = match &is_stored {
+            Some(is_stored) => resource.open_if_modified(is_stored),
+            None => resource.open().map(|f| Some((f, None))),
+        };
+        let (f, validators) = match opened {
+            Ok(Some(opened)) => opened,
+            Ok(None) => {
+                pbar.log(format!("* {resource} unchanged since last imported"));
+                pbar.inc(1);
+                continue;
+            }
+            Err(err) if in_dir => {
+                pbar.log(format!("* {}", err));
+                failed += 1;
+                pbar.inc(1);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        let source = resource.to_string();
+        let mut names = Vec::new();
+
+        let result = read_dats(resource, f).and_then(|dats| {
+            dats.into_iter().try_for_each(|(resource, data)| {
+                let datafile = match quick_xml::de::from_reader(std::io::Cursor::new(data)) {
+                    Ok(dat) => dat,
+                    Err(error) => {
+                        return Err(Error::XmlFile(ResourceError {
+                            file: resource,
+                            error,
+                        }))
+                    }
+                };
+
+                let source = resource.to_string();
+                tracing::debug!(%source, "parsed DAT file");
+                let mut datfile = pbar.suspend(|| convert(resource, datafile))?;
+                datfile.set_imported(source);
+                names.push(datfile.name().to_owned());
+                datfiles.extend_item(datfile);
+                imported += 1;
+                Ok(())
+            })
+        });
 
-    for resource in dats {
-        for (resource, data) in read_dats(resource)? {
-            let datafile = match quick_xml::de::from_reader(std::io::Cursor::new(data)) {
-                Ok(dat) => dat,
-                Err(error) => {
-                    return Err(Error::XmlFile(ResourceError {
-                        file: resource,
-                        error,
-                    }))
+        match result {
+            Ok(()) => {
+                if let Some(validators) = validators {
+                    validated.insert(source, validators, names);
                 }
-            };
-
-            datfiles.extend_item(convert(resource, datafile)?);
+            }
+            Err(err) if in_dir => {
+                tracing::warn!(%err, "skipping DAT file");
+                pbar.log(format!("* {}", err));
+                failed += 1;
+            }
+            Err(err) => return Err(err),
         }
+
+        pbar.inc(1);
+    }
+
+    pbar.finish_and_clear();
+
+    if from_dirs {
+        eprintln!("* {} DAT files imported, {} failed", imported, failed);
     }
 
-    Ok(datfiles)
+    Ok((datfiles, validated))
 }
 
 pub fn fetch_and_parse_single(
@@ -0,0 +1,179 @@
+use crate::Error;
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+// each database file starts with a header identifying it
+// so that a damaged file can be told apart from an outdated one
+const MAGIC: &[u8; 8] = b"EMUMANDB";
+
+// bumped whenever the header layout changes
+const FORMAT_VERSION: u32 = 1;
+
+// magic, format version, payload length, payload SHA-1
+const HEADER_SIZE: usize = MAGIC.len() + 4 + 8 + 20;
+
+#[derive(Debug)]
+pub enum Damage {
+    // fewer bytes on disk than were written
+    Truncated { expected: u64, found: u64 },
+    // the header's from a format this version doesn't know
+    Newer(u32),
+    // the right size, but the contents don't match the checksum
+    Corrupted,
+    // intact, but not laid out the way this version expects
+    Incompatible,
+    // neither a database with a header nor an older one without
+    Unrecognized,
+}
+
+impl std::fmt::Display for Damage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated { expected, found } => {
+                write!(f, "is truncated ({found} of {expected} bytes)")
+            }
+            Self::Newer(version) => write!(
+                f,
+                "is from a newer version of emuman (format {version}, expected {FORMAT_VERSION})"
+            ),
+            Self::Corrupted => write!(f, "is corrupted (checksum mismatch)"),
+            Self::Incompatible => write!(f, "is intact but from an incompatible version"),
+            Self::Unrecognized => write!(f, "is not a recognized database"),
+        }
+    }
+}
+
+pub fn write<S: Serialize>(path: &Path, db: S) -> Result<(), Error> {
+    use std::io::Write;
+
+    let mut payload = Vec::new();
+    ciborium::ser::into_writer(&db, &mut payload).map_err(Error::CborWrite)?;
+
+    let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+    w.write_all(MAGIC)?;
+    w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    w.write_all(&(payload.len() as u64).to_le_bytes())?;
+    w.write_all(&sha1_smol::Sha1::from(&payload).digest().bytes())?;
+    w.write_all(&payload)?;
+    w.flush().map_err(Error::IO)
+}
+
+// the payload's SHA-1 from a database's header, without reading the rest,
+// so a file derived from it can tell whether it's since been replaced
+pub fn digest(path: &Path) -> Option<[u8; 20]> {
+    let mut header = [0; HEADER_SIZE];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .ok()?;
+
+    header
+        .starts_with(MAGIC)
+        .then(|| header[HEADER_SIZE - 20..].try_into().unwrap())
+}
+
+// a missing file is MissingCache, anything unreadable is InvalidCache
+pub fn read<D: DeserializeOwned>(utility: &'static str, path: &Path) -> Result<D, Error> {
+    let f = std::fs::File::open(path).map_err(|_| Error::MissingCache(utility))?;
+
+    read_file(f)?.map_err(|damage| Error::InvalidCache(utility, path.to_owned(), damage))
+}
+
+// for data the user builds up, where a file never written is the default
+// and a damaged one is reported rather than silently started over
+pub fn read_or_default<D: DeserializeOwned + Default>(path: &Path) -> Result<D, Error> {
+    match std::fs::File::open(path) {
+        Ok(f) => read_file(f)?.map_err(|damage| Error::InvalidData(path.to_owned(), damage)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(D::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn read_file<D: DeserializeOwned>(f: std::fs::File) -> Result<Result<D, Damage>, std::io::Error> {
+    let found = f.metadata()?.len();
+
+    parse(std::io::BufReader::new(f), found)
+}
+
+// the payload's deserialized as it's read and hashed along the way,
+// with the digest checked once the whole payload has been read
+fn parse<D: DeserializeOwned>(
+    mut r: impl Read,
+    found: u64,
+) -> Result<Result<D, Damage>, std::io::Error> {
+    let mut magic = Vec::with_capacity(MAGIC.len());
+    r.by_ref()
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+
+    if magic != MAGIC {
+        // written before databases had headers
+        return Ok(
+            ciborium::de::from_reader(magic.as_slice().chain(r)).map_err(|_| Damage::Unrecognized)
+        );
+    }
+
+    if found < HEADER_SIZE as u64 {
+        return Ok(Err(Damage::Truncated {
+            expected: HEADER_SIZE as u64,
+            found,
+        }));
+    }
+
+    let mut version = [0; 4];
+    let mut length = [0; 8];
+    let mut sha1 = [0; 20];
+    r.read_exact(&mut version)?;
+    r.read_exact(&mut length)?;
+    r.read_exact(&mut sha1)?;
+
+    match u32::from_le_bytes(version) {
+        FORMAT_VERSION => {}
+        version if version > FORMAT_VERSION => return Ok(Err(Damage::Newer(version))),
+        _ => return Ok(Err(Damage::Unrecognized)),
+    }
+
+    let length = u64::from_le_bytes(length);
+    let payload = found - HEADER_SIZE as u64;
+    if payload < length {
+        return Ok(Err(Damage::Truncated {
+            expected: HEADER_SIZE as u64 + length,
+            found,
+        }));
+    } else if payload > length {
+        return Ok(Err(Damage::Corrupted));
+    }
+
+    let mut hashed = Hashed {
+        r: r.take(length),
+        sha1: sha1_smol::Sha1::new(),
+        read: 0,
+    };
+    let db = ciborium::de::from_reader(&mut hashed);
+
+    // anything the deserializer left unread still needs hashing
+    std::io::copy(&mut hashed, &mut std::io::sink())?;
+
+    Ok(
+        if hashed.read != length || hashed.sha1.digest().bytes() != sha1 {
+            Err(Damage::Corrupted)
+        } else {
+            db.map_err(|_| Damage::Incompatible)
+        },
+    )
+}
+
+struct Hashed<R> {
+    r: R,
+    sha1: sha1_smol::Sha1,
+    read: u64,
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.r.read(buf)?;
+        self.sha1.update(&buf[..read]);
+        self.read += read as u64;
+        Ok(read)
+    }
+}
@@ -1,14 +1,22 @@
-use super::{terminal_height, Error};
+use super::Error;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
-const DIR_CONFIG_FILE: &str = "dirs.toml";
+pub const DIR_CONFIG_FILE: &str = "dirs.toml";
 
 #[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 struct DirectoryConfig {
+    // in portable mode, directories under this root
+    // are stored relative to it and resolved at runtime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root: Option<String>,
     mame: Option<String>,
     mess: Option<String>,
+    // No-Intro DATs whose directories are organized by letter
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    nointro_letters: BTreeSet<String>,
     extra: BTreeMap<String, String>,
     redump: BTreeMap<String, String>,
     nointro: BTreeMap<String, String>,
@@ -33,6 +41,51 @@ impl DirectoryConfig {
         toml::from_str(&toml).ok()
     }
 
+    // the configuration file along with any directories
+    // overridden by environment variables, which are never saved
+    fn load() -> Option<Self> {
+        use crate::{read_db_names, DIR_EXTRA, DIR_NOINTRO, DIR_REDUMP};
+
+        fn overrides(db_dir: &'static str, prefix: &str) -> BTreeMap<String, String> {
+            read_db_names(db_dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|name| {
+                    env_dir(&format!("{}{}", prefix, env_name(&name))).map(|dir| (name, dir))
+                })
+                .collect()
+        }
+
+        let config = Self::new();
+
+        let mame = env_dir(ENV_MAME_ROMS);
+        let mess = env_dir(ENV_SL_ROMS);
+        let mut extra = overrides(DIR_EXTRA, ENV_EXTRA_PREFIX);
+        let mut redump = overrides(DIR_REDUMP, ENV_REDUMP_PREFIX);
+        let mut nointro = overrides(DIR_NOINTRO, ENV_NOINTRO_PREFIX);
+
+        if mame.is_none()
+            && mess.is_none()
+            && extra.is_empty()
+            && redump.is_empty()
+            && nointro.is_empty()
+        {
+            return config;
+        }
+
+        let mut config = config.unwrap_or_default();
+        if mame.is_some() {
+            config.mame = mame;
+        }
+        if mess.is_some() {
+            config.mess = mess;
+        }
+        config.extra.append(&mut extra);
+        config.redump.append(&mut redump);
+        config.nointro.append(&mut nointro);
+        Some(config)
+    }
+
     fn save(self) -> Result<(), Error> {
         use std::io::Write;
 
@@ -44,10 +97,12 @@ impl DirectoryConfig {
     }
 
     fn location() -> PathBuf {
-        directories::ProjectDirs::from("", "", "EmuMan")
-            .expect("no valid home directory")
-            .data_local_dir()
-            .join(DIR_CONFIG_FILE)
+        data_dir().join(DIR_CONFIG_FILE)
+    }
+
+    // a relative root is relative to the data directory
+    fn root(&self) -> Option<PathBuf> {
+        self.root.as_ref().map(|root| data_dir().join(root))
     }
 
     #[inline]
@@ -55,24 +110,294 @@ impl DirectoryConfig {
     where
         F: FnOnce(DirectoryConfig) -> Option<String>,
     {
-        f(Self::new()?).map(PathBuf::from)
+        let config = Self::load()?;
+        let root = config.root();
+        f(config).map(|dir| resolve(root.as_deref(), dir))
     }
 
     fn set<F>(f: F, value: PathBuf) -> Result<Set, Error>
     where
         F: FnOnce(&mut DirectoryConfig, String) -> Set,
     {
-        let value = value
+        let mut config = Self::new().unwrap_or_default();
+
+        let value = relativize(config.root().as_deref(), value)
             .into_os_string()
             .into_string()
             .map_err(|_| Error::InvalidPath)?;
 
-        let mut config = Self::new().unwrap_or_default();
         match f(&mut config, value) {
             set @ Set::Unchanged => Ok(set),
             set => config.save().map(|()| set),
         }
     }
+
+    fn update<F>(f: F) -> Result<Set, Error>
+    where
+        F: FnOnce(&mut DirectoryConfig) -> Result<Set, Error>,
+    {
+        let mut config = Self::new().unwrap_or_default();
+        match f(&mut config)? {
+            set @ Set::Unchanged => Ok(set),
+            set => config.save().map(|()| set),
+        }
+    }
+}
+
+const ENV_MAME_ROMS: &str = "EMUMAN_MAME_ROMS";
+const ENV_SL_ROMS: &str = "EMUMAN_SL_ROMS";
+const ENV_EXTRA_PREFIX: &str = "EMUMAN_EXTRA_";
+const ENV_REDUMP_PREFIX: &str = "EMUMAN_REDUMP_";
+const ENV_NOINTRO_PREFIX: &str = "EMUMAN_NOINTRO_";
+
+// converts a DAT name to its environment variable suffix,
+// such as "Nintendo - Game Boy" to "NINTENDO___GAME_BOY"
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// environment directories are made absolute so that
+// they're never resolved against a portable root
+fn env_dir(var: &str) -> Option<String> {
+    std::env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .and_then(|dir| std::path::absolute(dir).ok())
+        .and_then(|dir| dir.into_os_string().into_string().ok())
+}
+
+fn resolve(root: Option<&Path>, dir: String) -> PathBuf {
+    match root {
+        Some(root) => root.join(dir),
+        None => PathBuf::from(dir),
+    }
+}
+
+// directories outside the root remain absolute
+fn relativize(root: Option<&Path>, dir: PathBuf) -> PathBuf {
+    match root.and_then(|root| root.canonicalize().ok()) {
+        Some(root) => match dir.strip_prefix(&root) {
+            Ok(relative) if relative.as_os_str().is_empty() => PathBuf::from("."),
+            Ok(relative) => relative.to_owned(),
+            Err(_) => dir,
+        },
+        None => dir,
+    }
+}
+
+// returns a list of problems with the given configuration text
+fn validate_config(toml: &str) -> Vec<String> {
+    use crate::{read_db_names, DIR_EXTRA, DIR_NOINTRO, DIR_REDUMP};
+
+    fn check_dir(problems: &mut Vec<String>, root: Option<&Path>, dir: &str) {
+        if root.is_none() && !Path::new(dir).is_absolute() {
+            problems.push(format!("\"{}\" is not an absolute path", dir));
+        } else if !resolve(root, dir.to_owned()).is_dir() {
+            problems.push(format!("\"{}\" is not a directory", dir));
+        }
+    }
+
+    fn check_dirs(
+        problems: &mut Vec<String>,
+        root: Option<&Path>,
+        dirs: &BTreeMap<String, String>,
+        db_dir: &'static str,
+        category: &str,
+    ) {
+        let names = read_db_names(db_dir)
+            .map(|i| i.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for (name, dir) in dirs {
+            if !names.contains(name) {
+                problems.push(format!("unknown {} \"{}\"", category, name));
+            }
+            check_dir(problems, root, dir);
+        }
+    }
+
+    let table = match toml.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(err) => return vec![err.to_string()],
+    };
+
+    let mut problems = Vec::new();
+
+    match DirectoryConfig::deserialize(table) {
+        Ok(config) => {
+            let root = config.root();
+            let root = root.as_deref();
+
+            if let Some(root) = root {
+                if !root.is_dir() {
+                    problems.push(format!("root \"{}\" is not a directory", root.display()));
+                }
+            }
+            if let Some(dir) = &config.mame {
+                check_dir(&mut problems, root, dir);
+            }
+            if let Some(dir) = &config.mess {
+                check_dir(&mut problems, root, dir);
+            }
+            check_dirs(
+                &mut problems,
+                root,
+                &config.extra,
+                DIR_EXTRA,
+                "extras category",
+            );
+            check_dirs(
+                &mut problems,
+                root,
+                &config.redump,
+                DIR_REDUMP,
+                "Redump DAT",
+            );
+            check_dirs(
+                &mut problems,
+                root,
+                &config.nointro,
+                DIR_NOINTRO,
+                "No-Intro DAT",
+            );
+        }
+        Err(err) => problems.push(err.to_string()),
+    }
+
+    problems
+}
+
+// switches to portable mode, storing directories relative to root,
+// or back to absolute directories if no root is given
+pub fn set_portable_root(root: Option<&Path>) -> Result<(), Error> {
+    let root = match root {
+        Some(root) if !root.is_dir() => return Err(Error::NoSuchDirectory(root.to_owned())),
+        Some(root) => Some(root.canonicalize()?),
+        None => None,
+    };
+
+    DirectoryConfig::update(|config| {
+        let old_root = config.root();
+        let mut rebase = |dir: &mut String| -> Result<(), Error> {
+            let absolute = resolve(old_root.as_deref(), std::mem::take(dir));
+            *dir = relativize(root.as_deref(), absolute)
+                .into_os_string()
+                .into_string()
+                .map_err(|_| Error::InvalidPath)?;
+            Ok(())
+        };
+
+        config.mame.iter_mut().try_for_each(&mut rebase)?;
+        config.mess.iter_mut().try_for_each(&mut rebase)?;
+        config.extra.values_mut().try_for_each(&mut rebase)?;
+        config.redump.values_mut().try_for_each(&mut rebase)?;
+        config.nointro.values_mut().try_for_each(&mut rebase)?;
+
+        config.root = root
+            .as_ref()
+            .map(|root| {
+                // keep the root itself portable if it's the data directory
+                match data_dir().canonicalize() {
+                    Ok(data) if &data == root => Ok(".".to_owned()),
+                    _ => root
+                        .clone()
+                        .into_os_string()
+                        .into_string()
+                        .map_err(|_| Error::InvalidPath),
+                }
+            })
+            .transpose()?;
+
+        Ok(Set::Changed)
+    })
+    .map(|_| ())
+}
+
+// opens the directory configuration in the user's editor
+// and only replaces the original once the edits are valid
+pub fn edit_config() -> Result<(), Error> {
+    let location = DirectoryConfig::location();
+
+    let editing = location.with_extension("edit.toml");
+
+    match std::fs::read(&location) {
+        Ok(data) => std::fs::write(&editing, data)?,
+        Err(_) => std::fs::write(
+            &editing,
+            toml::to_string_pretty(&DirectoryConfig::default())?,
+        )?,
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(target_os = "windows") {
+                "notepad".to_owned()
+            } else {
+                "vi".to_owned()
+            }
+        });
+    let mut editor = editor.split_whitespace();
+    let program = editor.next().ok_or(Error::EditorFailed)?;
+    let args = editor.collect::<Vec<_>>();
+
+    let result = loop {
+        match std::process::Command::new(program)
+            .args(&args)
+            .arg(&editing)
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(_) => break Err(Error::EditorFailed),
+            Err(err) => break Err(Error::IO(err)),
+        }
+
+        let problems = match std::fs::read_to_string(&editing) {
+            Ok(toml) => validate_config(&toml),
+            Err(err) => break Err(Error::IO(err)),
+        };
+
+        if problems.is_empty() {
+            break std::fs::rename(&editing, &location).map_err(Error::IO);
+        }
+
+        for problem in problems {
+            eprintln!("* {}", problem);
+        }
+
+        match inquire::Confirm::new("edit again?")
+            .with_default(true)
+            .prompt()
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("* {} left unchanged", DIR_CONFIG_FILE);
+                break Ok(());
+            }
+            Err(err) => break Err(Error::Inquire(err)),
+        }
+    };
+
+    if editing.exists() {
+        let _ = std::fs::remove_file(&editing);
+    }
+
+    result
+}
+
+#[inline]
+pub fn data_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "EmuMan")
+        .expect("no valid home directory")
+        .data_local_dir()
+        .to_owned()
 }
 
 #[inline]
@@ -160,6 +485,12 @@ pub fn mame_roms(roms: Option<PathBuf>) -> MameRoms {
     MameRoms::new(roms)
 }
 
+// the MAME ROMs directory, only if one's been configured
+#[inline]
+pub fn mame_dir() -> Option<PathBuf> {
+    DirectoryConfig::get(|d| d.mame)
+}
+
 pub struct MessRoms<'s> {
     roms: RomSource,
     software_list: Option<&'s str>,
@@ -227,6 +558,12 @@ pub fn mess_roms(roms: Option<PathBuf>, software_list: &str) -> MessRoms<'_> {
     MessRoms::new(roms, Some(software_list))
 }
 
+// the software list ROMs directory, only if one's been configured
+#[inline]
+pub fn sl_dir() -> Option<PathBuf> {
+    DirectoryConfig::get(|d| d.mess)
+}
+
 pub struct ExtraParts<'e> {
     extras: RomSource,
     extra: &'e str,
@@ -276,9 +613,15 @@ impl<'e> Drop for ExtraParts<'e> {
 
 #[inline]
 pub fn extra_dirs() -> Box<dyn ExactSizeIterator<Item = (String, PathBuf)>> {
-    match DirectoryConfig::new() {
-        Some(DirectoryConfig { extra, .. }) => {
-            Box::new(extra.into_iter().map(|(k, v)| (k, PathBuf::from(v))))
+    match DirectoryConfig::load() {
+        Some(config) => {
+            let root = config.root();
+            Box::new(
+                config
+                    .extra
+                    .into_iter()
+                    .map(move |(k, v)| (k, resolve(root.as_deref(), v))),
+            )
         }
         None => Box::new(std::iter::empty()),
     }
@@ -289,20 +632,114 @@ pub fn extra_dir(dir: Option<PathBuf>, extra: &str) -> ExtraParts<'_> {
     ExtraParts::new(dir, extra)
 }
 
+// all configured directories, for use as ROM sources
+pub fn collection_dirs() -> Vec<PathBuf> {
+    configured_dirs().into_iter().map(|(_, dir)| dir).collect()
+}
+
+// all configured directories, labeled by what they're for
+pub fn configured_dirs() -> Vec<(String, PathBuf)> {
+    fn labeled(
+        label: &'static str,
+        dirs: BTreeMap<String, String>,
+    ) -> impl Iterator<Item = (String, String)> {
+        dirs.into_iter()
+            .map(move |(name, dir)| (format!("{label} \"{name}\""), dir))
+    }
+
+    match DirectoryConfig::load() {
+        Some(config) => {
+            let root = config.root();
+            config
+                .mame
+                .map(|dir| ("mame".to_owned(), dir))
+                .into_iter()
+                .chain(config.mess.map(|dir| ("sl".to_owned(), dir)))
+                .chain(labeled("extra", config.extra))
+                .chain(labeled("redump", config.redump))
+                .chain(labeled("nointro", config.nointro))
+                .map(|(label, dir)| (label, resolve(root.as_deref(), dir)))
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+fn set_dir(
+    dirs: impl FnOnce(&mut DirectoryConfig) -> &mut BTreeMap<String, String>,
+    name: &str,
+    dir: &Path,
+) -> Result<(), Error> {
+    if !dir.is_dir() {
+        return Err(Error::NoSuchDirectory(dir.to_owned()));
+    }
+
+    DirectoryConfig::set(
+        |d, s| {
+            dirs(d).insert(name.to_owned(), s);
+            Set::Changed
+        },
+        dir.canonicalize()?,
+    )
+    .map(|_| ())
+}
+
+#[inline]
+pub fn set_extra_dir(extra: &str, dir: &Path) -> Result<(), Error> {
+    set_dir(|d| &mut d.extra, extra, dir)
+}
+
+pub fn unset_extra_dir(extra: &str) -> Result<(), Error> {
+    DirectoryConfig::update(|d| match d.extra.remove(extra) {
+        Some(_) => Ok(Set::Changed),
+        None => Err(Error::NoDirectorySet(extra.to_owned())),
+    })
+    .map(|_| ())
+}
+
+fn rename_dir(
+    dirs: impl FnOnce(&mut DirectoryConfig) -> &mut BTreeMap<String, String>,
+    old: &str,
+    new: &str,
+) -> Result<(), Error> {
+    DirectoryConfig::update(|d| {
+        let dirs = dirs(d);
+
+        if dirs.contains_key(new) {
+            return Err(Error::DirectoryAlreadySet(new.to_owned()));
+        }
+
+        match dirs.remove(old) {
+            Some(dir) => {
+                dirs.insert(new.to_owned(), dir);
+                Ok(Set::Changed)
+            }
+            None => Err(Error::NoDirectorySet(old.to_owned())),
+        }
+    })
+    .map(|_| ())
+}
+
+#[inline]
+pub fn rename_extra_dir(old: &str, new: &str) -> Result<(), Error> {
+    rename_dir(|d| &mut d.extra, old, new)
+}
+
 pub fn extra_dir_names() -> Option<Vec<String>> {
-    DirectoryConfig::new()
+    DirectoryConfig::load()
         .map(|DirectoryConfig { extra, .. }| extra.into_keys().collect::<Vec<_>>())
         .filter(|v| !v.is_empty())
 }
 
 pub fn select_extra_name() -> Result<String, Error> {
-    select_by_name("select extras category", extra_dir_names())
+    select_by_name(crate::EXTRA, "select extras category", extra_dir_names())
 }
 
 pub fn select_any_extra_name() -> Result<String, Error> {
     use crate::{read_db_names, DIR_EXTRA};
 
     select_by_name(
+        crate::EXTRA,
         "select extras category",
         read_db_names(DIR_EXTRA).map(|i| {
             let mut v = i.collect::<Vec<_>>();
@@ -365,28 +802,80 @@ pub fn nointro_roms(roms: Option<PathBuf>, name: &str) -> NointroRoms<'_> {
 }
 
 pub fn nointro_dirs() -> Box<dyn ExactSizeIterator<Item = (String, PathBuf)>> {
-    match DirectoryConfig::new() {
-        Some(DirectoryConfig { nointro, .. }) => {
-            Box::new(nointro.into_iter().map(|(k, v)| (k, PathBuf::from(v))))
+    match DirectoryConfig::load() {
+        Some(config) => {
+            let root = config.root();
+            Box::new(
+                config
+                    .nointro
+                    .into_iter()
+                    .map(move |(k, v)| (k, resolve(root.as_deref(), v))),
+            )
         }
         None => Box::new(std::iter::empty()),
     }
 }
 
+#[inline]
+pub fn set_nointro_dir(name: &str, dir: &Path) -> Result<(), Error> {
+    set_dir(|d| &mut d.nointro, name, dir)
+}
+
+#[inline]
+pub fn rename_nointro_dir(old: &str, new: &str) -> Result<(), Error> {
+    rename_dir(|d| &mut d.nointro, old, new)?;
+
+    DirectoryConfig::update(|d| match d.nointro_letters.remove(old) {
+        true => {
+            d.nointro_letters.insert(new.to_owned());
+            Ok(Set::Changed)
+        }
+        false => Ok(Set::Unchanged),
+    })
+    .map(|_| ())
+}
+
+// how the DAT's directory was last organized
+pub fn nointro_layout(name: &str) -> crate::dat::Layout {
+    use crate::dat::Layout;
+
+    match DirectoryConfig::load().is_some_and(|d| d.nointro_letters.contains(name)) {
+        true => Layout::Letters,
+        false => Layout::Files,
+    }
+}
+
+pub fn set_nointro_layout(name: &str, layout: crate::dat::Layout) -> Result<(), Error> {
+    use crate::dat::Layout;
+
+    DirectoryConfig::update(|d| {
+        let changed = match layout {
+            Layout::Letters => d.nointro_letters.insert(name.to_owned()),
+            Layout::Files | Layout::Archives => d.nointro_letters.remove(name),
+        };
+        Ok(match changed {
+            true => Set::Changed,
+            false => Set::Unchanged,
+        })
+    })
+    .map(|_| ())
+}
+
 pub fn nointro_dir_names() -> Option<Vec<String>> {
-    DirectoryConfig::new()
+    DirectoryConfig::load()
         .map(|DirectoryConfig { nointro, .. }| nointro.into_keys().collect::<Vec<_>>())
         .filter(|v| !v.is_empty())
 }
 
 pub fn select_nointro_name() -> Result<String, Error> {
-    select_by_name("select DAT", nointro_dir_names())
+    select_by_name(crate::NOINTRO, "select DAT", nointro_dir_names())
 }
 
 pub fn select_any_nointro_name() -> Result<String, Error> {
     use crate::{read_db_names, DIR_NOINTRO};
 
     select_by_name(
+        crate::NOINTRO,
         "select DAT",
         read_db_names(DIR_NOINTRO).map(|i| {
             let mut v = i.collect::<Vec<_>>();
@@ -447,28 +936,45 @@ pub fn redump_roms(roms: Option<PathBuf>, name: &str) -> RedumpRoms<'_> {
 }
 
 pub fn redump_dirs() -> Box<dyn ExactSizeIterator<Item = (String, PathBuf)>> {
-    match DirectoryConfig::new() {
-        Some(DirectoryConfig { redump, .. }) => {
-            Box::new(redump.into_iter().map(|(k, v)| (k, PathBuf::from(v))))
+    match DirectoryConfig::load() {
+        Some(config) => {
+            let root = config.root();
+            Box::new(
+                config
+                    .redump
+                    .into_iter()
+                    .map(move |(k, v)| (k, resolve(root.as_deref(), v))),
+            )
         }
         None => Box::new(std::iter::empty()),
     }
 }
 
+#[inline]
+pub fn set_redump_dir(name: &str, dir: &Path) -> Result<(), Error> {
+    set_dir(|d| &mut d.redump, name, dir)
+}
+
+#[inline]
+pub fn rename_redump_dir(old: &str, new: &str) -> Result<(), Error> {
+    rename_dir(|d| &mut d.redump, old, new)
+}
+
 pub fn redump_dir_names() -> Option<Vec<String>> {
-    DirectoryConfig::new()
+    DirectoryConfig::load()
         .map(|DirectoryConfig { redump, .. }| redump.into_keys().collect::<Vec<_>>())
         .filter(|v| !v.is_empty())
 }
 
 pub fn select_redump_name() -> Result<String, Error> {
-    select_by_name("select DAT", redump_dir_names())
+    select_by_name(crate::REDUMP, "select DAT", redump_dir_names())
 }
 
 pub fn select_any_redump_name() -> Result<String, Error> {
     use crate::{read_db_names, DIR_REDUMP};
 
     select_by_name(
+        crate::REDUMP,
         "select DAT",
         read_db_names(DIR_REDUMP).map(|i| {
             let mut v = i.collect::<Vec<_>>();
@@ -478,11 +984,12 @@ pub fn select_any_redump_name() -> Result<String, Error> {
     )
 }
 
-fn select_by_name(prompt: &'static str, names: Option<Vec<String>>) -> Result<String, Error> {
-    names.ok_or(Error::NoDatFiles).and_then(|names| {
-        inquire::Select::new(prompt, names)
-            .with_page_size(terminal_height())
-            .prompt()
-            .map_err(Error::Inquire)
-    })
+fn select_by_name(
+    key: &'static str,
+    prompt: &'static str,
+    names: Option<Vec<String>>,
+) -> Result<String, Error> {
+    names
+        .ok_or(Error::NoDatFiles)
+        .and_then(|names| crate::selection::select(key, prompt, names, |name| name))
 }
@@ -0,0 +1,239 @@
+use crate::game::Part;
+use crate::{dat, game, split};
+use comfy_table::{Cell, Color};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+// database files from before each DAT got its own file,
+// which should have been promoted automatically
+const LEGACY_DBS: [&str; 4] = ["mess.cbor", "extra.cbor", "redump.cbor", "nointro.cbor"];
+
+const PROBE_FILE: &str = ".emuman-doctor";
+
+#[derive(Default)]
+pub struct Report {
+    rows: Vec<(&'static str, String, Result<String, String>)>,
+}
+
+impl Report {
+    fn ok(&mut self, check: &'static str, item: impl Into<String>, status: impl Into<String>) {
+        self.rows.push((check, item.into(), Ok(status.into())));
+    }
+
+    fn problem(
+        &mut self,
+        check: &'static str,
+        item: impl Into<String>,
+        problem: impl Into<String>,
+    ) {
+        self.rows.push((check, item.into(), Err(problem.into())));
+    }
+
+    pub fn problems(&self) -> usize {
+        self.rows.iter().filter(|(_, _, r)| r.is_err()).count()
+    }
+
+    pub fn display(&self) {
+        let mut table = crate::table::new();
+        table.set_header(vec!["Check", "Item", "Status"]);
+
+        for (check, item, status) in &self.rows {
+            table.add_row(vec![
+                Cell::new(check),
+                Cell::new(item),
+                match status {
+                    Ok(status) => Cell::new(status).fg(Color::Green),
+                    Err(problem) => Cell::new(problem).fg(Color::Red),
+                },
+            ]);
+        }
+
+        println!("{table}");
+    }
+}
+
+// ensures every database on disk can be read by this version
+pub fn check_databases(report: &mut Report) {
+    fn check_db<D: DeserializeOwned>(
+        report: &mut Report,
+        name: &'static str,
+        db_file: &'static str,
+    ) {
+        match crate::read_game_db::<D>(name, db_file) {
+            Ok(_) => report.ok("database", db_file, "ok"),
+            Err(crate::Error::MissingCache(_)) => report.ok("database", db_file, "not initialized"),
+            Err(crate::Error::InvalidCache(_, _, damage)) => {
+                report.problem("database", db_file, format!("{damage}, re-run init"))
+            }
+            Err(_) => report.problem("database", db_file, "unreadable, re-run init"),
+        }
+    }
+
+    fn check_named_dbs<D: DeserializeOwned>(report: &mut Report, db_dir: &'static str) {
+        let Ok(dir) = std::fs::read_dir(crate::named_db_dir(db_dir)) else {
+            return;
+        };
+
+        let mut readable = 0;
+
+        for path in dir.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let item = format!("{}/{}", db_dir, path.file_name().unwrap().to_string_lossy());
+
+            match crate::path_db_name(&path) {
+                None => report.problem("database", item, "orphaned, name is not valid base64"),
+                Some(name) => match crate::dbfile::read::<D>("", &path) {
+                    Ok(_) => readable += 1,
+                    Err(crate::Error::InvalidCache(_, _, damage)) => report.problem(
+                        "database",
+                        format!("{db_dir}/{name}"),
+                        format!("{damage}, re-import DAT"),
+                    ),
+                    Err(_) => report.problem(
+                        "database",
+                        format!("{db_dir}/{name}"),
+                        "unreadable, re-import DAT",
+                    ),
+                },
+            }
+        }
+
+        report.ok(
+            "database",
+            format!("{db_dir}/*"),
+            format!("{readable} readable"),
+        );
+    }
+
+    check_db::<game::GameDb>(report, crate::MAME, crate::DB_MAME);
+    check_db::<crate::MameMetadata>(report, crate::MAME, crate::DB_MAME_META);
+    check_db::<split::SplitDb>(report, crate::MESS, crate::DB_MESS_SPLIT);
+    check_db::<split::SplitDb>(report, crate::REDUMP, crate::DB_REDUMP_SPLIT);
+    check_named_dbs::<game::GameDb>(report, crate::DIR_SL);
+    check_named_dbs::<dat::DatFile>(report, crate::DIR_EXTRA);
+    check_named_dbs::<dat::DatFile>(report, crate::DIR_REDUMP);
+    check_named_dbs::<dat::DatFile>(report, crate::DIR_NOINTRO);
+
+    for legacy in LEGACY_DBS {
+        if crate::named_db_dir(legacy).is_file() {
+            report.problem("database", legacy, "old format, unable to convert");
+        }
+    }
+}
+
+// ensures the MAME database was built from the MAME that's installed,
+// since an upgraded MAME expects ROMs the old database doesn't know about
+pub fn check_versions(report: &mut Report) {
+    let Ok(output) = std::process::Command::new("mame").arg("-version").output() else {
+        return;
+    };
+
+    let installed = String::from_utf8_lossy(&output.stdout);
+    let Some(installed) = installed.split_whitespace().next() else {
+        return;
+    };
+
+    let Ok(db) = crate::read_mame_metadata() else {
+        return;
+    };
+
+    match db.description().split_whitespace().next() {
+        Some(build) if build == installed => {
+            report.ok("version", crate::MAME, format!("{build}, current"))
+        }
+        Some(build) => report.problem(
+            "version",
+            crate::MAME,
+            format!("built from {build} but {installed} is installed, re-run init"),
+        ),
+        None => report.problem(
+            "version",
+            crate::MAME,
+            format!("unknown build but {installed} is installed, re-run init"),
+        ),
+    }
+}
+
+// ensures configured directories exist, are writable
+// and support the extended attributes used for caching hashes
+pub fn check_directories(report: &mut Report) {
+    let dirs = crate::dirs::configured_dirs();
+
+    if dirs.is_empty() {
+        report.ok("directory", "dirs.toml", "no directories set");
+    }
+
+    for (label, dir) in dirs {
+        let item = format!("{label} : {}", dir.display());
+
+        match probe_dir(&dir) {
+            Ok(true) => report.ok("directory", item, "ok"),
+            Ok(false) if cfg!(target_os = "windows") => report.ok("directory", item, "ok"),
+            Ok(false) => report.problem("directory", item, "no xattr support, hashes not cached"),
+            Err(problem) => report.problem("directory", item, problem),
+        }
+    }
+}
+
+// returns whether the directory supports xattrs
+fn probe_dir(dir: &Path) -> Result<bool, String> {
+    use std::io::ErrorKind;
+
+    if !dir.is_dir() {
+        return Err(if dir.exists() {
+            "not a directory".to_owned()
+        } else {
+            "missing".to_owned()
+        });
+    }
+
+    let probe = dir.join(PROBE_FILE);
+
+    std::fs::File::create(&probe).map_err(|err| match err.kind() {
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => "not writable".to_owned(),
+        _ => err.to_string(),
+    })?;
+
+    Part::new_empty().set_xattr(&probe);
+    let has_xattr = Part::has_xattr(&probe).unwrap_or(false);
+    let _ = std::fs::remove_file(&probe);
+
+    Ok(has_xattr)
+}
+
+// ensures DATs imported from URLs can still be fetched
+pub fn check_urls(report: &mut Report) {
+    use rayon::prelude::*;
+
+    let mut urls = [crate::DIR_EXTRA, crate::DIR_REDUMP, crate::DIR_NOINTRO]
+        .into_iter()
+        .flat_map(|db_dir| {
+            crate::read_named_dbs::<dat::DatFile>(db_dir)
+                .into_iter()
+                .flatten()
+        })
+        .filter_map(|(_, datfile)| {
+            datfile
+                .imported_from()
+                .filter(|source| source.starts_with("http://") || source.starts_with("https://"))
+                .map(|source| source.to_owned())
+        })
+        .collect::<Vec<_>>();
+
+    urls.sort_unstable();
+    urls.dedup();
+
+    let results = urls
+        .into_par_iter()
+        .map(|url| {
+            let result = crate::http::check_url(&url);
+            (url, result)
+        })
+        .collect::<Vec<_>>();
+
+    for (url, result) in results {
+        match result {
+            Ok(()) => report.ok("network", url, "reachable"),
+            Err(err) => report.problem("network", url, err.to_string()),
+        }
+    }
+}
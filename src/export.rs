@@ -0,0 +1,717 @@
+use crate::Error;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// FAT filesystems only store modification times to within 2 seconds
+const MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+// the file in each managed directory listing what we've exported there,
+// one path relative to that directory per line
+const MANIFEST: &str = ".emuman-exported";
+
+// MiSTer's directories under games/ for each DAT,
+// by DAT name without any suffix such as "(Headered)"
+const MISTER_CORES: &[(&str, &str)] = &[
+    ("Atari - 2600", "ATARI2600"),
+    ("Atari - 5200", "ATARI5200"),
+    ("Atari - 7800", "ATARI7800"),
+    ("Atari - Lynx", "AtariLynx"),
+    ("Bandai - WonderSwan", "WonderSwan"),
+    ("Bandai - WonderSwan Color", "WonderSwan"),
+    ("Coleco - ColecoVision", "Coleco"),
+    ("Fairchild - Channel F", "ChannelF"),
+    ("GCE - Vectrex", "VECTREX"),
+    ("Magnavox - Odyssey2", "ODYSSEY2"),
+    ("Mattel - Intellivision", "Intellivision"),
+    ("NEC - PC Engine - TurboGrafx-16", "TGFX16"),
+    ("NEC - PC Engine SuperGrafx", "TGFX16"),
+    ("NEC - PC Engine CD & TurboGrafx CD", "TGFX16-CD"),
+    ("Nintendo - Game Boy", "GAMEBOY"),
+    ("Nintendo - Game Boy Color", "GAMEBOY"),
+    ("Nintendo - Game Boy Advance", "GBA"),
+    ("Nintendo - Nintendo Entertainment System", "NES"),
+    ("Nintendo - Family Computer Disk System", "NES"),
+    ("Nintendo - Super Nintendo Entertainment System", "SNES"),
+    ("Sega - 32X", "S32X"),
+    ("Sega - Game Gear", "SMS"),
+    ("Sega - Master System - Mark III", "SMS"),
+    ("Sega - Mega Drive - Genesis", "Genesis"),
+    ("Sega - Mega CD & Sega CD", "MegaCD"),
+    ("Sega - Saturn", "Saturn"),
+    ("Sega - SG-1000", "SG1000"),
+    ("SNK - Neo Geo CD", "NeoGeo-CD"),
+    ("SNK - Neo Geo Pocket", "NGP"),
+    ("SNK - Neo Geo Pocket Color", "NGP"),
+    ("Sony - PlayStation", "PSX"),
+    ("Watara - Supervision", "SuperVision"),
+];
+
+pub fn mister_core(dat_name: &str) -> Option<&'static str> {
+    let name = match dat_name.find(" (") {
+        Some(index) => &dat_name[..index],
+        None => dat_name,
+    };
+
+    MISTER_CORES
+        .iter()
+        .find(|(dat, _)| *dat == name)
+        .map(|(_, core)| *core)
+}
+
+// a file from the collection and where it belongs on the target
+pub struct Export {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+#[derive(Default)]
+pub struct Synced {
+    pub exported: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+impl std::fmt::Display for Synced {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files exported, {} unchanged, {} removed",
+            self.exported, self.unchanged, self.removed
+        )
+    }
+}
+
+// brings the target up to date with the exports, linking files
+// when on the same filesystem and copying them otherwise,
+// and if delete is set, removes anything exported to the
+// managed directories before which is no longer exported,
+// leaving files put there by anything else alone
+pub fn sync(exports: Vec<Export>, managed: &[PathBuf], delete: bool) -> Result<Synced, Error> {
+    use crate::progress::Log;
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let pbar = crate::progress::bar(exports.len() as u64)
+        .with_style(crate::game::verify_style())
+        .with_message("exporting");
+
+    let exported = AtomicUsize::new(0);
+
+    exports
+        .par_iter()
+        .progress_with(pbar.clone())
+        .try_for_each(|Export { source, target }| {
+            if is_current(source, target) {
+                return Ok(());
+            }
+
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if target.exists() {
+                crate::protect::unprotect(target);
+                std::fs::remove_file(target)?;
+            }
+            copy(source, target)?;
+
+            pbar.log(format!(
+                "{} {} {}",
+                source.display(),
+                crate::glyphs::copied(),
+                target.display()
+            ));
+            exported.fetch_add(1, Ordering::Relaxed);
+            Ok::<(), Error>(())
+        })?;
+
+    pbar.finish_and_clear();
+
+    let exported = exported.into_inner();
+    let mut synced = Synced {
+        exported,
+        unchanged: exports.len() - exported,
+        removed: 0,
+    };
+
+    let targets = exports
+        .iter()
+        .map(|e| e.target.as_path())
+        .collect::<HashSet<_>>();
+
+    for root in managed {
+        let mut listed = read_manifest(root)?
+            .into_iter()
+            .filter(|path| !targets.contains(path.as_path()))
+            .collect::<Vec<_>>();
+
+        if delete {
+            for path in listed.drain(..) {
+                if path.symlink_metadata().is_err() {
+                    continue;
+                }
+                crate::protect::unprotect(&path);
+                std::fs::remove_file(&path)?;
+                eprintln!("removed : {}", path.display());
+                synced.removed += 1;
+
+                // directories emptied by removals, such as old
+                // alphabetical subdirectories, are removed too
+                for dir in path.ancestors().skip(1) {
+                    if dir == root.as_path() || std::fs::remove_dir(dir).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // anything exported before and left in place is still ours
+        listed.retain(|path| path.symlink_metadata().is_ok());
+        listed.extend(
+            targets
+                .iter()
+                .filter(|target| target.starts_with(root))
+                .map(|target| target.to_path_buf()),
+        );
+        write_manifest(root, listed)?;
+    }
+
+    Ok(synced)
+}
+
+// the files a previous sync exported to the managed directory
+fn read_manifest(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    match std::fs::read_to_string(root.join(MANIFEST)) {
+        Ok(manifest) => Ok(manifest
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| root.join(line))
+            .collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_manifest(root: &Path, mut exported: Vec<PathBuf>) -> Result<(), Error> {
+    use std::io::Write;
+
+    if !root.is_dir() {
+        return Ok(());
+    }
+
+    exported.sort_unstable();
+    exported.dedup();
+
+    let mut w = std::io::BufWriter::new(std::fs::File::create(root.join(MANIFEST))?);
+    for path in exported {
+        if let Ok(relative) = path.strip_prefix(root) {
+            writeln!(w, "{}", relative.display())?;
+        }
+    }
+    w.flush()?;
+
+    Ok(())
+}
+
+// whether the target is already the same size and age as its source
+fn is_current(source: &Path, target: &Path) -> bool {
+    let (Ok(source), Ok(target)) = (source.metadata(), target.metadata()) else {
+        return false;
+    };
+
+    source.len() == target.len()
+        && match (source.modified(), target.modified()) {
+            (Ok(s), Ok(t)) => {
+                s.max(t).duration_since(s.min(t)).unwrap_or_default() <= MTIME_TOLERANCE
+            }
+            _ => false,
+        }
+}
+
+fn copy(source: &Path, target: &Path) -> Result<(), Error> {
+    if std::fs::hard_link(source, target).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(source, target)?;
+    crate::protect::unprotect(target);
+
+    // keep the source's time so the next sync can skip it
+    if let Ok(modified) = source.metadata().and_then(|m| m.modified()) {
+        std::fs::File::options()
+            .write(true)
+            .open(target)
+            .and_then(|f| f.set_modified(modified))?;
+    }
+
+    Ok(())
+}
+
+// characters FAT32 rejects in file names
+const FAT_RESERVED: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+// a flashcart's limits on its SD card's layout
+pub struct Flashcart {
+    // the longest file name, in characters, including its extension
+    pub max_length: usize,
+    // the most files in any one directory
+    pub max_files: usize,
+}
+
+impl Flashcart {
+    // lays out files by name alone, in alphabetical subdirectories
+    // if there are too many of them for one directory
+    pub fn layout(&self, files: Vec<(PathBuf, PathBuf)>, target: &Path) -> Vec<Export> {
+        use std::collections::BTreeMap;
+
+        let mut names = HashSet::new();
+        let mut files = files
+            .into_iter()
+            .map(|(relative, source)| {
+                let name = self.unique_name(&relative, &mut names);
+                (name, source)
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|(name, _)| name.to_lowercase());
+
+        if files.len() <= self.max_files {
+            return files
+                .into_iter()
+                .map(|(name, source)| Export {
+                    source,
+                    target: target.join(name),
+                })
+                .collect();
+        }
+
+        let mut groups: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+        for (name, source) in files {
+            groups
+                .entry(match name.chars().next() {
+                    Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase().to_string(),
+                    _ => "#".to_owned(),
+                })
+                .or_default()
+                .push((name, source));
+        }
+
+        // groups which are still too large are numbered
+        groups
+            .into_iter()
+            .flat_map(|(group, files)| {
+                let chunks = files.len().div_ceil(self.max_files.max(1));
+                let mut files = files.into_iter();
+
+                (1..=chunks)
+                    .map(move |chunk| {
+                        let dir = match chunks {
+                            1 => target.join(&group),
+                            _ => target.join(format!("{group} ({chunk})")),
+                        };
+                        files
+                            .by_ref()
+                            .take(self.max_files.max(1))
+                            .map(|(name, source)| Export {
+                                source,
+                                target: dir.join(name),
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .flatten()
+            .collect()
+    }
+
+    // a FAT32-safe name within the length limit, keeping its extension
+    // and made unique among the names given so far
+    fn unique_name(&self, relative: &Path, names: &mut HashSet<String>) -> String {
+        let name = relative
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = name
+            .chars()
+            .filter(|c| !c.is_control() && !FAT_RESERVED.contains(c))
+            .collect::<String>();
+
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+            _ => (name.as_str(), String::new()),
+        };
+
+        let mut suffix = String::new();
+        let mut duplicates = 0;
+        loop {
+            let length = self
+                .max_length
+                .saturating_sub(extension.chars().count() + suffix.chars().count());
+            let stem = stem.chars().take(length).collect::<String>();
+            let candidate = format!("{}{suffix}{extension}", stem.trim_end_matches(['.', ' ']));
+
+            if names.insert(candidate.to_lowercase()) {
+                break candidate;
+            }
+
+            duplicates += 1;
+            suffix = format!("~{duplicates}");
+        }
+    }
+}
+
+// a verified game as a frontend's library sees it
+pub struct Entry {
+    pub title: String,
+    pub developer: Option<String>,
+    pub year: Option<String>,
+    // the file or directory a frontend launches, relative to the collection
+    pub launch: PathBuf,
+    // the game's files, as pairs of their paths
+    // relative to the collection and their actual paths
+    pub files: Vec<(PathBuf, PathBuf)>,
+}
+
+// files which describe a whole disc and should be launched
+// in preference to any of its tracks
+const DISC_DESCRIPTORS: &[&str] = &["m3u", "cue", "gdi", "ccd"];
+
+impl Entry {
+    // a game from a DAT, which has no metadata beyond its name
+    pub fn from_dat(name: String, files: Vec<(PathBuf, PathBuf)>) -> Self {
+        match files.as_slice() {
+            [(relative, _)] if relative == Path::new(&name) => Self {
+                title: relative
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or(name),
+                developer: None,
+                year: None,
+                launch: relative.clone(),
+                files,
+            },
+            _ => Self {
+                launch: DISC_DESCRIPTORS
+                    .iter()
+                    .find_map(|descriptor| {
+                        files.iter().find_map(|(relative, _)| {
+                            relative
+                                .extension()
+                                .is_some_and(|e| e.eq_ignore_ascii_case(descriptor))
+                                .then(|| relative.clone())
+                        })
+                    })
+                    .unwrap_or_else(|| PathBuf::from(&name)),
+                title: name,
+                developer: None,
+                year: None,
+                files,
+            },
+        }
+    }
+
+    // a game from a GameDb, launched by its directory's name
+    pub fn from_game(game: &crate::game::Game, files: Vec<(PathBuf, PathBuf)>) -> Self {
+        Self {
+            title: game.description.clone(),
+            developer: (!game.creator.is_empty()).then(|| game.creator.to_string()),
+            year: (game.year.len() == 4 && game.year.chars().all(|c| c.is_ascii_digit()))
+                .then(|| game.year.to_string()),
+            launch: PathBuf::from(&game.name),
+            files,
+        }
+    }
+}
+
+// the fields of a game element as (tag, escaped text) pairs,
+// with the element identified by the unescaped text of its first field
+type GameFields = Vec<(&'static str, String)>;
+
+// writes a LaunchBox platform XML whose games
+// are launched from the collection in place,
+// merged into any existing XML for the platform
+pub fn launchbox(xml: &Path, platform: &str, root: &Path, entries: &[Entry]) -> Result<(), Error> {
+    use quick_xml::escape::escape;
+
+    write_games(
+        xml,
+        r#"<?xml version="1.0" standalone="yes"?>"#,
+        "LaunchBox",
+        "Game",
+        entries
+            .iter()
+            .map(|entry| {
+                let mut fields = vec![
+                    ("ID", launchbox_id(platform, &entry.title)),
+                    ("Title", escape(&entry.title).into_owned()),
+                    ("Platform", escape(platform).into_owned()),
+                    (
+                        "ApplicationPath",
+                        escape(&root.join(&entry.launch).to_string_lossy()).into_owned(),
+                    ),
+                ];
+                if let Some(developer) = &entry.developer {
+                    fields.push(("Developer", escape(developer).into_owned()));
+                }
+                if let Some(year) = &entry.year {
+                    fields.push(("ReleaseDate", format!("{year}-01-01T00:00:00")));
+                }
+                fields
+            })
+            .collect(),
+    )
+}
+
+// LaunchBox identifies games by GUID, so derive one from the
+// platform and title so that regenerated XMLs keep the same IDs
+fn launchbox_id(platform: &str, title: &str) -> String {
+    let mut sha1 = sha1_smol::Sha1::new();
+    sha1.update(platform.as_bytes());
+    sha1.update(b"\0");
+    sha1.update(title.as_bytes());
+    let hex = sha1.digest().to_string();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+// writes an EmulationStation gamelist.xml, as used by Batocera,
+// for games whose files are laid out beside it,
+// merged into any existing gamelist
+pub fn gamelist(xml: &Path, entries: &[Entry]) -> Result<(), Error> {
+    use quick_xml::escape::escape;
+
+    write_games(
+        xml,
+        r#"<?xml version="1.0"?>"#,
+        "gameList",
+        "game",
+        entries
+            .iter()
+            .map(|entry| {
+                let mut fields = vec![
+                    (
+                        "path",
+                        format!("./{}", escape(&entry.launch.to_string_lossy())),
+                    ),
+                    ("name", escape(&entry.title).into_owned()),
+                ];
+                if let Some(developer) = &entry.developer {
+                    fields.push(("developer", escape(developer).into_owned()));
+                }
+                if let Some(year) = &entry.year {
+                    fields.push(("releasedate", format!("{year}0101T000000")));
+                }
+                fields
+            })
+            .collect(),
+    )
+}
+
+// writes the games to a frontend's XML file, where any game
+// already in the file with the same identifying field has
+// only the fields we write replaced, keeping the rest
+// (such as scraped media or play counts) along with
+// every other game and element the file already has
+fn write_games(
+    xml: &Path,
+    declaration: &str,
+    root_tag: &str,
+    game_tag: &str,
+    games: Vec<GameFields>,
+) -> Result<(), Error> {
+    use std::io::Write;
+
+    let existing = match std::fs::read_to_string(xml) {
+        Ok(existing) if !existing.trim().is_empty() => Some(existing),
+        Ok(_) => None,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let merged = match existing {
+        Some(existing) => merge_games(&existing, root_tag, game_tag, games).map_err(|error| {
+            Error::XmlFile(crate::ResourceError {
+                error: quick_xml::de::DeError::InvalidXml(error),
+                file: crate::Resource::File(xml.to_owned()),
+            })
+        })?,
+        None => {
+            let mut fresh = format!("{declaration}\n<{root_tag}>\n");
+            for fields in games {
+                fresh.push_str(&format_game(game_tag, fields));
+            }
+            fresh.push_str(&format!("</{root_tag}>\n"));
+            fresh
+        }
+    };
+
+    let mut w = std::io::BufWriter::new(std::fs::File::create(xml)?);
+    w.write_all(merged.as_bytes())?;
+    w.flush()?;
+
+    Ok(())
+}
+
+// copies the existing document, merging our fields into each game
+// whose identifying child matches the first field of one of ours,
+// and adding the rest of ours just before the root element's end
+fn merge_games(
+    existing: &str,
+    root_tag: &str,
+    game_tag: &str,
+    games: Vec<GameFields>,
+) -> Result<String, quick_xml::Error> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+    use std::collections::HashMap;
+
+    let key_tag = games.first().map_or("", |fields| fields[0].0);
+    let index = games
+        .iter()
+        .enumerate()
+        .map(|(index, fields)| (unescaped(&fields[0].1), index))
+        .collect::<HashMap<_, _>>();
+    let mut games = games.into_iter().map(Some).collect::<Vec<_>>();
+
+    let mut reader = Reader::from_str(existing);
+    let mut merged = String::with_capacity(existing.len());
+    let mut copied = 0;
+    let mut depth = 0;
+
+    loop {
+        let start = reader.buffer_position();
+        match reader.read_event()? {
+            Event::Start(e) if depth == 1 && e.name().as_ref() == game_tag.as_bytes() => {
+                let open = &existing[start..reader.buffer_position()];
+                let mut children = Vec::new();
+                loop {
+                    let child_start = reader.buffer_position();
+                    match reader.read_event()? {
+                        Event::Start(child) => {
+                            let tag = String::from_utf8_lossy(child.name().as_ref()).into_owned();
+                            let inner = reader.read_to_end(child.name())?;
+                            children.push((
+                                tag,
+                                &existing[child_start..reader.buffer_position()],
+                                &existing[inner.start..inner.end],
+                            ));
+                        }
+                        Event::Empty(child) => children.push((
+                            String::from_utf8_lossy(child.name().as_ref()).into_owned(),
+                            &existing[child_start..reader.buffer_position()],
+                            "",
+                        )),
+                        Event::End(_) => break,
+                        Event::Eof => {
+                            return Err(quick_xml::Error::UnexpectedEof(game_tag.to_owned()))
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(fields) = children
+                    .iter()
+                    .find(|(tag, _, _)| tag == key_tag)
+                    .and_then(|(_, _, text)| index.get(&unescaped(text)))
+                    .and_then(|index| games[*index].take())
+                {
+                    merged.push_str(&existing[copied..start]);
+                    merged.push_str(open);
+                    merged.push('\n');
+                    merged.push_str(&merge_fields(
+                        children.into_iter().map(|(tag, raw, _)| (tag, raw)),
+                        fields,
+                    ));
+                    merged.push_str(&format!("  </{game_tag}>"));
+                    copied = reader.buffer_position();
+                }
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    merged.push_str(&existing[copied..start]);
+                    if !merged.ends_with('\n') {
+                        merged.push('\n');
+                    }
+                    for fields in games.iter_mut().filter_map(Option::take) {
+                        merged.push_str(&format_game(game_tag, fields));
+                    }
+                    copied = start;
+                }
+            }
+            Event::Empty(e) if depth == 0 && e.name().as_ref() == root_tag.as_bytes() => {
+                merged.push_str(&existing[copied..start]);
+                merged.push_str(&format!("<{root_tag}>\n"));
+                for fields in games.iter_mut().filter_map(Option::take) {
+                    merged.push_str(&format_game(game_tag, fields));
+                }
+                merged.push_str(&format!("</{root_tag}>"));
+                copied = reader.buffer_position();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    merged.push_str(&existing[copied..]);
+
+    Ok(merged)
+}
+
+// an existing game's children, with those we write
+// replaced in place and any others we write added after them
+fn merge_fields<'e>(
+    children: impl ExactSizeIterator<Item = (String, &'e str)>,
+    fields: GameFields,
+) -> String {
+    let mut fields = fields.into_iter().map(Some).collect::<Vec<_>>();
+    let mut lines = Vec::with_capacity(children.len() + fields.len());
+
+    for (tag, raw) in children {
+        match fields
+            .iter_mut()
+            .find(|field| field.as_ref().is_some_and(|(t, _)| *t == tag))
+        {
+            Some(field) => {
+                let (tag, text) = field.take().unwrap();
+                lines.push(format!("<{tag}>{text}</{tag}>"));
+            }
+            None => lines.push(raw.to_owned()),
+        }
+    }
+    lines.extend(
+        fields
+            .into_iter()
+            .flatten()
+            .map(|(tag, text)| format!("<{tag}>{text}</{tag}>")),
+    );
+
+    let mut game = String::new();
+    for line in lines {
+        game.push_str(&format!("    {line}\n"));
+    }
+    game
+}
+
+fn format_game(game_tag: &str, fields: GameFields) -> String {
+    let mut game = format!("  <{game_tag}>\n");
+    for (tag, text) in fields {
+        game.push_str(&format!("    <{tag}>{text}</{tag}>\n"));
+    }
+    game.push_str(&format!("  </{game_tag}>\n"));
+    game
+}
+
+fn unescaped(text: &str) -> String {
+    quick_xml::escape::unescape(text)
+        .map(|text| text.into_owned())
+        .unwrap_or_else(|_| text.to_owned())
+}
@@ -1,18 +1,22 @@
-use super::{is_zip, Error};
+use super::{is_7z, is_zip, Error};
 use comfy_table::Table;
 use core::num::ParseIntError;
 use dashmap::mapref::entry::OccupiedEntry;
 use dashmap::DashMap;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressStyle};
 use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "fast-sha1")]
+use sha1::{Digest as _, Sha1};
+#[cfg(not(feature = "fast-sha1"))]
 use sha1_smol::Sha1;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::io::{Read, Seek};
 use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 const CACHE_XATTR: &str = "user.emupart";
@@ -56,6 +60,64 @@ impl GameDb {
         self.games.values()
     }
 
+    // every part of every game as (game, part name, part) triples
+    pub fn all_parts(&self) -> impl Iterator<Item = (&str, &str, &Part)> {
+        self.games_iter().flat_map(|game| {
+            game.parts
+                .iter()
+                .map(move |(name, part)| (game.name.as_str(), name.as_str(), part))
+        })
+    }
+
+    // what changed from this database to a newer one
+    pub fn changes(&self, new: &GameDb) -> Changes {
+        fn games(db: &GameDb) -> GameContents<'_> {
+            db.games_iter()
+                .map(|game| {
+                    (
+                        game.name.as_str(),
+                        game.parts.iter().map(|(n, p)| (n.as_str(), p)).collect(),
+                    )
+                })
+                .collect()
+        }
+
+        Changes::new(games(self), games(new))
+    }
+
+    // a copy of the database with every game's ROMs left out,
+    // for listings which don't need them and shouldn't pay to load them,
+    // though its few disks are kept so reports can see which are present
+    pub fn metadata(&self) -> Self {
+        Self {
+            description: self.description.clone(),
+            games: self
+                .games
+                .iter()
+                .map(|(name, game)| {
+                    (
+                        name.clone(),
+                        Game {
+                            name: game.name.clone(),
+                            description: game.description.clone(),
+                            creator: game.creator.clone(),
+                            year: game.year.clone(),
+                            status: game.status,
+                            is_device: game.is_device,
+                            parts: game
+                                .parts
+                                .iter()
+                                .filter(|(_, part)| part.is_disk())
+                                .map(|(name, part)| (name.clone(), part.clone()))
+                                .collect(),
+                            devices: game.devices.clone(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
     #[inline]
     pub fn into_games(self) -> impl ExactSizeIterator<Item = Game> {
         self.games.into_values()
@@ -67,76 +129,137 @@ impl GameDb {
     }
 
     #[inline]
-    fn valid_game(&self, game: &str) -> Result<&Game, Error> {
+    pub fn valid_game(&self, game: &str) -> Result<&Game, Error> {
         self.games
             .get(game)
             .ok_or_else(|| Error::NoSuchSoftware(game.to_string()))
     }
 
-    #[inline]
+    // games by name, where names may also be glob patterns such as "mslug*",
+    // and it's an error for a name or pattern to match nothing
     pub fn valid_games<'g, I, C>(&'g self, games: I) -> Result<C, Error>
     where
         I: IntoIterator,
         I::Item: AsRef<str>,
         C: FromIterator<&'g Game>,
     {
-        games
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+
+        for name in games {
+            let name = name.as_ref();
+
+            match name
+                .contains(['*', '?', '['])
+                .then(|| glob::Pattern::new(name).ok())
+                .flatten()
+            {
+                Some(pattern) => {
+                    let mut matched = self
+                        .games
+                        .values()
+                        .filter(|game| pattern.matches(&game.name))
+                        .collect::<Vec<_>>();
+                    if matched.is_empty() {
+                        return Err(Error::NoSuchSoftware(name.to_string()));
+                    }
+                    matched.sort_unstable_by(|x, y| x.name.cmp(&y.name));
+                    found.extend(matched);
+                }
+                None => found.push(self.valid_game(name)?),
+            }
+        }
+
+        Ok(found
             .into_iter()
-            .map(|name| self.valid_game(name.as_ref()))
-            .collect()
+            .filter(|game| seen.insert(game.name.as_str()))
+            .collect())
     }
 
-    pub fn verify<'g>(&'g self, root: &Path, game: &'g Game) -> Vec<VerifyFailure<'g>> {
+    // verifies the game's parts and, if devices is set,
+    // the parts of any devices it depends on
+    pub fn verify<'g>(
+        &'g self,
+        root: &Path,
+        game: &'g Game,
+        devices: bool,
+    ) -> Vec<VerifyFailure<'g>> {
         let mut results = game.parts.verify_failures(&root.join(&game.name));
-        results.extend(
-            game.devices
-                .iter()
-                .filter_map(|device| self.game(device))
-                .flat_map(|device| self.verify(root, device)),
-        );
+        if devices {
+            results.extend(
+                game.devices
+                    .iter()
+                    .filter_map(|device| self.game(device))
+                    .flat_map(|device| self.verify(root, device, true)),
+            );
+        }
         results
     }
 
-    pub fn list_results(&self, search: Option<&str>, simple: bool) -> Vec<GameRow> {
+    // all games, less any which are only devices if devices isn't set
+    pub fn games_with_devices(&self, devices: bool) -> Vec<&Game> {
+        self.games_iter()
+            .filter(|game| devices || !game.is_device)
+            .collect()
+    }
+
+    // games matching the search, if any,
+    // and among the tagged games, if any
+    pub fn list_results(
+        &self,
+        search: Option<&Search>,
+        tagged: Option<&BTreeSet<String>>,
+        simple: bool,
+    ) -> Vec<GameRow<'_>> {
+        let games = self
+            .games_iter()
+            .filter(|g| !g.is_device)
+            .filter(|g| tagged.is_none_or(|tagged| tagged.contains(&g.name)));
+
         if let Some(search) = search {
-            self.games_iter()
-                .filter(|g| !g.is_device)
+            games
                 .map(|g| g.report(simple))
                 .filter(|g| g.matches(search))
                 .collect()
         } else {
-            self.games_iter()
-                .filter(|g| !g.is_device)
-                .map(|g| g.report(simple))
-                .collect()
+            games.map(|g| g.report(simple)).collect()
         }
     }
 
-    pub fn list(&self, search: Option<&str>, sort: GameColumn, simple: bool) {
-        let mut results = self.list_results(search, simple);
+    pub fn list(
+        &self,
+        search: Option<&Search>,
+        tagged: Option<&BTreeSet<String>>,
+        sort: &[GameColumn],
+        simple: bool,
+    ) {
+        let mut results = self.list_results(search, tagged, simple);
         results.sort_by(|a, b| a.compare(b, sort));
-        GameDb::display_report(&results)
+        GameDb::display_report(&results, None)
     }
 
-    pub fn games<I>(&self, games: I, simple: bool)
+    pub fn games<I>(&self, games: I, simple: bool) -> Result<(), Error>
     where
         I: IntoIterator,
         I::Item: AsRef<str>,
     {
         GameDb::display_report(
-            &games
+            &self
+                .valid_games::<_, Vec<_>>(games)?
                 .into_iter()
-                .filter_map(|g| self.game(g.as_ref()).map(|g| g.report(simple)))
+                .map(|g| g.report(simple))
                 .collect::<Vec<GameRow>>(),
-        )
+            None,
+        );
+        Ok(())
     }
 
     pub fn report_results(
         &self,
         games: &HashSet<String>,
-        search: Option<&str>,
+        search: Option<&Search>,
         simple: bool,
-    ) -> Vec<GameRow> {
+    ) -> Vec<GameRow<'_>> {
         let mut results: Vec<GameRow> = games
             .iter()
             .filter_map(|g| {
@@ -153,28 +276,80 @@ impl GameDb {
         results
     }
 
+    // games are reported along with how much of each
+    // is present in the root directory
     pub fn report(
         &self,
+        root: &Path,
         games: &HashSet<String>,
-        search: Option<&str>,
-        sort: GameColumn,
+        search: Option<&Search>,
+        sort: &[GameColumn],
         simple: bool,
-    ) {
+        csv: Option<&Path>,
+    ) -> Result<(), Error> {
         let mut results = self.report_results(games, search, simple);
         results.sort_by(|a, b| a.compare(b, sort));
-        GameDb::display_report(&results)
+        let presence = |name: &str| match self.game(name) {
+            Some(game) => game.presence(root),
+            None => Presence::Missing,
+        };
+        if let Some(path) = csv {
+            GameDb::export_report(path, &results, &presence)?;
+        }
+        GameDb::display_report(&results, Some(&presence));
+        Ok(())
+    }
+
+    // writes reported games to a CSV file, one row per game,
+    // for tracking a collection in a spreadsheet
+    fn export_report(
+        path: &Path,
+        games: &[GameRow],
+        presence: &dyn Fn(&str) -> Presence,
+    ) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct ReportRow<'g> {
+            name: &'g str,
+            description: &'g str,
+            creator: &'g str,
+            year: &'g str,
+            status: &'static str,
+            present: &'static str,
+        }
+
+        let mut csv = csv::Writer::from_path(path).map_err(std::io::Error::from)?;
+        for game in games {
+            csv.serialize(ReportRow {
+                name: game.name,
+                description: game.description,
+                creator: game.creator,
+                year: game.year,
+                status: match game.status {
+                    Status::Working => "working",
+                    Status::Partial => "partial",
+                    Status::NotWorking => "not working",
+                },
+                present: match presence(game.name) {
+                    Presence::Complete => "complete",
+                    Presence::Partial => "partial",
+                    Presence::Missing => "missing",
+                },
+            })
+            .map_err(std::io::Error::from)?;
+        }
+        csv.flush()?;
+        Ok(())
     }
 
-    fn display_report(games: &[GameRow]) {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
+    fn display_report(games: &[GameRow], presence: Option<&dyn Fn(&str) -> Presence>) {
         use comfy_table::{Cell, Color};
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Game", "Creator", "Year", "Shortname"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        let mut header = vec!["Game", "Creator", "Year", "Shortname"];
+        if presence.is_some() {
+            header.push("Present");
+        }
+        table.set_header(header);
 
         for GameRow {
             description,
@@ -184,7 +359,7 @@ impl GameDb {
             status,
         } in games
         {
-            table.add_row(vec![
+            let mut row = vec![
                 match status {
                     Status::Working => Cell::new(description),
                     Status::Partial => Cell::new(description).fg(Color::Yellow),
@@ -193,43 +368,47 @@ impl GameDb {
                 Cell::new(creator),
                 Cell::new(year),
                 Cell::new(name),
-            ]);
+            ];
+            if let Some(presence) = presence {
+                row.push(match presence(name) {
+                    Presence::Complete => Cell::new("complete"),
+                    Presence::Partial => Cell::new("partial").fg(Color::Yellow),
+                    Presence::Missing => Cell::new("missing").fg(Color::Red),
+                });
+            }
+            table.add_row(row);
         }
 
         println!("{table}");
     }
 
-    pub fn display_parts(&self, name: &str) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
+    pub fn display_parts(&self, name: &str, devices: bool) -> Result<(), Error> {
         use comfy_table::{Attribute, Cell, CellAlignment};
 
         let game = self
             .game(name)
             .ok_or_else(|| Error::NoSuchSoftware(name.to_string()))?;
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
-        let devices: BTreeMap<&str, &Game> = game
+        let device_parts: BTreeMap<&str, &Game> = game
             .devices
             .iter()
+            .filter(|_| devices)
             .map(|dev| self.game(dev).expect("unknown device in game"))
             .filter(|game| !game.parts.is_empty())
             .map(|game| (game.name.as_str(), game))
             .collect();
 
-        if devices.is_empty() {
+        if device_parts.is_empty() {
             game.display_parts(&mut table);
         } else {
             table.add_row(vec![Cell::new(name)
                 .set_alignment(CellAlignment::Center)
                 .add_attribute(Attribute::Bold)]);
             game.display_parts(&mut table);
-            for (dev_name, dev) in devices.into_iter() {
+            for (dev_name, dev) in device_parts.into_iter() {
                 table.add_row(vec![Cell::new(dev_name)
                     .set_alignment(CellAlignment::Center)
                     .add_attribute(Attribute::Bold)]);
@@ -240,9 +419,60 @@ impl GameDb {
         println!("{table}");
         Ok(())
     }
+
+    // prints the machine's transitive device_ref tree,
+    // flagging which devices contribute parts of their own
+    pub fn display_deps(&self, name: &str) -> Result<(), Error> {
+        use std::collections::HashSet;
+
+        fn label(game: &Game) -> String {
+            match game.parts.len() {
+                0 => game.name.clone(),
+                1 => format!("{} [1 part]", game.name),
+                parts => format!("{} [{} parts]", game.name, parts),
+            }
+        }
+
+        fn display_devices<'g>(
+            db: &'g GameDb,
+            game: &'g Game,
+            prefix: &str,
+            seen: &mut HashSet<&'g str>,
+        ) {
+            // devices may be referenced more than once,
+            // but only need to be listed once
+            let mut devices = game.devices.iter().map(|d| d.as_str()).collect::<Vec<_>>();
+            devices.sort_unstable();
+            devices.dedup();
+
+            let [last, other] = crate::glyphs::tree_branches();
+
+            for (i, device) in devices.iter().enumerate() {
+                let (branch, indent) = if i + 1 == devices.len() { last } else { other };
+
+                match db.game(device) {
+                    Some(dev) if seen.insert(dev.name.as_str()) => {
+                        println!("{prefix}{branch}{}", label(dev));
+                        display_devices(db, dev, &format!("{prefix}{indent}"), seen);
+                    }
+                    Some(dev) => println!("{prefix}{branch}{} (see above)", dev.name),
+                    None => println!("{prefix}{branch}{device} (unknown device)"),
+                }
+            }
+        }
+
+        let game = self
+            .game(name)
+            .ok_or_else(|| Error::NoSuchSoftware(name.to_string()))?;
+
+        println!("{}", label(game));
+        display_devices(self, game, "", &mut HashSet::from([game.name.as_str()]));
+
+        Ok(())
+    }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Status {
     #[default]
     Working,
@@ -250,12 +480,48 @@ pub enum Status {
     NotWorking,
 }
 
+// creators and years repeat across thousands of games,
+// so each distinct one is shared rather than stored again and again
+pub fn intern(s: String) -> Arc<str> {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static INTERNED: RefCell<HashSet<Arc<str>>> = RefCell::default();
+    }
+
+    INTERNED.with_borrow_mut(|interned| match interned.get(s.as_str()) {
+        Some(shared) => shared.clone(),
+        None => {
+            let shared = Arc::<str>::from(s);
+            interned.insert(shared.clone());
+            shared
+        }
+    })
+}
+
+#[inline]
+fn deserialize_interned<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Arc<str>, D::Error> {
+    <String as serde::Deserialize>::deserialize(d).map(intern)
+}
+
+// how much of a game is in its directory, by file names alone
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Presence {
+    // the directory and all its disks
+    Complete,
+    // the directory, but not all its disks
+    Partial,
+    Missing,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Game {
     pub name: String,
     pub description: String,
-    pub creator: String,
-    pub year: String,
+    #[serde(deserialize_with = "deserialize_interned")]
+    pub creator: Arc<str>,
+    #[serde(deserialize_with = "deserialize_interned")]
+    pub year: Arc<str>,
     pub status: Status,
     pub is_device: bool,
     pub parts: GameParts,
@@ -263,7 +529,26 @@ pub struct Game {
 }
 
 impl Game {
-    pub fn report(&self, simple: bool) -> GameRow {
+    // CHDs are often fetched separately from a machine's ROMs,
+    // so a directory may be present without all of them
+    pub fn presence(&self, root: &Path) -> Presence {
+        let dir = root.join(&self.name);
+
+        if !dir.is_dir() {
+            Presence::Missing
+        } else if self
+            .parts
+            .iter()
+            .filter(|(_, part)| part.is_disk())
+            .all(|(name, _)| dir.join(name).is_file())
+        {
+            Presence::Complete
+        } else {
+            Presence::Partial
+        }
+    }
+
+    pub fn report(&self, simple: bool) -> GameRow<'_> {
         #[inline]
         fn no_parens(s: &str) -> &str {
             if let Some(index) = s.find('(') {
@@ -304,23 +589,21 @@ impl Game {
     pub fn add_and_verify(
         &self,
         rom_sources: &RomSources,
+        policy: &RepairPolicy,
         target_dir: &Path,
         handle_repair: impl Fn(Repaired<'_>) -> Option<PathBuf> + Send + Sync + Copy,
-    ) -> Result<Vec<VerifyFailure>, Error> {
-        self.parts
-            .add_and_verify_failures(rom_sources, &target_dir.join(&self.name), handle_repair)
+    ) -> Result<Vec<VerifyFailure<'_>>, Error> {
+        self.parts.add_and_verify_failures(
+            rom_sources,
+            policy,
+            &target_dir.join(&self.name),
+            handle_repair,
+        )
     }
 
+    #[inline]
     pub fn display_parts(&self, table: &mut Table) {
-        let parts: BTreeMap<&str, &Part> = self
-            .parts
-            .iter()
-            .map(|(name, part)| (name.as_str(), part))
-            .collect();
-
-        for (name, part) in parts {
-            table.add_row(vec![name, &part.digest().to_string()]);
-        }
+        self.parts.display_parts(table)
     }
 }
 
@@ -355,16 +638,25 @@ where
         let mut failures = E::default();
 
         for entry in dir.filter_map(|e| e.ok()) {
-            match entry.file_type() {
+            match crate::symlinks::file_type(&entry) {
                 Ok(t) if t.is_file() => match entry_to_part(entry) {
                     Ok(pair) => files.extend_item(pair),
-                    Err(pb) => failures.extend_item(VerifyFailure::extra(pb)),
+                    Err(pb) => {
+                        tracing::debug!(path = %pb.display(), "extra: file name is not valid UTF-8");
+                        failures.extend_item(VerifyFailure::extra(pb))
+                    }
                 },
                 Ok(t) if t.is_dir() => match entry_to_part(entry) {
                     Ok(pair) => dirs.extend_item(pair),
-                    Err(pb) => failures.extend_item(VerifyFailure::extra_dir(pb)),
+                    Err(pb) => {
+                        tracing::debug!(path = %pb.display(), "extra: directory name is not valid UTF-8");
+                        failures.extend_item(VerifyFailure::extra_dir(pb))
+                    }
                 },
-                Ok(_) => { /* neither file or dir, so do nothing */ }
+                Ok(_) => {
+                    // neither file or dir, so do nothing
+                    tracing::trace!(path = %entry.path().display(), "skipping non-file entry");
+                }
                 Err(err) => failures.extend_item(VerifyFailure::error(entry.path(), err)),
             }
         }
@@ -377,10 +669,30 @@ where
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-#[serde(transparent)]
+// parts are kept sorted by name in a plain Vec rather than a HashMap,
+// since most games have only a few and a table for each one
+// adds up to a lot of memory across all of MAME,
+// but are still stored as a map like before
+#[derive(Clone, Debug, Default)]
 pub struct GameParts {
-    parts: HashMap<String, Part>,
+    parts: Vec<(String, Part)>,
+}
+
+impl GameParts {
+    // sorts parts by name, where the last of any duplicates wins
+    // like inserting them into a map one after another
+    fn from_unsorted(mut parts: Vec<(String, Part)>) -> Self {
+        parts.sort_by(|(x, _), (y, _)| x.cmp(y));
+        parts.dedup_by(|later, earlier| {
+            let duplicate = later.0 == earlier.0;
+            if duplicate {
+                std::mem::swap(later, earlier);
+            }
+            duplicate
+        });
+        parts.shrink_to_fit();
+        Self { parts }
+    }
 }
 
 impl FromIterator<(String, Part)> for GameParts {
@@ -389,9 +701,7 @@ impl FromIterator<(String, Part)> for GameParts {
     where
         T: IntoIterator<Item = (String, Part)>,
     {
-        Self {
-            parts: HashMap::from_iter(iter),
-        }
+        Self::from_unsorted(iter.into_iter().collect())
     }
 }
 
@@ -401,11 +711,169 @@ impl Extend<(String, Part)> for GameParts {
     where
         T: IntoIterator<Item = (String, Part)>,
     {
-        self.parts.extend(iter)
+        let mut parts = std::mem::take(&mut self.parts);
+        parts.extend(iter);
+        *self = Self::from_unsorted(parts);
+    }
+}
+
+impl serde::Serialize for GameParts {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.parts.iter().map(|(name, part)| (name, part)))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for GameParts {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PartsVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PartsVisitor {
+            type Value = GameParts;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of part names to parts")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<GameParts, A::Error> {
+                let mut parts = Vec::with_capacity(map.size_hint().unwrap_or_default().min(4096));
+                while let Some(entry) = map.next_entry()? {
+                    parts.push(entry);
+                }
+                Ok(GameParts::from_unsorted(parts))
+            }
+        }
+
+        deserializer.deserialize_map(PartsVisitor)
+    }
+}
+
+// how every game's parts are written out at once
+#[derive(Copy, Clone, Default)]
+pub enum PartsFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for PartsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "table" => Ok(PartsFormat::Table),
+            "csv" => Ok(PartsFormat::Csv),
+            "json" => Ok(PartsFormat::Json),
+            _ => Err("invalid format, use \"table\", \"csv\" or \"json\"".to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PartRow<'g> {
+    game: &'g str,
+    part: &'g str,
+    sha1: String,
+    size: Option<u64>,
+}
+
+// writes (game, part name, part) triples to standard output,
+// sorted by game and then by part
+pub fn export_parts<'g>(
+    parts: impl IntoIterator<Item = (&'g str, &'g str, &'g Part)>,
+    format: PartsFormat,
+) -> Result<(), std::io::Error> {
+    use std::io::Write;
+
+    let mut rows = parts
+        .into_iter()
+        .map(|(game, part, p)| PartRow {
+            game,
+            part,
+            sha1: p.digest().to_string(),
+            size: p.size(),
+        })
+        .collect::<Vec<_>>();
+    rows.sort_unstable_by(|x, y| (x.game, x.part).cmp(&(y.game, y.part)));
+
+    let mut w = std::io::stdout().lock();
+
+    match format {
+        PartsFormat::Table => {
+            use comfy_table::{Cell, CellAlignment};
+
+            let mut table = crate::table::new();
+            table.set_header(vec!["Game", "Part", "SHA1 Hash", "Size"]);
+
+            for row in rows {
+                table.add_row(vec![
+                    Cell::new(row.game),
+                    Cell::new(row.part),
+                    Cell::new(row.sha1),
+                    Cell::new(row.size.map(|s| Size(s).to_string()).unwrap_or_default())
+                        .set_alignment(CellAlignment::Right),
+                ]);
+            }
+
+            writeln!(w, "{table}")?;
+        }
+        PartsFormat::Csv => {
+            let mut csv = csv::Writer::from_writer(&mut w);
+            for row in rows {
+                csv.serialize(row)?;
+            }
+            csv.flush()?;
+        }
+        PartsFormat::Json => {
+            serde_json::to_writer_pretty(&mut w, &rows)?;
+            writeln!(w)?;
+        }
     }
+
+    w.flush()
 }
 
 impl GameParts {
+    // adds a row per part, sorted by name, followed by a row of their total size
+    pub fn display_parts(&self, table: &mut Table) {
+        use comfy_table::{Cell, CellAlignment};
+
+        let parts: BTreeMap<&str, &Part> = self
+            .parts
+            .iter()
+            .map(|(name, part)| (name.as_str(), part))
+            .collect();
+
+        for (name, part) in parts {
+            table.add_row(vec![
+                Cell::new(name),
+                Cell::new(part.digest()),
+                Cell::new(
+                    part.size()
+                        .map(Size)
+                        .map(|s| s.to_string())
+                        .unwrap_or_default(),
+                )
+                .set_alignment(CellAlignment::Right),
+            ]);
+        }
+
+        table.add_row(vec![
+            Cell::new("Total"),
+            Cell::new(""),
+            Cell::new(Size(self.expected_size())).set_alignment(CellAlignment::Right),
+        ]);
+    }
+
+    // total of the sizes the parts are declared to have
+    #[inline]
+    pub fn expected_size(&self) -> u64 {
+        self.parts.iter().filter_map(|(_, p)| p.size()).sum()
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.parts.is_empty()
@@ -418,7 +886,7 @@ impl GameParts {
 
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Part)> {
-        self.parts.iter()
+        self.parts.iter().map(|(name, part)| (name, part))
     }
 
     #[inline]
@@ -428,38 +896,46 @@ impl GameParts {
 
     #[inline]
     pub fn keys(&self) -> impl Iterator<Item = &String> {
-        self.parts.keys()
-    }
-
-    #[inline]
-    pub fn paths<'r>(&'r self, root: &'r Path) -> impl Iterator<Item = PathBuf> + 'r {
-        self.keys().map(|name| root.join(name))
+        self.parts.iter().map(|(name, _)| name)
     }
 
     #[inline]
-    pub fn size(&self, root: &Path) -> FileSize {
-        self.paths(root)
-            .map(|pb| FileSize::new(&pb).unwrap_or_default())
-            .sum()
+    pub fn insert(&mut self, k: String, v: Part) -> Option<Part> {
+        match self
+            .parts
+            .binary_search_by(|(name, _)| name.as_str().cmp(&k))
+        {
+            Ok(index) => Some(std::mem::replace(&mut self.parts[index].1, v)),
+            Err(index) => {
+                self.parts.insert(index, (k, v));
+                None
+            }
+        }
     }
 
     #[inline]
-    pub fn insert(&mut self, k: String, v: Part) -> Option<Part> {
-        self.parts.insert(k, v)
+    pub fn get(&self, name: &str) -> Option<&Part> {
+        self.parts
+            .binary_search_by(|(k, _)| k.as_str().cmp(name))
+            .ok()
+            .map(|index| &self.parts[index].1)
     }
 
     #[inline]
     pub fn remove(&mut self, name: &str) -> Option<Part> {
-        self.parts.remove(name)
+        self.parts
+            .binary_search_by(|(k, _)| k.as_str().cmp(name))
+            .ok()
+            .map(|index| self.parts.remove(index).1)
     }
 
     // game_root is the root directory to start looking for files
-    // increment_progress is called once per (name, part) pair
+    // increment_progress is called once per (name, part) pair with the part
     // handle_failure is an attempt to recover from failures
     pub fn process_parts<'s, S, F, E>(
         &'s self,
         game_root: &Path,
-        increment_progress: impl Fn() + Send + Sync,
+        increment_progress: impl Fn(&Part) + Send + Sync,
         handle_failure: impl Fn(VerifyFailure) -> Result<Result<Option<PathBuf>, VerifyFailure>, E>
             + Send
             + Sync,
@@ -469,16 +945,21 @@ impl GameParts {
         F: Default + ExtendOne<VerifyFailure<'s>> + Send,
         E: Send,
     {
+        let _span = tracing::debug_span!("game", root = %game_root.display()).entered();
+
         let GameDir {
             files,
             dirs,
             mut failures,
         }: GameDir<DashMap<_, _>, Vec<_>, F> = GameDir::open(game_root);
 
-        failures.extend(
-            dirs.into_iter()
-                .map(|(_, dir)| VerifyFailure::extra_dir(dir)),
-        );
+        // extra directories are never removed,
+        // but repairs may still need to see them
+        for (_, dir) in dirs {
+            if let Err(failure) = handle_failure(VerifyFailure::extra_dir(dir))? {
+                failures.extend_item(failure);
+            }
+        }
 
         let successes = self.process(
             files,
@@ -494,14 +975,14 @@ impl GameParts {
     // files is a map of files to be processed
     // failures is a running total of existing validation failures
     // missing_path takes a ROM name and returns its desired path
-    // increment_progress is called once per (name, part) pair
+    // increment_progress is called once per (name, part) pair with the part
     // handle failure is how to handle failures that might occur
     pub fn process<'s, S, F, E>(
         &'s self,
         files: DashMap<String, PathBuf>,
         failures: &mut F,
         missing_path: impl Fn(&str) -> PathBuf + Send + Sync,
-        increment_progress: impl Fn() + Send + Sync,
+        increment_progress: impl Fn(&Part) + Send + Sync,
         handle_failure: impl Fn(VerifyFailure) -> Result<Result<Option<PathBuf>, VerifyFailure>, E>
             + Send
             + Sync,
@@ -520,18 +1001,29 @@ impl GameParts {
 
         // verify all game parts
         if files.is_empty() {
-            missing = Mutex::new(self.parts.iter().collect());
+            missing = Mutex::new(self.iter().collect());
         } else {
             missing = Mutex::new(Vec::new());
 
             self.parts.par_iter().try_for_each(|(name, part)| {
+                if crate::interrupt::requested() {
+                    return Ok(());
+                }
+
                 match files.remove(name) {
                     Some((_, path)) => {
-                        match part.verify(name, path) {
-                            Ok(success) => successes.lock().unwrap().extend_item(success),
+                        match part.verify(name, path.clone()) {
+                            Ok(success) => {
+                                tracing::trace!(name, "verified");
+                                crate::protect::protect(&path);
+                                successes.lock().unwrap().extend_item(success)
+                            }
 
                             Err(failure) => match handle_failure(failure)? {
-                                Ok(Some(_)) => successes.lock().unwrap().extend_item(VerifySuccess),
+                                Ok(Some(path)) => {
+                                    crate::protect::protect(&path);
+                                    successes.lock().unwrap().extend_item(VerifySuccess)
+                                }
 
                                 Ok(None) => { /* file deleted, so do nothing */ }
 
@@ -539,25 +1031,47 @@ impl GameParts {
                             },
                         }
 
-                        increment_progress();
+                        increment_progress(part);
                     }
 
-                    None => missing.lock().unwrap().push((name, part)),
+                    None => {
+                        tracing::trace!(name, "not found by name");
+                        missing.lock().unwrap().push((name, part))
+                    }
                 }
 
                 Ok(())
             })?;
         }
 
+        // once interrupted, anything left over on disk
+        // may belong to parts which were skipped,
+        // so it's left alone rather than treated as extras
+        if crate::interrupt::requested() {
+            return Ok(successes.into_inner().unwrap());
+        }
+
         // process anything left over on disk
         let extras = PartMap::default();
 
+        // the de-interleaved parts of any SMD files among them,
+        // along with the parts they're in the extras map by
+        let interleaved: DashMap<Part, (Part, PathBuf)> = DashMap::default();
+
         files.into_par_iter().try_for_each(|(_, path)| {
             match Part::from_cached_path(&path) {
                 Ok(part) => {
+                    if let Some(plain) = Part::from_smd_path(&path) {
+                        interleaved.insert(plain, (part.clone(), path.clone()));
+                    }
+
                     // populate extras map
                     if let Some(path) = extras.insert(part.clone(), path) {
                         // treat multiple files that hash the same as extras
+                        tracing::debug!(
+                            path = %path.display(),
+                            "extra: duplicate of another unexpected file"
+                        );
                         if let Err(failure) = handle_failure(VerifyFailure::Extra {
                             path,
                             part: Ok(part),
@@ -570,45 +1084,93 @@ impl GameParts {
                 }
 
                 // treat everything we can't read as extras
-                part @ Err(_) => failures
-                    .lock()
-                    .unwrap()
-                    .extend_item(VerifyFailure::Extra { path, part }),
+                part @ Err(_) => {
+                    tracing::debug!(path = %path.display(), "extra: unable to read file");
+                    failures
+                        .lock()
+                        .unwrap()
+                        .extend_item(VerifyFailure::Extra { path, part })
+                }
             };
             Ok::<(), E>(())
         })?;
 
+        if crate::interrupt::requested() {
+            return Ok(successes.into_inner().unwrap());
+        }
+
         // process everything tagged as missing
         missing
             .into_inner()
             .unwrap()
             .into_par_iter()
             .try_for_each(|(name, part)| {
+                if crate::interrupt::requested() {
+                    return Ok(());
+                }
+
                 let destination = missing_path(name);
 
-                match handle_failure(match extras.remove(part) {
+                let misnamed = extras
+                    .remove(part)
+                    .or_else(|| remove_crc_extra(&extras, part));
+
+                match handle_failure(match misnamed {
                     // if the missing file is in the extras pile
                     // treat it as a rename and handle it
-                    Some((_, source)) => VerifyFailure::Rename {
-                        source,
-                        destination,
-                    },
+                    Some((_, source)) => {
+                        tracing::debug!(
+                            name,
+                            source = %source.display(),
+                            "found under another name"
+                        );
+                        VerifyFailure::Rename {
+                            source,
+                            destination,
+                        }
+                    }
 
-                    // otherwise, treat it as a missing file and handle it
-                    None => VerifyFailure::Missing {
-                        path: destination,
-                        name,
-                        part,
+                    None => match interleaved
+                        .remove(part)
+                        .and_then(|(_, (extra, source))| extras.remove(&extra).map(|_| source))
+                    {
+                        // if the missing file is an interleaved extra
+                        // treat it as a conversion and handle it
+                        Some(source) => {
+                            tracing::debug!(
+                                name,
+                                source = %source.display(),
+                                "found interleaved"
+                            );
+                            VerifyFailure::Interleaved {
+                                source,
+                                destination,
+                                part,
+                            }
+                        }
+
+                        // otherwise, treat it as a missing file and handle it
+                        None => {
+                            tracing::debug!(name, "missing: no file with matching contents");
+                            VerifyFailure::Missing {
+                                path: destination,
+                                name,
+                                part,
+                            }
+                        }
                     },
                 })? {
-                    Ok(Some(_)) => successes.lock().unwrap().extend_item(VerifySuccess),
+                    Ok(Some(path)) => {
+                        crate::protect::protect(&path);
+                        successes.lock().unwrap().extend_item(VerifySuccess)
+                    }
 
                     Ok(None) => { /* file deleted, so do nothing (shouldn't happen) */ }
 
                     Err(failure) => failures.lock().unwrap().extend_item(failure),
                 }
 
-                increment_progress();
+                increment_progress(part);
 
                 Ok(())
             })?;
@@ -625,6 +1187,10 @@ impl GameParts {
             // and Extra files can't be promoted to VerifySuccesses
             // (since they have no valid names)
             // so only the Err case needs to be handled
+            tracing::debug!(
+                path = %extra.path().display(),
+                "extra: name not in DAT and contents match no missing part"
+            );
             if let Err(failure) = handle_failure(extra)? {
                 failures.extend_item(failure);
             }
@@ -637,7 +1203,7 @@ impl GameParts {
     pub fn verify_with_progress<'s, S, F>(
         &'s self,
         game_root: &Path,
-        increment_progress: impl Fn() + Send + Sync,
+        increment_progress: impl Fn(&Part) + Send + Sync,
     ) -> (S, F)
     where
         S: Default + ExtendOne<VerifySuccess> + Send,
@@ -655,7 +1221,7 @@ impl GameParts {
         S: Default + ExtendOne<VerifySuccess> + Send,
         F: Default + ExtendOne<VerifyFailure<'s>> + Send,
     {
-        self.verify_with_progress(game_root, || {})
+        self.verify_with_progress(game_root, |_| {})
     }
 
     #[inline]
@@ -668,8 +1234,9 @@ impl GameParts {
     pub fn add_and_verify_with_progress<'s, S, F>(
         &'s self,
         rom_sources: &RomSources,
+        policy: &RepairPolicy,
         game_root: &Path,
-        increment_progress: impl Fn() + Send + Sync,
+        increment_progress: impl Fn(&Part) + Send + Sync,
         handle_repair: impl Fn(Repaired<'_>) -> Option<PathBuf> + Send + Sync + Copy,
     ) -> Result<(S, F), Error>
     where
@@ -677,7 +1244,9 @@ impl GameParts {
         F: Default + ExtendOne<VerifyFailure<'s>> + Send,
     {
         self.process_parts(game_root, increment_progress, |failure| {
-            failure.try_fix(rom_sources).map(|r| r.map(handle_repair))
+            failure
+                .try_fix(rom_sources, policy)
+                .map(|r| r.map(handle_repair))
         })
     }
 
@@ -685,6 +1254,7 @@ impl GameParts {
     pub fn add_and_verify<'s, S, F>(
         &'s self,
         rom_sources: &RomSources,
+        policy: &RepairPolicy,
         game_root: &Path,
         handle_repair: impl Fn(Repaired<'_>) -> Option<PathBuf> + Send + Sync + Copy,
     ) -> Result<(S, F), Error>
@@ -692,17 +1262,18 @@ impl GameParts {
         S: Default + ExtendOne<VerifySuccess> + Send,
         F: Default + ExtendOne<VerifyFailure<'s>> + Send,
     {
-        self.add_and_verify_with_progress(rom_sources, game_root, || {}, handle_repair)
+        self.add_and_verify_with_progress(rom_sources, policy, game_root, |_| {}, handle_repair)
     }
 
     #[inline]
     pub fn add_and_verify_failures(
         &self,
         rom_sources: &RomSources,
+        policy: &RepairPolicy,
         game_root: &Path,
         handle_repair: impl Fn(Repaired<'_>) -> Option<PathBuf> + Send + Sync + Copy,
-    ) -> Result<Vec<VerifyFailure>, Error> {
-        self.add_and_verify(rom_sources, game_root, handle_repair)
+    ) -> Result<Vec<VerifyFailure<'_>>, Error> {
+        self.add_and_verify(rom_sources, policy, game_root, handle_repair)
             .map(|(_, failures): (ExtendSink<_>, _)| failures)
     }
 }
@@ -714,13 +1285,25 @@ pub struct FileSize {
 }
 
 impl FileSize {
-    fn new(path: &Path) -> Result<Self, std::io::Error> {
+    pub fn new(path: &Path) -> Result<Self, std::io::Error> {
         let metadata = path.metadata()?;
         Ok(Self {
             len: metadata.len(),
             real: filesize::file_real_size_fast(path, &metadata)?,
         })
     }
+
+    // the combined size of the given files,
+    // counting each hardlinked file only once
+    pub fn unique<I: IntoIterator<Item = PathBuf>>(paths: I) -> Self {
+        let mut seen = HashSet::new();
+
+        paths
+            .into_iter()
+            .filter(|pb| FileId::new(pb).is_ok_and(|id| seen.insert(id)))
+            .map(|pb| Self::new(&pb).unwrap_or_default())
+            .sum()
+    }
 }
 
 impl std::ops::Add for FileSize {
@@ -743,41 +1326,214 @@ impl std::iter::Sum<FileSize> for FileSize {
     }
 }
 
-pub struct GameRow<'a> {
-    pub name: &'a str,
-    pub description: &'a str,
-    pub creator: &'a str,
-    pub year: &'a str,
-    pub status: Status,
-}
+// a human-readable size in bytes
+pub struct Size(pub u64);
 
-impl<'a> GameRow<'a> {
-    pub fn matches(&self, search: &str) -> bool {
-        self.name.starts_with(search)
-            || self.description.contains(search)
-            || self.creator.contains(search)
-            || (self.year == search)
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const K: f64 = (1 << 10) as f64;
+        const M: f64 = (1 << 20) as f64;
+        const G: f64 = (1 << 30) as f64;
+        const T: f64 = (1u64 << 40) as f64;
+
+        match self.0 {
+            b if b < (1 << 10) => write!(f, "{:.2} B", b),
+            b if b < (1 << 20) => write!(f, "{:.2} KiB", b as f64 / K),
+            b if b < (1 << 30) => write!(f, "{:.2} MiB", b as f64 / M),
+            b if b < (1 << 40) => write!(f, "{:.2} GiB", b as f64 / G),
+            b => write!(f, "{:.2} TiB", b as f64 / T),
+        }
     }
+}
+
+pub enum Search {
+    Text(String),
+    Regex(regex::Regex),
+}
 
-    fn sort_key(&self, sort: GameColumn) -> (&str, &str, &str) {
-        match sort {
-            GameColumn::Description => (self.description, self.creator, self.year),
-            GameColumn::Creator => (self.creator, self.description, self.year),
-            GameColumn::Year => (self.year, self.description, self.creator),
+impl Search {
+    pub fn new(search: String, regex: bool) -> Result<Self, regex::Error> {
+        if regex {
+            regex::Regex::new(&search).map(Search::Regex)
+        } else {
+            Ok(Search::Text(search))
         }
     }
 
-    pub fn compare(&self, other: &GameRow, sort: GameColumn) -> Ordering {
-        self.sort_key(sort).cmp(&other.sort_key(sort))
+    #[inline]
+    pub fn is_match(&self, s: &str) -> bool {
+        match self {
+            Search::Text(search) => s.contains(search.as_str()),
+            Search::Regex(regex) => regex.is_match(s),
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct VerifySuccess;
+// how a database's games differ from one import to the next
+#[derive(Default)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    // as (old name, new name) pairs
+    pub renamed: Vec<(String, String)>,
+    // games whose parts differ
+    pub changed: Vec<String>,
+}
 
-#[derive(Debug)]
-pub enum VerifyFailure<'s> {
-    Missing {
+// each game's parts as (name, part) pairs in name order
+pub type GameContents<'a> = HashMap<&'a str, Vec<(&'a str, &'a Part)>>;
+
+impl Changes {
+    // where a game that's gone and one that's new with the same parts
+    // count as a rename rather than a removal and an addition
+    pub fn new(old: GameContents<'_>, new: GameContents<'_>) -> Self {
+        fn contents<'a>(parts: &[(&str, &'a Part)]) -> Vec<Digest<'a>> {
+            let mut digests = parts.iter().map(|(_, p)| p.digest()).collect::<Vec<_>>();
+            digests.sort_unstable();
+            digests
+        }
+
+        let mut changes = Changes::default();
+
+        let mut removed = old
+            .iter()
+            .filter(|(name, parts)| !new.contains_key(*name) && !parts.is_empty())
+            .map(|(name, parts)| (contents(parts), *name))
+            .collect::<HashMap<_, _>>();
+
+        for (name, parts) in &new {
+            match old.get(name) {
+                Some(old_parts) => {
+                    if old_parts != parts {
+                        changes.changed.push(name.to_string());
+                    }
+                }
+                None => match removed
+                    .remove(&contents(parts))
+                    .filter(|_| !parts.is_empty())
+                {
+                    Some(old) => changes.renamed.push((old.to_owned(), name.to_string())),
+                    None => changes.added.push(name.to_string()),
+                },
+            }
+        }
+
+        changes.removed = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .filter(|name| !changes.renamed.iter().any(|(old, _)| old == *name))
+            .map(|name| name.to_string())
+            .collect();
+
+        changes.added.sort_unstable();
+        changes.removed.sort_unstable();
+        changes.renamed.sort_unstable();
+        changes.changed.sort_unstable();
+
+        changes
+    }
+
+    // only those changes to games which were already present,
+    // such as those in a collection
+    pub fn affecting(self, present: impl Fn(&str) -> bool) -> Self {
+        Self {
+            added: Vec::new(),
+            removed: self.removed.into_iter().filter(|g| present(g)).collect(),
+            renamed: self
+                .renamed
+                .into_iter()
+                .filter(|(old, _)| present(old))
+                .collect(),
+            changed: self.changed.into_iter().filter(|g| present(g)).collect(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    pub fn report(&self, name: &str) {
+        eprintln!(
+            "* \"{name}\" : {} added, {} removed, {} renamed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.renamed.len(),
+            self.changed.len()
+        );
+        for game in &self.added {
+            eprintln!("   ADDED : {game}");
+        }
+        for game in &self.removed {
+            eprintln!(" REMOVED : {game}");
+        }
+        for (old, new) in &self.renamed {
+            eprintln!(" RENAMED : {old} {} {new}", crate::glyphs::copied());
+        }
+        for game in &self.changed {
+            eprintln!(" CHANGED : {game}");
+        }
+    }
+}
+
+pub struct GameRow<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub creator: &'a str,
+    pub year: &'a str,
+    pub status: Status,
+}
+
+impl<'a> GameRow<'a> {
+    pub fn matches(&self, search: &Search) -> bool {
+        match search {
+            Search::Text(search) => {
+                self.name.starts_with(search.as_str())
+                    || self.description.contains(search.as_str())
+                    || self.creator.contains(search.as_str())
+                    || (self.year == search)
+            }
+            Search::Regex(regex) => {
+                regex.is_match(self.name)
+                    || regex.is_match(self.description)
+                    || regex.is_match(self.creator)
+                    || regex.is_match(self.year)
+            }
+        }
+    }
+
+    fn compare_column(&self, other: &GameRow, column: GameColumn) -> Ordering {
+        match column {
+            GameColumn::Description => self.description.cmp(other.description),
+            GameColumn::Creator => self.creator.cmp(other.creator),
+            GameColumn::Year => self.year.cmp(other.year),
+            GameColumn::Name => self.name.cmp(other.name),
+            GameColumn::Status => self.status.cmp(&other.status),
+        }
+    }
+
+    // compares by each column in turn,
+    // falling back to description, creator and year for any ties
+    pub fn compare(&self, other: &GameRow, sort: &[GameColumn]) -> Ordering {
+        use GameColumn::{Creator, Description, Year};
+
+        sort.iter()
+            .chain(&[Description, Creator, Year])
+            .map(|column| self.compare_column(other, *column))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+#[derive(Debug)]
+pub struct VerifySuccess;
+
+#[derive(Debug)]
+pub enum VerifyFailure<'s> {
+    Missing {
         path: PathBuf,
         name: &'s str,
         part: &'s Part,
@@ -790,6 +1546,12 @@ pub enum VerifyFailure<'s> {
         source: PathBuf,
         destination: PathBuf,
     },
+    // a missing part found as an SMD-interleaved file
+    Interleaved {
+        source: PathBuf,
+        destination: PathBuf,
+        part: &'s Part,
+    },
     ExtraDir {
         path: PathBuf,
     },
@@ -797,17 +1559,31 @@ pub enum VerifyFailure<'s> {
         path: PathBuf,
         name: &'s str,
         expected: &'s Part,
-        actual: Part,
+        // not known if the file was rejected by size alone
+        actual: Option<Part>,
+    },
+    // the expected part followed by nothing but padding
+    Overdump {
+        path: PathBuf,
+        name: &'s str,
+        expected: &'s Part,
+        size: u64,
     },
     Error {
         path: PathBuf,
         err: std::io::Error,
     },
+    // in a dry run, a failure which would have been repaired,
+    // along with the repair which would have been made
+    Repairable {
+        path: PathBuf,
+        repair: String,
+    },
 }
 
 impl<'s> VerifyFailure<'s> {
     #[inline]
-    fn extra(path: PathBuf) -> Self {
+    pub fn extra(path: PathBuf) -> Self {
         Self::Extra {
             part: Part::from_path(&path),
             path,
@@ -824,15 +1600,42 @@ impl<'s> VerifyFailure<'s> {
         Self::Error { path, err }
     }
 
+    #[inline]
+    pub fn is_extra(&self) -> bool {
+        matches!(
+            self,
+            VerifyFailure::Extra { .. } | VerifyFailure::ExtraDir { .. }
+        )
+    }
+
+    // the part which still needs to be supplied from elsewhere
+    pub fn needed(&self) -> Option<(&'s str, &'s Part)> {
+        match self {
+            VerifyFailure::Missing { name, part, .. } => Some((name, part)),
+            VerifyFailure::Bad { name, expected, .. }
+            | VerifyFailure::Overdump { name, expected, .. } => Some((name, expected)),
+            _ => None,
+        }
+    }
+
+    // whether the failure shouldn't count against verification
+    #[inline]
+    pub fn is_ignored(&self, keep_extra: bool) -> bool {
+        keep_extra && self.is_extra()
+    }
+
     #[inline]
     pub fn path(&self) -> &Path {
         match self {
             VerifyFailure::Missing { path, .. }
             | VerifyFailure::Extra { path, .. }
             | VerifyFailure::Rename { source: path, .. }
+            | VerifyFailure::Interleaved { source: path, .. }
             | VerifyFailure::ExtraDir { path, .. }
             | VerifyFailure::Bad { path, .. }
-            | VerifyFailure::Error { path, .. } => path.as_path(),
+            | VerifyFailure::Overdump { path, .. }
+            | VerifyFailure::Error { path, .. }
+            | VerifyFailure::Repairable { path, .. } => path.as_path(),
         }
     }
 
@@ -840,9 +1643,33 @@ impl<'s> VerifyFailure<'s> {
     // repair successful            - Ok(Ok(Repaired))
     // unable to repair             - Ok(Err(Self))
     // error occurred during repair - Err(Error)
+    //
+    // where a dry run's repairs are only previewed,
+    // so each one it would make is still a failure
     pub fn try_fix<'u>(
         self,
         rom_sources: &RomSources<'u>,
+        policy: &RepairPolicy,
+    ) -> Result<Result<Repaired<'u>, Self>, Error> {
+        if !policy.dry_run {
+            return self.fix(rom_sources, policy);
+        }
+
+        let path = self.path().to_owned();
+
+        Ok(match self.fix(rom_sources, policy)? {
+            Ok(repaired) => Err(VerifyFailure::Repairable {
+                path,
+                repair: repaired.to_string(),
+            }),
+            Err(failure) => Err(failure),
+        })
+    }
+
+    fn fix<'u>(
+        self,
+        rom_sources: &RomSources<'u>,
+        policy: &RepairPolicy,
     ) -> Result<Result<Repaired<'u>, Self>, Error> {
         use dashmap::mapref::entry::Entry;
 
@@ -850,11 +1677,36 @@ impl<'s> VerifyFailure<'s> {
             mut entry: OccupiedEntry<'_, Part, RomSource<'u>>,
             target: PathBuf,
             part: &Part,
+            policy: &RepairPolicy,
         ) -> Result<Repaired<'u>, Error> {
             let source = entry.get();
 
-            match source.extract(target.as_ref())? {
+            tracing::debug!(
+                target = %target.display(),
+                %source,
+                move_source = policy.move_sources,
+                dry_run = policy.dry_run,
+                "repairing from source"
+            );
+
+            if policy.dry_run {
+                return Ok(Repaired::Extracted {
+                    extracted: match policy.move_sources {
+                        true => Extracted::Moved { has_xattr: true },
+                        false => Extracted::Copied { rate: None },
+                    },
+                    source: source.clone(),
+                    target,
+                });
+            }
+
+            match source.extract(target.as_ref(), policy.move_sources)? {
                 extracted @ Extracted::Copied { .. } => {
+                    crate::throughput::written(&target);
+                    if policy.verify_writes {
+                        verify_written(&target, part)?;
+                    }
+                    source.stamp(&target, policy.timestamp);
                     part.set_xattr(&target);
 
                     Ok(Repaired::Extracted {
@@ -879,36 +1731,155 @@ impl<'s> VerifyFailure<'s> {
                         target,
                     })
                 }
+
+                // the original source is gone,
+                // so future repairs should use the moved file
+                extracted @ Extracted::Moved { has_xattr } => {
+                    if let Timestamp::Dat(_) = policy.timestamp {
+                        source.stamp(&target, policy.timestamp);
+                    }
+                    if !has_xattr {
+                        part.set_xattr(&target);
+                    }
+
+                    Ok(Repaired::Extracted {
+                        extracted,
+                        source: entry.insert(RomSource::File {
+                            file: Arc::from(target.clone()),
+                            has_xattr: true,
+                            zip_parts: ZipParts::default(),
+                        }),
+                        target,
+                    })
+                }
             }
         }
 
+        // whether the part's repair source is the file at the path,
+        // such as when repairing from the collection itself
+        fn is_source(rom_sources: &RomSources<'_>, part: &Part, path: &Path) -> bool {
+            rom_sources.get(part).is_some_and(|source| {
+                matches!(source.value(), RomSource::File { file, .. } if file.as_ref() == path)
+            })
+        }
+
+        // sources of parts known only by their CRC32 are found as they're needed
+        if let Some((_, part)) = self
+            .needed()
+            .filter(|_| !policy.add_only || matches!(self, VerifyFailure::Missing { .. }))
+        {
+            add_crc_source(rom_sources, part);
+        }
+
         match self {
+            // only missing files may be filled in when adding,
+            // everything else on disk is left untouched
+            failure @ (VerifyFailure::Bad { .. }
+            | VerifyFailure::Overdump { .. }
+            | VerifyFailure::Rename { .. }
+            | VerifyFailure::Interleaved { .. }
+            | VerifyFailure::Extra { .. })
+                if policy.add_only =>
+            {
+                tracing::debug!(path = %failure.path().display(), "left untouched: only adding");
+                Ok(Err(failure))
+            }
+
+            // extras being kept are never candidates for deletion
+            failure @ VerifyFailure::Extra { .. } if policy.keep_extra => {
+                tracing::debug!(path = %failure.path().display(), "left untouched: keeping extras");
+                Ok(Err(failure))
+            }
+
             VerifyFailure::Bad {
                 path,
                 name,
                 expected,
                 actual,
+            } => {
+                // once replaced, the bad file can't supply its own part,
+                // which is removed before taking the entry for its replacement
+                // since both may be in the same locked shard
+                if !policy.dry_run && rom_sources.contains_key(expected) {
+                    if let Some(actual) = actual.as_ref().filter(|actual| *actual != expected) {
+                        rom_sources.remove_if(actual, |_, source| {
+                            matches!(source, RomSource::File { file, .. } if file.as_ref() == path)
+                        });
+                    }
+                    policy.remove(&path)?;
+                }
+
+                match rom_sources.entry(expected.clone()) {
+                    Entry::Occupied(entry) => extract_to(entry, path, expected, policy).map(Ok),
+
+                    Entry::Vacant(_) => {
+                        tracing::debug!(path = %path.display(), "no source for bad file");
+                        Ok(Err(VerifyFailure::Bad {
+                            path,
+                            name,
+                            expected,
+                            actual,
+                        }))
+                    }
+                }
+            }
+
+            VerifyFailure::Overdump { path, expected, .. } if policy.trim_overdumps => {
+                let size = expected.size().unwrap_or_default();
+                if !policy.dry_run {
+                    crate::protect::unprotect(&path);
+                    if let Some(dir) = &policy.backup_dir {
+                        crate::quarantine::preserve(dir, &path)?;
+                    }
+                    std::fs::File::options()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|f| f.set_len(size))?;
+                    if policy.verify_writes {
+                        verify_written(&path, expected)?;
+                    }
+                    expected.set_xattr(&path);
+                }
+                Ok(Ok(Repaired::Trimmed { path, size }))
+            }
+
+            // without trimming, an overdump is replaced like any bad file
+            VerifyFailure::Overdump {
+                path,
+                name,
+                expected,
+                size,
             } => match rom_sources.entry(expected.clone()) {
                 Entry::Occupied(entry) => {
-                    std::fs::remove_file(&path)?;
-                    extract_to(entry, path, expected).map(Ok)
+                    if !policy.dry_run {
+                        policy.remove(&path)?;
+                    }
+                    extract_to(entry, path, expected, policy).map(Ok)
                 }
 
-                Entry::Vacant(_) => Ok(Err(VerifyFailure::Bad {
-                    path,
-                    name,
-                    expected,
-                    actual,
-                })),
+                Entry::Vacant(_) => {
+                    tracing::debug!(path = %path.display(), "no source for overdumped file");
+                    Ok(Err(VerifyFailure::Overdump {
+                        path,
+                        name,
+                        expected,
+                        size,
+                    }))
+                }
             },
 
             VerifyFailure::Missing { path, part, name } => match rom_sources.entry(part.clone()) {
                 Entry::Occupied(entry) => {
-                    std::fs::create_dir_all(path.parent().unwrap())?;
-                    extract_to(entry, path, part).map(Ok)
+                    if !policy.dry_run {
+                        std::fs::create_dir_all(path.parent().unwrap())?;
+                    }
+                    extract_to(entry, path, part, policy).map(Ok)
                 }
 
-                Entry::Vacant(_) => Ok(Err(VerifyFailure::Missing { path, part, name })),
+                Entry::Vacant(_) => {
+                    tracing::debug!(path = %path.display(), "no source for missing file");
+                    Ok(Err(VerifyFailure::Missing { path, part, name }))
+                }
             },
 
             VerifyFailure::Rename {
@@ -916,18 +1887,91 @@ impl<'s> VerifyFailure<'s> {
                 destination,
                 ..
             } => {
-                std::fs::rename(&source, &destination)?;
+                if !policy.dry_run {
+                    crate::protect::unprotect(&source);
+                    std::fs::rename(&source, &destination)?;
+
+                    // the file may be the source for parts elsewhere,
+                    // which should now be found under its new name
+                    if let Ok(part) = Part::from_cached_path(&destination) {
+                        if let Some(mut entry) = rom_sources.get_mut(&part).filter(|entry| {
+                            matches!(entry.value(), RomSource::File { file, .. } if file.as_ref() == source)
+                        }) {
+                            if let RomSource::File { file, .. } = entry.value_mut() {
+                                *file = Arc::from(destination.as_path());
+                            }
+                        }
+                    }
+                }
                 Ok(Ok(Repaired::Moved {
                     source,
                     destination,
                 }))
             }
 
+            VerifyFailure::Interleaved {
+                source,
+                destination,
+                part,
+            } => {
+                if !policy.dry_run {
+                    let converted = RomSource::File {
+                        file: Arc::from(source.clone()),
+                        has_xattr: false,
+                        zip_parts: vec![Compression::Smd],
+                    };
+                    converted.extract(&destination, false)?;
+                    crate::throughput::written(&destination);
+                    if policy.verify_writes {
+                        verify_written(&destination, part)?;
+                    }
+                    converted.stamp(&destination, policy.timestamp);
+                    part.set_xattr(&destination);
+                    // an interleaved file which is the source
+                    // for parts elsewhere is left for them
+                    if !Part::from_cached_path(&source)
+                        .is_ok_and(|interleaved| is_source(rom_sources, &interleaved, &source))
+                    {
+                        policy.remove(&source)?;
+                    }
+                }
+                Ok(Ok(Repaired::Converted {
+                    source,
+                    destination,
+                }))
+            }
+
+            // an extra which is the source for parts elsewhere,
+            // such as when repairing from the collection itself,
+            // is left in place until nothing needs it
+            VerifyFailure::Extra {
+                path,
+                part: Ok(part),
+            } if is_source(rom_sources, &part, &path) => {
+                tracing::debug!(path = %path.display(), "left untouched: repair source");
+                Ok(Err(VerifyFailure::Extra {
+                    path,
+                    part: Ok(part),
+                }))
+            }
+
             VerifyFailure::Extra { path, part: Ok(_) } => {
-                std::fs::remove_file(&path)?;
+                if !policy.dry_run {
+                    policy.remove(&path)?;
+                }
                 Ok(Ok(Repaired::Deleted(path)))
             }
 
+            // extra directories are never removed by repairs,
+            // but files an earlier run protected shouldn't
+            // get in the way of the user removing them
+            VerifyFailure::ExtraDir { path } => {
+                if !policy.dry_run {
+                    crate::protect::unprotect_all(&path);
+                }
+                Ok(Err(VerifyFailure::ExtraDir { path }))
+            }
+
             failure => Ok(Err(failure)),
         }
     }
@@ -945,11 +1989,384 @@ impl fmt::Display for VerifyFailure<'_> {
             VerifyFailure::Rename { source, .. } => {
                 write!(f, "MISNAMED : {}", source.display())
             }
+            VerifyFailure::Interleaved { source, .. } => {
+                write!(f, "     SMD : {}", source.display())
+            }
             VerifyFailure::Bad { path, .. } => write!(f, "     BAD : {}", path.display()),
+            VerifyFailure::Overdump {
+                path,
+                expected,
+                size,
+                ..
+            } => write!(
+                f,
+                "OVERDUMP : {} ({} bytes, expected {})",
+                path.display(),
+                size,
+                expected.size().unwrap_or_default()
+            ),
             VerifyFailure::Error { path, err } => {
                 write!(f, "   ERROR : {} : {}", path.display(), err)
             }
+            VerifyFailure::Repairable { repair, .. } => write!(f, "  REPAIR : {repair}"),
+        }
+    }
+}
+
+impl VerifyFailure<'_> {
+    // the failure as a JSON object, for --output json
+    pub fn json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        fn part(part: &Part) -> serde_json::Value {
+            match part {
+                Part::Crc { .. } => json!({
+                    "crc": part.digest().to_string(),
+                    "size": part.size(),
+                }),
+                _ => json!({
+                    "sha1": part.digest().to_string(),
+                    "size": part.size(),
+                }),
+            }
+        }
+
+        match self {
+            VerifyFailure::Missing {
+                path,
+                name,
+                part: p,
+            } => json!({
+                "status": "missing",
+                "path": path.display().to_string(),
+                "name": name,
+                "expected": part(p),
+            }),
+            VerifyFailure::Extra { path, part: p } => json!({
+                "status": "extra",
+                "path": path.display().to_string(),
+                "actual": p.as_ref().ok().map(part),
+            }),
+            VerifyFailure::ExtraDir { path } => json!({
+                "status": "extra",
+                "path": path.display().to_string(),
+            }),
+            VerifyFailure::Rename {
+                source,
+                destination,
+            } => json!({
+                "status": "misnamed",
+                "path": source.display().to_string(),
+                "destination": destination.display().to_string(),
+            }),
+            VerifyFailure::Interleaved {
+                source,
+                destination,
+                part: p,
+            } => json!({
+                "status": "smd",
+                "path": source.display().to_string(),
+                "destination": destination.display().to_string(),
+                "expected": part(p),
+            }),
+            VerifyFailure::Bad {
+                path,
+                name,
+                expected,
+                actual,
+            } => json!({
+                "status": "bad",
+                "path": path.display().to_string(),
+                "name": name,
+                "expected": part(expected),
+                "actual": actual.as_ref().map(part),
+            }),
+            VerifyFailure::Overdump {
+                path,
+                name,
+                expected,
+                size,
+            } => json!({
+                "status": "overdump",
+                "path": path.display().to_string(),
+                "name": name,
+                "expected": part(expected),
+                "size": size,
+            }),
+            VerifyFailure::Error { path, err } => json!({
+                "status": "error",
+                "path": path.display().to_string(),
+                "error": err.to_string(),
+            }),
+            VerifyFailure::Repairable { path, repair } => json!({
+                "status": "repairable",
+                "path": path.display().to_string(),
+                "repair": repair,
+            }),
+        }
+    }
+}
+
+// extras are keyed by SHA-1, so one holding a part known only by its CRC32
+// has to be found by checking each extra of the right size
+fn remove_crc_extra(extras: &PartMap<PathBuf>, part: &Part) -> Option<(Part, PathBuf)> {
+    if !matches!(part, Part::Crc { .. }) {
+        return None;
+    }
+
+    let candidates = extras
+        .iter()
+        .filter(|entry| {
+            part.size()
+                .is_none_or(|size| entry.value().metadata().is_ok_and(|m| m.len() == size))
+        })
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect::<Vec<_>>();
+
+    candidates
+        .into_iter()
+        .find(|(_, path)| part.is_valid(path).unwrap_or(false))
+        .and_then(|(extra, _)| extras.remove(&extra))
+}
+
+// flushes a freshly written file to its device and reads it back,
+// removing it if it doesn't match the part it was written from
+// so that it's never mistaken for a good copy
+fn verify_written(path: &Path, part: &Part) -> Result<(), Error> {
+    evict(path)?;
+
+    if part.is_valid(path)? {
+        Ok(())
+    } else {
+        std::fs::remove_file(path)?;
+        Err(Error::WriteVerifyFailed(path.to_owned()))
+    }
+}
+
+// flushes a file to its device and drops it from the page cache,
+// so that reading it back reads what's actually on the device
+// rather than what was just written to memory
+pub fn evict(path: &Path) -> Result<(), std::io::Error> {
+    let f = std::fs::File::options().write(true).open(path)?;
+    f.sync_all()?;
+
+    // purely advisory, where pages that stay cached are merely read from memory
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    let _ = rustix::fs::fadvise(&f, 0, None, rustix::fs::Advice::DontNeed);
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RepairPolicy {
+    // rename local source files into place instead of linking or copying
+    pub move_sources: bool,
+    // fill in missing files only, without deleting, renaming or overwriting
+    pub add_only: bool,
+    // leave extra files in place and don't count them as failures
+    pub keep_extra: bool,
+    // the modification time given to copied files
+    pub timestamp: Timestamp,
+    // truncate overdumped files instead of replacing them
+    pub trim_overdumps: bool,
+    // re-hash copied files once written, before caching their parts
+    pub verify_writes: bool,
+    // report each repair without touching the filesystem
+    pub dry_run: bool,
+    // where deleted and replaced files are moved to, instead of being removed
+    pub backup_dir: Option<PathBuf>,
+}
+
+impl RepairPolicy {
+    // the policy for repairing files from a DAT with the given date
+    pub fn dated(&self, date: Option<std::time::SystemTime>) -> Self {
+        Self {
+            timestamp: match self.timestamp {
+                Timestamp::Source => Timestamp::Source,
+                Timestamp::Dat(_) => Timestamp::Dat(date),
+            },
+            ..self.clone()
+        }
+    }
+
+    // removes a file being deleted or replaced,
+    // or moves it to the backup directory if there is one
+    pub fn remove(&self, path: &Path) -> Result<(), std::io::Error> {
+        crate::protect::unprotect(path);
+        match &self.backup_dir {
+            Some(dir) => crate::quarantine::store(dir, path),
+            None => std::fs::remove_file(path),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum Timestamp {
+    // the time of whatever the file was copied from
+    #[default]
+    Source,
+    // the date declared by the DAT, if it has one
+    Dat(Option<std::time::SystemTime>),
+}
+
+// missing or bad parts collected during verification
+// which can be exported for use by external tools
+#[derive(Default)]
+pub struct NeededParts(std::sync::Mutex<BTreeSet<(String, String)>>);
+
+impl NeededParts {
+    pub fn record(&self, failures: &[VerifyFailure]) {
+        let mut needed = self.0.lock().unwrap();
+        needed.extend(
+            failures
+                .iter()
+                .filter_map(|f| f.needed())
+                // parts known only by their CRC32 have no SHA-1 to list
+                .filter(|(_, part)| !matches!(part, Part::Crc { .. }))
+                .map(|(name, part)| (name.to_owned(), part.digest().to_string())),
+        );
+    }
+
+    // writes one "<sha1>  <name>" line per part, like sha1sum
+    pub fn export(self, path: &Path) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for (name, sha1) in self.0.into_inner().unwrap() {
+            writeln!(w, "{sha1}  {name}")?;
+        }
+        w.flush()
+    }
+}
+
+// complete and incomplete sets collected during verification,
+// along with the parts each incomplete set needs,
+// which can be exported as have and miss lists
+// in the format ClrMamePro and datutil use, or as a fixdat
+#[derive(Default)]
+pub struct SetLists(std::sync::Mutex<BTreeMap<String, BTreeMap<String, Part>>>);
+
+impl SetLists {
+    // a set is had unless any of its parts are missing or bad
+    #[inline]
+    pub fn record(&self, set: &str, failures: &[VerifyFailure]) {
+        self.record_set(set.to_owned(), failures.iter().filter_map(|f| f.needed()));
+    }
+
+    #[inline]
+    pub fn record_set<'p>(
+        &self,
+        set: String,
+        needed: impl IntoIterator<Item = (&'p str, &'p Part)>,
+    ) {
+        self.0.lock().unwrap().insert(
+            set,
+            needed
+                .into_iter()
+                .map(|(name, part)| (name.to_owned(), part.clone()))
+                .collect(),
+        );
+    }
+
+    // writes one set name per line to the have and/or miss lists,
+    // and the incomplete sets' needed parts to the fixdat
+    pub fn export(
+        self,
+        have: Option<&Path>,
+        miss: Option<&Path>,
+        fixdat: Option<&Path>,
+    ) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        let sets = self.0.into_inner().unwrap();
+
+        for (path, wanted) in [(have, true), (miss, false)] {
+            if let Some(path) = path {
+                let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+                for (set, _) in sets
+                    .iter()
+                    .filter(|(_, needed)| needed.is_empty() == wanted)
+                {
+                    writeln!(w, "{set}")?;
+                }
+                w.flush()?;
+            }
+        }
+
+        if let Some(path) = fixdat {
+            let mut w = std::io::BufWriter::new(std::fs::File::create(path)?);
+            crate::dat::write_dat(
+                &mut w,
+                &path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy())
+                    .unwrap_or_else(|| "fixdat".into()),
+                sets.iter()
+                    .filter(|(_, needed)| !needed.is_empty())
+                    .map(|(set, needed)| (set.as_str(), needed)),
+            )?;
+            w.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+// how many of each game's missing or bad parts the sources can supply,
+// for deciding whether a repair is worth running
+#[derive(Default)]
+pub struct Preflight(std::sync::Mutex<BTreeMap<String, (usize, usize)>>);
+
+impl Preflight {
+    // a game's needed parts are available if the sources have them,
+    // while misnamed or interleaved files in place only need fixing up
+    pub fn record<'f, 's: 'f>(
+        &self,
+        game: String,
+        failures: impl IntoIterator<Item = &'f VerifyFailure<'s>>,
+        sources: &RomSources,
+    ) {
+        let (available, remaining) =
+            failures
+                .into_iter()
+                .fold((0, 0), |(available, remaining), failure| match failure {
+                    VerifyFailure::Rename { .. } | VerifyFailure::Interleaved { .. } => {
+                        (available + 1, remaining)
+                    }
+                    failure => match failure.needed() {
+                        Some((_, part)) if sources.contains_key(part) => (available + 1, remaining),
+                        Some(_) => (available, remaining + 1),
+                        None => (available, remaining),
+                    },
+                });
+
+        if available + remaining > 0 {
+            self.0.lock().unwrap().insert(game, (available, remaining));
+        }
+    }
+
+    pub fn display(self) {
+        let games = self.0.into_inner().unwrap();
+
+        let mut table = crate::table::new();
+        table.set_header(vec!["Game", "Available", "Remaining"]);
+        for (game, (available, remaining)) in &games {
+            table.add_row(vec![
+                game.clone(),
+                available.to_string(),
+                remaining.to_string(),
+            ]);
         }
+        println!("{table}");
+
+        let available = games.values().map(|(a, _)| a).sum::<usize>();
+        let remaining = games.values().map(|(_, r)| r).sum::<usize>();
+        let completed = games.values().filter(|(_, r)| *r == 0).count();
+        eprintln!("* {available} missing or bad parts available, {remaining} would remain missing");
+        eprintln!(
+            "* {completed} of {} incomplete games would be complete",
+            games.len()
+        );
     }
 }
 
@@ -964,6 +2381,14 @@ pub enum Repaired<'u> {
         destination: PathBuf,
     },
     Deleted(PathBuf),
+    Converted {
+        source: PathBuf,
+        destination: PathBuf,
+    },
+    Trimmed {
+        path: PathBuf,
+        size: u64,
+    },
 }
 
 impl<'u> Repaired<'u> {
@@ -971,6 +2396,8 @@ impl<'u> Repaired<'u> {
         match self {
             Self::Extracted { target, .. } => Some(target),
             Self::Moved { destination, .. } => Some(destination),
+            Self::Trimmed { path, .. } => Some(path),
+            Self::Converted { destination, .. } => Some(destination),
             Self::Deleted(_) => None,
         }
     }
@@ -984,29 +2411,67 @@ impl<'u> fmt::Display for Repaired<'u> {
                 source,
                 target,
             } => {
-                write!(f, "{} \u{21D2} {}", source, target.display())
+                write!(
+                    f,
+                    "{} {} {}",
+                    source,
+                    crate::glyphs::copied(),
+                    target.display()
+                )
             }
             Self::Extracted {
                 extracted: Extracted::Copied { rate: Some(rate) },
                 source,
                 target,
             } => {
-                write!(f, "{} \u{21D2} {} ({})", source, target.display(), rate)
+                write!(
+                    f,
+                    "{} {} {} ({})",
+                    source,
+                    crate::glyphs::copied(),
+                    target.display(),
+                    rate
+                )
             }
             Self::Extracted {
-                extracted: Extracted::Linked { .. },
+                extracted: Extracted::Linked { .. } | Extracted::Moved { .. },
                 source,
                 target,
             } => {
-                write!(f, "{} \u{2192} {}", source, target.display())
+                write!(
+                    f,
+                    "{} {} {}",
+                    source,
+                    crate::glyphs::moved(),
+                    target.display()
+                )
             }
             Self::Moved {
                 source,
                 destination,
             } => {
-                write!(f, "{} \u{2192} {}", source.display(), destination.display())
+                write!(
+                    f,
+                    "{} {} {}",
+                    source.display(),
+                    crate::glyphs::moved(),
+                    destination.display()
+                )
             }
             Self::Deleted(path) => write!(f, "removed : {}", path.display()),
+            Self::Converted {
+                source,
+                destination,
+            } => write!(
+                f,
+                "{} {} {} (de-interleaved)",
+                source.display(),
+                crate::glyphs::copied(),
+                destination.display()
+            ),
+            Self::Trimmed { path, size } => {
+                write!(f, "trimmed : {} to {} bytes", path.display(), size)
+            }
         }
     }
 }
@@ -1173,16 +2638,71 @@ impl FileId {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Part {
-    Rom { sha1: [u8; 20] },
-    Disk { sha1: [u8; 20] },
+    Rom {
+        sha1: [u8; 20],
+        // size declared by the DAT, if any,
+        // which plays no part in comparisons
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    Disk {
+        sha1: [u8; 20],
+    },
+    // a ROM whose DAT gives only its CRC32,
+    // with its size likewise playing no part in comparisons
+    Crc {
+        crc32: [u8; 4],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+}
+
+impl PartialEq for Part {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Part::Rom { sha1: x, .. }, Part::Rom { sha1: y, .. }) => x == y,
+            (Part::Disk { sha1: x }, Part::Disk { sha1: y }) => x == y,
+            (Part::Crc { crc32: x, .. }, Part::Crc { crc32: y, .. }) => x == y,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Part {}
+
+impl std::hash::Hash for Part {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Part::Rom { sha1, .. } | Part::Disk { sha1 } => sha1.hash(state),
+            Part::Crc { crc32, .. } => crc32.hash(state),
+        }
+    }
 }
 
 impl Part {
     #[inline]
     pub fn new_rom(sha1: &str) -> Result<Self, hex::FromHexError> {
-        parse_sha1(sha1).map(|sha1| Part::Rom { sha1 })
+        parse_sha1(sha1).map(|sha1| Part::Rom { sha1, size: None })
+    }
+
+    #[inline]
+    pub fn with_size(self, size: Option<u64>) -> Self {
+        match self {
+            Part::Rom { sha1, .. } => Part::Rom { sha1, size },
+            Part::Crc { crc32, .. } => Part::Crc { crc32, size },
+            disk @ Part::Disk { .. } => disk,
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            Part::Rom { size, .. } | Part::Crc { size, .. } => *size,
+            Part::Disk { .. } => None,
+        }
     }
 
     #[inline]
@@ -1190,16 +2710,25 @@ impl Part {
         parse_sha1(sha1).map(|sha1| Part::Disk { sha1 })
     }
 
+    #[inline]
+    pub fn new_crc(crc: &str) -> Result<Self, hex::FromHexError> {
+        let mut crc32 = [0; 4];
+
+        hex::decode_to_slice(crc.trim().as_bytes(), &mut crc32)
+            .map(|()| Part::Crc { crc32, size: None })
+    }
+
     #[inline]
     pub fn new_empty() -> Self {
-        Self::from_slice(b"").unwrap()
+        Self::from_slice(b"").unwrap().with_size(Some(0))
     }
 
     #[inline]
-    pub fn digest(&self) -> Digest {
+    pub fn digest(&self) -> Digest<'_> {
         match self {
-            Part::Rom { sha1 } => Digest(sha1),
+            Part::Rom { sha1, .. } => Digest(sha1),
             Part::Disk { sha1 } => Digest(sha1),
+            Part::Crc { crc32, .. } => Digest(crc32),
         }
     }
 
@@ -1208,9 +2737,12 @@ impl Part {
         use std::fs::File;
         use std::io::BufReader;
 
-        File::open(path)
-            .map(BufReader::new)
-            .and_then(|mut r| Part::from_reader(&mut r))
+        let file = crate::throughput::DeviceReader::new(path, File::open(path)?);
+
+        match crate::sequential::device_guard(path) {
+            Some(_guard) => crate::sequential::read_ahead(file, Part::from_reader),
+            None => Part::from_reader(BufReader::with_capacity(buffer_size(), file)),
+        }
     }
 
     fn from_cached_path(path: &Path) -> Result<Self, std::io::Error> {
@@ -1239,7 +2771,7 @@ impl Part {
 
     #[cfg(not(target_os = "windows"))]
     pub fn get_xattr(path: &Path) -> Option<Self> {
-        if xattr::SUPPORTED_PLATFORM {
+        let part = if xattr::SUPPORTED_PLATFORM {
             xattr::get(path, CACHE_XATTR)
                 .ok()
                 .flatten()
@@ -1247,7 +2779,7 @@ impl Part {
                     [b'r', sha1_hex @ ..] => {
                         let mut sha1 = [0; 20];
                         hex::decode_to_slice(sha1_hex, &mut sha1)
-                            .map(|()| Self::Rom { sha1 })
+                            .map(|()| Self::Rom { sha1, size: None })
                             .ok()
                     }
                     [b'd', sha1_hex @ ..] => {
@@ -1260,11 +2792,16 @@ impl Part {
                 })
         } else {
             None
-        }
+        };
+
+        crate::throughput::cached(part.is_some());
+
+        part
     }
 
     #[cfg(target_os = "windows")]
     pub fn get_xattr(_path: &Path) -> Option<Self> {
+        crate::throughput::cached(false);
         None
     }
 
@@ -1273,7 +2810,7 @@ impl Part {
         if xattr::SUPPORTED_PLATFORM {
             let mut attr = [0; 41];
             match self {
-                Self::Rom { sha1 } => {
+                Self::Rom { sha1, .. } => {
                     attr[0] = b'r';
                     hex::encode_to_slice(sha1, &mut attr[1..]).unwrap();
                 }
@@ -1281,6 +2818,9 @@ impl Part {
                     attr[0] = b'd';
                     hex::encode_to_slice(sha1, &mut attr[1..]).unwrap();
                 }
+                // the cache holds a file's SHA-1,
+                // which a CRC32 can't stand in for
+                Self::Crc { .. } => return,
             }
 
             let _ = xattr::set(path, CACHE_XATTR, &attr);
@@ -1336,64 +2876,167 @@ impl Part {
         Self::from_reader(std::io::Cursor::new(bytes))
     }
 
-    fn from_reader<R: Read>(r: R) -> Result<Self, std::io::Error> {
-        use std::io::{copy, sink};
-
+    pub fn from_reader<R: Read>(r: R) -> Result<Self, std::io::Error> {
         let mut r = Sha1Reader::new(r);
         match Part::disk_from_reader(&mut r) {
-            Ok(Some(part)) => Ok(part),
-            Ok(None) => copy(&mut r, &mut sink()).map(|_| r.into()),
+            Ok(Some(part)) => {
+                crate::throughput::hashed(r.bytes);
+                Ok(part)
+            }
+            Ok(None) => r.hash_remaining().map(|()| r.into()),
             Err(err) => Err(err),
         }
     }
 
+    #[inline]
     fn disk_from_reader<R: Read>(r: R) -> Result<Option<Self>, std::io::Error> {
-        use bitstream_io::{BigEndian, ByteRead, ByteReader};
+        Ok(ChdHeader::from_reader(r)?.map(|header| Part::Disk { sha1: header.sha1 }))
+    }
 
-        let mut r = ByteReader::endian(r, BigEndian);
+    pub fn verify<'s>(
+        &'s self,
+        name: &'s str,
+        path: PathBuf,
+    ) -> Result<VerifySuccess, VerifyFailure<'s>> {
+        // a file of the wrong size can't possibly match,
+        // so there's no need to hash it
+        if let Some(size) = self.size() {
+            if let Some(actual) = path.metadata().ok().map(|m| m.len()).filter(|l| *l > size) {
+                if self.is_overdump(&path, size).unwrap_or(false) {
+                    tracing::debug!(name, expected = size, actual, "overdump");
+                    return Err(VerifyFailure::Overdump {
+                        path,
+                        name,
+                        expected: self,
+                        size: actual,
+                    });
+                }
+            }
 
-        if r.read::<[u8; 8]>()
-            .map(|tag| &tag != b"MComprHD")
-            .unwrap_or(true)
-        {
-            return Ok(None);
+            if path.metadata().is_ok_and(|m| m.len() != size) {
+                tracing::debug!(name, expected = size, "bad: wrong size, not hashed");
+                return Err(VerifyFailure::Bad {
+                    path,
+                    name,
+                    expected: self,
+                    actual: None,
+                });
+            }
         }
 
-        // at this point we'll treat the file as a CHD
+        let disk_part = match self {
+            Part::Crc { .. } => Part::crc_from_path(&path),
+            _ => Part::from_cached_path(&path),
+        };
 
-        r.skip(4)?; // unused length field
+        match disk_part {
+            Ok(ref disk_part) if self == disk_part => Ok(VerifySuccess),
+            Ok(disk_part) => {
+                tracing::debug!(name, expected = %self.digest(), actual = %disk_part.digest(), "bad: wrong hash");
+                Err(VerifyFailure::Bad {
+                    path,
+                    name,
+                    expected: self,
+                    actual: Some(disk_part),
+                })
+            }
+            Err(err) => {
+                tracing::debug!(name, %err, "unable to read file");
+                Err(VerifyFailure::Error { path, err })
+            }
+        }
+    }
 
-        let bytes_to_skip = match r.read::<u32>()? {
-            3 => (32 + 32 + 32 + 64 + 64 + 8 * 16 + 8 * 16 + 32) / 8,
-            4 => (32 + 32 + 32 + 64 + 64 + 32) / 8,
-            5 => (32 * 4 + 64 + 64 + 64 + 32 + 32 + 8 * 20) / 8,
-            _ => return Ok(None),
-        };
-        r.skip(bytes_to_skip)?;
+    // the de-interleaved part of a Super Magic Drive dump of the given size,
+    // or None if it doesn't look like one
+    fn from_smd_reader<R: Read>(mut r: R, size: u64) -> Option<Self> {
+        let blocks = size.checked_sub(SMD_HEADER as u64)?;
+        if blocks == 0 || blocks % SMD_BLOCK as u64 != 0 {
+            return None;
+        }
+
+        let mut header = [0; SMD_HEADER];
+        r.read_exact(&mut header).ok()?;
+        if header[8..11] != [0xAA, 0xBB, 0x06] {
+            return None;
+        }
 
-        Ok(Some(Part::Disk { sha1: r.read()? }))
+        let mut plain = Vec::with_capacity(blocks as usize);
+        deinterleave_smd(r, &mut plain).ok()?;
+        Part::from_slice(&plain).ok()
     }
 
-    pub fn verify<'s>(
-        &'s self,
-        name: &'s str,
-        path: PathBuf,
-    ) -> Result<VerifySuccess, VerifyFailure<'s>> {
-        match Part::from_cached_path(path.as_ref()) {
-            Ok(ref disk_part) if self == disk_part => Ok(VerifySuccess),
-            Ok(disk_part) => Err(VerifyFailure::Bad {
-                path,
-                name,
-                expected: self,
-                actual: disk_part,
-            }),
-            Err(err) => Err(VerifyFailure::Error { path, err }),
+    pub fn from_smd_path(path: &Path) -> Option<Self> {
+        let size = path.metadata().ok()?.len();
+        let r = std::fs::File::open(path)
+            .map(std::io::BufReader::new)
+            .ok()?;
+        Self::from_smd_reader(r, size)
+    }
+
+    // whether the file's first bytes are this part
+    // and everything after them is the same padding byte
+    fn is_overdump(&self, path: &Path, size: u64) -> Result<bool, std::io::Error> {
+        let Part::Rom { sha1, .. } = self else {
+            return Ok(false);
+        };
+
+        let mut r = std::io::BufReader::new(std::fs::File::open(path)?);
+
+        let mut prefix = Sha1Reader::new(Read::take(&mut r, size));
+        prefix.hash_remaining()?;
+        if prefix.bytes != size || &prefix.sha1() != sha1 {
+            return Ok(false);
+        }
+
+        let mut buf = vec![0; buffer_size()];
+        let mut padding = None;
+        loop {
+            match r.read(&mut buf) {
+                Ok(0) => break Ok(padding.is_some()),
+                Ok(bytes) => {
+                    let pad = *padding.get_or_insert(buf[0]);
+                    if !matches!(pad, 0x00 | 0xFF) || buf[..bytes].iter().any(|b| *b != pad) {
+                        break Ok(false);
+                    }
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => break Err(err),
+            }
         }
     }
 
     #[inline]
     pub fn is_valid(&self, path: &Path) -> Result<bool, std::io::Error> {
-        Part::from_path(path).map(|disk_part| self == &disk_part)
+        match self {
+            Part::Crc { .. } => Part::crc_from_path(path).map(|disk_part| self.matches(&disk_part)),
+            _ => Part::from_path(path).map(|disk_part| self == &disk_part),
+        }
+    }
+
+    // a file's CRC32 and size,
+    // for comparing against parts known only by their CRC32
+    fn crc_from_path(path: &Path) -> Result<Self, std::io::Error> {
+        Ok(Part::Crc {
+            crc32: crate::checksum::file_crc32(path)?.to_be_bytes(),
+            size: Some(path.metadata()?.len()),
+        })
+    }
+
+    // whether the other part is this one, including its size if both are known,
+    // since CRC32s alone are far more likely to collide than SHA-1s
+    #[inline]
+    pub fn matches(&self, other: &Part) -> bool {
+        self == other
+            && match (self.size(), other.size()) {
+                (Some(x), Some(y)) => x == y,
+                _ => true,
+            }
+    }
+
+    #[inline]
+    pub fn is_disk(&self) -> bool {
+        matches!(self, Part::Disk { .. })
     }
 
     #[inline]
@@ -1405,6 +3048,7 @@ impl Part {
 struct Sha1Reader<R> {
     reader: R,
     sha1: Sha1,
+    bytes: u64,
 }
 
 impl<R> Sha1Reader<R> {
@@ -1413,23 +3057,310 @@ impl<R> Sha1Reader<R> {
         Sha1Reader {
             reader,
             sha1: Sha1::new(),
+            bytes: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for Sha1Reader<R> {
+    fn read(&mut self, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        let bytes = self.reader.read(data)?;
+        self.sha1.update(&data[0..bytes]);
+        self.bytes += bytes as u64;
+        Ok(bytes)
+    }
+}
+
+impl<R: Read> Sha1Reader<R> {
+    // hashes the rest of the reader in large blocks,
+    // rather than std::io::copy's small ones
+    fn hash_remaining(&mut self) -> Result<(), std::io::Error> {
+        let mut buf = vec![0; buffer_size()];
+
+        loop {
+            match self.reader.read(&mut buf) {
+                Ok(0) => break Ok(()),
+                Ok(bytes) => {
+                    self.sha1.update(&buf[0..bytes]);
+                    self.bytes += bytes as u64;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => break Err(err),
+            }
+        }
+    }
+}
+
+impl<R> Sha1Reader<R> {
+    #[inline]
+    fn sha1(self) -> [u8; 20] {
+        crate::throughput::hashed(self.bytes);
+        #[cfg(feature = "fast-sha1")]
+        return self.sha1.finalize().into();
+        #[cfg(not(feature = "fast-sha1"))]
+        return self.sha1.digest().bytes();
+    }
+}
+
+impl<R> From<Sha1Reader<R>> for Part {
+    #[inline]
+    fn from(other: Sha1Reader<R>) -> Part {
+        Part::Rom {
+            size: Some(other.bytes),
+            sha1: other.sha1(),
+        }
+    }
+}
+
+static BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_BUFFER_SIZE);
+
+pub const DEFAULT_BUFFER_SIZE: usize = 1 << 20;
+
+// the size of reads when hashing files,
+// where larger sizes help fast NVMe and network filesystems
+pub fn set_buffer_size(size: usize) {
+    BUFFER_SIZE.store(size.max(1), std::sync::atomic::Ordering::Relaxed);
+}
+
+#[inline]
+pub fn buffer_size() -> usize {
+    BUFFER_SIZE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// the parts of a CHD's header which decide whether MAME accepts it
+pub struct ChdHeader {
+    pub version: u32,
+    pub compressors: Vec<String>,
+    pub logical_size: u64,
+    // the SHA-1 of the uncompressed data and metadata
+    pub sha1: [u8; 20],
+    // the SHA-1 of the parent CHD, for CHDs which are diffs of another
+    pub parent: Option<[u8; 20]>,
+    // where the chain of metadata entries starts, or 0 if there are none
+    metadata_offset: u64,
+    // the tag of each metadata entry, once scanned
+    pub metadata: Vec<[u8; 4]>,
+}
+
+// a CHD which can't be hashed the way MAME would,
+// rather than a file to be hashed as a plain ROM
+#[derive(Clone, Debug)]
+pub enum ChdError {
+    // either too old to have SHA-1s or newer than this version knows
+    UnsupportedVersion(u32),
+    // a header whose length doesn't match its version's layout
+    HeaderLength { version: u32, length: u32 },
+}
+
+impl fmt::Display for ChdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(f, "unsupported CHD version {version}"),
+            Self::HeaderLength { version, length } => write!(
+                f,
+                "CHD v{version} header is {length} bytes, expected {}",
+                ChdHeader::header_length(*version).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChdError {}
+
+impl ChdError {
+    // recovers a CHD error from the I/O error it was raised as
+    pub fn from_io(err: &std::io::Error) -> Option<Self> {
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<Self>())
+            .cloned()
+    }
+}
+
+impl From<ChdError> for std::io::Error {
+    #[inline]
+    fn from(err: ChdError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+impl ChdHeader {
+    const TAG: &'static [u8; 8] = b"MComprHD";
+
+    // the tag, header length and version which every version starts with
+    const PREAMBLE: usize = 16;
+
+    // metadata entries are chained by offset, so a damaged CHD
+    // could otherwise loop forever
+    const MAX_METADATA: usize = 256;
+
+    // the header's total length in each version with SHA-1s
+    fn header_length(version: u32) -> Option<u32> {
+        match version {
+            3 => Some(120),
+            4 => Some(108),
+            5 => Some(124),
+            _ => None,
+        }
+    }
+
+    // Ok(None) if the reader isn't a CHD,
+    // or an InvalidData error if it's a CHD this can't hash
+    pub fn from_reader<R: Read>(mut r: R) -> Result<Option<Self>, std::io::Error> {
+        // flag set by v3 and v4 headers for CHDs with a parent
+        const HAS_PARENT: u32 = 0x1;
+
+        fn u32_at(header: &[u8], offset: usize) -> u32 {
+            u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap())
+        }
+
+        fn u64_at(header: &[u8], offset: usize) -> u64 {
+            u64::from_be_bytes(header[offset..offset + 8].try_into().unwrap())
+        }
+
+        fn sha1_at(header: &[u8], offset: usize) -> [u8; 20] {
+            header[offset..offset + 20].try_into().unwrap()
+        }
+
+        let mut tag = [0; 8];
+        if r.read_exact(&mut tag).is_err() || &tag != Self::TAG {
+            return Ok(None);
+        }
+
+        // at this point we'll treat the file as a CHD
+
+        let mut preamble = [0; Self::PREAMBLE - 8];
+        r.read_exact(&mut preamble)?;
+        let length = u32_at(&preamble, 0);
+        let version = u32_at(&preamble, 4);
+
+        match Self::header_length(version) {
+            Some(expected) if expected == length => {}
+            Some(_) => return Err(ChdError::HeaderLength { version, length }.into()),
+            None => return Err(ChdError::UnsupportedVersion(version).into()),
+        }
+
+        // the whole header, so that fields are read at their offsets
+        // rather than by skipping from one to the next
+        let mut header = vec![0; length as usize];
+        header[..8].copy_from_slice(&tag);
+        header[8..Self::PREAMBLE].copy_from_slice(&preamble);
+        r.read_exact(&mut header[Self::PREAMBLE..])?;
+
+        Ok(Some(match version {
+            3 | 4 => {
+                let (sha1, parent) = match version {
+                    3 => (80, 100),
+                    _ => (48, 68),
+                };
+
+                Self {
+                    version,
+                    compressors: vec![match u32_at(&header, 20) {
+                        0 => "none".to_owned(),
+                        1 => "zlib".to_owned(),
+                        2 => "zlib+".to_owned(),
+                        3 => "avhuff".to_owned(),
+                        other => format!("unknown ({other})"),
+                    }],
+                    logical_size: u64_at(&header, 28),
+                    sha1: sha1_at(&header, sha1),
+                    parent: (u32_at(&header, 16) & HAS_PARENT != 0)
+                        .then(|| sha1_at(&header, parent)),
+                    metadata_offset: u64_at(&header, 36),
+                    metadata: vec![],
+                }
+            }
+            _ => {
+                let parent = sha1_at(&header, 104);
+
+                Self {
+                    version,
+                    compressors: header[16..32]
+                        .chunks(4)
+                        .filter(|codec| *codec != [0; 4])
+                        .map(|codec| String::from_utf8_lossy(codec).into_owned())
+                        .collect(),
+                    logical_size: u64_at(&header, 32),
+                    sha1: sha1_at(&header, 84),
+                    parent: (parent != [0; 20]).then_some(parent),
+                    metadata_offset: u64_at(&header, 48),
+                    metadata: vec![],
+                }
+            }
+        }))
+    }
+
+    // follows the chain of metadata entries from the header,
+    // recording each one's tag
+    pub fn scan_metadata<R: Read + Seek>(&mut self, mut r: R) -> Result<(), std::io::Error> {
+        let mut offset = self.metadata_offset;
+        let mut seen = HashSet::new();
+
+        while offset != 0 && seen.insert(offset) && self.metadata.len() < Self::MAX_METADATA {
+            // each entry is a tag, flags and length, then the next entry's offset
+            let mut entry = [0; 16];
+            r.seek(std::io::SeekFrom::Start(offset))?;
+            r.read_exact(&mut entry)?;
+            self.metadata.push(entry[0..4].try_into().unwrap());
+            offset = u64::from_be_bytes(entry[8..16].try_into().unwrap());
+        }
+
+        Ok(())
+    }
+
+    // the kind of media the CHD's metadata says it holds, if any
+    pub fn media(&self) -> Option<&'static str> {
+        self.metadata.iter().find_map(|tag| match tag {
+            b"CHCD" | b"CHTR" | b"CHT2" => Some("CD-ROM"),
+            b"CHGT" | b"CHGD" => Some("GD-ROM"),
+            b"DVD " => Some("DVD"),
+            b"GDDD" => Some("hard disk"),
+            b"AVAV" => Some("LaserDisc"),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for ChdHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CHD v{}, {}, {}",
+            self.version,
+            match self.compressors.as_slice() {
+                [] => "uncompressed".to_owned(),
+                compressors => compressors.join("/"),
+            },
+            Size(self.logical_size),
+        )?;
+        if let Some(media) = self.media() {
+            write!(f, ", {media}")?;
+        }
+        match &self.parent {
+            Some(parent) => write!(f, ", parent {}", hex::encode(parent)),
+            None => Ok(()),
         }
     }
 }
 
-impl<R: Read> Read for Sha1Reader<R> {
-    fn read(&mut self, data: &mut [u8]) -> Result<usize, std::io::Error> {
-        let bytes = self.reader.read(data)?;
-        self.sha1.update(&data[0..bytes]);
-        Ok(bytes)
-    }
+// the SHA-1 of some bytes as-is, without checking for CHD headers
+pub fn slice_sha1(bytes: &[u8]) -> [u8; 20] {
+    let mut sha1 = Sha1::new();
+    sha1.update(bytes);
+    #[cfg(feature = "fast-sha1")]
+    return sha1.finalize().into();
+    #[cfg(not(feature = "fast-sha1"))]
+    return sha1.digest().bytes();
 }
 
-impl<R> From<Sha1Reader<R>> for Part {
-    #[inline]
-    fn from(other: Sha1Reader<R>) -> Part {
-        Part::Rom {
-            sha1: other.sha1.digest().bytes(),
+// the SHA-1 of a file's raw contents, using cached hashes where possible
+pub fn file_sha1(path: &Path) -> Result<[u8; 20], std::io::Error> {
+    match Part::from_cached_path(path)? {
+        Part::Rom { sha1, .. } => Ok(sha1),
+        // a CHD's hash covers its uncompressed data rather than the file itself
+        Part::Disk { .. } | Part::Crc { .. } => {
+            let mut r = Sha1Reader::new(std::fs::File::open(path)?);
+            r.hash_remaining().map(|()| r.sha1())
         }
     }
 }
@@ -1441,6 +3372,7 @@ pub fn parse_sha1(hex: &str) -> Result<[u8; 20], hex::FromHexError> {
     hex::decode_to_slice(hex.trim().as_bytes(), &mut bin).map(|()| bin)
 }
 
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Digest<'a>(&'a [u8]);
 
 impl<'a> fmt::Display for Digest<'a> {
@@ -1454,6 +3386,8 @@ pub enum GameColumn {
     Description,
     Creator,
     Year,
+    Name,
+    Status,
 }
 
 impl FromStr for GameColumn {
@@ -1462,8 +3396,10 @@ impl FromStr for GameColumn {
     fn from_str(s: &str) -> Result<Self, String> {
         match s {
             "description" => Ok(GameColumn::Description),
-            "creator" => Ok(GameColumn::Creator),
+            "creator" | "publisher" => Ok(GameColumn::Creator),
             "year" => Ok(GameColumn::Year),
+            "name" => Ok(GameColumn::Name),
+            "status" => Ok(GameColumn::Status),
             _ => Err("invalid sort by value".to_string()),
         }
     }
@@ -1471,33 +3407,64 @@ impl FromStr for GameColumn {
 
 #[inline]
 pub fn find_files_style() -> ProgressStyle {
-    ProgressStyle::default_spinner()
-        .template("{spinner} {wide_msg} {pos}")
-        .unwrap()
+    crate::glyphs::ticks(
+        ProgressStyle::default_spinner()
+            .template("{spinner} {wide_msg} {pos}")
+            .unwrap(),
+    )
+}
+
+#[inline]
+pub fn bytes_style() -> ProgressStyle {
+    crate::glyphs::ticks(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner} {wide_msg} {binary_bytes} / {binary_total_bytes} \
+                 ({binary_bytes_per_sec}, ETA {eta})",
+            )
+            .unwrap(),
+    )
 }
 
 #[inline]
 pub fn verify_style() -> ProgressStyle {
-    ProgressStyle::default_bar()
-        .template("{spinner} {wide_msg} {pos} / {len}")
-        .unwrap()
+    crate::glyphs::ticks(
+        ProgressStyle::default_bar()
+            .template("{spinner} {wide_msg} {pos} / {len}")
+            .unwrap(),
+    )
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Compression {
     Zip { index: usize },
+    // a byte range of a larger file, such as a track of a combined disc image
+    Range { start: u64, end: u64 },
+    // a Mega Drive ROM interleaved by a Super Magic Drive copier
+    Smd,
+    // a byte range of a larger file preceded by silence the file leaves out,
+    // such as a track of a combined disc image without its pregaps
+    Gapped { pregap: u64, start: u64, end: u64 },
+    // the nth entry of a 7-Zip file, in the order it's decoded
+    SevenZip { index: usize },
 }
 
 impl std::fmt::Display for Compression {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Compression::Zip { index } => write!(f, "{}", index),
+            Compression::Range { start, end } => write!(f, "{}-{}", start, end),
+            Compression::Smd => write!(f, "smd"),
+            Compression::SevenZip { index } => write!(f, "7z{}", index),
+            Compression::Gapped { pregap, start, end } => {
+                write!(f, "{}+{}-{}", pregap, start, end)
+            }
         }
     }
 }
 
 impl Compression {
-    fn extract<R, W>(&self, i: R, mut o: W) -> Result<u64, Error>
+    fn extract<R, W>(&self, mut i: R, mut o: W) -> Result<u64, Error>
     where
         R: Read + Seek,
         W: std::io::Write,
@@ -1507,20 +3474,129 @@ impl Compression {
                 std::io::copy(&mut zip::ZipArchive::new(i)?.by_index(*index)?, &mut o)
                     .map_err(Error::IO)
             }
+            Self::Range { start, end } => {
+                i.seek(std::io::SeekFrom::Start(*start))?;
+                std::io::copy(&mut i.take(end - start), &mut o).map_err(Error::IO)
+            }
+            Self::Gapped { pregap, start, end } => {
+                std::io::copy(&mut std::io::repeat(0).take(*pregap), &mut o)?;
+                i.seek(std::io::SeekFrom::Start(*start))?;
+                std::io::copy(&mut i.take(end - start), &mut o)
+                    .map(|bytes| pregap + bytes)
+                    .map_err(Error::IO)
+            }
+            Self::Smd => {
+                i.seek(std::io::SeekFrom::Start(SMD_HEADER as u64))?;
+                deinterleave_smd(i, o).map_err(Error::IO)
+            }
+            Self::SevenZip { index } => {
+                let len = i.seek(std::io::SeekFrom::End(0))?;
+                i.seek(std::io::SeekFrom::Start(0))?;
+
+                // entries are decoded in order, even those skipped over,
+                // and a folder stops early only once its entry is found
+                let mut copied = None;
+                let mut entry = 0;
+                sevenz_rust::SevenZReader::new(i, len, sevenz_rust::Password::empty())?
+                    .for_each_entries(|_, r| {
+                        if copied.is_some() {
+                            return Ok(false);
+                        }
+                        if entry == *index {
+                            copied = Some(std::io::copy(r, &mut o)?);
+                            return Ok(false);
+                        }
+                        entry += 1;
+                        std::io::copy(r, &mut std::io::sink())?;
+                        Ok(true)
+                    })?;
+
+                copied.ok_or_else(|| Error::IO(std::io::ErrorKind::NotFound.into()))
+            }
         }
     }
 
-    fn extract_to_buf<R>(&self, i: R) -> Result<std::io::Cursor<Vec<u8>>, Error>
+    fn extract_to_spool<R>(&self, i: R) -> Result<crate::spool::Spooled, Error>
     where
         R: Read + Seek,
     {
-        let mut buf = Vec::new();
-        self.extract(i, &mut buf).map(|_| std::io::Cursor::new(buf))
+        let mut spool = crate::spool::Spool::default();
+        self.extract(i, &mut spool)?;
+        spool.finish().map_err(Error::IO)
+    }
+
+    // extracts from a file on disk, whose 7-Zip entries
+    // come from decoding the archive once per run
+    fn extract_from_file<W>(&self, file: &Path, o: W) -> Result<u64, Error>
+    where
+        W: std::io::Write,
+    {
+        match self {
+            Self::SevenZip { index } => crate::sevenz::extract(file, *index, o),
+            _ => self.extract(
+                std::io::BufReader::with_capacity(buffer_size(), std::fs::File::open(file)?),
+                o,
+            ),
+        }
+    }
+
+    fn extract_file_to_spool(&self, file: &Path) -> Result<crate::spool::Spooled, Error> {
+        let mut spool = crate::spool::Spool::default();
+        self.extract_from_file(file, &mut spool)?;
+        spool.finish().map_err(Error::IO)
     }
 }
 
 type ZipParts = Vec<Compression>;
 
+// whether a part is unpacked from an archive, rather than cut from a file
+fn is_archived(compression: &Compression) -> bool {
+    matches!(
+        compression,
+        Compression::Zip { .. } | Compression::SevenZip { .. }
+    )
+}
+
+#[cfg(unix)]
+fn metadata_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn metadata_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+// the modification time and any Unix permissions of a Zip file's entry,
+// preferring its extended timestamp, which is in UTC,
+// to its MS-DOS one, which records no time zone and is taken as UTC
+fn zip_entry_stamp(
+    file: &Path,
+    index: usize,
+) -> Result<(Option<std::time::SystemTime>, Option<u32>), Error> {
+    use std::time::{Duration, SystemTime};
+
+    let mut zip = zip::ZipArchive::new(std::io::BufReader::new(std::fs::File::open(file)?))?;
+    let entry = zip.by_index_raw(index)?;
+
+    let extended = entry.extra_data_fields().find_map(|field| match field {
+        zip::ExtraField::ExtendedTimestamp(timestamp) => timestamp
+            .mod_time()
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs.into())),
+        _ => None,
+    });
+
+    let modified = extended.or_else(|| {
+        let dos = entry.last_modified()?;
+        chrono::NaiveDate::from_ymd_opt(dos.year().into(), dos.month().into(), dos.day().into())?
+            .and_hms_opt(dos.hour().into(), dos.minute().into(), dos.second().into())
+            .map(|time| SystemTime::from(time.and_utc()))
+    });
+
+    Ok((modified, entry.unix_mode()))
+}
+
 #[derive(Clone, Debug)]
 pub enum RomSource<'u> {
     File {
@@ -1539,6 +3615,29 @@ pub enum RomSource<'u> {
 }
 
 impl<'u> RomSource<'u> {
+    // the header of an unzipped CHD, if this source is one
+    pub fn chd_header(&self) -> Option<ChdHeader> {
+        match self {
+            RomSource::File {
+                file, zip_parts, ..
+            } if zip_parts.is_empty() => {
+                let mut r = std::io::BufReader::new(std::fs::File::open(file).ok()?);
+                let mut header = ChdHeader::from_reader(&mut r).ok().flatten()?;
+                let _ = header.scan_metadata(r);
+                Some(header)
+            }
+            RomSource::Url {
+                data, zip_parts, ..
+            } if zip_parts.is_empty() => {
+                let mut r = std::io::Cursor::new(data.as_ref());
+                let mut header = ChdHeader::from_reader(&mut r).ok().flatten()?;
+                let _ = header.scan_metadata(r);
+                Some(header)
+            }
+            _ => None,
+        }
+    }
+
     // returns true if this source is more "local" than the other,
     // (that is, local files are more local than remote URLs,
     //  and non-zipped files are more local than zipped files)
@@ -1576,8 +3675,9 @@ impl<'u> RomSource<'u> {
         // return it as-is without any further parsing
         // and flag it so we don't attempt to set the xattr again
         if let Some(part) = Part::get_xattr(&pb) {
+            let size = pb.metadata().ok().map(|m| m.len());
             return Ok(vec![(
-                part,
+                part.with_size(size),
                 RomSource::File {
                     file: Arc::from(pb),
                     has_xattr: true,
@@ -1587,32 +3687,40 @@ impl<'u> RomSource<'u> {
         }
 
         let file = Arc::from(pb);
-        let mut r = File::open(&file).map(BufReader::new)?;
-
-        Ok(if is_zip(&mut r).unwrap_or(false) {
-            unpack_zip_parts(r, File::open(&file).map(BufReader::new)?)
-                .into_iter()
-                .map(|(part, zip_parts)| {
-                    (
-                        part,
-                        RomSource::File {
-                            file: Arc::clone(&file),
-                            has_xattr: false,
-                            zip_parts: zip_parts.into(),
-                        },
-                    )
-                })
-                .collect()
+        let device_reader = |f| crate::throughput::DeviceReader::new(&file, f);
+        let mut r = File::open(&file)
+            .map(device_reader)
+            .map(|f| BufReader::with_capacity(buffer_size(), f))?;
+        let _guard = crate::sequential::device_guard(&file);
+
+        let unpacked = if is_zip(&mut r).unwrap_or(false) {
+            unpack_zip_parts(r, File::open(&file).map(device_reader).map(BufReader::new)?)?
+        } else if is_7z(&mut r).unwrap_or(false) {
+            unpack_7z_parts(r, File::open(&file).map(device_reader).map(BufReader::new)?)?
         } else {
-            vec![(
+            return Ok(vec![(
                 Part::from_reader(&mut r)?,
                 RomSource::File {
                     file: Arc::clone(&file),
                     has_xattr: false,
                     zip_parts: ZipParts::default(),
                 },
-            )]
-        })
+            )]);
+        };
+
+        Ok(unpacked
+            .into_iter()
+            .map(|(part, zip_parts)| {
+                (
+                    part,
+                    RomSource::File {
+                        file: Arc::clone(&file),
+                        has_xattr: false,
+                        zip_parts: zip_parts.into(),
+                    },
+                )
+            })
+            .collect())
     }
 
     pub fn from_url(url: &'u str, progress: &MultiProgress) -> Result<Vec<(Part, Self)>, Error> {
@@ -1628,28 +3736,108 @@ impl<'u> RomSource<'u> {
             },
         )];
 
-        if matches!(data[..], [0x50, 0x4B, 0x03, 0x04, ..]) {
-            let sub_zip = std::io::Cursor::new(data.clone());
+        let sub_zip = std::io::Cursor::new(data.clone());
+
+        let unpacked = match data[..] {
+            [0x50, 0x4B, 0x03, 0x04, ..] => unpack_zip_parts(sub_zip.clone(), sub_zip)?,
+            [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, ..] => unpack_7z_parts(sub_zip.clone(), sub_zip)?,
+            _ => Vec::new(),
+        };
+
+        result.extend(unpacked.into_iter().map(|(part, zip_parts)| {
+            (
+                part,
+                RomSource::Url {
+                    url,
+                    data: data.clone(),
+                    zip_parts: zip_parts.into(),
+                },
+            )
+        }));
+
+        Ok(result)
+    }
 
-            result.extend(unpack_zip_parts(sub_zip.clone(), sub_zip).into_iter().map(
-                |(part, zip_parts)| {
+    // if the source is an iNES-headered ROM, either a plain file
+    // or within a Zip file, returns its headerless payload as a source
+    fn headerless_nes(&self) -> Option<(Part, Self)> {
+        self.converted(|r, size| {
+            let mut header = [0; 16];
+            r.read_exact(&mut header).ok()?;
+            (&header[0..4] == b"NES\x1a")
+                .then(|| Part::from_reader(r).ok())
+                .flatten()
+                .map(|part| {
                     (
                         part,
-                        RomSource::Url {
-                            url,
-                            data: data.clone(),
-                            zip_parts: zip_parts.into(),
+                        Compression::Range {
+                            start: 16,
+                            end: size,
                         },
                     )
-                },
-            ));
-        }
+                })
+        })
+    }
 
-        Ok(result)
+    // if the source is an SMD-interleaved Mega Drive ROM, either a plain file
+    // or within a Zip file, returns its de-interleaved payload as a source
+    fn deinterleaved_smd(&self) -> Option<(Part, Self)> {
+        self.converted(|r, size| {
+            Part::from_smd_reader(r, size).map(|part| (part, Compression::Smd))
+        })
+    }
+
+    // applies a conversion to a plain file or a file within a Zip file,
+    // which is given the file's contents and size
+    fn converted<F>(&self, convert: F) -> Option<(Part, Self)>
+    where
+        F: FnOnce(&mut dyn Read, u64) -> Option<(Part, Compression)>,
+    {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        let RomSource::File {
+            file, zip_parts, ..
+        } = self
+        else {
+            return None;
+        };
+
+        let (part, compression, mut zip_parts) = match zip_parts.as_slice() {
+            [] => {
+                let size = file.metadata().ok()?.len();
+                let (part, compression) =
+                    convert(&mut File::open(file).map(BufReader::new).ok()?, size)?;
+                (part, compression, Vec::new())
+            }
+            [Compression::Zip { index }] => {
+                let mut zip =
+                    zip::ZipArchive::new(File::open(file).map(BufReader::new).ok()?).ok()?;
+                let mut entry = zip.by_index(*index).ok()?;
+                let size = entry.size();
+                let (part, compression) = convert(&mut entry, size)?;
+                (part, compression, zip_parts.clone())
+            }
+            _ => return None,
+        };
+
+        zip_parts.push(compression);
+
+        Some((
+            part,
+            RomSource::File {
+                file: Arc::clone(file),
+                has_xattr: false,
+                zip_parts,
+            },
+        ))
     }
 
-    fn extract(&self, target: &Path) -> Result<Extracted, Error> {
-        use std::fs::{copy, hard_link, File};
+    // if move_source is set, plain files are renamed into place
+    // and fall back to linking or copying if the rename fails
+    // (such as when the target is on a different filesystem)
+    fn extract(&self, target: &Path, move_source: bool) -> Result<Extracted, Error> {
+        use std::fs::{copy, hard_link, rename, File};
 
         match self {
             RomSource::File {
@@ -1657,11 +3845,21 @@ impl<'u> RomSource<'u> {
                 has_xattr,
                 zip_parts,
             } => match zip_parts.as_slice() {
+                [] if move_source => rename(source, target)
+                    .map(|()| Extracted::Moved {
+                        has_xattr: *has_xattr,
+                    })
+                    .or_else(|err| {
+                        tracing::debug!(%err, "unable to move source, linking or copying instead");
+                        RomSource::extract(self, target, false)
+                    }),
+
                 [] => hard_link(source, target)
                     .map(|()| Extracted::Linked {
                         has_xattr: *has_xattr,
                     })
-                    .or_else(|_| {
+                    .or_else(|err| {
+                        tracing::debug!(%err, "unable to link source, copying instead");
                         Rate::from_copy(|| copy(source, target))
                             .map(|rate| Extracted::Copied { rate })
                             .map_err(Error::IO)
@@ -1669,14 +3867,13 @@ impl<'u> RomSource<'u> {
 
                 [c] => std::fs::File::create(target)
                     .map_err(Error::IO)
-                    .and_then(|w| Rate::from_copy(|| c.extract(File::open(source.as_ref())?, w)))
+                    .and_then(|w| Rate::from_copy(|| c.extract_from_file(source.as_ref(), w)))
                     .map(|rate| Extracted::Copied { rate }),
 
-                [c, rest @ ..] => extract_from_zip_file(
-                    rest,
-                    c.extract_to_buf(File::open(source.as_ref())?)?,
-                    target,
-                ),
+                [c, rest @ ..] => {
+                    let spooled = c.extract_file_to_spool(source.as_ref())?;
+                    extract_from_zip_file(rest, spooled.reader()?, target)
+                }
             },
 
             RomSource::Url {
@@ -1690,6 +3887,133 @@ impl<'u> RomSource<'u> {
     }
 }
 
+impl RomSource<'_> {
+    // gives a copied file its source's modification time and,
+    // on Unix, its permissions, or the DAT's date instead,
+    // where failing to do so doesn't fail the repair
+    fn stamp(&self, target: &Path, timestamp: Timestamp) {
+        use std::time::SystemTime;
+
+        // the modification time and any permissions of what was copied,
+        // which for a file in a Zip file are those of its entry
+        let (modified, mode): (Option<SystemTime>, Option<u32>) = match self {
+            RomSource::File {
+                file, zip_parts, ..
+            } => match zip_parts.as_slice() {
+                [Compression::Zip { index }, rest @ ..] if !rest.iter().any(is_archived) => {
+                    zip_entry_stamp(file, *index).unwrap_or_default()
+                }
+                // entries of 7-Zip files and nested archives
+                // are left with the time they were copied
+                parts if parts.iter().any(is_archived) => (None, None),
+                _ => match file.metadata() {
+                    Ok(metadata) => (metadata.modified().ok(), metadata_mode(&metadata)),
+                    Err(_) => (None, None),
+                },
+            },
+            RomSource::Url { .. } | RomSource::Empty => (None, None),
+        };
+
+        let modified = match timestamp {
+            Timestamp::Dat(Some(date)) => Some(date),
+            Timestamp::Dat(None) | Timestamp::Source => modified,
+        };
+
+        if let Some(modified) = modified {
+            if let Err(err) = std::fs::File::options()
+                .write(true)
+                .open(target)
+                .and_then(|f| f.set_modified(modified))
+            {
+                tracing::debug!(target = %target.display(), %err, "unable to set modification time");
+            }
+        }
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+
+            if let Err(err) =
+                std::fs::set_permissions(target, std::fs::Permissions::from_mode(mode & 0o7777))
+            {
+                tracing::debug!(target = %target.display(), %err, "unable to set permissions");
+            }
+        }
+    }
+
+    // writes the source's contents, such as a file within a Zip file,
+    // returning the number of bytes written
+    pub fn copy_to<W: std::io::Write>(&self, w: W) -> Result<u64, Error> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        fn copy_from<R: Read + Seek, W: std::io::Write>(
+            indexes: &[Compression],
+            mut r: R,
+            mut w: W,
+        ) -> Result<u64, Error> {
+            match indexes {
+                [] => std::io::copy(&mut r, &mut w).map_err(Error::IO),
+                [c] => c.extract(r, w),
+                [c, rest @ ..] => {
+                    let spooled = c.extract_to_spool(r)?;
+                    copy_from(rest, spooled.reader()?, w)
+                }
+            }
+        }
+
+        match self {
+            RomSource::File {
+                file, zip_parts, ..
+            } => match zip_parts.as_slice() {
+                [] => copy_from(
+                    zip_parts,
+                    BufReader::with_capacity(buffer_size(), File::open(file.as_ref())?),
+                    w,
+                ),
+                [c] => c.extract_from_file(file.as_ref(), w),
+                [c, rest @ ..] => {
+                    let spooled = c.extract_file_to_spool(file.as_ref())?;
+                    copy_from(rest, spooled.reader()?, w)
+                }
+            },
+            RomSource::Url {
+                data, zip_parts, ..
+            } => copy_from(zip_parts, std::io::Cursor::new(data), w),
+            RomSource::Empty => Ok(0),
+        }
+    }
+
+    // the CRC32 of the source's contents,
+    // taken from the Zip file's directory where possible
+    fn crc32(&self) -> Option<u32> {
+        use std::fs::File;
+        use std::io::BufReader;
+
+        match self {
+            RomSource::File {
+                file, zip_parts, ..
+            } => match zip_parts.as_slice() {
+                [] => crate::checksum::file_crc32(file).ok(),
+                [Compression::Zip { index }] => {
+                    zip::ZipArchive::new(File::open(file).map(BufReader::new).ok()?)
+                        .ok()?
+                        .by_index_raw(*index)
+                        .ok()
+                        .map(|entry| entry.crc32())
+                }
+                _ => self.copied_crc32(),
+            },
+            _ => self.copied_crc32(),
+        }
+    }
+
+    fn copied_crc32(&self) -> Option<u32> {
+        let mut w = crate::checksum::Crc32Writer(crc32fast::Hasher::new());
+        self.copy_to(&mut w).ok().map(|_| w.0.finalize())
+    }
+}
+
 impl fmt::Display for RomSource<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -1704,7 +4028,7 @@ impl fmt::Display for RomSource<'_> {
                 .fmt(f)
                 .and_then(|()| zip_parts.iter().try_for_each(|part| write!(f, ":{}", part))),
 
-            RomSource::Empty => write!(f, "\u{2039}EMPTY\u{203A}"),
+            RomSource::Empty => f.write_str(crate::glyphs::empty()),
         }
     }
 }
@@ -1725,20 +4049,48 @@ fn extract_from_zip_file<R: Read + Seek>(
             .and_then(|w| Rate::from_copy(|| c.extract(r, w)))
             .map(|rate| Extracted::Copied { rate }),
 
-        [c, rest @ ..] => extract_from_zip_file(rest, c.extract_to_buf(r)?, target),
+        [c, rest @ ..] => {
+            let spooled = c.extract_to_spool(r)?;
+            extract_from_zip_file(rest, spooled.reader()?, target)
+        }
     }
 }
 
-fn unpack_zip_parts<Z, F>(mut zip: Z, whole_file: F) -> Vec<(Part, VecDeque<Compression>)>
+// unpacks the parts of a Zip file and any Zip files nested within it,
+// along with the part of the whole file itself
+fn unpack_zip_parts<Z, F>(
+    zip: Z,
+    whole_file: F,
+) -> Result<Vec<(Part, VecDeque<Compression>)>, Error>
+where
+    Z: Read + Seek + Send,
+    F: Read + Send + 'static,
+{
+    unpack_nested_zip_parts(zip, whole_file, &crate::limits::Budget::default(), 1)
+}
+
+fn unpack_nested_zip_parts<Z, F>(
+    mut zip: Z,
+    whole_file: F,
+    budget: &crate::limits::Budget,
+    depth: usize,
+) -> Result<Vec<(Part, VecDeque<Compression>)>, Error>
 where
     Z: Read + Seek + Send,
     F: Read + Send + 'static,
 {
+    use crate::limits::Exceeded;
+
     // a valid ROM might be an invalid Zip file
     // so a failure to unpack Zip parts from a file
-    // should not be considered a fatal error
-
-    fn unpack<F: Read + Seek>(zip: F) -> Result<Vec<(Part, VecDeque<Compression>)>, Error> {
+    // should not be considered a fatal error,
+    // unless it's from exceeding one of the unpacking limits
+
+    fn unpack<F: Read + Seek>(
+        zip: F,
+        budget: &crate::limits::Budget,
+        depth: usize,
+    ) -> Result<Vec<(Part, VecDeque<Compression>)>, Error> {
         fn is_zip<R: Read>(mut reader: R) -> bool {
             let mut buf = [0; 4];
             match reader.read_exact(&mut buf) {
@@ -1747,26 +4099,46 @@ where
             }
         }
 
+        // I/O errors may be limits exceeded while reading
+        fn io_error(err: std::io::Error) -> Error {
+            match Exceeded::from_io(&err) {
+                Some(exceeded) => Error::ArchiveLimit(exceeded),
+                None => Error::IO(err),
+            }
+        }
+
         let mut zip = zip::ZipArchive::new(zip)?;
         let mut results = Vec::new();
 
         for index in 0..zip.len() {
-            if is_zip(zip.by_index(index)?) {
-                let mut zip_data = Vec::new();
-
-                zip.by_index(index)?.read_to_end(&mut zip_data)?;
-
-                let sub_zip = std::io::Cursor::new(zip_data);
-
-                results.extend(unpack_zip_parts(sub_zip.clone(), sub_zip).into_iter().map(
-                    |(part, mut zip_parts)| {
+            let entry = zip.by_index(index)?;
+            let size = entry.size();
+            let is_nested = is_zip(entry);
+
+            if is_nested {
+                crate::limits::check_nesting(depth + 1)?;
+
+                // large nested Zips go to a temporary file rather than memory,
+                // so only those are limited in size
+                let entry = budget.limit(zip.by_index(index)?, size)?;
+                let sub_zip = crate::spool::Spool::from_reader(entry).map_err(io_error)?;
+
+                results.extend(
+                    unpack_nested_zip_parts(
+                        sub_zip.reader()?,
+                        sub_zip.reader()?,
+                        budget,
+                        depth + 1,
+                    )?
+                    .into_iter()
+                    .map(|(part, mut zip_parts)| {
                         zip_parts.push_front(Compression::Zip { index });
                         (part, zip_parts)
-                    },
-                ))
+                    }),
+                )
             } else {
                 results.push((
-                    Part::from_reader(zip.by_index(index)?)?,
+                    Part::from_reader(budget.track(zip.by_index(index)?)).map_err(io_error)?,
                     vec![Compression::Zip { index }].into(),
                 ))
             }
@@ -1775,22 +4147,92 @@ where
         Ok(results)
     }
 
-    let (mut unpacked, whole) = rayon::join(
-        || unpack(&mut zip).unwrap_or_default(),
+    let (unpacked, whole) = rayon::join(
+        || unpack(&mut zip, budget, depth),
         || Part::from_reader(whole_file),
     );
 
+    let mut unpacked = match unpacked {
+        Ok(unpacked) => unpacked,
+        Err(err @ Error::ArchiveLimit(_)) => return Err(err),
+        Err(_) => Vec::new(),
+    };
+
+    if let Ok(part) = whole {
+        unpacked.push((part, VecDeque::default()));
+    }
+
+    Ok(unpacked)
+}
+
+// unpacks the parts of a 7-Zip file, which aren't searched
+// for nested archives, along with the part of the whole file itself
+fn unpack_7z_parts<Z, F>(zip: Z, whole_file: F) -> Result<Vec<(Part, VecDeque<Compression>)>, Error>
+where
+    Z: Read + Seek + Send,
+    F: Read + Send,
+{
+    use crate::limits::{Budget, Exceeded};
+
+    // as with Zip files, a failure to unpack isn't fatal
+    // unless it's from exceeding one of the unpacking limits
+    fn unpack<Z: Read + Seek>(mut zip: Z) -> Result<Vec<(Part, VecDeque<Compression>)>, Error> {
+        let budget = Budget::default();
+        let mut results = Vec::new();
+        let mut exceeded = None;
+        let mut index = 0;
+
+        let len = zip.seek(std::io::SeekFrom::End(0))?;
+        zip.seek(std::io::SeekFrom::Start(0))?;
+
+        let unpacked = sevenz_rust::SevenZReader::new(zip, len, sevenz_rust::Password::empty())?
+            .for_each_entries(|entry, r| {
+                let compression = Compression::SevenZip { index };
+                index += 1;
+
+                if entry.is_directory() {
+                    return Ok(true);
+                }
+
+                match Part::from_reader(budget.track(r)) {
+                    Ok(part) => {
+                        results.push((part, vec![compression].into()));
+                        Ok(true)
+                    }
+                    Err(err) => {
+                        exceeded = Exceeded::from_io(&err);
+                        Err(err.into())
+                    }
+                }
+            });
+
+        match (unpacked, exceeded) {
+            (_, Some(exceeded)) => Err(Error::ArchiveLimit(exceeded)),
+            (Ok(()), None) => Ok(results),
+            (Err(err), None) => Err(err.into()),
+        }
+    }
+
+    let (unpacked, whole) = rayon::join(|| unpack(zip), || Part::from_reader(whole_file));
+
+    let mut unpacked = match unpacked {
+        Ok(unpacked) => unpacked,
+        Err(err @ Error::ArchiveLimit(_)) => return Err(err),
+        Err(_) => Vec::new(),
+    };
+
     if let Ok(part) = whole {
         unpacked.push((part, VecDeque::default()));
     }
 
-    unpacked
+    Ok(unpacked)
 }
 
 #[derive(Copy, Clone)]
 pub enum Extracted {
     Copied { rate: Option<Rate> },
     Linked { has_xattr: bool },
+    Moved { has_xattr: bool },
 }
 
 #[derive(Copy, Clone)]
@@ -1865,11 +4307,11 @@ pub fn file_rom_sources<'r>(root: &Path, progress: &MultiProgress) -> RomSources
 
     with_progress(
         progress,
-        ProgressBar::new_spinner()
+        crate::progress::spinner()
             .with_style(find_files_style())
             .with_message("locating files"),
         |pbar| {
-            walkdir::WalkDir::new(root)
+            crate::symlinks::walk(root)
                 .into_iter()
                 .filter_map(|e| e.ok())
                 .filter(|e| {
@@ -1879,15 +4321,142 @@ pub fn file_rom_sources<'r>(root: &Path, progress: &MultiProgress) -> RomSources
                         true
                     }
                 })
+                .take_while(|_| !crate::interrupt::requested())
                 .map(|e| e.into_path())
                 .par_bridge()
-                .progress_with(pbar)
-                .flat_map(|pb| RomSource::from_path(pb).unwrap_or_default().into_par_iter())
+                .progress_with(pbar.clone())
+                .flat_map(|pb| match RomSource::from_path(pb.clone()) {
+                    Ok(sources) => sources.into_par_iter(),
+                    Err(err @ Error::ArchiveLimit(_)) => {
+                        use crate::progress::Log;
+
+                        pbar.log(format!("* {} : {err}", pb.display()));
+                        Vec::new().into_par_iter()
+                    }
+                    Err(Error::IO(err)) if ChdError::from_io(&err).is_some() => {
+                        use crate::progress::Log;
+
+                        pbar.log(format!("* {} : {err}", pb.display()));
+                        Vec::new().into_par_iter()
+                    }
+                    Err(_) => Vec::new().into_par_iter(),
+                })
                 .collect()
         },
     )
 }
 
+// adds the headerless payloads of any iNES-headered sources
+// and the de-interleaved payloads of any SMD sources
+// so that DATs expecting plain ROMs can be repaired from them
+pub fn add_converted_sources(sources: &RomSources<'_>) {
+    use rayon::prelude::*;
+
+    let converted = sources
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .flat_map_iter(|source| [source.headerless_nes(), source.deinterleaved_smd()])
+        .flatten()
+        .collect::<Vec<_>>();
+
+    for (part, source) in converted {
+        sources.entry(part).or_insert(source);
+    }
+}
+
+// adds a source for a part known only by its CRC32, if any has one,
+// which can't be looked up by SHA-1 like other parts;
+// only sources of the part's size are read, and each of those
+// only once per run however many parts are looked for
+pub fn add_crc_source(sources: &RomSources<'_>, part: &Part) {
+    use fxhash::FxBuildHasher;
+    use rayon::prelude::*;
+    use std::sync::OnceLock as OnceCell;
+
+    // each source's CRC32, by its description
+    static CRC_CACHE: OnceCell<DashMap<String, [u8; 4], FxBuildHasher>> = OnceCell::new();
+
+    if !matches!(part, Part::Crc { .. })
+        || sources
+            .get(part)
+            .is_some_and(|found| part.matches(found.key()))
+    {
+        return;
+    }
+
+    let cache = CRC_CACHE.get_or_init(DashMap::default);
+
+    let candidates = sources
+        .iter()
+        .filter(|entry| !matches!(entry.key(), Part::Crc { .. }))
+        .filter_map(|entry| {
+            let size = entry.key().size().or_else(|| match entry.value() {
+                RomSource::File {
+                    file, zip_parts, ..
+                } if zip_parts.is_empty() => file.metadata().ok().map(|m| m.len()),
+                // a source of unknown size would have to be read in full,
+                // which isn't worth it for a CRC32 that's unlikely to match
+                _ => None,
+            })?;
+            part.size()
+                .is_none_or(|wanted| wanted == size)
+                .then(|| (size, entry.value().clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let found = candidates.into_par_iter().find_map_any(|(size, source)| {
+        let key = source.to_string();
+        let crc32 = match cache.get(&key) {
+            Some(crc32) => *crc32,
+            None => {
+                let crc32 = source.crc32()?.to_be_bytes();
+                cache.insert(key, crc32);
+                crc32
+            }
+        };
+        let found = Part::Crc {
+            crc32,
+            size: Some(size),
+        };
+        part.matches(&found).then_some((found, source))
+    });
+
+    if let Some((found, source)) = found {
+        sources.insert(found, source);
+    }
+}
+
+// Super Magic Drive dumps have a 512 byte header followed by 16 KiB blocks,
+// each of which holds its odd bytes followed by its even bytes
+const SMD_HEADER: usize = 512;
+const SMD_BLOCK: usize = 16384;
+
+// writes the de-interleaved blocks following an SMD header,
+// returning the number of bytes written
+fn deinterleave_smd<R: Read, W: std::io::Write>(mut r: R, mut w: W) -> std::io::Result<u64> {
+    let mut block = vec![0; SMD_BLOCK];
+    let mut plain = vec![0; SMD_BLOCK];
+    let mut written = 0;
+
+    loop {
+        match r.read_exact(&mut block) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break Ok(written),
+            Err(err) => break Err(err),
+        }
+
+        let (odd, even) = block.split_at(SMD_BLOCK / 2);
+        for (i, (odd, even)) in odd.iter().zip(even).enumerate() {
+            plain[i * 2] = *even;
+            plain[i * 2 + 1] = *odd;
+        }
+        w.write_all(&plain)?;
+        written += SMD_BLOCK as u64;
+    }
+}
+
 #[inline]
 pub fn url_rom_sources<'u>(url: &'u str, progress: &MultiProgress) -> RomSources<'u> {
     RomSource::from_url(url, progress)
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    // UTF-8 unless the terminal's encoding is something else
+    #[default]
+    Auto,
+    // arrows and borders drawn with ASCII characters only
+    Ascii,
+    // arrows and borders drawn with UTF-8 characters
+    Utf8,
+}
+
+impl std::str::FromStr for Charset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "ascii" => Ok(Self::Ascii),
+            "utf8" => Ok(Self::Utf8),
+            _ => Err(format!(
+                "unknown charset \"{s}\", use \"auto\", \"ascii\" or \"utf8\""
+            )),
+        }
+    }
+}
+
+pub fn set_charset(charset: Charset) {
+    ASCII.store(
+        match charset {
+            Charset::Auto => !terminal_is_utf8(),
+            Charset::Ascii => true,
+            Charset::Utf8 => false,
+        },
+        Ordering::Relaxed,
+    );
+}
+
+#[inline]
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+// Rust writes to Windows consoles as UTF-16 whatever the code page,
+// but only newer terminals have fonts with the arrows and borders
+#[cfg(windows)]
+fn terminal_is_utf8() -> bool {
+    std::env::var_os("WT_SESSION").is_some() || std::env::var_os("TERM_PROGRAM").is_some()
+}
+
+// elsewhere, the locale names its encoding, such as "en_US.UTF-8",
+// and an unset locale is taken to be UTF-8
+#[cfg(not(windows))]
+fn terminal_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .is_none_or(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+// a file copied or extracted from one place to another
+pub fn copied() -> &'static str {
+    if is_ascii() {
+        "=>"
+    } else {
+        "\u{21D2}"
+    }
+}
+
+// a file moved or linked from one place to another
+pub fn moved() -> &'static str {
+    if is_ascii() {
+        "->"
+    } else {
+        "\u{2192}"
+    }
+}
+
+// a source with no contents at all
+pub fn empty() -> &'static str {
+    if is_ascii() {
+        "<EMPTY>"
+    } else {
+        "\u{2039}EMPTY\u{203A}"
+    }
+}
+
+// the branch and indentation of a tree's entries,
+// for the last entry and for all the others
+pub fn tree_branches() -> [(&'static str, &'static str); 2] {
+    if is_ascii() {
+        [("`-- ", "    "), ("|-- ", "|   ")]
+    } else {
+        [
+            ("\u{2514}\u{2500}\u{2500} ", "    "),
+            ("\u{251C}\u{2500}\u{2500} ", "\u{2502}   "),
+        ]
+    }
+}
+
+// a progress style whose spinner is drawn in ASCII, if need be
+pub fn ticks(style: indicatif::ProgressStyle) -> indicatif::ProgressStyle {
+    if is_ascii() {
+        style.tick_chars("|/-\\ ")
+    } else {
+        style
+    }
+}
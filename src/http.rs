@@ -1,44 +1,185 @@
 use crate::Error;
 use indicatif::{MultiProgress, ProgressBar};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
 
 const RETRIES: u32 = 10;
 
+// the validators of each URL's last successful import
+pub const VALIDATORS_FILE: &str = "http-validators.cbor";
+
+// the state of each URL being downloaded as a ROM source
+pub const DOWNLOADS_FILE: &str = "downloads.cbor";
+
+// where each queued URL's data is kept as it arrives
+const DIR_DOWNLOADS: &str = "downloads";
+
+// how long unfinished data is kept without being resumed
+const STALE_DOWNLOAD: std::time::Duration = std::time::Duration::from_secs(30 * 24 * 60 * 60);
+
 pub fn fetch_url_data(source: &str) -> Result<Box<[u8]>, Error> {
     let mut data = Vec::new();
-    retry(|| fetch(source, |pb| pb, |_| {}, &mut data), RETRIES).map(|()| data.into_boxed_slice())
+    let mut validator = None;
+    retry(
+        || fetch(source, |pb| pb, |_| {}, &mut data, &mut validator),
+        RETRIES,
+    )
+    .map(|()| data.into_boxed_slice())
 }
 
+// fetches the URL through the download queue, whose data is kept
+// on disk as it arrives so that a run that's interrupted
+// resumes from where it left off the next time it's needed
 pub fn fetch_url_data_with_progress(
     source: &str,
     progress: &MultiProgress,
 ) -> Result<Box<[u8]>, Error> {
-    let mut data = Vec::new();
-    retry(
+    let path = download_path(source);
+
+    // the version of the URL any data already on disk came from
+    let mut validator = match DownloadQueue::load()?.0.remove(source) {
+        Some(Download::Done) => {
+            if let Ok(data) = std::fs::read(&path) {
+                tracing::debug!(url = source, "already downloaded");
+                return Ok(data.into_boxed_slice());
+            }
+            None
+        }
+        Some(Download::InProgress { validator, .. } | Download::Failed { validator, .. }) => {
+            validator
+        }
+        Some(Download::Pending) | None => None,
+    };
+
+    std::fs::create_dir_all(crate::dirs::data_dir().join(DIR_DOWNLOADS))?;
+    let mut file = std::fs::File::options()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+
+    let result = retry(
         || {
-            fetch(
+            DownloadQueue::set(
+                source,
+                Download::InProgress {
+                    offset: file.offset(),
+                    validator: validator.clone(),
+                },
+            )?;
+            let result = fetch(
                 source,
                 |pb| progress.add(pb),
                 |pb| progress.remove(pb),
-                &mut data,
-            )
+                &mut file,
+                &mut validator,
+            );
+            // keep the validator of whatever's arrived so far
+            // in case the run ends before the next attempt
+            if result.is_err() {
+                DownloadQueue::set(
+                    source,
+                    Download::InProgress {
+                        offset: file.offset(),
+                        validator: validator.clone(),
+                    },
+                )?;
+            }
+            result
         },
         RETRIES,
-    )
-    .map(|()| data.into_boxed_slice())
+    );
+
+    match result {
+        Ok(()) => {
+            DownloadQueue::set(source, Download::Done)?;
+            Ok(std::fs::read(&path)?.into_boxed_slice())
+        }
+        Err(err) => {
+            DownloadQueue::set(
+                source,
+                Download::Failed {
+                    offset: file.offset(),
+                    error: err.to_string(),
+                    validator,
+                },
+            )?;
+            Err(err)
+        }
+    }
 }
 
-fn fetch<A, R>(source: &str, add_bar: A, remove_bar: R, zip_data: &mut Vec<u8>) -> Result<(), Error>
+// somewhere fetched data is written, which may already hold
+// some of it from an earlier attempt
+trait Sink: std::io::Write {
+    // how much data has been written so far
+    fn offset(&self) -> u64;
+
+    // discards everything written so far
+    fn restart(&mut self) -> Result<(), std::io::Error>;
+}
+
+impl Sink for Vec<u8> {
+    fn offset(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn restart(&mut self) -> Result<(), std::io::Error> {
+        self.clear();
+        Ok(())
+    }
+}
+
+impl Sink for std::fs::File {
+    fn offset(&self) -> u64 {
+        self.metadata().map(|m| m.len()).unwrap_or_default()
+    }
+
+    // the file is opened for appending,
+    // so writes always go to its new end
+    fn restart(&mut self) -> Result<(), std::io::Error> {
+        self.set_len(0)
+    }
+}
+
+// validator is the ETag or Last-Modified date of whatever
+// the sink already holds, and is updated from the response,
+// so that a resumed fetch only continues the same version of the URL
+fn fetch<A, R, S>(
+    source: &str,
+    add_bar: A,
+    remove_bar: R,
+    zip_data: &mut S,
+    validator: &mut Option<String>,
+) -> Result<(), Error>
 where
     A: FnOnce(ProgressBar) -> ProgressBar,
     R: FnOnce(&ProgressBar),
+    S: Sink,
 {
-    use attohttpc::header::CONTENT_LENGTH;
-    use std::io::Read;
+    use attohttpc::header::{CONTENT_LENGTH, IF_RANGE, RANGE};
+    use attohttpc::StatusCode;
+
+    // data of an unknown version can't be safely continued
+    if zip_data.offset() > 0 && validator.is_none() {
+        tracing::debug!(url = source, "no validator for partial data, restarting");
+        zip_data.restart()?;
+    }
+
+    let offset = zip_data.offset();
 
-    let builder = if zip_data.is_empty() {
-        attohttpc::get(source)
-    } else {
-        attohttpc::get(source).header("Range", format!("bytes={}-", zip_data.len()))
+    let builder = match validator.as_deref() {
+        Some(known) if offset > 0 => {
+            tracing::debug!(url = source, offset, "resuming fetch");
+            attohttpc::get(source)
+                .header(RANGE, format!("bytes={offset}-"))
+                .header(IF_RANGE, known)
+        }
+        _ => {
+            tracing::debug!(url = source, "fetching");
+            attohttpc::get(source)
+        }
     };
 
     match builder.send()?.split() {
@@ -48,19 +189,364 @@ where
                 .and_then(|v| v.to_str().ok())
                 .and_then(|s| s.parse::<u64>().ok());
 
+            tracing::debug!(url = source, %code, ?length, "response received");
+
+            // a server which ignores the range, or whose data
+            // has changed since, sends everything again
+            if offset > 0 && code != StatusCode::PARTIAL_CONTENT {
+                tracing::debug!(url = source, "range ignored, restarting");
+                zip_data.restart()?;
+            }
+
+            if offset == 0 || code != StatusCode::PARTIAL_CONTENT {
+                *validator = Validators::from_headers(&map).and_then(Validators::if_range);
+            }
+
             let pbar = add_bar(progress_bar(source, length));
 
-            let result = pbar
-                .wrap_read(reader)
-                .read_to_end(zip_data)
-                .map(|_| ())
+            let result = std::io::copy(&mut pbar.wrap_read(reader), zip_data)
+                .map(crate::throughput::downloaded)
                 .map_err(Error::IO);
 
             remove_bar(&pbar);
 
             result
         }
-        (code, _, _) => Err(Error::HttpCode(code)),
+        // everything may already have arrived before the last attempt ended,
+        // so start over rather than guess whether that's so
+        (code @ StatusCode::RANGE_NOT_SATISFIABLE, _, _) => {
+            tracing::debug!(url = source, %code, "range not satisfiable, restarting");
+            zip_data.restart()?;
+            Err(Error::HttpCode(code))
+        }
+        (code, _, _) => {
+            tracing::debug!(url = source, %code, "request failed");
+            Err(Error::HttpCode(code))
+        }
+    }
+}
+
+// what a server told us identifies the version of a URL we fetched,
+// which can be sent back to ask whether it's changed since,
+// along with the names of the databases imported from it
+#[derive(Default, Serialize, Deserialize)]
+pub struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    #[serde(default)]
+    dats: Vec<String>,
+}
+
+impl Validators {
+    fn from_headers(headers: &attohttpc::header::HeaderMap) -> Option<Self> {
+        use attohttpc::header::{ETAG, LAST_MODIFIED};
+
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_owned())
+        };
+
+        match (header(ETAG), header(LAST_MODIFIED)) {
+            (None, None) => None,
+            (etag, last_modified) => Some(Self {
+                etag,
+                last_modified,
+                dats: Vec::new(),
+            }),
+        }
+    }
+
+    // what If-Range can use to resume this version of the URL,
+    // which must be a strong ETag or else a date
+    fn if_range(self) -> Option<String> {
+        self.etag
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified)
+    }
+
+    fn load_all() -> Result<BTreeMap<String, Self>, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(VALIDATORS_FILE))
+    }
+}
+
+// the validators of URLs whose DATs have been parsed,
+// to be saved only once those DATs have been written
+#[derive(Default)]
+pub struct Imported(BTreeMap<String, Validators>);
+
+impl Imported {
+    pub fn insert(&mut self, source: String, mut validators: Validators, dats: Vec<String>) {
+        validators.dats = dats;
+        self.0.insert(source, validators);
+    }
+
+    // remembers the validators once the URLs' data has been stored,
+    // so a failed import doesn't keep the next one from fetching it again
+    pub fn save(self) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut all = Validators::load_all()?;
+        all.extend(self.0);
+
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(VALIDATORS_FILE), &all)
+    }
+}
+
+// where a queued URL's data is kept, named by a hash of the URL
+fn download_path(source: &str) -> PathBuf {
+    crate::dirs::data_dir()
+        .join(DIR_DOWNLOADS)
+        .join(sha1_smol::Sha1::from(source).digest().to_string())
+}
+
+// the validator of unfinished downloads is the ETag or date
+// of the version of the URL whose data is on disk so far
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Download {
+    Pending,
+    InProgress {
+        offset: u64,
+        #[serde(default)]
+        validator: Option<String>,
+    },
+    Done,
+    Failed {
+        offset: u64,
+        error: String,
+        #[serde(default)]
+        validator: Option<String>,
+    },
+}
+
+impl std::fmt::Display for Download {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => "pending".fmt(f),
+            Self::InProgress { .. } => "in progress".fmt(f),
+            Self::Done => "done".fmt(f),
+            Self::Failed { error, .. } => write!(f, "failed : {error}"),
+        }
+    }
+}
+
+// URLs are downloaded in parallel,
+// so only one thread at a time may update the queue on disk
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+// every URL queued for download, kept until the run using it finishes
+#[derive(Default, Serialize, Deserialize)]
+pub struct DownloadQueue(BTreeMap<String, Download>);
+
+impl DownloadQueue {
+    pub fn load() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(DOWNLOADS_FILE))
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(DOWNLOADS_FILE), self)
+    }
+
+    fn update<F: FnOnce(&mut Self)>(f: F) -> Result<(), Error> {
+        let _lock = QUEUE_LOCK.lock().unwrap();
+        let mut queue = Self::load()?;
+        f(&mut queue);
+        queue.save()
+    }
+
+    fn set(source: &str, download: Download) -> Result<(), Error> {
+        Self::update(|queue| {
+            queue.0.insert(source.to_owned(), download);
+        })
+    }
+
+    // adds URLs to the queue, leaving any already in it as they are
+    pub fn enqueue<'s, I>(sources: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'s str>,
+    {
+        let mut sources = sources.into_iter().peekable();
+        if sources.peek().is_none() {
+            return Ok(());
+        }
+
+        Self::update(|queue| {
+            for source in sources {
+                queue
+                    .0
+                    .entry(source.to_owned())
+                    .or_insert(Download::Pending);
+            }
+        })
+    }
+
+    // drops finished downloads and their data once the run
+    // that needed them is over, keeping unfinished ones to resume
+    // unless they've gone unresumed for too long,
+    // along with any data no longer queued at all
+    pub fn finish() -> Result<(), Error> {
+        if !crate::dirs::data_dir().join(DOWNLOADS_FILE).exists() {
+            return Ok(());
+        }
+
+        let is_stale = |path: &std::path::Path| {
+            path.metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > STALE_DOWNLOAD)
+        };
+
+        Self::update(|queue| {
+            queue.0.retain(|source, download| {
+                let path = download_path(source);
+                match download {
+                    Download::Done => {
+                        let _ = std::fs::remove_file(path);
+                        false
+                    }
+                    Download::InProgress { .. } | Download::Failed { .. } if is_stale(&path) => {
+                        let _ = std::fs::remove_file(path);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+
+            let queued = queue
+                .0
+                .keys()
+                .map(|source| download_path(source))
+                .collect::<std::collections::HashSet<_>>();
+            for entry in std::fs::read_dir(crate::dirs::data_dir().join(DIR_DOWNLOADS))
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+                .filter(|e| !queued.contains(&e.path()))
+            {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        })
+    }
+
+    // drops every download and any partial data
+    pub fn clear() -> Result<usize, Error> {
+        let _lock = QUEUE_LOCK.lock().unwrap();
+        let cleared = Self::load()?.0.len();
+        match std::fs::remove_dir_all(crate::dirs::data_dir().join(DIR_DOWNLOADS)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        Self::default().save()?;
+        Ok(cleared)
+    }
+
+    pub fn list(&self) {
+        let mut table = crate::table::new();
+        table.set_header(vec!["URL", "State", "Bytes"]);
+        for (source, download) in &self.0 {
+            // data on disk is more current than the last recorded offset
+            let bytes = match download {
+                Download::Pending => None,
+                Download::Done => std::fs::metadata(download_path(source))
+                    .ok()
+                    .map(|m| m.len()),
+                Download::InProgress { offset, .. } | Download::Failed { offset, .. } => Some(
+                    std::fs::metadata(download_path(source))
+                        .map(|m| m.len())
+                        .unwrap_or(*offset),
+                ),
+            };
+            table.add_row(vec![
+                source.clone(),
+                download.to_string(),
+                bytes.map(|b| b.to_string()).unwrap_or_default(),
+            ]);
+        }
+        println!("{table}");
+    }
+}
+
+pub enum Fetched {
+    Modified {
+        data: Box<[u8]>,
+        validators: Option<Validators>,
+    },
+    NotModified,
+}
+
+// fetches the URL unless the server says it hasn't changed
+// since the last time it was successfully imported
+// and every database imported from it is still stored
+pub fn fetch_url_data_if_modified(
+    source: &str,
+    is_stored: impl Fn(&str) -> bool,
+) -> Result<Fetched, Error> {
+    use attohttpc::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH};
+    use attohttpc::StatusCode;
+    use std::io::Read;
+
+    let mut builder = attohttpc::get(source);
+    if let Some(known) = Validators::load_all()?
+        .remove(source)
+        .filter(|known| !known.dats.is_empty() && known.dats.iter().all(|dat| is_stored(dat)))
+    {
+        if let Some(etag) = known.etag {
+            builder = builder.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = known.last_modified {
+            builder = builder.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    tracing::debug!(url = source, "fetching if modified");
+
+    match builder.send()?.split() {
+        (StatusCode::NOT_MODIFIED, _, _) => {
+            tracing::debug!(url = source, "not modified");
+            Ok(Fetched::NotModified)
+        }
+        (code, headers, reader) if code.is_success() => {
+            use attohttpc::header::CONTENT_LENGTH;
+
+            let validators = Validators::from_headers(&headers);
+            let length = headers
+                .get(CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.parse::<u64>().ok());
+
+            let pbar = progress_bar(source, length);
+            let mut data = Vec::new();
+            let result = pbar.wrap_read(reader).read_to_end(&mut data);
+            pbar.finish_and_clear();
+            crate::throughput::downloaded(data.len() as u64);
+
+            let data = match result {
+                Ok(_) => data.into_boxed_slice(),
+                // fall back to the usual fetch, which resumes and retries
+                Err(_) => fetch_url_data(source)?,
+            };
+            Ok(Fetched::Modified { data, validators })
+        }
+        (code, _, _) => {
+            tracing::debug!(url = source, %code, "request failed");
+            Err(Error::HttpCode(code))
+        }
+    }
+}
+
+// checks that a URL can still be fetched without downloading it
+pub fn check_url(source: &str) -> Result<(), Error> {
+    match attohttpc::head(source).send()?.status() {
+        code if code.is_success() => Ok(()),
+        code => Err(Error::HttpCode(code)),
     }
 }
 
@@ -68,12 +554,16 @@ where
 fn retry<T, E, F>(mut f: F, mut retries: u32) -> Result<T, E>
 where
     F: FnMut() -> Result<T, E>,
+    E: std::fmt::Display,
 {
     loop {
         match f() {
             ok @ Ok(_) => break ok,
             err @ Err(_) if retries == 0 => break err,
-            Err(_) => retries -= 1,
+            Err(err) => {
+                tracing::warn!(%err, retries, "retrying");
+                retries -= 1
+            }
         }
     }
 }
@@ -82,12 +572,12 @@ fn progress_bar(source: &str, total_bytes: Option<u64>) -> ProgressBar {
     use indicatif::ProgressStyle;
 
     match total_bytes {
-        Some(total_bytes) => ProgressBar::new(total_bytes).with_style(
+        Some(total_bytes) => crate::progress::bar(total_bytes).with_style(
             ProgressStyle::default_bar()
                 .template("{wide_msg} {bytes} ({bytes_per_sec}) {eta}")
                 .unwrap(),
         ),
-        None => ProgressBar::new_spinner().with_style(
+        None => crate::progress::spinner().with_style(
             ProgressStyle::default_spinner()
                 .template("{wide_msg} {bytes} ({bytes_per_sec})")
                 .unwrap(),
@@ -0,0 +1,170 @@
+use crate::game::{Compression, Part, RomSource, RomSources};
+use crate::Error;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+pub const SOURCE_INDEX_FILE: &str = "source-index.cbor";
+
+// a saved scan of source files, so that large source directories
+// needn't be rescanned from scratch before every repair
+#[derive(Default, Serialize, Deserialize)]
+pub struct SourceIndex {
+    files: BTreeMap<PathBuf, IndexedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexedFile {
+    modified: SystemTime,
+    size: u64,
+    parts: Vec<(Part, Vec<Compression>)>,
+}
+
+impl IndexedFile {
+    fn new(path: &Path) -> Option<Self> {
+        let metadata = path.metadata().ok()?;
+
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            size: metadata.len(),
+            parts: RomSource::from_path(path.to_owned())
+                .ok()?
+                .into_iter()
+                .filter_map(|(part, source)| match source {
+                    RomSource::File { zip_parts, .. } => Some((part, zip_parts)),
+                    _ => None,
+                })
+                .collect(),
+        })
+    }
+
+    fn is_current(&self, path: &Path) -> bool {
+        path.metadata().is_ok_and(|metadata| {
+            metadata.len() == self.size && metadata.modified().ok() == Some(self.modified)
+        })
+    }
+}
+
+impl SourceIndex {
+    fn location() -> PathBuf {
+        crate::dirs::data_dir().join(SOURCE_INDEX_FILE)
+    }
+
+    pub fn load() -> Result<Self, Error> {
+        let location = Self::location();
+
+        if !location.exists() {
+            return Err(Error::MissingSourceIndex);
+        }
+
+        crate::dbfile::read_or_default(&location)
+    }
+
+    // an index that's yet to be built starts out empty
+    pub fn load_or_default() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&Self::location())
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let location = Self::location();
+
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        crate::dbfile::write(&location, self)
+    }
+
+    // replaces any existing entries under root with a fresh scan
+    pub fn index(&mut self, root: &Path) -> Result<(), Error> {
+        use indicatif::ParallelProgressIterator;
+        use rayon::prelude::*;
+
+        let root = root.canonicalize()?;
+
+        self.files.retain(|path, _| !path.starts_with(&root));
+
+        let pbar = crate::progress::spinner()
+            .with_style(crate::game::find_files_style())
+            .with_message("locating files");
+        let files = pbar
+            .wrap_iter(crate::unique_sub_files(root))
+            .collect::<Vec<_>>();
+        pbar.finish_and_clear();
+
+        let pbar = crate::progress::bar(files.len().try_into().unwrap())
+            .with_style(crate::game::verify_style())
+            .with_message("indexing files");
+
+        self.files.par_extend(
+            files
+                .into_par_iter()
+                .progress_with(pbar.clone())
+                .filter_map(|path| IndexedFile::new(&path).map(|file| (path, file))),
+        );
+
+        pbar.finish_and_clear();
+
+        Ok(())
+    }
+
+    // rescans files modified since they were indexed
+    // and drops files which no longer exist,
+    // returning true if anything has changed
+    pub fn revalidate(&mut self) -> bool {
+        use rayon::prelude::*;
+
+        let stale = self
+            .files
+            .par_iter()
+            .filter(|(path, file)| !file.is_current(path))
+            .map(|(path, _)| (path.clone(), IndexedFile::new(path)))
+            .collect::<Vec<_>>();
+
+        let changed = !stale.is_empty();
+
+        for (path, file) in stale {
+            match file {
+                Some(file) => {
+                    self.files.insert(path, file);
+                }
+                None => {
+                    self.files.remove(&path);
+                }
+            }
+        }
+
+        changed
+    }
+
+    pub fn rom_sources<'u>(&self) -> RomSources<'u> {
+        let sources = RomSources::default();
+
+        for (path, file) in &self.files {
+            let path: Arc<Path> = Arc::from(path.as_path());
+
+            for (part, zip_parts) in &file.parts {
+                let source = RomSource::File {
+                    file: Arc::clone(&path),
+                    has_xattr: false,
+                    zip_parts: zip_parts.clone(),
+                };
+
+                match sources.entry(part.clone()) {
+                    dashmap::mapref::entry::Entry::Occupied(mut o) => {
+                        if source.more_local_than(o.get()) {
+                            o.insert(source);
+                        }
+                    }
+                    dashmap::mapref::entry::Entry::Vacant(v) => {
+                        v.insert(source);
+                    }
+                }
+            }
+        }
+
+        sources
+    }
+}
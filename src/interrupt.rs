@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// the first Ctrl-C stops new work from being started
+// while files already in progress are finished
+// and a summary of what was done is displayed,
+// while a second Ctrl-C exits immediately
+pub fn install() {
+    let installed = ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        } else {
+            eprintln!("* interrupted, finishing files in progress (Ctrl-C again to quit now)");
+        }
+    });
+
+    if let Err(err) = installed {
+        tracing::debug!(%err, "unable to install interrupt handler");
+    }
+}
+
+#[inline]
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
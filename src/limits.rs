@@ -0,0 +1,145 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+pub const DEFAULT_MAX_NESTING: usize = 4;
+pub const DEFAULT_MAX_EXTRACTED: u64 = 64 << 30;
+pub const DEFAULT_MAX_ENTRY_SIZE: u64 = 16 << 30;
+
+static MAX_NESTING: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_NESTING);
+static MAX_EXTRACTED: AtomicU64 = AtomicU64::new(DEFAULT_MAX_EXTRACTED);
+static MAX_ENTRY_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_ENTRY_SIZE);
+
+// how deeply Zip files may be nested within one another
+pub fn set_max_nesting(depth: usize) {
+    MAX_NESTING.store(depth, Ordering::Relaxed);
+}
+
+// how many bytes may be unpacked from a single source file in all
+pub fn set_max_extracted(bytes: u64) {
+    MAX_EXTRACTED.store(bytes, Ordering::Relaxed);
+}
+
+// how large any Zip file nested within another may be,
+// since those are unpacked to a temporary file
+pub fn set_max_entry_size(bytes: u64) {
+    MAX_ENTRY_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+#[derive(Clone, Debug)]
+pub enum Exceeded {
+    Nesting(usize),
+    Extracted(u64),
+    EntrySize(u64),
+}
+
+impl std::fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nesting(depth) => {
+                write!(
+                    f,
+                    "Zip files nested more than {depth} deep (see --max-nesting)"
+                )
+            }
+            Self::Extracted(bytes) => write!(
+                f,
+                "unpacks to more than {} (see --max-extracted)",
+                crate::game::Size(*bytes)
+            ),
+            Self::EntrySize(bytes) => write!(
+                f,
+                "contains a Zip file larger than {} (see --max-entry-size)",
+                crate::game::Size(*bytes)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Exceeded {}
+
+impl Exceeded {
+    // recovers a limit from an I/O error raised by a Limited reader
+    pub fn from_io(err: &std::io::Error) -> Option<Self> {
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<Self>())
+            .cloned()
+    }
+}
+
+// where a source file itself is at depth 1,
+// a Zip file within it at depth 2, and so on
+pub fn check_nesting(depth: usize) -> Result<(), Exceeded> {
+    let max = MAX_NESTING.load(Ordering::Relaxed);
+    if depth > max {
+        Err(Exceeded::Nesting(max))
+    } else {
+        Ok(())
+    }
+}
+
+// the bytes unpacked from one source file,
+// shared by every Zip file nested within it
+#[derive(Default)]
+pub struct Budget {
+    extracted: AtomicU64,
+}
+
+impl Budget {
+    // wraps a file within an archive which is only hashed as it's read,
+    // counting what it unpacks toward the source file's total
+    pub fn track<R: Read>(&self, r: R) -> Limited<'_, R> {
+        Limited {
+            r,
+            budget: self,
+            read: 0,
+            max_entry: None,
+        }
+    }
+
+    // wraps a Zip file nested within another, given its declared size,
+    // which fails early if the file is declared too large
+    // and fails while reading if it turns out larger than declared
+    pub fn limit<R: Read>(&self, r: R, declared: u64) -> Result<Limited<'_, R>, Exceeded> {
+        let max = MAX_ENTRY_SIZE.load(Ordering::Relaxed);
+        if declared > max {
+            Err(Exceeded::EntrySize(max))
+        } else {
+            Ok(Limited {
+                r,
+                budget: self,
+                read: 0,
+                max_entry: Some(max),
+            })
+        }
+    }
+}
+
+pub struct Limited<'b, R> {
+    r: R,
+    budget: &'b Budget,
+    read: u64,
+    max_entry: Option<u64>,
+}
+
+impl<R: Read> Read for Limited<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.r.read(buf)?;
+        self.read += read as u64;
+
+        if let Some(max_entry) = self.max_entry.filter(|max| self.read > *max) {
+            return Err(std::io::Error::other(Exceeded::EntrySize(max_entry)));
+        }
+
+        let max_extracted = MAX_EXTRACTED.load(Ordering::Relaxed);
+        let extracted = self
+            .budget
+            .extracted
+            .fetch_add(read as u64, Ordering::Relaxed)
+            + read as u64;
+        if extracted > max_extracted {
+            return Err(std::io::Error::other(Exceeded::Extracted(max_extracted)));
+        }
+
+        Ok(read)
+    }
+}
@@ -3,21 +3,51 @@
 use base64::Engine;
 use clap::{Args, Parser, Subcommand};
 use indicatif::{MultiProgress, ProgressBar};
+use progress::Log;
 use serde::{de::DeserializeOwned, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 use std::fs::File;
 use std::io::{Read, Seek};
 use std::path::{Path, PathBuf};
 
+mod adopt;
+mod archive;
+mod backup;
+mod checksum;
 mod dat;
+mod dbfile;
 mod dirs;
+mod doctor;
 mod duplicates;
+mod export;
 mod game;
+mod glyphs;
 mod http;
+mod index;
+mod interrupt;
+mod limits;
 mod mame;
 mod mess;
+mod notify;
+mod output;
+mod priority;
+mod progress;
+mod protect;
+mod quarantine;
+mod sbi;
+mod selection;
+mod sequential;
+mod sevenz;
 mod split;
+mod spool;
+mod symlinks;
+mod table;
+mod tags;
+mod throughput;
+mod torrent;
+mod update;
+mod wish;
 
 static MAME: &str = "mame";
 static MESS: &str = "mess";
@@ -26,6 +56,7 @@ static REDUMP: &str = "redump";
 static NOINTRO: &str = "nointro";
 
 static DB_MAME: &str = "mame.cbor";
+static DB_MAME_META: &str = "mame-meta.cbor";
 static DB_MESS_SPLIT: &str = "mess-split.cbor";
 static DB_REDUMP_SPLIT: &str = "redump-split.cbor";
 
@@ -69,7 +100,9 @@ pub enum Error {
     XmlFile(ResourceError<quick_xml::de::DeError>),
     CborWrite(ciborium::ser::Error<std::io::Error>),
     TomlWrite(toml::ser::Error),
+    EditorFailed,
     Zip(zip::result::ZipError),
+    SevenZip(sevenz_rust::Error),
     Http(attohttpc::Error),
     HttpCode(attohttpc::StatusCode),
     Inquire(inquire::error::InquireError),
@@ -81,9 +114,28 @@ pub enum Error {
     NoSoftwareLists,
     NoSuchSoftware(String),
     MissingCache(&'static str),
-    InvalidCache(&'static str),
+    InvalidCache(&'static str, PathBuf, dbfile::Damage),
+    InvalidData(PathBuf, dbfile::Damage),
     InvalidPath,
     InvalidSha1(ResourceError<hex::FromHexError>),
+    InvalidChecksums(ResourceError<checksum::ParseError>),
+    InvalidTorrent(ResourceError<serde_bencode::Error>),
+    NoSha1Checksums(&'static str),
+    MissingSourceIndex,
+    NoSbiSource,
+    NoSuchDirectory(PathBuf),
+    NoDirectorySet(String),
+    DirectoryAlreadySet(String),
+    InvalidBackup(PathBuf),
+    Regex(regex::Error),
+    UnsupportedLayout,
+    NoSuchTag(String),
+    ChdmanFailed(PathBuf),
+    ArchiveLimit(limits::Exceeded),
+    WriteVerifyFailed(PathBuf),
+    HookFailed(String),
+    MailFailed(String),
+    Interrupted,
 }
 
 macro_rules! err_from {
@@ -99,9 +151,12 @@ macro_rules! err_from {
 
 err_from!(std::io::Error, IO);
 err_from!(zip::result::ZipError, Zip);
+err_from!(sevenz_rust::Error, SevenZip);
 err_from!(attohttpc::Error, Http);
 err_from!(toml::ser::Error, TomlWrite);
 err_from!(inquire::error::InquireError, Inquire);
+err_from!(regex::Error, Regex);
+err_from!(limits::Exceeded, ArchiveLimit);
 
 impl std::error::Error for Error {}
 
@@ -113,7 +168,9 @@ impl fmt::Display for Error {
             Error::XmlFile(err) => err.fmt(f),
             Error::CborWrite(err) => err.fmt(f),
             Error::TomlWrite(err) => err.fmt(f),
+            Error::EditorFailed => write!(f, "editor exited unsuccessfully"),
             Error::Zip(err) => err.fmt(f),
+            Error::SevenZip(err) => err.fmt(f),
             Error::Http(err) => err.fmt(f),
             Error::HttpCode(code) => match code.canonical_reason() {
                 Some(reason) => write!(f, "HTTP error {} - {}", code.as_str(), reason),
@@ -132,13 +189,53 @@ impl fmt::Display for Error {
                 "missing cache files, please run \"emuman {} init\" to populate",
                 s
             ),
-            Error::InvalidCache(s) => write!(
+            Error::InvalidCache(s, p, damage) => write!(
                 f,
-                "outdated or invalid cache files, please run \"emuman {} init\" to repopulate",
+                "\"{}\" {}, please run \"emuman {} init\" to repopulate",
+                p.display(),
+                damage,
                 s
             ),
+            Error::InvalidData(p, damage) => write!(
+                f,
+                "\"{}\" {}, please restore or remove it",
+                p.display(),
+                damage
+            ),
             Error::InvalidPath => write!(f, "invalid UTF-8 path"),
             Error::InvalidSha1(err) => err.fmt(f),
+            Error::InvalidChecksums(err) => err.fmt(f),
+            Error::InvalidTorrent(err) => err.fmt(f),
+            Error::NoSha1Checksums(action) => write!(f, "{action} requires SHA-1 checksums"),
+            Error::MissingSourceIndex => write!(
+                f,
+                "missing source index, please run \"emuman source index\" to populate"
+            ),
+            Error::NoSbiSource => write!(
+                f,
+                "no .sbi files indexed, please run \"emuman redump sbi <SOURCE>\" to populate"
+            ),
+            Error::NoSuchDirectory(p) => write!(f, "no such directory \"{}\"", p.display()),
+            Error::NoDirectorySet(s) => write!(f, "no directory assigned to \"{}\"", s),
+            Error::DirectoryAlreadySet(s) => {
+                write!(f, "directory already assigned to \"{}\"", s)
+            }
+            Error::InvalidBackup(p) => write!(f, "\"{}\" is not an emuman backup", p.display()),
+            Error::Regex(err) => err.fmt(f),
+            Error::NoSuchTag(tag) => write!(f, "no such tag \"{tag}\""),
+            Error::ChdmanFailed(p) => write!(f, "chdman failed to extract \"{}\"", p.display()),
+            Error::ArchiveLimit(exceeded) => write!(f, "archive skipped, {exceeded}"),
+            Error::HookFailed(command) => write!(f, "completion hook \"{command}\" failed"),
+            Error::Interrupted => write!(f, "interrupted before finishing"),
+            Error::MailFailed(to) => write!(f, "unable to mail report to \"{to}\""),
+            Error::WriteVerifyFailed(p) => write!(
+                f,
+                "\"{}\" didn't match its source when read back, removed",
+                p.display()
+            ),
+            Error::UnsupportedLayout => {
+                write!(f, "only \"letter\" or \"files\" layouts may be organized")
+            }
         }
     }
 }
@@ -158,7 +255,26 @@ impl Resource {
         }
     }
 
-    fn rom_sources(&self, progress: &MultiProgress) -> game::RomSources {
+    // None if a URL's server says it hasn't changed since last imported
+    // and everything imported from it is still stored
+    fn open_if_modified(
+        &self,
+        is_stored: impl Fn(&str) -> bool,
+    ) -> Result<Option<(ResourceFile, Option<http::Validators>)>, Error> {
+        match self {
+            Resource::File(_) => self.open().map(|f| Some((f, None))),
+            Resource::Url(u) => Ok(
+                match http::fetch_url_data_if_modified(u.as_str(), is_stored)? {
+                    http::Fetched::Modified { data, validators } => {
+                        Some((ResourceFile::Url(std::io::Cursor::new(data)), validators))
+                    }
+                    http::Fetched::NotModified => None,
+                },
+            ),
+        }
+    }
+
+    fn rom_sources(&self, progress: &MultiProgress) -> game::RomSources<'_> {
         match self {
             Self::File(f) => game::file_rom_sources(f, progress),
             Self::Url(url) => game::url_rom_sources(url, progress),
@@ -231,6 +347,300 @@ impl std::io::Seek for ResourceFile {
     }
 }
 
+#[derive(Args)]
+struct OptRepairPolicy {
+    /// move source files into place instead of copying them
+    #[clap(long = "move-sources")]
+    move_sources: bool,
+
+    /// only add missing files, never deleting, renaming or replacing files
+    #[clap(long = "add-only")]
+    add_only: bool,
+
+    /// leave extra files in place and don't treat them as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// truncate files which are a good dump followed by padding
+    #[clap(long = "trim-overdumps")]
+    trim_overdumps: bool,
+
+    /// read back each written file from its device to check it was written correctly
+    #[clap(long = "verify-writes")]
+    verify_writes: bool,
+
+    /// report what would be repaired without changing any files
+    #[clap(long = "dry-run")]
+    dry_run: bool,
+
+    /// move deleted and replaced files into this directory instead of removing them
+    #[clap(long = "backup-dir")]
+    backup_dir: Option<PathBuf>,
+
+    /// make files which verify OK read-only
+    #[clap(long = "protect")]
+    protect: bool,
+}
+
+impl OptRepairPolicy {
+    fn policy(&self) -> game::RepairPolicy {
+        if self.dry_run {
+            eprintln!("* dry run : no files will be changed");
+        }
+
+        // files left as they are shouldn't be made read-only either
+        protect::set_enabled(self.protect && !self.dry_run);
+
+        game::RepairPolicy {
+            move_sources: self.move_sources,
+            add_only: self.add_only,
+            keep_extra: self.keep_extra,
+            timestamp: game::Timestamp::Source,
+            trim_overdumps: self.trim_overdumps,
+            verify_writes: self.verify_writes,
+            dry_run: self.dry_run,
+            backup_dir: self.backup_dir.clone(),
+        }
+    }
+}
+
+// the repair policy of categories whose DATs declare a date
+#[derive(Args)]
+struct OptDatRepairPolicy {
+    #[clap(flatten)]
+    policy: OptRepairPolicy,
+
+    /// give copied files the DAT's declared date instead of their source's
+    #[clap(long = "dat-date")]
+    dat_date: bool,
+}
+
+impl OptDatRepairPolicy {
+    fn policy(&self) -> game::RepairPolicy {
+        game::RepairPolicy {
+            timestamp: match self.dat_date {
+                true => game::Timestamp::Dat(None),
+                false => game::Timestamp::Source,
+            },
+            ..self.policy.policy()
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptPresence {
+    /// only report games missing from the directory
+    #[clap(long = "missing", overrides_with = "present")]
+    missing: bool,
+
+    /// only report games present in the directory (default)
+    #[clap(long = "present", overrides_with = "missing")]
+    present: bool,
+}
+
+impl OptPresence {
+    // the names of the games to report, given those in the directory
+    fn select(&self, db: &game::GameDb, present: HashSet<String>) -> HashSet<String> {
+        match self.missing {
+            false => present,
+            true => db
+                .games_iter()
+                .filter(|game| !present.contains(&game.name))
+                .map(|game| game.name.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptSetLists {
+    /// write names of complete sets to file, one per line
+    #[clap(long = "have-list")]
+    have: Option<PathBuf>,
+
+    /// write names of incomplete sets to file, one per line
+    #[clap(long = "miss-list")]
+    miss: Option<PathBuf>,
+
+    /// write a DAT of the missing or bad parts of incomplete sets to file
+    #[clap(long = "fixdat")]
+    fixdat: Option<PathBuf>,
+}
+
+impl OptSetLists {
+    #[inline]
+    fn is_set(&self) -> bool {
+        self.have.is_some() || self.miss.is_some() || self.fixdat.is_some()
+    }
+
+    #[inline]
+    fn export(self, sets: game::SetLists) -> Result<(), Error> {
+        sets.export(
+            self.have.as_deref(),
+            self.miss.as_deref(),
+            self.fixdat.as_deref(),
+        )
+        .map_err(Error::IO)
+    }
+}
+
+#[derive(Args)]
+struct OptNameFilter {
+    /// only process DATs or software lists whose names match glob
+    #[clap(long = "only")]
+    only: Vec<glob::Pattern>,
+
+    /// skip DATs or software lists whose names match glob
+    #[clap(long = "skip")]
+    skip: Vec<glob::Pattern>,
+}
+
+impl OptNameFilter {
+    fn matches(&self, name: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::default()
+        };
+
+        (self.only.is_empty() || self.only.iter().any(|p| p.matches_with(name, options)))
+            && !self.skip.iter().any(|p| p.matches_with(name, options))
+    }
+
+    fn filter(
+        &self,
+        dirs: impl Iterator<Item = (String, PathBuf)>,
+    ) -> std::vec::IntoIter<(String, PathBuf)> {
+        dirs.filter(|(name, _)| self.matches(name))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+#[derive(Args)]
+struct OptLimits {
+    /// how deeply Zip files may be nested within one another in sources
+    #[clap(long = "max-nesting", default_value_t = limits::DEFAULT_MAX_NESTING)]
+    max_nesting: usize,
+
+    /// how much may be unpacked from any single source file, such as 64G
+    #[clap(long = "max-extracted", value_parser = parse_byte_size)]
+    max_extracted: Option<usize>,
+
+    /// how large any Zip file nested within a source Zip file may be, such as 16G
+    #[clap(long = "max-entry-size", value_parser = parse_byte_size)]
+    max_entry_size: Option<usize>,
+}
+
+impl OptLimits {
+    // must be applied before any sources are read
+    fn apply(&self) {
+        limits::set_max_nesting(self.max_nesting);
+        limits::set_max_extracted(
+            self.max_extracted
+                .map_or(limits::DEFAULT_MAX_EXTRACTED, |size| size as u64),
+        );
+        limits::set_max_entry_size(
+            self.max_entry_size
+                .map_or(limits::DEFAULT_MAX_ENTRY_SIZE, |size| size as u64),
+        );
+    }
+}
+
+#[derive(Args)]
+struct OptReport {
+    /// display only summary counts when verifying, not each failure
+    #[clap(long = "summary")]
+    summary: bool,
+
+    /// display a desktop notification when finished
+    #[clap(long = "notify")]
+    notify: bool,
+
+    /// shell command to run when finished, given results in EMUMAN_* variables
+    #[clap(long = "on-complete")]
+    on_complete: Option<String>,
+
+    /// URL to post a report of the results to when finished
+    #[clap(long = "webhook")]
+    webhook: Option<String>,
+
+    /// format of webhook reports, "json", "discord" or "slack"
+    #[clap(long = "webhook-format", default_value = "json")]
+    webhook_format: notify::WebhookFormat,
+
+    /// address to mail a report of the results to when finished, using sendmail
+    #[clap(long = "mail-to")]
+    mail_to: Option<String>,
+}
+
+impl OptReport {
+    // must be applied before anything is verified
+    fn apply(&self) {
+        set_summary_only(self.summary);
+        notify::set_targets(notify::Targets {
+            notify: self.notify,
+            on_complete: self.on_complete.clone(),
+            webhook: self.webhook.clone(),
+            webhook_format: self.webhook_format,
+            mail_to: self.mail_to.clone(),
+        });
+    }
+}
+
+#[derive(Args)]
+struct OptSources {
+    /// input file, directory, or URL
+    input: Vec<Resource>,
+
+    /// also use files from the saved source index as inputs
+    #[clap(long = "source-index")]
+    source_index: bool,
+
+    /// also use all configured directories as inputs,
+    /// where extras are kept while they're sources for anything
+    #[clap(long = "from-collection", conflicts_with = "move_sources")]
+    from_collection: bool,
+
+    #[clap(flatten)]
+    limits: OptLimits,
+}
+
+impl OptSources {
+    fn rom_sources(&self) -> Result<game::RomSources<'_>, Error> {
+        self.limits.apply();
+
+        let sources = rom_sources(&self.input);
+
+        let sources = if self.from_collection {
+            let mbar = progress::multi();
+            let sources = dirs::collection_dirs()
+                .into_iter()
+                .filter(|dir| dir.is_dir())
+                .fold(sources, |sources, dir| {
+                    merge_sources(sources, game::file_rom_sources(&dir, &mbar))
+                });
+            mbar.clear().unwrap();
+            sources
+        } else {
+            sources
+        };
+
+        let sources = if self.source_index {
+            let mut index = index::SourceIndex::load()?;
+            if index.revalidate() {
+                index.save()?;
+            }
+            merge_sources(sources, index.rom_sources())
+        } else {
+            sources
+        };
+
+        wish::Wishlist::load()?.report(&sources);
+
+        Ok(sources)
+    }
+}
+
 #[derive(Args)]
 struct OptMameInit {
     /// MAME's XML file or URL
@@ -239,29 +649,95 @@ struct OptMameInit {
 
 impl OptMameInit {
     fn execute(self) -> Result<(), Error> {
-        let xml_data = match self.xml {
-            Some(resource) => {
-                let mut f = resource.open()?;
-                let mut data = String::new();
-                if is_zip(&mut f)? {
-                    zip::ZipArchive::new(f)?
-                        .by_index(0)?
-                        .read_to_string(&mut data)?;
-                } else {
-                    f.read_to_string(&mut data)?;
-                }
-                data
-            }
-            None => {
-                let mut xml_data = String::new();
-                std::io::stdin().read_to_string(&mut xml_data)?;
-                xml_data
+        // files are recorded by absolute path, since later runs may be from somewhere else
+        let source = self.xml.as_ref().map(|resource| match resource {
+            Resource::File(path) => path
+                .canonicalize()
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+            Resource::Url(url) => url.clone(),
+        });
+
+        read_mame_xml(self.xml).and_then(|db| {
+            let metadata = db.metadata();
+            write_game_db(DB_MAME, db)?;
+            write_mame_metadata(metadata)
+        })?;
+
+        match source {
+            Some(source) => update::UpdateSources::set_mame(source),
+            None => Ok(()),
+        }
+    }
+}
+
+// reads MAME's XML from a file or URL, which may be zipped,
+// or from standard input if none is given
+fn read_mame_xml(xml: Option<Resource>) -> Result<game::GameDb, Error> {
+    let xml_data = match xml {
+        Some(resource) => {
+            let mut f = resource.open()?;
+            let mut data = String::new();
+            if is_zip(&mut f)? {
+                zip::ZipArchive::new(f)?
+                    .by_index(0)?
+                    .read_to_string(&mut data)?;
+            } else {
+                f.read_to_string(&mut data)?;
             }
-        };
+            data
+        }
+        None => {
+            let mut xml_data = String::new();
+            std::io::stdin().read_to_string(&mut xml_data)?;
+            xml_data
+        }
+    };
+
+    quick_xml::de::from_str(&xml_data)
+        .map_err(Error::Xml)
+        .map(|mame: mame::Mame| mame.into_game_db())
+}
+
+#[derive(Args)]
+struct OptMameCompare {
+    /// the earlier version's XML file or URL
+    old: Resource,
+
+    /// ROMs directory
+    #[clap(short = 'r', long = "roms")]
+    roms: Option<PathBuf>,
+}
+
+impl OptMameCompare {
+    fn execute(self) -> Result<(), Error> {
+        let roms = dirs::mame_roms(self.roms);
+        let machines: HashSet<String> = roms
+            .as_ref()
+            .read_dir()?
+            .filter_map(|e| e.ok().and_then(|e| e.file_name().into_string().ok()))
+            .collect();
 
-        quick_xml::de::from_str(&xml_data)
-            .map_err(Error::Xml)
-            .and_then(|mame: mame::Mame| write_game_db(DB_MAME, mame.into_game_db()))
+        let old = read_mame_xml(Some(self.old))?;
+        let new: game::GameDb = read_game_db(MAME, DB_MAME)?;
+
+        let changes = old
+            .changes(&new)
+            .affecting(|machine| machines.contains(machine));
+
+        if changes.is_empty() {
+            eprintln!("* nothing in \"{}\" is affected", roms.as_ref().display());
+        } else {
+            changes.report(&format!(
+                "{} {} {}",
+                old.description(),
+                glyphs::copied(),
+                new.description()
+            ));
+        }
+
+        Ok(())
     }
 }
 
@@ -270,19 +746,12 @@ struct OptMameDir;
 
 impl OptMameDir {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Version", "Directory"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Version", "Directory"]);
 
         table.add_row(vec![
-            read_game_db::<game::GameDb>(MAME, DB_MAME)?.description(),
-            &dirs::mame_roms(None).as_ref().to_string_lossy(),
+            comfy_table::Cell::new(read_mame_metadata()?.description()),
+            dir_cell(dirs::mame_roms(None).as_ref()),
         ]);
 
         println!("{table}");
@@ -293,22 +762,39 @@ impl OptMameDir {
 
 #[derive(Args)]
 struct OptMameList {
-    /// sorting order, use "description", "year" or "creator"
-    #[clap(short = 's', long = "sort", default_value = "description")]
-    sort: game::GameColumn,
+    /// comma-separated sorting order, use "description", "year", "creator", "name" or "status"
+    #[clap(
+        short = 's',
+        long = "sort",
+        default_value = "description",
+        value_delimiter = ','
+    )]
+    sort: Vec<game::GameColumn>,
 
     /// display simple list with less information
     #[clap(short = 'S', long = "simple")]
     simple: bool,
 
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
     /// search term for querying specific machines
     search: Option<String>,
 }
 
 impl OptMameList {
     fn execute(self) -> Result<(), Error> {
-        let db = read_game_db::<game::GameDb>(MAME, DB_MAME)?;
-        db.list(self.search.as_deref(), self.sort, self.simple);
+        let db = read_mame_metadata()?;
+        db.list(
+            search_term(self.search, self.regex)?.as_ref(),
+            self.tag.tagged()?.as_ref(),
+            &self.sort,
+            self.simple,
+        );
         Ok(())
     }
 }
@@ -319,18 +805,17 @@ struct OptMameGames {
     #[clap(short = 'S', long = "simple")]
     simple: bool,
 
-    /// games to search for, by short name
+    /// games to search for, by short name or glob pattern such as "mslug*"
     games: Vec<String>,
 }
 
 impl OptMameGames {
     fn execute(self) -> Result<(), Error> {
-        let db = read_game_db::<game::GameDb>(MAME, DB_MAME)?;
+        let db = read_mame_metadata()?;
         match self.games.as_slice() {
             [] => db.games(&select_game_names(&db)?, self.simple),
             games => db.games(games, self.simple),
         }
-        Ok(())
     }
 }
 
@@ -338,6 +823,10 @@ impl OptMameGames {
 struct OptMameParts {
     /// game's parts to search for
     game: Option<String>,
+
+    /// don't include parts from the game's devices
+    #[clap(long = "no-devices")]
+    no_devices: bool,
 }
 
 impl OptMameParts {
@@ -345,17 +834,39 @@ impl OptMameParts {
         let db = read_game_db::<game::GameDb>(MAME, DB_MAME)?;
 
         match self.game {
-            Some(game) => db.display_parts(&game),
-            None => db.display_parts(select_game_name(&db)?),
+            Some(game) => db.display_parts(&game, !self.no_devices),
+            None => db.display_parts(select_game_name(&db)?, !self.no_devices),
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptMameDeps {
+    /// game's devices to list
+    game: Option<String>,
+}
+
+impl OptMameDeps {
+    fn execute(self) -> Result<(), Error> {
+        let db = read_game_db::<game::GameDb>(MAME, DB_MAME)?;
+
+        match self.game {
+            Some(game) => db.display_deps(&game),
+            None => db.display_deps(select_game_name(&db)?),
         }
     }
 }
 
 #[derive(Args)]
 struct OptMameReport {
-    /// sorting order, use "description", "year" or "creator"
-    #[clap(short = 's', long = "sort", default_value = "description")]
-    sort: game::GameColumn,
+    /// comma-separated sorting order, use "description", "year", "creator", "name" or "status"
+    #[clap(
+        short = 's',
+        long = "sort",
+        default_value = "description",
+        value_delimiter = ','
+    )]
+    sort: Vec<game::GameColumn>,
 
     /// ROMs directory
     #[clap(short = 'r', long = "roms")]
@@ -365,22 +876,46 @@ struct OptMameReport {
     #[clap(short = 'S', long = "simple")]
     simple: bool,
 
+    #[clap(flatten)]
+    presence: OptPresence,
+
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
+    /// also write the reported games to a CSV file
+    #[clap(long = "csv")]
+    csv: Option<PathBuf>,
+
     /// search term for querying specific machines
     search: Option<String>,
 }
 
 impl OptMameReport {
     fn execute(self) -> Result<(), Error> {
-        let machines: HashSet<String> = dirs::mame_roms(self.roms)
+        let roms = dirs::mame_roms(self.roms);
+        let machines: HashSet<String> = roms
             .as_ref()
             .read_dir()?
             .filter_map(|e| e.ok().and_then(|e| e.file_name().into_string().ok()))
             .collect();
 
-        let db = read_game_db::<game::GameDb>(MAME, DB_MAME)?;
-        db.report(&machines, self.search.as_deref(), self.sort, self.simple);
-
-        Ok(())
+        let db = read_mame_metadata()?;
+        let mut selected = self.presence.select(&db, machines);
+        if let Some(tagged) = self.tag.tagged()? {
+            selected.retain(|game| tagged.contains(game));
+        }
+        db.report(
+            roms.as_ref(),
+            &selected,
+            search_term(self.search, self.regex)?.as_ref(),
+            &self.sort,
+            self.simple,
+            self.csv.as_deref(),
+        )
     }
 }
 
@@ -390,26 +925,75 @@ struct OptMameVerify {
     #[clap(short = 'r', long = "roms")]
     roms: Option<PathBuf>,
 
-    /// game to verify
+    /// game to verify, which may be a glob pattern such as "mslug*"
     #[clap(short = 'g', long = "game")]
     machines: Vec<String>,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    /// only verify each game's own parts, not those of its devices
+    #[clap(long = "no-devices")]
+    no_devices: bool,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMameVerify {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
         let db: game::GameDb = read_game_db(MAME, DB_MAME)?;
 
         let roms_dir = dirs::mame_roms(self.roms);
+        let devices = !self.no_devices;
 
-        match self.machines.as_slice() {
-            [] => verify(&db, roms_dir, db.games_iter()),
-            machines => verify(
-                &db,
-                roms_dir,
-                db.valid_games::<_, Vec<_>>(machines)?.into_iter(),
-            ),
+        let run = throughput::start();
+
+        let games = match self.machines.as_slice() {
+            [] => db.games_with_devices(devices),
+            machines => db.valid_games(machines)?,
+        };
+
+        verify(
+            &db,
+            roms_dir,
+            self.tag.filter(games)?.into_iter(),
+            self.keep_extra,
+            devices,
+            &needed,
+            &sets,
+        );
+
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
         }
 
+        self.sets.export(sets)?;
+
+        run.display();
+
         Ok(())
     }
 }
@@ -420,29 +1004,52 @@ struct OptMameRepair {
     #[clap(short = 'r', long = "roms")]
     roms: Option<PathBuf>,
 
-    /// game to add
+    /// game to add, which may be a glob pattern such as "mslug*"
     #[clap(short = 'g', long = "game")]
     machines: Vec<String>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
+
+    #[clap(flatten)]
+    policy: OptRepairPolicy,
+
+    /// skip machines which are only devices
+    #[clap(long = "no-devices")]
+    no_devices: bool,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMameRepair {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
         let db: game::GameDb = read_game_db(MAME, DB_MAME)?;
 
         let roms_dir = dirs::mame_roms(self.roms);
 
-        let mut roms = rom_sources(&self.input);
+        let run = throughput::start();
+        let mut roms = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
 
-        match self.machines.as_slice() {
-            [] => add_and_verify(&mut roms, roms_dir, db.games_iter()),
-            machines => add_and_verify(
-                &mut roms,
-                roms_dir,
-                db.valid_games::<_, Vec<_>>(machines)?.into_iter(),
-            ),
+        let games = match self.machines.as_slice() {
+            [] => db.games_with_devices(!self.no_devices),
+            machines => db.valid_games(machines)?,
+        };
+
+        match self.preflight {
+            true => {
+                preflight_games(&db, roms_dir.as_ref(), games.into_iter(), &roms);
+                Ok(())
+            }
+            false => add_and_verify(&mut roms, &policy, roms_dir, games.into_iter())
+                .map(|()| run.display()),
         }
     }
 }
@@ -452,6 +1059,9 @@ enum OptMame {
     /// initialize internal database
     Init(OptMameInit),
 
+    /// list machines in collection changed since an earlier version
+    Compare(OptMameCompare),
+
     /// list defined directory
     Dir(OptMameDir),
 
@@ -464,6 +1074,9 @@ enum OptMame {
     /// list given games, in order
     Games(OptMameGames),
 
+    /// list a game's device dependencies
+    Deps(OptMameDeps),
+
     /// generate report of games in collection
     Report(OptMameReport),
 
@@ -479,10 +1092,12 @@ impl OptMame {
     fn execute(self) -> Result<(), Error> {
         match self {
             OptMame::Init(o) => o.execute(),
+            OptMame::Compare(o) => o.execute(),
             OptMame::Dir(o) => o.execute(),
             OptMame::List(o) => o.execute(),
             OptMame::Parts(o) => o.execute(),
             OptMame::Games(o) => o.execute(),
+            OptMame::Deps(o) => o.execute(),
             OptMame::Report(o) => o.execute(),
             OptMame::Verify(o) => o.execute(),
             OptMame::Repair(o) => o.execute(),
@@ -498,26 +1113,59 @@ struct OptMessInit {
 
 impl OptMessInit {
     fn execute(self) -> Result<(), Error> {
-        let mut split_db = split::SplitDb::new();
+        init_sl(self.xml).map(|_| ())
+    }
+}
 
-        for file in self.xml.into_iter() {
-            let sl: mess::Softwarelist =
-                quick_xml::de::from_reader(File::open(&file).map(std::io::BufReader::new)?)
-                    .map_err(|error| {
-                        Error::XmlFile(ResourceError {
-                            error,
-                            file: Resource::File(file),
-                        })
-                    })?;
+// imports software lists, returning how each list already imported changed
+fn init_sl(xml: Vec<PathBuf>) -> Result<BTreeMap<String, game::Changes>, Error> {
+    let mut changed = BTreeMap::new();
 
-            sl.populate_split_db(&mut split_db);
-            write_named_db(DIR_SL, &sl.name().to_owned(), sl.into_game_db())?;
-        }
+    // hash files which have since gone missing are left as they were imported
+    let (xml, missing): (Vec<_>, Vec<_>) = xml.into_iter().partition(|file| file.is_file());
+    for file in missing {
+        eprintln!("* skipping missing software list \"{}\"", file.display());
+    }
 
-        write_game_db(DB_MESS_SPLIT, &split_db)?;
+    update::UpdateSources::add_sl(&xml)?;
 
-        Ok(())
+    // the split database keeps the games of lists not imported this time,
+    // apart from those recorded without their list
+    let mut split_db = read_game_db::<split::SplitDb>(MESS, DB_MESS_SPLIT)
+        .unwrap_or_else(|_| split::SplitDb::new());
+    split_db.retain(|game| game.software_list().is_some());
+
+    for file in xml.into_iter() {
+        let sl: mess::Softwarelist = quick_xml::de::from_reader(
+            File::open(&file).map(std::io::BufReader::new)?,
+        )
+        .map_err(|error| {
+            Error::XmlFile(ResourceError {
+                error,
+                file: Resource::File(file),
+            })
+        })?;
+
+        split_db.retain(|game| game.software_list() != Some(sl.name()));
+        sl.populate_split_db(&mut split_db);
+        let name = sl.name().to_owned();
+        let db = sl.into_game_db();
+
+        // lists imported for the first time have nothing to compare
+        if let Ok(old) = read_named_db::<game::GameDb>(MESS, DIR_SL, &name) {
+            let changes = old.changes(&db);
+            if !changes.is_empty() {
+                changes.report(&name);
+                changed.insert(name.clone(), changes);
+            }
+        }
+
+        write_named_db(DIR_SL, &name, db)?;
     }
+
+    write_game_db(DB_MESS_SPLIT, &split_db)?;
+
+    Ok(changed)
 }
 
 #[derive(Args)]
@@ -527,15 +1175,8 @@ struct OptMessDirs {
 
 impl OptMessDirs {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Software", "Directory"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Software", "Directory"]);
 
         table.add_rows(
             read_collected_dbs::<BTreeMap<String, game::GameDb>, _>(DIR_SL)
@@ -567,14 +1208,26 @@ struct OptMessList {
     #[clap(short = 'L', long = "software")]
     software_list: Option<String>,
 
-    /// sorting order, use "description", "year" or "publisher"
-    #[clap(short = 's', long = "sort", default_value = "description")]
-    sort: game::GameColumn,
+    /// comma-separated sorting order, use "description", "year", "publisher", "name" or "status"
+    #[clap(
+        short = 's',
+        long = "sort",
+        default_value = "description",
+        value_delimiter = ','
+    )]
+    sort: Vec<game::GameColumn>,
 
     /// display simple list with less information
     #[clap(short = 'S', long = "simple")]
     simple: bool,
 
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
     /// search term for querying specific items
     search: Option<String>,
 }
@@ -584,12 +1237,18 @@ impl OptMessList {
         match self.software_list.as_deref() {
             Some("any") => mess::list(
                 &read_collected_dbs(DIR_SL),
-                self.search.as_deref(),
-                self.sort,
+                search_term(self.search, self.regex)?.as_ref(),
+                self.tag.tagged()?.as_ref(),
+                &self.sort,
                 self.simple,
             ),
             Some(software_list) => read_named_db::<game::GameDb>(MESS, DIR_SL, software_list)?
-                .list(self.search.as_deref(), self.sort, self.simple),
+                .list(
+                    search_term(self.search, self.regex)?.as_ref(),
+                    self.tag.tagged()?.as_ref(),
+                    &self.sort,
+                    self.simple,
+                ),
             None => mess::list_all(&read_collected_dbs(DIR_SL)),
         }
 
@@ -607,7 +1266,7 @@ struct OptMessGames {
     #[clap(short = 'L', long = "software")]
     software_list: Option<String>,
 
-    /// games to search for, by short name
+    /// games to search for, by short name or glob pattern such as "mslug*"
     games: Vec<String>,
 }
 
@@ -622,8 +1281,24 @@ impl OptMessGames {
             [] => software_list.games(&select_game_names(&software_list)?, self.simple),
             games => software_list.games(games, self.simple),
         }
+    }
+}
 
-        Ok(())
+#[derive(Args)]
+struct OptAllParts {
+    /// display the parts of every game at once
+    #[clap(long = "all", conflicts_with = "game")]
+    all: bool,
+
+    /// format of every game's parts, "table", "csv" or "json", implies --all
+    #[clap(long = "format", conflicts_with = "game")]
+    format: Option<game::PartsFormat>,
+}
+
+impl OptAllParts {
+    #[inline]
+    fn is_set(&self) -> bool {
+        self.all || self.format.is_some()
     }
 }
 
@@ -635,19 +1310,26 @@ struct OptMessParts {
 
     /// game's parts to search for
     game: Option<String>,
+
+    #[clap(flatten)]
+    all: OptAllParts,
 }
 
 impl OptMessParts {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
         let mut software_list = match self.software_list {
             Some(software_list) => read_named_db(MESS, DIR_SL, &software_list)?,
             None => select_software_list()?,
         };
 
+        if self.all.is_set() {
+            return game::export_parts(
+                software_list.all_parts(),
+                self.all.format.unwrap_or_default(),
+            )
+            .map_err(Error::IO);
+        }
+
         let game = match self.game {
             Some(game) => software_list
                 .remove_game(&game)
@@ -655,11 +1337,8 @@ impl OptMessParts {
             None => select_software_list_game(software_list)?,
         };
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
         game.display_parts(&mut table);
         println!("{table}");
@@ -669,9 +1348,14 @@ impl OptMessParts {
 
 #[derive(Args)]
 struct OptMessReport {
-    /// sorting order, use "description", "year" or "creator"
-    #[clap(short = 's', long = "sort", default_value = "description")]
-    sort: game::GameColumn,
+    /// comma-separated sorting order, use "description", "year", "creator", "name" or "status"
+    #[clap(
+        short = 's',
+        long = "sort",
+        default_value = "description",
+        value_delimiter = ','
+    )]
+    sort: Vec<game::GameColumn>,
 
     /// ROMs directory
     #[clap(short = 'r', long = "roms")]
@@ -685,6 +1369,20 @@ struct OptMessReport {
     #[clap(short = 'S', long = "simple")]
     simple: bool,
 
+    #[clap(flatten)]
+    presence: OptPresence,
+
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
+    /// also write the reported games to a CSV file
+    #[clap(long = "csv")]
+    csv: Option<PathBuf>,
+
     /// search term for querying specific software
     search: Option<String>,
 }
@@ -699,15 +1397,26 @@ impl OptMessReport {
             None => select_software_list_and_name()?,
         };
 
-        let software: HashSet<String> = dirs::mess_roms(self.roms, &software_list)
+        let roms = dirs::mess_roms(self.roms, &software_list);
+        let software: HashSet<String> = roms
             .as_ref()
             .read_dir()?
             .filter_map(|e| e.ok().and_then(|e| e.file_name().into_string().ok()))
             .collect();
 
-        db.report(&software, self.search.as_deref(), self.sort, self.simple);
+        let mut selected = self.presence.select(&db, software);
+        if let Some(tagged) = self.tag.tagged()? {
+            selected.retain(|game| tagged.contains(game));
+        }
 
-        Ok(())
+        db.report(
+            roms.as_ref(),
+            &selected,
+            search_term(self.search, self.regex)?.as_ref(),
+            &self.sort,
+            self.simple,
+            self.csv.as_deref(),
+        )
     }
 }
 
@@ -721,13 +1430,41 @@ struct OptMessVerify {
     #[clap(short = 'L', long = "software")]
     software_list: Option<String>,
 
-    /// game to verify
+    /// game to verify, which may be a glob pattern such as "mslug*"
     #[clap(short = 'g', long = "game")]
     software: Vec<String>,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    #[clap(flatten)]
+    tag: OptTag,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMessVerify {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
         let (db, software_list) = match self.software_list {
             Some(software_list) => (
                 read_named_db::<game::GameDb>(MESS, DIR_SL, &software_list)?,
@@ -738,18 +1475,34 @@ impl OptMessVerify {
 
         let roms_dir = dirs::mess_roms(self.roms, &software_list);
 
-        match self.software.as_slice() {
-            [] => verify(&db, roms_dir, db.games_iter()),
-            machines => verify(
-                &db,
-                roms_dir,
-                db.valid_games::<_, Vec<_>>(machines)?.into_iter(),
-            ),
-        }
+        let run = throughput::start();
 
-        Ok(())
-    }
-}
+        let games = match self.software.as_slice() {
+            [] => db.games_iter().collect(),
+            software => db.valid_games(software)?,
+        };
+
+        verify(
+            &db,
+            roms_dir,
+            self.tag.filter(games)?.into_iter(),
+            self.keep_extra,
+            true,
+            &needed,
+            &sets,
+        );
+
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        self.sets.export(sets)?;
+
+        run.display();
+
+        Ok(())
+    }
+}
 
 #[derive(Args)]
 struct OptMessVerifyAll {
@@ -760,20 +1513,63 @@ struct OptMessVerifyAll {
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    /// number of DAT files to verify at once
+    #[clap(short = 'j', long = "jobs", default_value = "1")]
+    jobs: usize,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMessVerifyAll {
     fn execute(self) -> Result<(), Error> {
         use crate::game::Never;
 
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+
+        let run = throughput::start();
+
         process_all_mess(
             "verifying software lists",
             self.roms,
-            |parts, path, _| -> Result<_, Never> { Ok(parts.verify_failures(path)) },
+            |parts, path, _| -> Result<_, Never> {
+                let failures = parts.verify_failures(path);
+                needed.record(&failures);
+                Ok(failures)
+            },
             self.show_all,
+            self.keep_extra,
+            &self.filter,
+            self.jobs,
         )
         .unwrap();
 
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        run.display();
+
         Ok(())
     }
 }
@@ -788,16 +1584,28 @@ struct OptMessRepair {
     #[clap(short = 'L', long = "software")]
     software_list: Option<String>,
 
-    /// game to add
+    /// game to add, which may be a glob pattern such as "mslug*"
     #[clap(short = 'g', long = "game")]
     software: Vec<String>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
+
+    #[clap(flatten)]
+    policy: OptRepairPolicy,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMessRepair {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
         let (db, software_list) = match self.software_list {
             Some(software_list) => (
                 read_named_db::<game::GameDb>(MESS, DIR_SL, &software_list)?,
@@ -808,15 +1616,22 @@ impl OptMessRepair {
 
         let roms_dir = dirs::mess_roms(self.roms, &software_list);
 
-        let mut roms = rom_sources(&self.input);
+        let run = throughput::start();
+        let mut roms = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
 
-        match self.software.as_slice() {
-            [] => add_and_verify(&mut roms, &roms_dir, db.games_iter()),
-            software => add_and_verify(
-                &mut roms,
-                roms_dir,
-                db.valid_games::<_, Vec<_>>(software)?.into_iter(),
-            ),
+        let games: Vec<_> = match self.software.as_slice() {
+            [] => db.games_iter().collect(),
+            software => db.valid_games(software)?,
+        };
+
+        match self.preflight {
+            true => {
+                preflight_games(&db, roms_dir.as_ref(), games.into_iter(), &roms);
+                Ok(())
+            }
+            false => add_and_verify(&mut roms, &policy, roms_dir, games.into_iter())
+                .map(|()| run.display()),
         }
     }
 }
@@ -827,29 +1642,58 @@ struct OptMessRepairAll {
     #[clap(short = 'r', long = "roms")]
     roms: Option<PathBuf>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
 
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    #[clap(flatten)]
+    policy: OptRepairPolicy,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptMessRepairAll {
     fn execute(self) -> Result<(), Error> {
-        let rom_sources = rom_sources(&self.input);
+        self.report.apply();
+
+        let run = throughput::start();
+        let rom_sources = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_all_mess(self.roms, &self.filter, &rom_sources);
+            return Ok(());
+        }
 
         process_all_mess(
             "adding and verifying software lists",
             self.roms,
             |parts, path, mbar| {
-                parts.add_and_verify_failures(&rom_sources, path, |repaired| {
-                    mbar.println(repaired.to_string()).unwrap();
+                parts.add_and_verify_failures(&rom_sources, &policy, path, |repaired| {
+                    mbar.log(repaired.to_string());
                     repaired.into_fixed_pathbuf()
                 })
             },
             self.show_all,
-        )
+            policy.keep_extra,
+            &self.filter,
+            1,
+        )?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
@@ -859,6 +1703,10 @@ struct OptMessSplit {
     #[clap(short = 'r', long = "roms", default_value = ".")]
     output: PathBuf,
 
+    /// write into the software list's own ROMs directory and verify
+    #[clap(long = "install", conflicts_with = "output")]
+    install: bool,
+
     /// ROMs to split
     roms: Vec<PathBuf>,
 }
@@ -895,7 +1743,11 @@ impl OptMessSplit {
                     .iter()
                     .find(|m| m.matches(data))
                 {
-                    exact_match.extract(&self.output, data)?;
+                    if self.install {
+                        install_split(rom, exact_match, data)?;
+                    } else {
+                        exact_match.extract(&self.output, data)?;
+                    }
                 }
             }
 
@@ -904,6 +1756,61 @@ impl OptMessSplit {
     }
 }
 
+// splits a ROM into the directory of the software list it belongs to
+// and verifies the result against that list
+fn install_split(rom: &Path, split: &split::SplitGame, data: &[u8]) -> Result<(), Error> {
+    let Some(software_list) = split.software_list() else {
+        eprintln!(
+            "* {} : software list of \"{}\" unknown, run \"sl init\" again",
+            rom.display(),
+            split.name()
+        );
+        return Ok(());
+    };
+
+    let db = read_named_db::<game::GameDb>(MESS, DIR_SL, software_list)?;
+    let game = db.valid_game(split.name())?;
+    let list_root = dirs::mess_roms(None, software_list);
+    std::fs::create_dir_all(list_root.as_ref())?;
+    split.extract(list_root.as_ref(), data)?;
+
+    let game_root = list_root.as_ref().join(split.name());
+    let failures = game.parts.verify_failures(&game_root);
+    for line in failure_lines(&failures, &[]) {
+        println!("{line}");
+    }
+    if failures.is_empty() {
+        println!(
+            "{} {} {}",
+            rom.display(),
+            glyphs::copied(),
+            game_root.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Args)]
+struct OptMessSplitList {
+    /// game to display track layout of
+    game: Option<String>,
+}
+
+impl OptMessSplitList {
+    fn execute(self) -> Result<(), Error> {
+        let db = read_game_db::<split::SplitDb>(MESS, DB_MESS_SPLIT)?;
+
+        match self.game {
+            Some(game) => db.display_tracks(&game),
+            None => {
+                db.display_games();
+                Ok(())
+            }
+        }
+    }
+}
+
 #[derive(Subcommand)]
 #[clap(name = "sl")]
 enum OptMess {
@@ -941,6 +1848,9 @@ enum OptMess {
 
     /// split ROM into software list-compatible parts, if necessary
     Split(OptMessSplit),
+
+    /// list software which can be split, or a software's layout
+    SplitList(OptMessSplitList),
 }
 
 impl OptMess {
@@ -957,6 +1867,7 @@ impl OptMess {
             OptMess::Repair(o) => o.execute(),
             OptMess::RepairAll(o) => o.execute(),
             OptMess::Split(o) => o.execute(),
+            OptMess::SplitList(o) => o.execute(),
         }
     }
 }
@@ -973,15 +1884,28 @@ struct OptExtraInit {
 
 impl OptExtraInit {
     fn execute(self) -> Result<(), Error> {
-        if self.replace {
-            clear_named_dbs(DIR_EXTRA)?;
+        let migration = DirMigration::new(EXTRA, DIR_EXTRA, dirs::extra_dir_names());
+
+        // replaced DATs must all be imported again, changed or not
+        let (datfiles, validators) = dat::fetch_modified_and_parse::<_, Vec<_>>(
+            self.dats,
+            (!self.replace).then_some(|name: &str| named_db_path(DIR_EXTRA, name).exists()),
+            |file, datfile| {
+                dat::DatFile::new_unflattened(datfile)
+                    .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
+            },
+        )?;
+
+        for datfile in &datfiles {
+            write_named_db(DIR_EXTRA, datfile.name(), datfile)?;
         }
+        validators.save()?;
 
-        for datfile in dat::fetch_and_parse::<_, Vec<_>>(self.dats, |file, datfile| {
-            dat::DatFile::new_unflattened(datfile)
-                .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
-        })? {
-            write_named_db(DIR_EXTRA, datfile.name(), &datfile)?;
+        migration.apply(&datfiles, dirs::rename_extra_dir);
+
+        // old DATs are only removed once their directories have moved on
+        if self.replace {
+            retain_named_dbs(DIR_EXTRA, |name| datfiles.iter().any(|d| d.name() == name))?;
         }
 
         Ok(())
@@ -1025,10 +1949,13 @@ impl OptExtraDestroy {
 
 #[derive(Args)]
 struct OptExtraDirs {
-    // sort output by version
+    /// sort output by version
     #[clap(short = 'V')]
     sort_by_version: bool,
 
+    #[clap(flatten)]
+    filter: OptDirsFilter,
+
     search: Option<String>,
 }
 
@@ -1039,12 +1966,93 @@ impl OptExtraDirs {
             read_collected_dbs(DIR_EXTRA),
             self.search,
             self.sort_by_version,
+            self.filter,
         );
 
         Ok(())
     }
 }
 
+#[derive(Args)]
+struct OptExtraDirSet {
+    /// extras category name
+    name: String,
+
+    /// directory to assign
+    dir: PathBuf,
+}
+
+impl OptExtraDirSet {
+    fn execute(self) -> Result<(), Error> {
+        if !read_db_names(DIR_EXTRA)
+            .into_iter()
+            .flatten()
+            .any(|n| n == self.name)
+        {
+            return Err(Error::NoSuchDatFile(self.name));
+        }
+
+        dirs::set_extra_dir(&self.name, &self.dir)
+    }
+}
+
+#[derive(Args)]
+struct OptExtraDirUnset {
+    /// extras category name
+    name: String,
+}
+
+impl OptExtraDirUnset {
+    fn execute(self) -> Result<(), Error> {
+        dirs::unset_extra_dir(&self.name)
+    }
+}
+
+#[derive(Args)]
+struct OptExtraDirRename {
+    /// current extras category name
+    old: String,
+
+    /// new extras category name
+    new: String,
+}
+
+impl OptExtraDirRename {
+    fn execute(self) -> Result<(), Error> {
+        if !read_db_names(DIR_EXTRA)
+            .into_iter()
+            .flatten()
+            .any(|n| n == self.new)
+        {
+            return Err(Error::NoSuchDatFile(self.new));
+        }
+
+        dirs::rename_extra_dir(&self.old, &self.new)
+    }
+}
+
+#[derive(Subcommand)]
+enum OptExtraDir {
+    /// assign directory to extras category
+    Set(OptExtraDirSet),
+
+    /// remove extras category's directory assignment
+    Unset(OptExtraDirUnset),
+
+    /// move directory assignment to another extras category
+    Rename(OptExtraDirRename),
+}
+
+impl OptExtraDir {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptExtraDir::Set(o) => o.execute(),
+            OptExtraDir::Unset(o) => o.execute(),
+            OptExtraDir::Rename(o) => o.execute(),
+        }
+    }
+}
+
 #[derive(Args)]
 struct OptExtraSizes {
     /// sort output by total size
@@ -1072,15 +2080,18 @@ struct OptExtraList {
     /// extras name
     name: Option<String>,
 
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
     search: Option<String>,
 }
 
 impl OptExtraList {
     fn execute(self) -> Result<(), Error> {
         match self.name.as_deref() {
-            Some(name) => {
-                read_named_db::<dat::DatFile>(EXTRA, DIR_EXTRA, name)?.list(self.search.as_deref())
-            }
+            Some(name) => read_named_db::<dat::DatFile>(EXTRA, DIR_EXTRA, name)?
+                .list(search_term(self.search, self.regex)?.as_ref()),
             None => dat::DatFile::list_all(read_collected_dbs::<BTreeMap<_, _>, _>(DIR_EXTRA)),
         }
 
@@ -1097,12 +2108,37 @@ struct OptExtraVerify {
     /// extras category to verify
     #[clap(short = 'E', long = "extra")]
     extra: Option<String>,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptExtraVerify {
     fn execute(self) -> Result<(), Error> {
         use crate::game::Never;
 
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
         let dir = self.dir;
 
         let extra = match self.extra {
@@ -1111,11 +2147,25 @@ impl OptExtraVerify {
             None => dirs::select_any_extra_name()?,
         };
 
+        let run = throughput::start();
+
         process_dat(read_named_db(EXTRA, DIR_EXTRA, &extra)?, |datfile, pbar| {
-            Ok::<_, Never>(datfile.verify(dirs::extra_dir(dir, &extra).as_ref(), pbar))
+            let root = dirs::extra_dir(dir, &extra);
+            let results = datfile.verify(root.as_ref(), self.keep_extra, pbar);
+            needed.record(&results.failures);
+            datfile.record_sets(root.as_ref(), &results.failures, &sets);
+            Ok::<_, Never>(results)
         })
         .unwrap();
 
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        self.sets.export(sets)?;
+
+        run.display();
+
         Ok(())
     }
 }
@@ -1125,20 +2175,58 @@ struct OptExtraVerifyAll {
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    /// number of DAT files to verify at once
+    #[clap(short = 'j', long = "jobs", default_value = "1")]
+    jobs: usize,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptExtraVerifyAll {
     fn execute(self) -> Result<(), Error> {
-        use game::Never;
+        self.report.apply();
 
-        process_all_dat(
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+
+        let run = throughput::start();
+
+        verify_all_dat(
             "verifying all MAME extras",
-            dirs::extra_dirs(),
+            self.filter.filter(dirs::extra_dirs()),
             |name| read_named_db(EXTRA, DIR_EXTRA, name),
-            |datfile, dir, pbar| Ok::<_, Never>(datfile.verify(dir, pbar)),
+            |datfile, dir, increment| {
+                let results = datfile.verify_with_progress(dir, self.keep_extra, increment);
+                needed.record(&results.failures);
+                results
+            },
             self.show_all,
-        )
-        .unwrap();
+            self.jobs,
+        );
+
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        run.display();
 
         Ok(())
     }
@@ -1154,12 +2242,24 @@ struct OptExtraRepair {
     #[clap(short = 'E', long = "extra")]
     extra: Option<String>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptExtraRepair {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
         let dir = self.dir;
         let extra = match self.extra {
             Some(extra) => extra,
@@ -1167,39 +2267,89 @@ impl OptExtraRepair {
             None => dirs::select_any_extra_name()?,
         };
         let datfile: dat::DatFile = read_named_db::<dat::DatFile>(EXTRA, DIR_EXTRA, &extra)?;
-        let mut rom_sources = rom_sources(&self.input);
+        let run = throughput::start();
+        let rom_sources = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_dat(
+                &datfile,
+                dirs::extra_dir(dir, &extra).as_ref(),
+                &rom_sources,
+                false,
+            );
+            return Ok(());
+        }
 
         process_dat(datfile, |datfile, pbar| {
             datfile.add_and_verify(
-                &mut rom_sources,
+                &rom_sources,
+                &policy,
                 dirs::extra_dir(dir, &extra).as_ref(),
                 pbar,
             )
-        })
+        })?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
 #[derive(Args)]
 struct OptExtraRepairAll {
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
 
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptExtraRepairAll {
     fn execute(self) -> Result<(), Error> {
-        let mut parts = rom_sources(&self.input);
+        self.report.apply();
+
+        let run = throughput::start();
+        let parts = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_all_dat(
+                self.filter.filter(dirs::extra_dirs()),
+                |name| read_named_db(EXTRA, DIR_EXTRA, name),
+                &parts,
+            );
+            return Ok(());
+        }
 
         process_all_dat(
             "adding and verifying all MAME extras",
-            dirs::extra_dirs(),
+            self.filter.filter(dirs::extra_dirs()),
             |name| read_named_db(EXTRA, DIR_EXTRA, name),
-            |datfile, dir, pbar| datfile.add_and_verify(&mut parts, dir, pbar),
+            |datfile, dir, pbar, increment| {
+                datfile.add_and_verify_with_progress(&parts, &policy, dir, pbar, increment)
+            },
             self.show_all,
-        )
+            1,
+        )?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
@@ -1215,10 +2365,6 @@ struct OptExtraParts {
 
 impl OptExtraParts {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
         let mut datfile = match self.name {
             Some(name) => read_named_db::<dat::DatFile>(EXTRA, DIR_EXTRA, &name),
             None => {
@@ -1230,10 +2376,7 @@ impl OptExtraParts {
 
                 dats.sort_unstable_by(|x, y| x.name().cmp(y.name()));
 
-                inquire::Select::new("select DAT", dats)
-                    .with_page_size(terminal_height())
-                    .prompt()
-                    .map_err(Error::Inquire)
+                selection::select(EXTRA, "select DAT", dats, |dat| dat.name())
             }
         }?;
 
@@ -1244,15 +2387,10 @@ impl OptExtraParts {
             None => select_datfile_game(datfile)?,
         };
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
-        for (name, part) in game.into_iter().collect::<BTreeMap<_, _>>() {
-            table.add_row(vec![name, part.digest().to_string()]);
-        }
+        game.display_parts(&mut table);
         println!("{table}");
 
         Ok(())
@@ -1271,6 +2409,10 @@ enum OptExtra {
     /// list defined directories
     Dirs(OptExtraDirs),
 
+    /// manage directory assignments
+    #[clap(subcommand)]
+    Dir(OptExtraDir),
+
     /// display total sizes of defined directories
     Sizes(OptExtraSizes),
 
@@ -1301,6 +2443,7 @@ impl OptExtra {
             OptExtra::Init(o) => o.execute(),
             OptExtra::Destroy(o) => o.execute(),
             OptExtra::Dirs(o) => o.execute(),
+            OptExtra::Dir(o) => o.execute(),
             OptExtra::Sizes(o) => o.execute(),
             OptExtra::List(o) => o.execute(),
             OptExtra::Verify(o) => o.execute(),
@@ -1324,28 +2467,44 @@ struct OptRedumpInit {
 
 impl OptRedumpInit {
     fn execute(self) -> Result<(), Error> {
-        let mut split_db = split::SplitDb::new();
+        let migration = DirMigration::new(REDUMP, DIR_REDUMP, dirs::redump_dir_names());
+
+        // the split database keeps the games of DATs not imported this time
+        let mut split_db = read_game_db::<split::SplitDb>(REDUMP, DB_REDUMP_SPLIT)
+            .unwrap_or_else(|_| split::SplitDb::new());
+
+        let datfiles = dat::fetch_and_parse::<_, Vec<_>>(self.xml, |file, datfile| {
+            let old_dat = read_named_db::<dat::DatFile>(REDUMP, DIR_REDUMP, datfile.name()).ok();
+            let mut replaced = old_dat
+                .iter()
+                .flat_map(|old| old.game_parts().map(|(game, _)| game.to_owned()))
+                .collect::<HashSet<_>>();
 
-        for datfile in dat::fetch_and_parse::<_, Vec<_>>(self.xml, |file, datfile| {
             (if self.edit {
-                let old_dat = read_named_db(REDUMP, DIR_REDUMP, datfile.name()).ok();
                 dat::edit_file(datfile, old_dat)
             } else {
                 Ok(datfile)
             })
             .inspect(|datfile| {
+                // games dropped from the DAT are dropped from the split database too
+                replaced.extend(datfile.games().map(|game| game.name().to_owned()));
+                split_db.retain(|game| !replaced.contains(game.name()));
                 split_db.populate(datfile);
             })
             .and_then(|datfile| {
                 dat::DatFile::new_flattened(datfile)
                     .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
             })
-        })? {
-            write_named_db(DIR_REDUMP, datfile.name(), &datfile)?;
+        })?;
+
+        for datfile in &datfiles {
+            write_named_db(DIR_REDUMP, datfile.name(), datfile)?;
         }
 
         write_game_db(DB_REDUMP_SPLIT, &split_db)?;
 
+        migration.apply(&datfiles, dirs::rename_redump_dir);
+
         Ok(())
     }
 }
@@ -1386,10 +2545,13 @@ impl OptRedumpDestroy {
 
 #[derive(Args)]
 struct OptRedumpDirs {
-    // sort output by version
+    /// sort output by version
     #[clap(short = 'V')]
     sort_by_version: bool,
 
+    #[clap(flatten)]
+    filter: OptDirsFilter,
+
     search: Option<String>,
 }
 
@@ -1400,6 +2562,7 @@ impl OptRedumpDirs {
             read_collected_dbs(DIR_REDUMP),
             self.search,
             self.sort_by_version,
+            self.filter,
         );
 
         Ok(())
@@ -1433,14 +2596,19 @@ struct OptRedumpList {
     /// software list to use
     software_list: Option<String>,
 
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
     search: Option<String>,
 }
 
 impl OptRedumpList {
     fn execute(self) -> Result<(), Error> {
         match self.software_list.as_deref() {
-            Some(name) => read_named_db::<dat::DatFile>(REDUMP, DIR_REDUMP, name)?
-                .list(self.search.as_deref()),
+            Some(name) => {
+                read_redump_db(name)?.list(search_term(self.search, self.regex)?.as_ref())
+            }
             None => dat::DatFile::list_all(read_collected_dbs::<BTreeMap<_, _>, _>(DIR_REDUMP)),
         }
 
@@ -1457,12 +2625,37 @@ struct OptRedumpVerify {
     /// DAT name to verify disk images for
     #[clap(short = 'D', long = "dat")]
     name: Option<String>,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptRedumpVerify {
     fn execute(self) -> Result<(), Error> {
         use crate::game::Never;
 
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
         let roms = self.roms;
 
         let name = match self.name {
@@ -1471,14 +2664,25 @@ impl OptRedumpVerify {
             None => dirs::select_any_redump_name()?,
         };
 
-        process_dat(
-            read_named_db(REDUMP, DIR_REDUMP, &name)?,
-            |datfile, pbar| {
-                Ok::<_, Never>(datfile.verify(dirs::redump_roms(roms, &name).as_ref(), pbar))
-            },
-        )
+        let run = throughput::start();
+
+        process_dat(read_redump_db(&name)?, |datfile, pbar| {
+            let root = dirs::redump_roms(roms, &name);
+            let results = datfile.verify(root.as_ref(), self.keep_extra, pbar);
+            needed.record(&results.failures);
+            datfile.record_sets(root.as_ref(), &results.failures, &sets);
+            Ok::<_, Never>(results)
+        })
         .unwrap();
 
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        self.sets.export(sets)?;
+
+        run.display();
+
         Ok(())
     }
 }
@@ -1488,20 +2692,58 @@ struct OptRedumpVerifyAll {
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    /// number of DAT files to verify at once
+    #[clap(short = 'j', long = "jobs", default_value = "1")]
+    jobs: usize,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptRedumpVerifyAll {
     fn execute(self) -> Result<(), Error> {
-        use game::Never;
+        self.report.apply();
 
-        process_all_dat(
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+
+        let run = throughput::start();
+
+        verify_all_dat(
             "verifying all Redump files",
-            dirs::redump_dirs(),
-            |name| read_named_db(REDUMP, DIR_REDUMP, name),
-            |datfile, dir, pbar| Ok::<_, Never>(datfile.verify(dir, pbar)),
+            self.filter.filter(dirs::redump_dirs()),
+            read_redump_db,
+            |datfile, dir, increment| {
+                let results = datfile.verify_with_progress(dir, self.keep_extra, increment);
+                needed.record(&results.failures);
+                results
+            },
             self.show_all,
-        )
-        .unwrap();
+            self.jobs,
+        );
+
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        run.display();
 
         Ok(())
     }
@@ -1517,52 +2759,120 @@ struct OptRedumpRepair {
     #[clap(short = 'D', long = "dat")]
     name: Option<String>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptRedumpRepair {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
         let roms = self.roms;
         let name = match self.name {
             Some(name) => name,
             None if roms.is_none() => dirs::select_redump_name()?,
             None => dirs::select_any_redump_name()?,
         };
-        let datfile: dat::DatFile = read_named_db::<dat::DatFile>(REDUMP, DIR_REDUMP, &name)?;
-        let mut rom_sources = rom_sources(&self.input);
+        let datfile = read_redump_db(&name)?;
+        let run = throughput::start();
+        let rom_sources = add_sbi_sources(self.sources.rom_sources()?)?;
+        let wanted = datfile
+            .all_parts()
+            .map(|(_, _, part)| part)
+            .collect::<HashSet<_>>();
+        add_redump_split_sources(&rom_sources, |part| wanted.contains(part));
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_dat(
+                &datfile,
+                dirs::redump_roms(roms, &name).as_ref(),
+                &rom_sources,
+                false,
+            );
+            return Ok(());
+        }
 
         process_dat(datfile, |datfile, pbar| {
             datfile.add_and_verify(
-                &mut rom_sources,
+                &rom_sources,
+                &policy,
                 dirs::redump_roms(roms, &name).as_ref(),
                 pbar,
             )
-        })
+        })?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
 #[derive(Args)]
 struct OptRedumpRepairAll {
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
 
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptRedumpRepairAll {
     fn execute(self) -> Result<(), Error> {
-        let mut parts = rom_sources(&self.input);
+        self.report.apply();
+
+        let run = throughput::start();
+        let parts = add_sbi_sources(self.sources.rom_sources()?)?;
+        add_redump_split_sources(&parts, |_| true);
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_all_dat(
+                self.filter.filter(dirs::redump_dirs()),
+                read_redump_db,
+                &parts,
+            );
+            return Ok(());
+        }
 
         process_all_dat(
             "adding and verifying all Redump files",
-            dirs::redump_dirs(),
-            |name| read_named_db(REDUMP, DIR_REDUMP, name),
-            |datfile, dir, pbar| datfile.add_and_verify(&mut parts, dir, pbar),
+            self.filter.filter(dirs::redump_dirs()),
+            read_redump_db,
+            |datfile, dir, pbar, increment| {
+                datfile.add_and_verify_with_progress(&parts, &policy, dir, pbar, increment)
+            },
             self.show_all,
-        )
+            1,
+        )?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
@@ -1574,14 +2884,13 @@ struct OptRedumpParts {
 
     /// game's parts to search for
     game: Option<String>,
+
+    #[clap(flatten)]
+    all: OptAllParts,
 }
 
 impl OptRedumpParts {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
         let mut datfile = match self.name {
             Some(name) => read_named_db::<dat::DatFile>(REDUMP, DIR_REDUMP, &name),
             None => {
@@ -1592,12 +2901,15 @@ impl OptRedumpParts {
                     .collect::<Vec<dat::DatFile>>();
 
                 dats.sort_unstable_by(|x, y| x.name().cmp(y.name()));
-                inquire::Select::new("select DAT", dats)
-                    .with_page_size(terminal_height())
-                    .prompt()
-                    .map_err(Error::Inquire)
+                selection::select(REDUMP, "select DAT", dats, |dat| dat.name())
             }
         }?;
+        sbi::SbiIndex::load()?.add_parts(&mut datfile);
+
+        if self.all.is_set() {
+            return game::export_parts(datfile.all_parts(), self.all.format.unwrap_or_default())
+                .map_err(Error::IO);
+        }
 
         let game = match self.game {
             Some(game) => datfile
@@ -1606,28 +2918,50 @@ impl OptRedumpParts {
             None => select_datfile_game(datfile)?,
         };
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
-        for (name, part) in game.into_iter().collect::<BTreeMap<_, _>>() {
-            table.add_row(vec![name, part.digest().to_string()]);
-        }
+        game.display_parts(&mut table);
         println!("{table}");
 
         Ok(())
     }
 }
 
+#[derive(Args)]
+struct OptRedumpSbi {
+    /// directory or Zip file of .sbi files, or the previous one if omitted
+    source: Option<PathBuf>,
+}
+
+impl OptRedumpSbi {
+    fn execute(self) -> Result<(), Error> {
+        let mut index = sbi::SbiIndex::load()?;
+
+        let source = match self.source {
+            Some(source) => source,
+            None => index.source().ok_or(Error::NoSbiSource)?.to_owned(),
+        };
+
+        let found = index.index(source)?;
+        index.save()?;
+
+        eprintln!(
+            "* {found} .sbi files indexed from \"{}\"",
+            index.source().unwrap_or(Path::new("")).display()
+        );
+
+        Ok(())
+    }
+}
+
 #[derive(Args)]
 struct OptRedumpSplit {
     /// directory to place output tracks
     #[clap(short = 'r', long = "roms", default_value = ".")]
     root: PathBuf,
 
-    /// input .bin file
+    /// input .bin file, laid out by any .cue file of the same name beside it
     bins: Vec<PathBuf>,
 }
 
@@ -1636,8 +2970,13 @@ impl OptRedumpSplit {
         let db: split::SplitDb = read_game_db(REDUMP, DB_REDUMP_SPLIT)?;
 
         self.bins.iter().try_for_each(|bin_path| {
-            match bin_path.metadata().map(|m| db.possible_matches(m.len())) {
-                Err(_) | Ok([]) => Ok(()),
+            let cue = split::CueSheet::beside(bin_path);
+            match bin_path
+                .metadata()
+                .map(|m| db.layouts(m.len(), cue.as_ref()))
+            {
+                Err(_) => Ok(()),
+                Ok(matches) if matches.is_empty() => Ok(()),
                 Ok(matches) => {
                     let mut bin_data = Vec::new();
                     File::open(bin_path).and_then(|mut f| f.read_to_end(&mut bin_data))?;
@@ -1651,6 +2990,161 @@ impl OptRedumpSplit {
     }
 }
 
+#[derive(Args)]
+struct OptRedumpExtract {
+    /// directory to place output tracks
+    #[clap(short = 'r', long = "roms", default_value = ".")]
+    root: PathBuf,
+
+    /// chdman program to extract with
+    #[clap(long = "chdman", default_value = "chdman")]
+    chdman: PathBuf,
+
+    /// extract DVD images to .iso instead of CD images to .bin/.cue
+    #[clap(long = "iso")]
+    iso: bool,
+
+    /// input .chd file
+    #[clap(required = true)]
+    chds: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    limits: OptLimits,
+}
+
+impl OptRedumpExtract {
+    fn execute(self) -> Result<(), Error> {
+        self.limits.apply();
+
+        let datfiles: BTreeMap<String, dat::DatFile> = read_collected_dbs(DIR_REDUMP);
+        let split_db: Option<split::SplitDb> = read_game_db(REDUMP, DB_REDUMP_SPLIT).ok();
+
+        for chd in &self.chds {
+            let stem = chd
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .ok_or(Error::InvalidPath)?;
+
+            // chdman's output is staged beside the final tracks
+            // so they can be moved into place rather than copied
+            let staging = self.root.join(format!(".{stem}.extracting"));
+            std::fs::create_dir_all(&staging)?;
+            let result = self.extract(chd, &stem, &staging, &datfiles, split_db.as_ref());
+            std::fs::remove_dir_all(&staging)?;
+            result?;
+        }
+
+        Ok(())
+    }
+
+    fn extract(
+        &self,
+        chd: &Path,
+        stem: &str,
+        staging: &Path,
+        datfiles: &BTreeMap<String, dat::DatFile>,
+        split_db: Option<&split::SplitDb>,
+    ) -> Result<(), Error> {
+        let mut command = std::process::Command::new(&self.chdman);
+        match self.iso {
+            true => command
+                .arg("extractdvd")
+                .arg("-o")
+                .arg(staging.join(format!("{stem}.iso"))),
+            false => command
+                .arg("extractcd")
+                .arg("-o")
+                .arg(staging.join(format!("{stem}.cue")))
+                .arg("-ob")
+                .arg(staging.join(format!("{stem}.bin"))),
+        };
+        if !command.arg("-i").arg(chd).status()?.success() {
+            return Err(Error::ChdmanFailed(chd.to_owned()));
+        }
+
+        let mut sources = game::empty_rom_sources();
+        for path in sub_files(staging.to_owned()) {
+            sources.extend(game::RomSource::from_path(path)?);
+        }
+        if let Some(split_db) = split_db {
+            split_db.add_split_sources(&sources, |_| true);
+        }
+
+        // a DAT's cue sheets are never quite what chdman writes,
+        // so a game matches if every other part was extracted
+        let found = datfiles.iter().find_map(|(dat, datfile)| {
+            datfile.game_parts().find_map(|(game, parts)| {
+                let mut tracks = parts
+                    .iter()
+                    .filter(|(name, _)| !name.ends_with(".cue"))
+                    .peekable();
+                (tracks.peek().is_some() && tracks.all(|(_, part)| sources.contains_key(part)))
+                    .then_some((dat, game, parts))
+            })
+        });
+
+        match found {
+            Some((dat, game, parts)) => {
+                let policy = game::RepairPolicy {
+                    move_sources: true,
+                    ..game::RepairPolicy::default()
+                };
+                let failures = parts.add_and_verify_failures(
+                    &sources,
+                    &policy,
+                    &self.root.join(game),
+                    |fixed| {
+                        println!("{fixed}");
+                        fixed.into_fixed_pathbuf()
+                    },
+                )?;
+                for failure in &failures {
+                    println!("{failure}");
+                }
+                eprintln!(
+                    "* \"{}\" verified as \"{game}\" from \"{dat}\" with {} failures",
+                    chd.display(),
+                    failures.len()
+                );
+            }
+
+            // nothing to verify against, so keep chdman's output as-is
+            None => {
+                for path in sub_files(staging.to_owned()) {
+                    if let Some(name) = path.file_name() {
+                        let target = self.root.join(name);
+                        std::fs::rename(&path, &target)?;
+                        println!("* {}", target.display());
+                    }
+                }
+                eprintln!("* \"{}\" not found in any Redump DAT", chd.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptRedumpSplitList {
+    /// game to display track layout of
+    game: Option<String>,
+}
+
+impl OptRedumpSplitList {
+    fn execute(self) -> Result<(), Error> {
+        let db: split::SplitDb = read_game_db(REDUMP, DB_REDUMP_SPLIT)?;
+
+        match self.game {
+            Some(game) => db.display_tracks(&game),
+            None => {
+                db.display_games();
+                Ok(())
+            }
+        }
+    }
+}
+
 #[derive(Subcommand)]
 #[clap(name = "redump")]
 enum OptRedump {
@@ -1686,8 +3180,17 @@ enum OptRedump {
     /// split .bin file into multiple tracks
     Split(OptRedumpSplit),
 
+    /// extract CHD back into tracks, verified against DATs
+    Extract(OptRedumpExtract),
+
+    /// list games which can be split, or a game's track layout
+    SplitList(OptRedumpSplitList),
+
     /// display game's parts
     Parts(OptRedumpParts),
+
+    /// index .sbi files for libcrypt-protected PSX games
+    Sbi(OptRedumpSbi),
 }
 
 impl OptRedump {
@@ -1703,7 +3206,10 @@ impl OptRedump {
             OptRedump::Repair(o) => o.execute(),
             OptRedump::RepairAll(o) => o.execute(),
             OptRedump::Split(o) => o.execute(),
+            OptRedump::Extract(o) => o.execute(),
+            OptRedump::SplitList(o) => o.execute(),
             OptRedump::Parts(o) => o.execute(),
+            OptRedump::Sbi(o) => o.execute(),
         }
     }
 }
@@ -1740,6 +3246,9 @@ enum OptNointro {
     #[clap(alias = "add-all")]
     RepairAll(OptNointroRepairAll),
 
+    /// move category's ROMs into or out of subdirectories by letter
+    Organize(OptNointroOrganize),
+
     /// display game's parts
     Parts(OptNointroParts),
 }
@@ -1756,6 +3265,7 @@ impl OptNointro {
             OptNointro::VerifyAll(o) => o.execute(),
             OptNointro::Repair(o) => o.execute(),
             OptNointro::RepairAll(o) => o.execute(),
+            OptNointro::Organize(o) => o.execute(),
             OptNointro::Parts(o) => o.execute(),
         }
     }
@@ -1777,23 +3287,43 @@ struct OptNointroInit {
 
 impl OptNointroInit {
     fn execute(self) -> Result<(), Error> {
-        if self.replace {
-            clear_named_dbs(DIR_NOINTRO)?;
+        let migration = DirMigration::new(NOINTRO, DIR_NOINTRO, dirs::nointro_dir_names());
+
+        // replaced DATs must all be imported again, changed or not,
+        // and DATs to be edited are wanted whether changed or not
+        let (datfiles, validators) = dat::fetch_modified_and_parse::<_, Vec<_>>(
+            self.dats,
+            (!self.replace && !self.edit)
+                .then_some(|name: &str| named_db_path(DIR_NOINTRO, name).exists()),
+            |file, datfile| {
+                (if self.edit {
+                    // replaced DATs are edited afresh
+                    let old_dat = (!self.replace)
+                        .then(|| read_named_db(NOINTRO, DIR_NOINTRO, datfile.name()).ok())
+                        .flatten();
+                    dat::edit_file(datfile, old_dat)
+                } else {
+                    Ok(datfile)
+                })
+                .and_then(|datfile| {
+                    dat::DatFile::new_flattened(datfile)
+                        .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
+                })
+            },
+        )?;
+
+        for datfile in &datfiles {
+            write_named_db(DIR_NOINTRO, datfile.name(), datfile)?;
         }
+        validators.save()?;
 
-        for datfile in dat::fetch_and_parse::<_, Vec<_>>(self.dats, |file, datfile| {
-            (if self.edit {
-                let old_dat = read_named_db(NOINTRO, DIR_NOINTRO, datfile.name()).ok();
-                dat::edit_file(datfile, old_dat)
-            } else {
-                Ok(datfile)
-            })
-            .and_then(|datfile| {
-                dat::DatFile::new_flattened(datfile)
-                    .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
-            })
-        })? {
-            write_named_db(DIR_NOINTRO, datfile.name(), &datfile)?;
+        migration.apply(&datfiles, dirs::rename_nointro_dir);
+
+        // old DATs are only removed once their directories have moved on
+        if self.replace {
+            retain_named_dbs(DIR_NOINTRO, |name| {
+                datfiles.iter().any(|d| d.name() == name)
+            })?;
         }
 
         Ok(())
@@ -1840,6 +3370,9 @@ struct OptNointroDirs {
     #[clap(short = 'V')]
     sort_by_version: bool,
 
+    #[clap(flatten)]
+    filter: OptDirsFilter,
+
     search: Option<String>,
 }
 
@@ -1850,6 +3383,7 @@ impl OptNointroDirs {
             read_collected_dbs(DIR_NOINTRO),
             self.search,
             self.sort_by_version,
+            self.filter,
         );
 
         Ok(())
@@ -1869,7 +3403,7 @@ impl OptNointroSizes {
     fn execute(self) -> Result<(), Error> {
         display_dir_sizes(
             dirs::nointro_dirs(),
-            read_collected_dbs(DIR_NOINTRO),
+            read_nointro_dbs(),
             self.search,
             self.sort_by_size,
         );
@@ -1883,15 +3417,20 @@ struct OptNointroList {
     /// category name
     name: Option<String>,
 
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
     search: Option<String>,
 }
 
 impl OptNointroList {
     fn execute(self) -> Result<(), Error> {
         match self.name.as_deref() {
-            Some(name) => read_named_db::<dat::DatFile>(NOINTRO, DIR_NOINTRO, name)?
-                .list(self.search.as_deref()),
-            None => dat::DatFile::list_all(read_collected_dbs::<BTreeMap<_, _>, _>(DIR_NOINTRO)),
+            Some(name) => read_nointro_db(name, None)?
+                .0
+                .list(search_term(self.search, self.regex)?.as_ref()),
+            None => dat::DatFile::list_all(read_nointro_dbs()),
         }
 
         Ok(())
@@ -1907,12 +3446,42 @@ struct OptNointroVerify {
     /// DAT name to verify ROMs for
     #[clap(short = 'D', long = "dat")]
     name: Option<String>,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    /// how games are stored, "files", "letter" for subdirectories by first letter
+    /// or "zip" for one Zip file per game, defaulting to how organize left them
+    #[clap(long = "layout")]
+    layout: Option<dat::Layout>,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptNointroVerify {
     fn execute(self) -> Result<(), Error> {
         use crate::game::Never;
 
+        self.report.apply();
+
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
         let roms = self.roms;
 
         let name = match self.name {
@@ -1921,14 +3490,34 @@ impl OptNointroVerify {
             None => dirs::select_any_nointro_name()?,
         };
 
-        process_dat(
-            read_named_db(NOINTRO, DIR_NOINTRO, &name)?,
-            |datfile, pbar| {
-                Ok::<_, Never>(datfile.verify(dirs::nointro_roms(roms, &name).as_ref(), pbar))
-            },
-        )
+        let run = throughput::start();
+
+        let (datfile, layout) = read_nointro_db(&name, self.layout)?;
+
+        process_dat(datfile, |datfile, pbar| {
+            let root = dirs::nointro_roms(roms, &name);
+            let results = match layout {
+                dat::Layout::Files | dat::Layout::Letters => {
+                    datfile.verify(root.as_ref(), self.keep_extra, pbar)
+                }
+                dat::Layout::Archives => {
+                    datfile.verify_archived(root.as_ref(), self.keep_extra, |_| pbar.inc(1))
+                }
+            };
+            needed.record(&results.failures);
+            datfile.record_sets(root.as_ref(), &results.failures, &sets);
+            Ok::<_, Never>(results)
+        })
         .unwrap();
 
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        self.sets.export(sets)?;
+
+        run.display();
+
         Ok(())
     }
 }
@@ -1938,20 +3527,70 @@ struct OptNointroVerifyAll {
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// format of results, "text" or "json"
+    #[clap(long = "output", default_value = "text")]
+    output: output::OutputFormat,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    /// number of DAT files to verify at once
+    #[clap(short = 'j', long = "jobs", default_value = "1")]
+    jobs: usize,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    /// how games are stored, "files", "letter" for subdirectories by first letter
+    /// or "zip" for one Zip file per game, defaulting to how organize left them
+    #[clap(long = "layout")]
+    layout: Option<dat::Layout>,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptNointroVerifyAll {
     fn execute(self) -> Result<(), Error> {
-        use game::Never;
+        self.report.apply();
 
-        process_all_dat(
+        output::set_format(self.output);
+
+        let needed = game::NeededParts::default();
+
+        let run = throughput::start();
+
+        verify_all_dat(
             "verifying all No-Intro files",
-            dirs::nointro_dirs(),
-            |name| read_named_db(NOINTRO, DIR_NOINTRO, name),
-            |datfile, dir, pbar| Ok::<_, Never>(datfile.verify(dir, pbar)),
+            self.filter.filter(dirs::nointro_dirs()),
+            |name| read_nointro_db(name, self.layout).map(|(datfile, _)| datfile),
+            |datfile, dir, increment| {
+                let results = match datfile.layout() {
+                    dat::Layout::Files | dat::Layout::Letters => {
+                        datfile.verify_with_progress(dir, self.keep_extra, increment)
+                    }
+                    dat::Layout::Archives => {
+                        datfile.verify_archived(dir, self.keep_extra, increment)
+                    }
+                };
+                needed.record(&results.failures);
+                results
+            },
             self.show_all,
-        )
-        .unwrap();
+            self.jobs,
+        );
+
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        run.display();
 
         Ok(())
     }
@@ -1967,53 +3606,216 @@ struct OptNointroRepair {
     #[clap(short = 'D', long = "dat")]
     name: Option<String>,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    /// how games are stored, "files", "letter" for subdirectories by first letter
+    /// or "zip" for one Zip file per game, defaulting to how organize left them
+    #[clap(long = "layout")]
+    layout: Option<dat::Layout>,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptNointroRepair {
     fn execute(self) -> Result<(), Error> {
+        self.report.apply();
+
         let roms = self.roms;
         let name = match self.name {
             Some(name) => name,
             None if roms.is_none() => dirs::select_nointro_name()?,
             None => dirs::select_any_nointro_name()?,
         };
-        let datfile: dat::DatFile = read_named_db::<dat::DatFile>(NOINTRO, DIR_NOINTRO, &name)?;
-        let mut rom_sources = rom_sources(&self.input);
+        let (datfile, layout) = read_nointro_db(&name, self.layout)?;
+        let run = throughput::start();
+        let rom_sources = self.sources.rom_sources()?;
+        game::add_converted_sources(&rom_sources);
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_dat(
+                &datfile,
+                dirs::nointro_roms(roms, &name).as_ref(),
+                &rom_sources,
+                layout == dat::Layout::Archives,
+            );
+            return Ok(());
+        }
 
         process_dat(datfile, |datfile, pbar| {
-            datfile.add_and_verify(
-                &mut rom_sources,
-                dirs::nointro_roms(roms, &name).as_ref(),
-                pbar,
-            )
-        })
+            let root = dirs::nointro_roms(roms, &name);
+            match layout {
+                dat::Layout::Files | dat::Layout::Letters => {
+                    datfile.add_and_verify(&rom_sources, &policy, root.as_ref(), pbar)
+                }
+                dat::Layout::Archives => datfile.add_and_verify_archived(
+                    &rom_sources,
+                    &policy,
+                    root.as_ref(),
+                    pbar,
+                    |_| pbar.inc(1),
+                ),
+            }
+        })?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
 #[derive(Args)]
 struct OptNointroRepairAll {
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
 
     /// show all systems in output table
     #[clap(short = 'A', long = "all")]
     show_all: bool,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    #[clap(flatten)]
+    filter: OptNameFilter,
+
+    /// how games are stored, "files", "letter" for subdirectories by first letter
+    /// or "zip" for one Zip file per game, defaulting to how organize left them
+    #[clap(long = "layout")]
+    layout: Option<dat::Layout>,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptNointroRepairAll {
     fn execute(self) -> Result<(), Error> {
-        let mut parts = rom_sources(&self.input);
+        self.report.apply();
+
+        let run = throughput::start();
+        let parts = self.sources.rom_sources()?;
+        game::add_converted_sources(&parts);
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_all_dat(
+                self.filter.filter(dirs::nointro_dirs()),
+                |name| read_nointro_db(name, self.layout).map(|(datfile, _)| datfile),
+                &parts,
+            );
+            return Ok(());
+        }
 
         process_all_dat(
             "adding and verifying No-Intro files",
-            dirs::nointro_dirs(),
-            |name| read_named_db(NOINTRO, DIR_NOINTRO, name),
-            |datfile, dir, pbar| datfile.add_and_verify(&mut parts, dir, pbar),
+            self.filter.filter(dirs::nointro_dirs()),
+            |name| read_nointro_db(name, self.layout).map(|(datfile, _)| datfile),
+            |datfile, dir, pbar, increment| match datfile.layout() {
+                dat::Layout::Files | dat::Layout::Letters => {
+                    datfile.add_and_verify_with_progress(&parts, &policy, dir, pbar, increment)
+                }
+                dat::Layout::Archives => {
+                    datfile.add_and_verify_archived(&parts, &policy, dir, pbar, increment)
+                }
+            },
             self.show_all,
-        )
+            1,
+        )?;
+
+        run.display();
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptNointroOrganize {
+    /// ROMs directory
+    #[clap(short = 'r', long = "roms")]
+    roms: Option<PathBuf>,
+
+    /// DAT name to organize ROMs for
+    #[clap(short = 'D', long = "dat")]
+    name: Option<String>,
+
+    /// layout to move ROMs into, "letter" for A/ B/ ... 0-9/ subdirectories
+    #[clap(long = "layout", required_unless_present = "flat")]
+    layout: Option<dat::Layout>,
+
+    /// move ROMs out of any subdirectories by letter
+    #[clap(long = "flat", conflicts_with = "layout")]
+    flat: bool,
+}
+
+impl OptNointroOrganize {
+    fn execute(self) -> Result<(), Error> {
+        let layout = match self.layout {
+            _ if self.flat => dat::Layout::Files,
+            Some(dat::Layout::Archives) => return Err(Error::UnsupportedLayout),
+            Some(layout) => layout,
+            None => dat::Layout::Files,
+        };
+
+        let name = match self.name {
+            Some(name) => name,
+            None => dirs::select_nointro_name()?,
+        };
+
+        let (mut datfile, _) = read_nointro_db(&name, None)?;
+        let root = dirs::nointro_roms(self.roms, &name);
+
+        let (moved, skipped) =
+            datfile.set_lettered_files(root.as_ref(), layout == dat::Layout::Letters)?;
+        dirs::set_nointro_layout(&name, layout)?;
+
+        for path in skipped {
+            eprintln!("* skipping unverified \"{}\"", path.display());
+        }
+
+        eprintln!(
+            "* {moved} {} moved",
+            match moved {
+                1 => "entry",
+                _ => "entries",
+            }
+        );
+
+        Ok(())
+    }
+}
+
+// reads a No-Intro DAT along with how its games are stored,
+// which is however organize left them unless given
+fn read_nointro_db(
+    name: &str,
+    layout: Option<dat::Layout>,
+) -> Result<(dat::DatFile, dat::Layout), Error> {
+    let mut datfile = read_named_db::<dat::DatFile>(NOINTRO, DIR_NOINTRO, name)?;
+    let layout = layout.unwrap_or_else(|| dirs::nointro_layout(name));
+    datfile.set_layout(layout);
+    Ok((datfile, layout))
+}
+
+// reads every No-Intro DAT along with how its games are stored
+fn read_nointro_dbs() -> BTreeMap<String, dat::DatFile> {
+    let mut dats: BTreeMap<String, dat::DatFile> = read_collected_dbs(DIR_NOINTRO);
+    for (name, datfile) in dats.iter_mut() {
+        datfile.set_layout(dirs::nointro_layout(name));
     }
+    dats
 }
 
 #[derive(Args)]
@@ -2024,32 +3826,28 @@ struct OptNointroParts {
 
     /// game's parts to search for
     game: Option<String>,
+
+    #[clap(flatten)]
+    all: OptAllParts,
 }
 
 impl OptNointroParts {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
         let mut datfile = match self.name {
-            Some(name) => read_named_db::<dat::DatFile>(NOINTRO, DIR_NOINTRO, &name),
+            Some(name) => read_nointro_db(&name, None).map(|(datfile, _)| datfile),
             None => {
-                let mut dats = read_named_dbs(DIR_NOINTRO)
-                    .into_iter()
-                    .flatten()
-                    .map(|(_, d)| d)
-                    .collect::<Vec<dat::DatFile>>();
+                // already sorted by name
+                let dats = read_nointro_dbs().into_values().collect::<Vec<_>>();
 
-                dats.sort_unstable_by(|x, y| x.name().cmp(y.name()));
-
-                inquire::Select::new("select DAT", dats)
-                    .with_page_size(terminal_height())
-                    .prompt()
-                    .map_err(Error::Inquire)
+                selection::select(NOINTRO, "select DAT", dats, |dat| dat.name())
             }
         }?;
 
+        if self.all.is_set() {
+            return game::export_parts(datfile.all_parts(), self.all.format.unwrap_or_default())
+                .map_err(Error::IO);
+        }
+
         let game = match self.game {
             Some(game) => datfile
                 .remove_game(&game)
@@ -2057,15 +3855,10 @@ impl OptNointroParts {
             None => select_datfile_game(datfile)?,
         };
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
-        for (name, part) in game.into_iter().collect::<BTreeMap<_, _>>() {
-            table.add_row(vec![name, part.digest().to_string()]);
-        }
+        game.display_parts(&mut table);
         println!("{table}");
 
         Ok(())
@@ -2087,6 +3880,10 @@ enum OptDat {
 
     /// display game's parts in DAT
     Parts(OptDatParts),
+
+    /// verify files by a torrent's piece hashes
+    #[clap(name = "verify-torrent")]
+    VerifyTorrent(OptDatVerifyTorrent),
 }
 
 impl OptDat {
@@ -2096,31 +3893,76 @@ impl OptDat {
             OptDat::Verify(o) => o.execute(),
             OptDat::Repair(o) => o.execute(),
             OptDat::Parts(o) => o.execute(),
+            OptDat::VerifyTorrent(o) => o.execute(),
         }
     }
 }
 
 #[derive(Args)]
-struct OptDatList {
-    dat: Resource,
+struct OptDatVerifyTorrent {
+    /// .torrent file
+    torrent: Resource,
 
-    search: Option<String>,
+    /// directory the torrent was downloaded to
+    dir: PathBuf,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
-impl OptDatList {
+impl OptDatVerifyTorrent {
     fn execute(self) -> Result<(), Error> {
-        dat::fetch_and_parse_single(self.dat, |file, datfile| {
-            dat::DatFile::new_flattened(datfile)
-                .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
-        })?
-        .list(self.search.as_deref());
+        self.report.apply();
+
+        let mut data = Vec::new();
+        self.torrent.open()?.read_to_end(&mut data)?;
+
+        let torrent = torrent::Torrent::parse(&data).map_err(|error| {
+            Error::InvalidTorrent(ResourceError {
+                file: self.torrent,
+                error,
+            })
+        })?;
+
+        let (failures, summary) = torrent.verify(&self.dir);
+        for line in failure_lines(&failures, &[]) {
+            println!("{line}");
+        }
+
+        notify::record(torrent.name(), &summary);
+        let mut table = init_dat_table();
+        table.add_row(summary.row(torrent.name()));
+        display_dat_table(table, None);
 
         Ok(())
     }
 }
 
 #[derive(Args)]
-struct OptDatVerify {
+struct OptDatList {
+    dat: Resource,
+
+    /// treat search term as a regular expression
+    #[clap(long = "regex")]
+    regex: bool,
+
+    search: Option<String>,
+}
+
+impl OptDatList {
+    fn execute(self) -> Result<(), Error> {
+        dat::fetch_and_parse_single(self.dat, |file, datfile| {
+            dat::DatFile::new_flattened(datfile)
+                .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
+        })?
+        .list(search_term(self.search, self.regex)?.as_ref());
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptDatVerify {
     dat: Resource,
 
     roms: PathBuf,
@@ -2128,13 +3970,61 @@ struct OptDatVerify {
     /// interactively edit DAT contents before verifying
     #[clap(long = "edit")]
     edit: bool,
+
+    /// read DAT as a sha1sum, md5sum or SFV checksum file
+    #[clap(long = "checksums", conflicts_with = "edit")]
+    checksums: bool,
+
+    /// don't treat extra files as failures
+    #[clap(long = "keep-extra")]
+    keep_extra: bool,
+
+    /// write SHA-1s and names of missing or bad parts to file
+    #[clap(long = "export-needed")]
+    export_needed: Option<PathBuf>,
+
+    #[clap(flatten)]
+    sets: OptSetLists,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptDatVerify {
     fn execute(self) -> Result<(), Error> {
         use crate::game::Never;
 
-        process_dat(
+        self.report.apply();
+
+        let needed = game::NeededParts::default();
+        let sets = game::SetLists::default();
+
+        let datfile = if self.checksums {
+            let (name, entries) = read_checksums(self.dat)?;
+            match checksum::into_parts(&entries) {
+                Some(parts) => dat::DatFile::from_parts(name, parts),
+                None => {
+                    // checksums other than SHA-1 can only be checked directly,
+                    // and without parts there's nothing to write set lists from
+                    if self.sets.is_set() {
+                        return Err(Error::NoSha1Checksums("writing set lists"));
+                    }
+                    let (failures, summary) =
+                        checksum::verify(&self.roms, &entries, self.keep_extra);
+                    for line in failure_lines(&failures, &[]) {
+                        println!("{line}");
+                    }
+                    notify::record(&name, &summary);
+                    let mut table = init_dat_table();
+                    table.add_row(summary.row(&name));
+                    display_dat_table(table, None);
+                    if let Some(path) = self.export_needed {
+                        checksum::export_needed(&path, &self.roms, &entries, &failures)?;
+                    }
+                    return Ok(());
+                }
+            }
+        } else {
             dat::fetch_and_parse_single(self.dat, |file, datfile| {
                 (if self.edit {
                     dat::edit_file(datfile, None)
@@ -2145,11 +4035,27 @@ impl OptDatVerify {
                     dat::DatFile::new_flattened(datfile)
                         .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
                 })
-            })?,
-            |datfile, pbar| Ok::<_, Never>(datfile.verify(&self.roms, pbar)),
-        )
+            })?
+        };
+
+        let run = throughput::start();
+
+        process_dat(datfile, |datfile, pbar| {
+            let results = datfile.verify(&self.roms, self.keep_extra, pbar);
+            needed.record(&results.failures);
+            datfile.record_sets(&self.roms, &results.failures, &sets);
+            Ok::<_, Never>(results)
+        })
         .unwrap();
 
+        if let Some(path) = self.export_needed {
+            needed.export(&path)?;
+        }
+
+        self.sets.export(sets)?;
+
+        run.display();
+
         Ok(())
     }
 }
@@ -2160,19 +4066,38 @@ struct OptDatRepair {
 
     roms: PathBuf,
 
-    /// input file, directory, or URL
-    input: Vec<Resource>,
+    #[clap(flatten)]
+    sources: OptSources,
 
     /// interactively edit DAT contents before verifying
     #[clap(long = "edit")]
     edit: bool,
+
+    /// read DAT as a sha1sum checksum file
+    #[clap(long = "checksums", conflicts_with = "edit")]
+    checksums: bool,
+
+    #[clap(flatten)]
+    policy: OptDatRepairPolicy,
+
+    /// report how many missing parts the sources can supply, without repairing
+    #[clap(long = "preflight")]
+    preflight: bool,
+
+    #[clap(flatten)]
+    report: OptReport,
 }
 
 impl OptDatRepair {
     fn execute(self) -> Result<(), Error> {
-        let mut rom_sources = rom_sources(&self.input);
+        self.report.apply();
 
-        process_dat(
+        let datfile = if self.checksums {
+            let (name, entries) = read_checksums(self.dat)?;
+            checksum::into_parts(&entries)
+                .map(|parts| dat::DatFile::from_parts(name, parts))
+                .ok_or(Error::NoSha1Checksums("repairing"))?
+        } else {
             dat::fetch_and_parse_single(self.dat, |file, datfile| {
                 (if self.edit {
                     dat::edit_file(datfile, None)
@@ -2183,9 +4108,25 @@ impl OptDatRepair {
                     dat::DatFile::new_flattened(datfile)
                         .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
                 })
-            })?,
-            |datfile, pbar| datfile.add_and_verify(&mut rom_sources, &self.roms, pbar),
-        )
+            })?
+        };
+
+        let run = throughput::start();
+        let rom_sources = self.sources.rom_sources()?;
+        let policy = self.policy.policy();
+
+        if self.preflight {
+            preflight_dat(&datfile, &self.roms, &rom_sources, false);
+            return Ok(());
+        }
+
+        process_dat(datfile, |datfile, pbar| {
+            datfile.add_and_verify(&rom_sources, &policy, &self.roms, pbar)
+        })?;
+
+        run.display();
+
+        Ok(())
     }
 }
 
@@ -2199,10 +4140,6 @@ struct OptDatParts {
 
 impl OptDatParts {
     fn execute(self) -> Result<(), Error> {
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-
         let mut datfile = dat::fetch_and_parse_single(self.dat, |file, datfile| {
             dat::DatFile::new_flattened(datfile)
                 .map_err(|error| Error::InvalidSha1(ResourceError { file, error }))
@@ -2215,21 +4152,146 @@ impl OptDatParts {
             None => select_datfile_game(datfile)?,
         };
 
-        let mut table = Table::new();
-        table
-            .set_header(vec!["Part", "SHA1 Hash"])
-            .load_preset(UTF8_FULL_CONDENSED)
-            .apply_modifier(UTF8_ROUND_CORNERS);
+        let mut table = crate::table::new();
+        table.set_header(vec!["Part", "SHA1 Hash", "Size"]);
 
-        for (name, part) in game.into_iter().collect::<BTreeMap<_, _>>() {
-            table.add_row(vec![name, part.digest().to_string()]);
-        }
+        game.display_parts(&mut table);
         println!("{table}");
 
         Ok(())
     }
 }
 
+#[derive(Args)]
+struct OptChecksum {
+    /// directory to generate checksums for
+    dir: PathBuf,
+
+    /// checksum file format, use "sha1", "md5" or "sfv"
+    #[clap(short = 'f', long = "format", default_value = "sha1")]
+    format: checksum::Format,
+
+    /// file to write checksums to, instead of standard output
+    #[clap(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+impl OptChecksum {
+    fn execute(self) -> Result<(), Error> {
+        use std::io::Write;
+
+        let checksums = checksum::generate(&self.dir, self.format);
+
+        let mut w: Box<dyn Write> = match self.output {
+            Some(path) => Box::new(std::io::BufWriter::new(File::create(path)?)),
+            None => Box::new(std::io::stdout().lock()),
+        };
+
+        for (path, result) in checksums {
+            match result {
+                Ok(checksum) => self.format.write_line(&mut w, &path, &checksum)?,
+                Err(err) => eprintln!("* {path} : {err}"),
+            }
+        }
+
+        w.flush().map_err(Error::IO)
+    }
+}
+
+#[derive(Args)]
+struct OptHash {
+    /// files, directories, archives or URLs to hash
+    resources: Vec<Resource>,
+
+    /// checksum output formats, "sha1", "md5" or "sfv",
+    /// given more than once or separated by commas for several
+    #[clap(
+        short = 'f',
+        long = "format",
+        default_value = "sha1",
+        value_delimiter = ','
+    )]
+    formats: Vec<checksum::Format>,
+
+    #[clap(flatten)]
+    limits: OptLimits,
+}
+
+impl OptHash {
+    fn execute(self) -> Result<(), Error> {
+        use crate::game::RomSource;
+        use rayon::prelude::*;
+        use std::io::Write;
+
+        self.limits.apply();
+
+        let mbar = progress::multi();
+
+        let (files, urls): (Vec<_>, Vec<_>) = self
+            .resources
+            .iter()
+            .partition(|resource| matches!(resource, Resource::File(_)));
+
+        let files = files
+            .into_iter()
+            .flat_map(|resource| match resource {
+                Resource::File(pb) => sub_files(pb.clone()),
+                Resource::Url(_) => Box::new(std::iter::empty()),
+            })
+            .collect::<Vec<_>>();
+
+        let pbar = mbar.add(
+            progress::bar(files.len() as u64)
+                .with_style(game::verify_style())
+                .with_message("hashing files"),
+        );
+
+        // keep command line order, with archive members
+        // following the archive they're in
+        let mut sources = files
+            .into_par_iter()
+            .map(|file| {
+                let sources = RomSource::from_path(file.clone());
+                pbar.inc(1);
+                (file.display().to_string(), sources)
+            })
+            .collect::<Vec<_>>();
+
+        pbar.finish_and_clear();
+
+        for resource in urls {
+            if let Resource::Url(url) = resource {
+                sources.push((url.clone(), RomSource::from_url(url, &mbar)));
+            }
+        }
+
+        mbar.clear().unwrap();
+
+        let mut w = std::io::stdout().lock();
+
+        for (resource, sources) in sources {
+            match sources {
+                Ok(sources) => {
+                    // each source's digests are listed together, in the order requested
+                    for (part, source) in sources {
+                        for format in &self.formats {
+                            match format.source_checksum(&part, &source) {
+                                Ok(checksum) => {
+                                    format.write_line(&mut w, &source.to_string(), &checksum)?
+                                }
+                                Err(err) => eprintln!("* {source} : {err}"),
+                            }
+                        }
+                    }
+                }
+                Err(err) => eprintln!("* {resource} : {err}"),
+            }
+        }
+
+        w.flush().map_err(Error::IO)
+    }
+}
+
 #[derive(Args)]
 struct OptIdentify {
     /// ROMs or CHDs to identify
@@ -2238,37 +4300,80 @@ struct OptIdentify {
     /// perform reverse lookup
     #[clap(short = 'l', long = "lookup")]
     lookup: bool,
+
+    /// only list where a file is missing from a configured directory
+    #[clap(short = 'w', long = "wanted")]
+    wanted: bool,
+
+    #[clap(flatten)]
+    limits: OptLimits,
 }
 
 impl OptIdentify {
     fn execute(self) -> Result<(), Error> {
         use crate::dat::DatFile;
         use crate::game::{GameDb, Part};
-        use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-        use comfy_table::presets::UTF8_FULL_CONDENSED;
-        use comfy_table::Table;
-        use indicatif::{ProgressDrawTarget, ProgressIterator};
+        use indicatif::ProgressIterator;
         use std::collections::{BTreeSet, HashMap};
 
-        let mbar = MultiProgress::with_draw_target(ProgressDrawTarget::stderr_with_hz(2));
+        self.limits.apply();
+
+        let mbar = progress::multi();
         let pbar1 = mbar.add(
-            ProgressBar::new(self.resources.len().try_into().unwrap())
+            progress::bar(self.resources.len().try_into().unwrap())
                 .with_style(game::verify_style()),
         );
         pbar1.set_message("retrieving ROMs");
 
-        if self.lookup {
+        let wishlist = wish::Wishlist::load()?;
+        let wished = wishlist.lookup();
+        let log_wished = |part: &Part, source: &game::RomSource| {
+            for (wish, name) in wished.get(part).into_iter().flatten() {
+                mbar.log(format!("* wished for {wish} : {name} found in {source}"));
+            }
+        };
+
+        if self.lookup || self.wanted {
             use iter_group::IntoGroup;
 
             let mame_db: GameDb = read_game_db(MAME, DB_MAME).unwrap_or_default();
             let mess_db: BTreeMap<String, GameDb> = read_collected_dbs(DIR_SL);
 
+            let nointro = read_nointro_dbs();
+
             let dat_parts: [(&str, BTreeMap<String, DatFile>); 3] = [
                 ("extra", read_collected_dbs(DIR_EXTRA)),
-                ("nointro", read_collected_dbs(DIR_NOINTRO)),
+                ("nointro", nointro),
                 ("redump", read_collected_dbs(DIR_REDUMP)),
             ];
 
+            // each DAT's configured directory, by category and DAT name
+            let dat_dirs: HashMap<(&str, String), PathBuf> = [
+                ("extra", dirs::extra_dirs()),
+                ("nointro", dirs::nointro_dirs()),
+                ("redump", dirs::redump_dirs()),
+            ]
+            .into_iter()
+            .flat_map(|(category, dirs)| dirs.map(move |(name, dir)| ((category, name), dir)))
+            .collect();
+            let (mame_dir, sl_dir) = (dirs::mame_dir(), dirs::sl_dir());
+
+            // where a part belongs in its configured directory, if any
+            let target = |[category, system, game, rom]: &[&str; 4]| -> Option<PathBuf> {
+                match *category {
+                    "mame" => Some(mame_dir.as_ref()?.join(game).join(rom)),
+                    "sl" => Some(sl_dir.as_ref()?.join(system).join(game).join(rom)),
+                    category => {
+                        let root = dat_dirs.get(&(category, system.to_string()))?;
+                        dat_parts
+                            .iter()
+                            .find(|(c, _)| *c == category)
+                            .and_then(|(_, datfiles)| datfiles.get(*system))
+                            .map(|datfile| datfile.part_path(root, game, rom))
+                    }
+                }
+            };
+
             let lookup = mame_db
                 .games_iter()
                 .flat_map(|game| {
@@ -2295,15 +4400,26 @@ impl OptIdentify {
                 .filter(|(part, _)| !part.is_placeholder())
                 .group::<HashMap<&Part, BTreeSet<[&str; 4]>>>();
 
-            let mut table = Table::new();
-            table
-                .set_header(vec!["Source", "Category", "System", "Game", "Part"])
-                .load_preset(UTF8_FULL_CONDENSED)
-                .apply_modifier(UTF8_ROUND_CORNERS);
+            let mut table = crate::table::new();
+            table.set_header(vec!["Source", "Category", "System", "Game", "Part"]);
 
             for resource in self.resources.into_iter().progress_with(pbar1) {
                 for (part, source) in resource.rom_sources(&mbar) {
-                    for [category, system, game, rom] in lookup.get(&part).into_iter().flatten() {
+                    if let Some(header) = part.is_disk().then(|| source.chd_header()).flatten() {
+                        mbar.log(format!("{source} : {header}"));
+                    }
+                    log_wished(&part, &source);
+                    // wanted wherever the part is configured to be but isn't
+                    let is_wanted = |entry: &&[&str; 4]| {
+                        target(entry).is_some_and(|path| !part.is_valid(&path).unwrap_or(false))
+                    };
+
+                    for [category, system, game, rom] in lookup
+                        .get(&part)
+                        .into_iter()
+                        .flatten()
+                        .filter(|entry| !self.wanted || is_wanted(entry))
+                    {
                         table.add_row(vec![
                             source.to_string().as_str(),
                             category,
@@ -2321,8 +4437,11 @@ impl OptIdentify {
         } else {
             for resource in self.resources.into_iter().progress_with(pbar1) {
                 for (part, source) in resource.rom_sources(&mbar) {
-                    mbar.println(format!("{}  {}", part.digest(), source))
-                        .unwrap();
+                    mbar.log(format!("{}  {}", part.digest(), source));
+                    if let Some(header) = part.is_disk().then(|| source.chd_header()).flatten() {
+                        mbar.log(format!("{:40}  {header}", ""));
+                    }
+                    log_wished(&part, &source);
                 }
             }
 
@@ -2334,192 +4453,1130 @@ impl OptIdentify {
 }
 
 #[derive(Subcommand)]
-enum OptCache {
-    /// add cache entries to files
-    Add(OptCacheAdd),
-
-    /// remove cache entries from files
-    #[clap(name = "delete")]
-    Delete(OptCacheDelete),
-
-    /// verify existing cache entries
-    #[clap(name = "verify")]
-    Verify(OptCacheVerify),
+enum OptSource {
+    /// scan and save files for use with --source-index
+    Index(OptSourceIndex),
 
-    /// find duplicate files and link them together
-    #[clap(name = "link-dupes")]
-    LinkDupes(OptCacheLinkDupes),
+    /// list URLs queued for download, which resume if interrupted
+    Downloads(OptSourceDownloads),
 }
 
-impl OptCache {
+impl OptSource {
     fn execute(self) -> Result<(), Error> {
         match self {
-            OptCache::Add(o) => o.execute(),
-            OptCache::Delete(o) => o.execute(),
-            OptCache::Verify(o) => o.execute(),
-            OptCache::LinkDupes(o) => o.execute(),
+            OptSource::Index(o) => o.execute(),
+            OptSource::Downloads(o) => o.execute(),
         }
     }
 }
 
 #[derive(Args)]
-struct OptCacheAdd {
-    /// files or directories
+struct OptSourceIndex {
+    /// files or directories to index
+    #[clap(required = true)]
     paths: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    limits: OptLimits,
 }
 
-impl OptCacheAdd {
+impl OptSourceIndex {
     fn execute(self) -> Result<(), Error> {
-        use crate::game::Part;
-        use indicatif::ParallelProgressIterator;
-        use rayon::prelude::*;
-
-        let pb = ProgressBar::new_spinner().with_message("locating files");
-        let files = {
-            pb.wrap_iter(
-                self.paths
-                    .into_iter()
-                    .flat_map(unique_sub_files)
-                    .filter(|pb| matches!(Part::has_xattr(pb), Ok(false))),
-            )
-            .collect::<Vec<PathBuf>>()
-        };
-        pb.finish_and_clear();
+        self.limits.apply();
 
-        let pb = ProgressBar::new(files.len() as u64)
-            .with_style(crate::game::verify_style())
-            .with_message("adding cache entries");
-
-        files
-            .into_par_iter()
-            .progress_with(pb.clone())
-            .for_each(|file: PathBuf| match Part::from_path(&file) {
-                Ok(part) => part.set_xattr(&file),
-                Err(err) => pb.println(format!("{} : {}", file.display(), err)),
-            });
+        let mut index = index::SourceIndex::load_or_default()?;
 
-        pb.finish_and_clear();
+        for path in &self.paths {
+            index.index(path)?;
+        }
 
-        Ok(())
+        index.save()
     }
 }
 
 #[derive(Args)]
-struct OptCacheDelete {
-    /// files or directories
-    paths: Vec<PathBuf>,
+struct OptSourceDownloads {
+    /// drop every queued download along with any partial data
+    #[clap(long = "clear")]
+    clear: bool,
 }
 
-impl OptCacheDelete {
+impl OptSourceDownloads {
     fn execute(self) -> Result<(), Error> {
-        use crate::game::Part;
-
-        let pb = ProgressBar::new_spinner().with_message("removing cache entries");
-
-        for file in pb.wrap_iter(
-            self.paths
-                .into_iter()
-                .flat_map(unique_sub_files)
-                .filter(|pb| matches!(Part::has_xattr(pb), Ok(true))),
-        ) {
-            Part::remove_xattr(&file)?;
+        if self.clear {
+            let cleared = http::DownloadQueue::clear()?;
+            eprintln!("* {cleared} downloads cleared");
+        } else {
+            http::DownloadQueue::load()?.list();
         }
 
-        pb.finish_and_clear();
-
         Ok(())
     }
 }
 
-#[derive(Args)]
-struct OptCacheVerify {
-    /// files or directories
-    paths: Vec<PathBuf>,
-}
+#[derive(Subcommand)]
+enum OptTags {
+    /// add games to tag
+    Add(OptTagsAdd),
 
-impl OptCacheVerify {
-    fn execute(self) -> Result<(), Error> {
-        use crate::game::Part;
-        use indicatif::ParallelProgressIterator;
-        use rayon::prelude::*;
-        use std::collections::HashMap;
+    /// remove games from tag, or the whole tag if no games are given
+    Remove(OptTagsRemove),
 
-        let pb = ProgressBar::new_spinner().with_message("locating files");
-        let files = {
-            pb.wrap_iter(self.paths.into_iter().flat_map(unique_sub_files))
-                .collect::<Vec<PathBuf>>()
-        };
-        pb.finish_and_clear();
+    /// list tags, or the games with a tag
+    List(OptTagsList),
+}
 
-        let pb = ProgressBar::new(files.len() as u64)
-            .with_style(crate::game::verify_style())
-            .with_message("reading cache entries");
+impl OptTags {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptTags::Add(o) => o.execute(),
+            OptTags::Remove(o) => o.execute(),
+            OptTags::List(o) => o.execute(),
+        }
+    }
+}
 
-        let cache = files
-            .into_par_iter()
-            .progress_with(pb.clone())
-            .filter_map(|file| Part::get_xattr(&file).map(|part| (file, part)))
-            .collect::<HashMap<PathBuf, Part>>();
+#[derive(Args)]
+struct OptTagsAdd {
+    /// tag to add games to, such as "favorites"
+    tag: String,
 
-        pb.finish_and_clear();
+    /// short names of MAME machines or software list entries
+    #[clap(required = true)]
+    games: Vec<String>,
+}
 
-        let pb = ProgressBar::new(cache.len() as u64)
-            .with_style(crate::game::verify_style())
-            .with_message("verifying cache entries");
+impl OptTagsAdd {
+    fn execute(self) -> Result<(), Error> {
+        let mut tags = tags::Tags::load()?;
+        let added = tags.add(&self.tag, self.games);
+        tags.save()?;
+        eprintln!("* {added} games tagged \"{}\"", self.tag);
+        Ok(())
+    }
+}
 
-        cache
-            .par_iter()
-            .progress_with(pb.clone())
-            .for_each(|(file, part)| match part.is_valid(file) {
-                Ok(true) => { /* do nothing*/ }
-                Ok(false) => pb.println(format!("BAD : {}", file.display())),
-                Err(err) => pb.println(format!("ERROR : {} : {}", file.display(), err)),
-            });
+#[derive(Args)]
+struct OptTagsRemove {
+    /// tag to remove games from
+    tag: String,
 
-        pb.finish_and_clear();
+    /// short names of games to untag
+    games: Vec<String>,
+}
 
+impl OptTagsRemove {
+    fn execute(self) -> Result<(), Error> {
+        let mut tags = tags::Tags::load()?;
+        let removed = tags.remove(&self.tag, &self.games)?;
+        tags.save()?;
+        eprintln!("* {removed} games untagged \"{}\"", self.tag);
         Ok(())
     }
 }
 
 #[derive(Args)]
-struct OptCacheLinkDupes {
-    /// files or directories
-    paths: Vec<PathBuf>,
+struct OptTagsList {
+    /// tag to list games of
+    tag: Option<String>,
 }
 
-impl OptCacheLinkDupes {
+impl OptTagsList {
     fn execute(self) -> Result<(), Error> {
-        use crate::duplicates::{DuplicateFiles, Duplicates};
+        let tags = tags::Tags::load()?;
+        match self.tag {
+            Some(tag) => tags.list_games(&tag),
+            None => {
+                tags.list();
+                Ok(())
+            }
+        }
+    }
+}
 
-        let mut db = DuplicateFiles::default();
+#[derive(Subcommand)]
+enum OptWish {
+    /// add games to wishlist
+    Add(OptWishAdd),
 
-        let pb = ProgressBar::new_spinner()
-            .with_style(crate::game::find_files_style())
-            .with_message("linking duplicate files");
+    /// remove games from wishlist
+    Remove(OptWishRemove),
 
-        for file in pb.wrap_iter(self.paths.into_iter().flat_map(sub_files)) {
-            use std::fs;
+    /// list wished-for games
+    List,
+}
+
+impl OptWish {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptWish::Add(o) => o.execute(),
+            OptWish::Remove(o) => o.execute(),
+            OptWish::List => {
+                wish::Wishlist::load()?.list();
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct OptWishSource {
+    /// wish for MAME machines
+    #[clap(long = "mame")]
+    mame: bool,
+
+    /// wish for software from a software list
+    #[clap(long = "software")]
+    software_list: Option<String>,
+
+    /// wish for games from a No-Intro DAT
+    #[clap(long = "nointro")]
+    nointro: Option<String>,
+
+    /// wish for games from a Redump DAT
+    #[clap(long = "redump")]
+    redump: Option<String>,
+}
+
+impl OptWishSource {
+    // how wishes from this source are labeled
+    fn label(&self) -> String {
+        if self.mame {
+            "mame".to_owned()
+        } else if let Some(software_list) = &self.software_list {
+            format!("sl \"{software_list}\"")
+        } else if let Some(dat) = &self.nointro {
+            format!("nointro \"{dat}\"")
+        } else if let Some(dat) = &self.redump {
+            format!("redump \"{dat}\"")
+        } else {
+            unreachable!("one source is required")
+        }
+    }
+
+    // the parts of each game, in the order given
+    fn parts(&self, games: &[String]) -> Result<Vec<game::GameParts>, Error> {
+        fn dat_parts(
+            datfile: dat::DatFile,
+            games: &[String],
+        ) -> Result<Vec<game::GameParts>, Error> {
+            let mut parts = datfile.into_game_parts().collect::<BTreeMap<_, _>>();
+            games
+                .iter()
+                .map(|game| {
+                    parts
+                        .remove(game)
+                        .ok_or_else(|| Error::NoSuchSoftware(game.clone()))
+                })
+                .collect()
+        }
+
+        fn game_parts(db: &game::GameDb, games: &[String]) -> Result<Vec<game::GameParts>, Error> {
+            games
+                .iter()
+                .map(|game| db.valid_game(game).map(|game| game.parts.clone()))
+                .collect()
+        }
+
+        if self.mame {
+            game_parts(&read_game_db(MAME, DB_MAME)?, games)
+        } else if let Some(software_list) = &self.software_list {
+            game_parts(&read_named_db(MESS, DIR_SL, software_list)?, games)
+        } else if let Some(dat) = &self.nointro {
+            dat_parts(read_nointro_db(dat, None)?.0, games)
+        } else if let Some(dat) = &self.redump {
+            dat_parts(read_redump_db(dat)?, games)
+        } else {
+            unreachable!("one source is required")
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptWishAdd {
+    #[clap(flatten)]
+    source: OptWishSource,
+
+    /// short names of games to wish for
+    #[clap(required = true)]
+    games: Vec<String>,
+}
+
+impl OptWishAdd {
+    fn execute(self) -> Result<(), Error> {
+        let mut wishlist = wish::Wishlist::load()?;
+        let label = self.source.label();
+        for (game, parts) in self.games.iter().zip(self.source.parts(&self.games)?) {
+            wishlist.add(label.clone(), game.clone(), parts);
+        }
+        wishlist.save()
+    }
+}
+
+#[derive(Args)]
+struct OptWishRemove {
+    #[clap(flatten)]
+    source: OptWishSource,
+
+    /// short names of games no longer wished for
+    #[clap(required = true)]
+    games: Vec<String>,
+}
+
+impl OptWishRemove {
+    fn execute(self) -> Result<(), Error> {
+        let mut wishlist = wish::Wishlist::load()?;
+        let label = self.source.label();
+        for game in &self.games {
+            wishlist.remove(&label, game)?;
+        }
+        wishlist.save()
+    }
+}
+
+// limits games to those with a tag
+#[derive(Args)]
+struct OptTag {
+    /// only include games with tag, such as "favorites"
+    #[clap(long = "tag")]
+    tag: Option<String>,
+}
+
+impl OptTag {
+    fn tagged(&self) -> Result<Option<BTreeSet<String>>, Error> {
+        self.tag
+            .as_deref()
+            .map(|tag| tags::Tags::load()?.games(tag).cloned())
+            .transpose()
+    }
+
+    fn filter<'g>(&self, games: Vec<&'g game::Game>) -> Result<Vec<&'g game::Game>, Error> {
+        Ok(match self.tagged()? {
+            Some(tagged) => games
+                .into_iter()
+                .filter(|game| tagged.contains(&game.name))
+                .collect(),
+            None => games,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum OptConfig {
+    /// edit directory configuration in $EDITOR
+    Edit,
+
+    /// store directories relative to a collection root
+    Portable(OptConfigPortable),
+}
+
+impl OptConfig {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptConfig::Edit => dirs::edit_config(),
+            OptConfig::Portable(o) => o.execute(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptConfigPortable {
+    /// collection root, defaults to the data directory
+    #[clap(conflicts_with = "disable")]
+    root: Option<PathBuf>,
+
+    /// go back to storing absolute directories
+    #[clap(long = "disable")]
+    disable: bool,
+}
+
+impl OptConfigPortable {
+    fn execute(self) -> Result<(), Error> {
+        if self.disable {
+            return dirs::set_portable_root(None);
+        }
+
+        match self.root {
+            Some(root) => dirs::set_portable_root(Some(&root)),
+            None => dirs::set_portable_root(Some(&dirs::data_dir())),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum OptCache {
+    /// add cache entries to files
+    Add(OptCacheAdd),
+
+    /// remove cache entries from files
+    #[clap(name = "delete")]
+    Delete(OptCacheDelete),
+
+    /// verify existing cache entries
+    #[clap(name = "verify")]
+    Verify(OptCacheVerify),
+
+    /// find duplicate files and link them together
+    #[clap(name = "link-dupes")]
+    LinkDupes(OptCacheLinkDupes),
+}
+
+impl OptCache {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptCache::Add(o) => o.execute(),
+            OptCache::Delete(o) => o.execute(),
+            OptCache::Verify(o) => o.execute(),
+            OptCache::LinkDupes(o) => o.execute(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptCacheAdd {
+    /// files or directories
+    paths: Vec<PathBuf>,
+}
+
+impl OptCacheAdd {
+    fn execute(self) -> Result<(), Error> {
+        use crate::game::Part;
+        use indicatif::ParallelProgressIterator;
+        use rayon::prelude::*;
+
+        let run = throughput::start();
+
+        let pb = progress::spinner().with_message("locating files");
+        let files = {
+            pb.wrap_iter(
+                self.paths.into_iter().flat_map(unique_sub_files).filter(
+                    |pb| match Part::has_xattr(pb) {
+                        Ok(has_xattr) => {
+                            throughput::cached(has_xattr);
+                            !has_xattr
+                        }
+                        Err(_) => false,
+                    },
+                ),
+            )
+            .collect::<Vec<PathBuf>>()
+        };
+        pb.finish_and_clear();
+
+        let pb = progress::bar(files.len() as u64)
+            .with_style(crate::game::verify_style())
+            .with_message("adding cache entries");
+
+        files
+            .into_par_iter()
+            .progress_with(pb.clone())
+            .for_each(|file: PathBuf| match Part::from_path(&file) {
+                Ok(part) => part.set_xattr(&file),
+                Err(err) => pb.log(format!("{} : {}", file.display(), err)),
+            });
+
+        pb.finish_and_clear();
+
+        run.display();
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptCacheDelete {
+    /// files or directories
+    paths: Vec<PathBuf>,
+}
+
+impl OptCacheDelete {
+    fn execute(self) -> Result<(), Error> {
+        use crate::game::Part;
+
+        let pb = progress::spinner().with_message("removing cache entries");
+
+        for file in pb.wrap_iter(
+            self.paths
+                .into_iter()
+                .flat_map(unique_sub_files)
+                .filter(|pb| matches!(Part::has_xattr(pb), Ok(true))),
+        ) {
+            Part::remove_xattr(&file)?;
+        }
+
+        pb.finish_and_clear();
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptCacheVerify {
+    /// files or directories
+    paths: Vec<PathBuf>,
+}
+
+impl OptCacheVerify {
+    fn execute(self) -> Result<(), Error> {
+        use crate::game::Part;
+        use indicatif::ParallelProgressIterator;
+        use rayon::prelude::*;
+        use std::collections::HashMap;
+
+        let run = throughput::start();
+
+        let pb = progress::spinner().with_message("locating files");
+        let files = {
+            pb.wrap_iter(self.paths.into_iter().flat_map(unique_sub_files))
+                .collect::<Vec<PathBuf>>()
+        };
+        pb.finish_and_clear();
+
+        let pb = progress::bar(files.len() as u64)
+            .with_style(crate::game::verify_style())
+            .with_message("reading cache entries");
+
+        let cache = files
+            .into_par_iter()
+            .progress_with(pb.clone())
+            .filter_map(|file| Part::get_xattr(&file).map(|part| (file, part)))
+            .collect::<HashMap<PathBuf, Part>>();
+
+        pb.finish_and_clear();
+
+        let pb = progress::bar(cache.len() as u64)
+            .with_style(crate::game::verify_style())
+            .with_message("verifying cache entries");
+
+        cache
+            .par_iter()
+            .progress_with(pb.clone())
+            .for_each(|(file, part)| match part.is_valid(file) {
+                Ok(true) => { /* do nothing*/ }
+                Ok(false) => pb.log(format!("BAD : {}", file.display())),
+                Err(err) => pb.log(format!("ERROR : {} : {}", file.display(), err)),
+            });
+
+        pb.finish_and_clear();
+
+        run.display();
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptCacheLinkDupes {
+    /// files or directories
+    paths: Vec<PathBuf>,
+}
+
+impl OptCacheLinkDupes {
+    fn execute(self) -> Result<(), Error> {
+        use crate::duplicates::{DuplicateFiles, Duplicates};
+
+        let mut db = DuplicateFiles::default();
+
+        let pb = progress::spinner()
+            .with_style(crate::game::find_files_style())
+            .with_message("linking duplicate files");
+
+        for file in pb.wrap_iter(self.paths.into_iter().flat_map(sub_files)) {
+            use std::fs;
+
+            match db.get_or_add(file) {
+                Ok(None) => {}
+                Ok(Some((duplicate, original))) => {
+                    match fs::remove_file(&duplicate)
+                        .and_then(|()| fs::hard_link(original, &duplicate))
+                    {
+                        Ok(()) => pb.log(format!(
+                            "{} {} {}",
+                            original.display(),
+                            glyphs::moved(),
+                            duplicate.display()
+                        )),
+                        Err(err) => pb.log(format!("{}: {}", duplicate.display(), err)),
+                    }
+                }
+                Err((source, err)) => pb.log(format!("{}: {}", source.display(), err)),
+            }
+        }
+
+        pb.finish_and_clear();
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptBackup {
+    /// backup file, gzipped if it ends with .gz or .tgz
+    file: PathBuf,
+}
+
+impl OptBackup {
+    fn execute(self) -> Result<(), Error> {
+        let backed_up = backup::backup(&self.file)?;
+        eprintln!(
+            "* {} entries backed up to {}",
+            backed_up,
+            self.file.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptUpdateAll {}
+
+impl OptUpdateAll {
+    fn execute(self) -> Result<(), Error> {
+        let sources = update::UpdateSources::load()?;
+        let mut summary: Vec<(String, game::Changes)> = Vec::new();
+
+        // one database failing to update shouldn't keep the rest from updating
+        match sources.mame {
+            Some(source) => {
+                eprintln!("* updating {MAME} from {source}");
+                let old = read_game_db::<game::GameDb>(MAME, DB_MAME).ok();
+                match (OptMameInit {
+                    xml: Some(Resource::from(source)),
+                })
+                .execute()
+                .and_then(|()| read_game_db::<game::GameDb>(MAME, DB_MAME))
+                {
+                    Ok(new) => {
+                        if let Some(old) = old {
+                            let changes = old.changes(&new);
+                            if !changes.is_empty() {
+                                changes.report(MAME);
+                            }
+                            summary.push((MAME.to_owned(), changes));
+                        }
+                    }
+                    Err(err) => eprintln!("* {err}"),
+                }
+            }
+            None => eprintln!("* no {MAME} source recorded, skipping"),
+        }
+
+        if !sources.sl.is_empty() {
+            eprintln!("* updating {} software lists", sources.sl.len());
+            match init_sl(sources.sl.into_iter().collect()) {
+                Ok(changed) => summary.extend(changed),
+                Err(err) => eprintln!("* {err}"),
+            }
+        }
+
+        for (utility, db_dir) in [
+            (EXTRA, DIR_EXTRA),
+            (NOINTRO, DIR_NOINTRO),
+            (REDUMP, DIR_REDUMP),
+        ] {
+            let old: BTreeMap<String, dat::DatFile> = read_collected_dbs(db_dir);
+
+            // DATs imported from local files may be long gone,
+            // so only those with a URL are fetched again
+            let urls = old
+                .values()
+                .filter_map(|datfile| datfile.imported_from())
+                .filter(|source| url::Url::parse(source).is_ok())
+                .collect::<BTreeSet<_>>();
+
+            if urls.is_empty() {
+                continue;
+            }
+
+            eprintln!("* updating {} {utility} DATs", urls.len());
+            let dats = urls
+                .into_iter()
+                .map(|source| Resource::from(source.to_owned()))
+                .collect();
+            match if utility == EXTRA {
+                OptExtraInit {
+                    dats,
+                    replace: false,
+                }
+                .execute()
+            } else if utility == NOINTRO {
+                OptNointroInit {
+                    dats,
+                    replace: false,
+                    edit: false,
+                }
+                .execute()
+            } else {
+                OptRedumpInit {
+                    xml: dats,
+                    edit: false,
+                }
+                .execute()
+            } {
+                Ok(()) => summary.extend(dat_changes(utility, &old, read_collected_dbs(db_dir))),
+                Err(err) => eprintln!("* {err}"),
+            }
+        }
+
+        summary.retain(|(_, changes)| !changes.is_empty());
+
+        if summary.is_empty() {
+            eprintln!("* everything up to date");
+        } else {
+            let mut table = table::new();
+            table.set_header(vec!["Database", "Added", "Removed", "Renamed", "Changed"]);
+            for (name, changes) in summary {
+                table.add_row(vec![
+                    name,
+                    changes.added.len().to_string(),
+                    changes.removed.len().to_string(),
+                    changes.renamed.len().to_string(),
+                    changes.changed.len().to_string(),
+                ]);
+            }
+            println!("{table}");
+        }
+
+        Ok(())
+    }
+}
 
-            match db.get_or_add(file) {
-                Ok(None) => {}
-                Ok(Some((duplicate, original))) => {
-                    match fs::remove_file(&duplicate)
-                        .and_then(|()| fs::hard_link(original, &duplicate))
-                    {
-                        Ok(()) => pb.println(format!(
-                            "{} \u{2192} {}",
-                            original.display(),
-                            duplicate.display()
-                        )),
-                        Err(err) => pb.println(format!("{}: {}", duplicate.display(), err)),
+// how each DAT already imported changed, reporting as it goes
+fn dat_changes(
+    utility: &str,
+    old: &BTreeMap<String, dat::DatFile>,
+    new: BTreeMap<String, dat::DatFile>,
+) -> Vec<(String, game::Changes)> {
+    new.into_iter()
+        .filter_map(|(name, new)| {
+            let changes = old.get(&name)?.changes(&new);
+            let name = format!("{utility} {name}");
+            if !changes.is_empty() {
+                changes.report(&name);
+            }
+            Some((name, changes))
+        })
+        .collect()
+}
+
+#[derive(Args)]
+struct OptRestore {
+    /// backup file, as written by "emuman backup"
+    file: PathBuf,
+}
+
+impl OptRestore {
+    fn execute(self) -> Result<(), Error> {
+        let restored = backup::restore(&self.file)?;
+        eprintln!(
+            "* {} entries restored from {}",
+            restored,
+            self.file.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptDoctor {
+    /// don't check whether DAT URLs are reachable
+    #[clap(long = "offline")]
+    offline: bool,
+}
+
+impl OptDoctor {
+    fn execute(self) -> Result<(), Error> {
+        let mut report = doctor::Report::default();
+
+        doctor::check_databases(&mut report);
+        doctor::check_versions(&mut report);
+        doctor::check_directories(&mut report);
+        if !self.offline {
+            doctor::check_urls(&mut report);
+        }
+
+        report.display();
+
+        match report.problems() {
+            0 => eprintln!("* no problems found"),
+            1 => eprintln!("* 1 problem found"),
+            problems => eprintln!("* {problems} problems found"),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptAdopt {
+    /// accept each directory's best match without prompting,
+    /// if most of its sampled files belong to it
+    #[clap(short = 'y', long = "yes")]
+    yes: bool,
+
+    /// root directory whose subdirectories to assign
+    root: PathBuf,
+
+    #[clap(flatten)]
+    limits: OptLimits,
+}
+
+impl OptAdopt {
+    fn execute(self) -> Result<(), Error> {
+        use adopt::Proposal;
+
+        self.limits.apply();
+
+        for proposal in adopt::propose(&self.root)? {
+            match proposal {
+                Proposal::Unmatched { dir } => {
+                    eprintln!("* no DAT matches \"{}\"", dir.display())
+                }
+                Proposal::Assigned { dir, candidate } => {
+                    eprintln!("* \"{}\" already assigned to {candidate}", dir.display())
+                }
+                Proposal::Candidates { dir, candidates } if self.yes => {
+                    match candidates.into_iter().next().filter(|c| c.is_likely()) {
+                        Some(candidate) => {
+                            candidate.adopt(&dir)?;
+                            eprintln!("* \"{}\" assigned to {candidate}", dir.display());
+                        }
+                        None => eprintln!("* no likely DAT for \"{}\"", dir.display()),
+                    }
+                }
+                Proposal::Candidates { dir, candidates } => {
+                    let mut options = candidates.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                    options.push("skip".to_owned());
+
+                    let choice = inquire::Select::new(&dir.display().to_string(), options)
+                        .with_page_size(terminal_height())
+                        .raw_prompt()?;
+
+                    if let Some(candidate) = candidates.get(choice.index) {
+                        candidate.adopt(&dir)?;
+                        eprintln!("* \"{}\" assigned to {candidate}", dir.display());
                     }
                 }
-                Err((source, err)) => pb.println(format!("{}: {}", source.display(), err)),
             }
         }
 
-        pb.finish_and_clear();
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum OptExport {
+    /// lay out verified games for a MiSTer SD card
+    Mister(OptExportMister),
+
+    /// copy a DAT's verified games to a flashcart's SD card
+    Flashcart(OptExportFlashcart),
+
+    /// add verified games to a LaunchBox platform XML
+    Launchbox(OptExportLaunchbox),
+
+    /// copy verified games to a Batocera system directory and its gamelist
+    Batocera(OptExportBatocera),
+}
+
+impl OptExport {
+    fn execute(self) -> Result<(), Error> {
+        match self {
+            OptExport::Mister(o) => o.execute(),
+            OptExport::Flashcart(o) => o.execute(),
+            OptExport::Launchbox(o) => o.execute(),
+            OptExport::Batocera(o) => o.execute(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct OptExportMister {
+    /// remove files exported to core directories earlier
+    /// which are no longer in the collection
+    #[clap(long = "delete")]
+    delete: bool,
+
+    /// root of the MiSTer SD card
+    root: PathBuf,
+}
+
+impl OptExportMister {
+    fn execute(self) -> Result<(), Error> {
+        use std::collections::BTreeSet;
+
+        if !self.root.is_dir() {
+            return Err(Error::NoSuchDirectory(self.root));
+        }
+
+        let games = self.root.join("games");
+        let mut exports = Vec::new();
+        let mut cores = BTreeSet::new();
+
+        let read_nointro: fn(&str) -> Result<dat::DatFile, Error> =
+            |name| read_nointro_db(name, None).map(|(datfile, _)| datfile);
+
+        for (read_named_db, dirs) in [
+            (read_nointro, dirs::nointro_dirs()),
+            (read_redump_db, dirs::redump_dirs()),
+        ] {
+            for (name, dir) in dirs {
+                let Some(core) = export::mister_core(&name) else {
+                    continue;
+                };
+                let Ok(datfile) = read_named_db(&name) else {
+                    continue;
+                };
+
+                let pbar = datfile.progress_bar();
+                let core_dir = games.join(core);
+                exports.extend(datfile.verified_files(&dir, &pbar).into_iter().map(
+                    |(relative, source)| export::Export {
+                        source,
+                        target: core_dir.join(relative),
+                    },
+                ));
+                pbar.finish_and_clear();
+
+                cores.insert(core_dir);
+            }
+        }
+
+        let synced = export::sync(exports, &Vec::from_iter(cores), self.delete)?;
+        eprintln!("* {synced}");
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptExportFlashcart {
+    /// longest file name the cart supports, including extension
+    #[clap(long = "max-length", default_value = "255")]
+    max_length: usize,
+
+    /// most files the cart supports in one directory
+    #[clap(long = "max-files", default_value = "1000")]
+    max_files: usize,
+
+    /// remove files exported to the target earlier
+    /// which are no longer in the DAT's collection
+    #[clap(long = "delete")]
+    delete: bool,
+
+    /// No-Intro DAT name
+    dat: String,
+
+    /// target directory on the SD card
+    target: PathBuf,
+}
+
+impl OptExportFlashcart {
+    fn execute(self) -> Result<(), Error> {
+        let (datfile, _) = match read_nointro_db(&self.dat, None) {
+            Err(Error::MissingCache(_)) => return Err(Error::NoSuchDatFile(self.dat)),
+            result => result?,
+        };
+        let dir = dirs::nointro_dirs()
+            .find_map(|(name, dir)| (name == self.dat).then_some(dir))
+            .ok_or_else(|| Error::NoDirectorySet(self.dat.clone()))?;
+
+        let pbar = datfile.progress_bar();
+        let files = datfile.verified_files(&dir, &pbar);
+        pbar.finish_and_clear();
+
+        let exports = export::Flashcart {
+            max_length: self.max_length,
+            max_files: self.max_files,
+        }
+        .layout(files, &self.target);
+
+        let synced = export::sync(exports, &[self.target], self.delete)?;
+        eprintln!("* {synced}");
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct OptFrontendSource {
+    /// export MAME machines
+    #[clap(long = "mame")]
+    mame: bool,
+
+    /// export a software list
+    #[clap(long = "software")]
+    software_list: Option<String>,
+
+    /// export a No-Intro DAT
+    #[clap(long = "nointro")]
+    nointro: Option<String>,
+
+    /// export a Redump DAT
+    #[clap(long = "redump")]
+    redump: Option<String>,
+}
+
+impl OptFrontendSource {
+    // the source's name, its collection's root
+    // and the entries of its verified games
+    fn entries(self) -> Result<(String, PathBuf, Vec<export::Entry>), Error> {
+        if self.mame {
+            let db: game::GameDb = read_game_db(MAME, DB_MAME)?;
+            let root = dirs::mame_roms(None).as_ref().to_owned();
+            let entries = Self::game_entries(&db, &root);
+            Ok(("Arcade".to_owned(), root, entries))
+        } else if let Some(software_list) = self.software_list {
+            let db: game::GameDb = read_named_db(MESS, DIR_SL, &software_list)?;
+            let root = dirs::mess_roms(None, &software_list).as_ref().to_owned();
+            let entries = Self::game_entries(&db, &root);
+            Ok((db.description().to_owned(), root, entries))
+        } else if let Some(dat) = self.nointro {
+            Self::dat_entries(
+                |name| read_nointro_db(name, None).map(|(datfile, _)| datfile),
+                dirs::nointro_dirs(),
+                dat,
+            )
+        } else if let Some(dat) = self.redump {
+            Self::dat_entries(read_redump_db, dirs::redump_dirs(), dat)
+        } else {
+            unreachable!("one source is required")
+        }
+    }
+
+    fn game_entries(db: &game::GameDb, root: &Path) -> Vec<export::Entry> {
+        use indicatif::ParallelProgressIterator;
+        use rayon::prelude::*;
+
+        let games = db.games_with_devices(false);
+        let pbar = progress::bar(games.len() as u64)
+            .with_style(game::verify_style())
+            .with_message("verifying games");
+
+        let mut entries = games
+            .into_par_iter()
+            .progress_with(pbar.clone())
+            .filter(|game| {
+                root.join(&game.name).is_dir() && db.verify(root, game, false).is_empty()
+            })
+            .map(|game| {
+                let files = symlinks::walk(root.join(&game.name))
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .filter_map(|e| {
+                        let relative = e.path().strip_prefix(root).ok()?.to_owned();
+                        Some((relative, e.into_path()))
+                    })
+                    .collect();
+                export::Entry::from_game(game, files)
+            })
+            .collect::<Vec<_>>();
+
+        pbar.finish_and_clear();
+
+        entries.sort_by(|a, b| a.title.cmp(&b.title));
+        entries
+    }
+
+    fn dat_entries(
+        read_named_db: impl Fn(&str) -> Result<dat::DatFile, Error>,
+        mut dirs: impl Iterator<Item = (String, PathBuf)>,
+        dat: String,
+    ) -> Result<(String, PathBuf, Vec<export::Entry>), Error> {
+        let datfile = match read_named_db(&dat) {
+            Err(Error::MissingCache(_)) => return Err(Error::NoSuchDatFile(dat)),
+            result => result?,
+        };
+        let root = dirs
+            .find_map(|(name, dir)| (name == dat).then_some(dir))
+            .ok_or_else(|| Error::NoDirectorySet(dat.clone()))?;
+
+        let pbar = datfile.progress_bar();
+        let entries = datfile
+            .verified_games(&root, &pbar)
+            .into_iter()
+            .map(|(name, files)| export::Entry::from_dat(name, files))
+            .collect();
+        pbar.finish_and_clear();
+
+        Ok((dat, root, entries))
+    }
+}
+
+#[derive(Args)]
+struct OptExportLaunchbox {
+    #[clap(flatten)]
+    source: OptFrontendSource,
+
+    /// platform name in LaunchBox, if not the source's name
+    #[clap(long = "platform")]
+    platform: Option<String>,
+
+    /// LaunchBox installation directory
+    launchbox: PathBuf,
+}
+
+impl OptExportLaunchbox {
+    fn execute(self) -> Result<(), Error> {
+        let platforms = self.launchbox.join("Data").join("Platforms");
+        if !platforms.is_dir() {
+            return Err(Error::NoSuchDirectory(platforms));
+        }
+
+        let (name, root, entries) = self.source.entries()?;
+        let platform = self.platform.unwrap_or(name);
+        let xml = platforms.join(format!("{}.xml", platform.replace(['/', '\\'], "-")));
+
+        export::launchbox(&xml, &platform, &root, &entries)?;
+        eprintln!("* {} games written to \"{}\"", entries.len(), xml.display());
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct OptExportBatocera {
+    #[clap(flatten)]
+    source: OptFrontendSource,
+
+    /// remove files exported to the system directory earlier
+    /// which are no longer in the collection
+    #[clap(long = "delete")]
+    delete: bool,
+
+    /// Batocera system name, such as "nes" or "mame"
+    system: String,
+
+    /// Batocera's roms directory
+    roms: PathBuf,
+}
+
+impl OptExportBatocera {
+    fn execute(self) -> Result<(), Error> {
+        if !self.roms.is_dir() {
+            return Err(Error::NoSuchDirectory(self.roms));
+        }
+
+        let (_, _, entries) = self.source.entries()?;
+        let target = self.roms.join(&self.system);
+
+        let exports = entries
+            .iter()
+            .flat_map(|entry| &entry.files)
+            .map(|(relative, source)| export::Export {
+                source: source.clone(),
+                target: target.join(relative),
+            })
+            .collect();
+
+        let synced = export::sync(exports, std::slice::from_ref(&target), self.delete)?;
+        std::fs::create_dir_all(&target)?;
+
+        export::gamelist(&target.join("gamelist.xml"), &entries)?;
+        eprintln!("* {synced}");
 
         Ok(())
     }
@@ -2527,6 +5584,88 @@ impl OptCacheLinkDupes {
 
 /// Emulation Database Manager
 #[derive(Parser)]
+struct Cli {
+    /// print occasional status lines instead of progress bars
+    #[clap(long = "no-progress", global = true)]
+    no_progress: bool,
+
+    /// read one file at a time per device, for spinning disks
+    #[clap(long = "sequential", global = true)]
+    sequential: bool,
+
+    /// run at low CPU and disk priority, keeping the machine responsive
+    #[clap(long = "nice", global = true)]
+    nice: bool,
+
+    /// size of reads when hashing files, such as 64K or 4M
+    #[clap(long = "buffer-size", global = true, value_parser = parse_byte_size)]
+    buffer_size: Option<usize>,
+
+    /// follow symbolic links when scanning directories
+    #[clap(
+        long = "follow-symlinks",
+        global = true,
+        overrides_with = "no_follow_symlinks"
+    )]
+    follow_symlinks: bool,
+
+    /// don't follow symbolic links when scanning directories (default)
+    #[clap(
+        long = "no-follow-symlinks",
+        global = true,
+        overrides_with = "follow_symlinks"
+    )]
+    no_follow_symlinks: bool,
+
+    /// style of table borders, "plain", "ascii" or "utf8", defaulting to the charset's
+    #[clap(long = "table-style", global = true)]
+    table_style: Option<table::TableStyle>,
+
+    /// characters for arrows and borders, "auto", "ascii" or "utf8"
+    #[clap(long = "charset", global = true, default_value = "auto")]
+    charset: glyphs::Charset,
+
+    /// when to color table output, "auto", "always" or "never"
+    #[clap(long = "color", global = true, default_value = "auto")]
+    color: table::ColorChoice,
+
+    #[clap(subcommand)]
+    opt: Opt,
+}
+
+impl Cli {
+    fn execute(self, command: String) -> Result<(), Error> {
+        use std::io::IsTerminal;
+
+        // before any worker threads start, so they inherit it
+        if self.nice {
+            priority::lower();
+        }
+
+        progress::set_plain(self.no_progress || !std::io::stderr().is_terminal());
+        sequential::set_forced(self.sequential);
+        game::set_buffer_size(self.buffer_size.unwrap_or(game::DEFAULT_BUFFER_SIZE));
+        symlinks::set_follow(self.follow_symlinks && !self.no_follow_symlinks);
+        glyphs::set_charset(self.charset);
+        table::set_style(self.table_style.unwrap_or(if glyphs::is_ascii() {
+            table::TableStyle::Ascii
+        } else {
+            table::TableStyle::Utf8
+        }));
+        table::set_color(self.color);
+
+        let result = match self.opt.execute() {
+            Ok(()) if interrupt::requested() => Err(Error::Interrupted),
+            result => result,
+        };
+
+        notify::complete(command, &result);
+
+        result
+    }
+}
+
+#[derive(Subcommand)]
 enum Opt {
     /// arcade software management
     #[clap(subcommand)]
@@ -2555,9 +5694,50 @@ enum Opt {
     /// identify ROM or CHD by hash
     Identify(OptIdentify),
 
+    /// generate checksum file for directory
+    Checksum(OptChecksum),
+
+    /// print hashes of files, without needing any database
+    Hash(OptHash),
+
     /// file cache management
     #[clap(subcommand)]
     Cache(OptCache),
+
+    /// configuration management
+    #[clap(subcommand)]
+    Config(OptConfig),
+
+    /// ROM source management
+    #[clap(subcommand)]
+    Source(OptSource),
+
+    /// check the health of the installation
+    Doctor(OptDoctor),
+
+    /// assign existing directories to DATs by their contents
+    Adopt(OptAdopt),
+
+    /// copy verified games to devices and frontends
+    #[clap(subcommand)]
+    Export(OptExport),
+
+    /// tag games for working with subsets, like favorites
+    #[clap(subcommand)]
+    Tag(OptTags),
+
+    /// track wanted games, noting when their parts turn up
+    #[clap(subcommand)]
+    Wish(OptWish),
+
+    /// save databases and configuration to a tar file
+    Backup(OptBackup),
+
+    /// replace databases and configuration from a tar file
+    Restore(OptRestore),
+
+    /// refresh every database from where it was last initialized
+    UpdateAll(OptUpdateAll),
 }
 
 impl Opt {
@@ -2572,15 +5752,78 @@ impl Opt {
             Opt::Nointro(o) => o.execute(),
             Opt::Dat(o) => o.execute(),
             Opt::Identify(o) => o.execute(),
+            Opt::Checksum(o) => o.execute(),
+            Opt::Hash(o) => o.execute(),
             Opt::Cache(o) => o.execute(),
+            Opt::Config(o) => o.execute(),
+            Opt::Source(o) => o.execute(),
+            Opt::Doctor(o) => o.execute(),
+            Opt::Adopt(o) => o.execute(),
+            Opt::Export(o) => o.execute(),
+            Opt::Tag(o) => o.execute(),
+            Opt::Wish(o) => o.execute(),
+            Opt::Backup(o) => o.execute(),
+            Opt::Restore(o) => o.execute(),
+            Opt::UpdateAll(o) => o.execute(),
         }
     }
 }
 
 fn main() {
-    if let Err(err) = Opt::parse().execute() {
-        eprintln!("* {}", err);
+    // diagnostics are off unless requested, such as with EMUMAN_LOG=debug
+    if let Ok(filter) = tracing_subscriber::EnvFilter::try_from_env("EMUMAN_LOG") {
+        use std::io::IsTerminal;
+
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    interrupt::install();
+
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    // the subcommands run, like "nointro verify-all"
+    let mut command = Vec::new();
+    let mut sub = matches.subcommand();
+    while let Some((name, matches)) = sub {
+        command.push(name);
+        sub = matches.subcommand();
     }
+
+    match cli.execute(command.join(" ")) {
+        // anything still unfinished is resumed by the next run,
+        // as is everything from a run that didn't complete
+        Ok(()) if !interrupt::requested() => {
+            if let Err(err) = http::DownloadQueue::finish() {
+                eprintln!("* {}", err);
+            }
+        }
+        Ok(()) => {}
+        Err(err) => eprintln!("* {}", err),
+    }
+}
+
+// parses a number of bytes with an optional K, M or G suffix
+fn parse_byte_size(s: &str) -> Result<usize, String> {
+    let (digits, multiplier) = match s.trim().to_ascii_uppercase() {
+        s if s.ends_with('K') => (s.trim_end_matches('K').to_owned(), 1 << 10),
+        s if s.ends_with('M') => (s.trim_end_matches('M').to_owned(), 1 << 20),
+        s if s.ends_with('G') => (s.trim_end_matches('G').to_owned(), 1 << 30),
+        s => (s, 1),
+    };
+
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|n| *n > 0)
+        .ok_or_else(|| format!("invalid size \"{s}\""))
 }
 
 fn is_zip<R>(mut reader: R) -> Result<bool, std::io::Error>
@@ -2589,10 +5832,35 @@ where
 {
     use std::io::SeekFrom;
 
+    // files too short to be Zip files must still be rewound
     let mut buf = [0; 4];
+    let read = reader.read_exact(&mut buf);
+    reader.seek(SeekFrom::Start(0))?;
+    read.map(|()| &buf == b"\x50\x4b\x03\x04")
+}
+
+fn is_7z<R>(mut reader: R) -> Result<bool, std::io::Error>
+where
+    R: Read + Seek,
+{
+    use std::io::SeekFrom;
+
+    let mut buf = [0; 6];
+    let read = reader.read_exact(&mut buf);
+    reader.seek(SeekFrom::Start(0))?;
+    read.map(|()| &buf == b"7z\xbc\xaf\x27\x1c")
+}
+
+fn is_gzip<R>(mut reader: R) -> Result<bool, std::io::Error>
+where
+    R: Read + Seek,
+{
+    use std::io::SeekFrom;
+
+    let mut buf = [0; 2];
     reader.read_exact(&mut buf)?;
     reader.seek(SeekFrom::Start(0))?;
-    Ok(&buf == b"\x50\x4b\x03\x04")
+    Ok(&buf == b"\x1f\x8b")
 }
 
 fn write_game_db<S>(db_file: &'static str, db: S) -> Result<(), Error>
@@ -2601,15 +5869,40 @@ where
 {
     use directories::ProjectDirs;
     use std::fs::create_dir_all;
-    use std::io::BufWriter;
 
     let dirs = ProjectDirs::from("", "", "EmuMan").expect("no valid home directory found");
     let dir = dirs.data_local_dir();
     create_dir_all(dir)?;
-    let path = dir.join(db_file);
-    let f = BufWriter::new(File::create(path)?);
-    ciborium::ser::into_writer(&db, f).map_err(Error::CborWrite)?;
-    Ok(())
+    dbfile::write(&dir.join(db_file), db)
+}
+
+// MAME's games without their parts, stamped with the digest
+// of the full database they were taken from
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct MameMetadata {
+    source: [u8; 20],
+    db: game::GameDb,
+}
+
+// written after the full database, so its digest can be stamped
+fn write_mame_metadata(db: game::GameDb) -> Result<(), Error> {
+    match dbfile::digest(&named_db_dir(DB_MAME)) {
+        Some(source) => write_game_db(DB_MAME_META, MameMetadata { source, db }),
+        None => Ok(()),
+    }
+}
+
+// MAME's games without their parts, which is much faster to load,
+// falling back to the full database if the metadata is missing
+// or was taken from some other version of it
+fn read_mame_metadata() -> Result<game::GameDb, Error> {
+    match (
+        read_game_db::<MameMetadata>(MAME, DB_MAME_META),
+        dbfile::digest(&named_db_dir(DB_MAME)),
+    ) {
+        (Ok(metadata), Some(source)) if metadata.source == source => Ok(metadata.db),
+        _ => read_game_db(MAME, DB_MAME),
+    }
 }
 
 fn read_game_db<D>(utility: &'static str, db_file: &'static str) -> Result<D, Error>
@@ -2617,14 +5910,9 @@ where
     D: DeserializeOwned,
 {
     use directories::ProjectDirs;
-    use std::io::BufReader;
 
     let dirs = ProjectDirs::from("", "", "EmuMan").expect("no valid home directory");
-    let f = BufReader::new(
-        File::open(dirs.data_local_dir().join(db_file))
-            .map_err(|_| Error::MissingCache(utility))?,
-    );
-    ciborium::de::from_reader(f).map_err(|_| Error::InvalidCache(utility))
+    dbfile::read(utility, &dirs.data_local_dir().join(db_file))
 }
 
 fn named_db_dir(db_dir: &'static str) -> PathBuf {
@@ -2642,14 +5930,74 @@ fn named_db_path(db_dir: &'static str, name: &str) -> PathBuf {
     named_db_dir(db_dir).join(BASE64_ENGINE.encode(name))
 }
 
-// extracts database name from existing path, if any
-fn path_db_name(path: &Path) -> Option<String> {
-    String::from_utf8(BASE64_ENGINE.decode(path.file_name()?.to_str()?).ok()?).ok()
+// extracts database name from existing path, if any
+fn path_db_name(path: &Path) -> Option<String> {
+    String::from_utf8(BASE64_ENGINE.decode(path.file_name()?.to_str()?).ok()?).ok()
+}
+
+// names of DATs with directories assigned, noted before an init
+// so their directories can follow them if they're renamed
+struct DirMigration {
+    utility: &'static str,
+    db_dir: &'static str,
+    assigned: Vec<String>,
+}
+
+impl DirMigration {
+    fn new(utility: &'static str, db_dir: &'static str, assigned: Option<Vec<String>>) -> Self {
+        Self {
+            utility,
+            db_dir,
+            assigned: assigned.unwrap_or_default(),
+        }
+    }
+
+    // carries each old DAT's directory forward to a near-identical
+    // new DAT under a different name, if that has no directory yet,
+    // reading each old DAT only if it's been left out of the import
+    fn apply(mut self, added: &[dat::DatFile], rename: impl Fn(&str, &str) -> Result<(), Error>) {
+        for name in self.assigned.clone() {
+            if added.iter().any(|d| d.name() == name) {
+                continue;
+            }
+
+            // once every new DAT has a directory, there's nothing left to carry forward to
+            if added
+                .iter()
+                .all(|d| self.assigned.iter().any(|name| name == d.name()))
+            {
+                break;
+            }
+
+            let Ok(old) = read_named_db::<dat::DatFile>(self.utility, self.db_dir, &name) else {
+                continue;
+            };
+
+            let Some(new) = added
+                .iter()
+                .filter(|d| !self.assigned.iter().any(|name| name == d.name()))
+                .find(|d| d.is_near_identical(&old))
+            else {
+                continue;
+            };
+
+            match rename(old.name(), new.name()) {
+                Ok(()) => {
+                    eprintln!(
+                        "* directory of \"{}\" carried forward to \"{}\"",
+                        old.name(),
+                        new.name()
+                    );
+                    self.assigned.push(new.name().to_owned());
+                }
+                Err(err) => eprintln!("* {}", err),
+            }
+        }
+    }
 }
 
 fn write_named_db<S: Serialize>(db_dir: &'static str, name: &str, cache: S) -> Result<(), Error> {
     use std::fs::create_dir_all;
-    use std::io::BufWriter;
 
     let path = named_db_path(db_dir, name);
 
@@ -2657,10 +6005,7 @@ fn write_named_db<S: Serialize>(db_dir: &'static str, name: &str, cache: S) -> R
         create_dir_all(parent)?;
     }
 
-    ciborium::ser::into_writer(&cache, BufWriter::new(File::create(&path)?))
-        .map_err(Error::CborWrite)?;
-
-    Ok(())
+    dbfile::write(&path, cache)
 }
 
 fn read_named_db<D: DeserializeOwned>(
@@ -2668,17 +6013,17 @@ fn read_named_db<D: DeserializeOwned>(
     db_dir: &'static str,
     name: &str,
 ) -> Result<D, Error> {
-    ciborium::de::from_reader(
-        File::open(named_db_path(db_dir, name))
-            .map(std::io::BufReader::new)
-            .map_err(|_| Error::MissingCache(utility))?,
-    )
-    .map_err(|_| Error::InvalidCache(utility))
+    dbfile::read(utility, &named_db_path(db_dir, name))
 }
 
-fn clear_named_dbs(db_dir: &'static str) -> Result<(), Error> {
+// removes every database whose name isn't kept
+fn retain_named_dbs(db_dir: &'static str, keep: impl Fn(&str) -> bool) -> Result<(), Error> {
     let files: Vec<_> = std::fs::read_dir(named_db_dir(db_dir))
-        .map(|dir| dir.filter_map(|e| e.map(|e| e.path()).ok()).collect())
+        .map(|dir| {
+            dir.filter_map(|e| e.map(|e| e.path()).ok())
+                .filter(|path| !path_db_name(path).is_some_and(|name| keep(&name)))
+                .collect()
+        })
         .unwrap_or_default();
 
     files
@@ -2700,28 +6045,31 @@ fn read_named_dbs<D>(db_dir: &'static str) -> Option<impl Iterator<Item = (Strin
 where
     D: DeserializeOwned,
 {
-    #[inline]
-    fn read_game_db<D: DeserializeOwned>(path: &Path) -> Option<(String, D)> {
-        Some((
-            path_db_name(path)?,
-            File::open(path)
-                .ok()
-                .map(std::io::BufReader::new)
-                .and_then(|f| ciborium::de::from_reader(f).ok())?,
-        ))
-    }
-
     match std::fs::read_dir(named_db_dir(db_dir)) {
         Ok(dir) => Some(dir.filter_map(|entry| {
             entry
                 .ok()
                 .map(|entry| entry.path())
-                .and_then(|path| read_game_db(&path))
+                .and_then(|path| read_named_db_file(&path))
         })),
         Err(_) => None,
     }
 }
 
+// damaged databases are reported and skipped
+// so that one bad file doesn't hide all the others
+fn read_named_db_file<D: DeserializeOwned>(path: &Path) -> Option<(String, D)> {
+    let name = path_db_name(path)?;
+    match dbfile::read("", path) {
+        Ok(db) => Some((name, db)),
+        Err(Error::InvalidCache(_, path, damage)) => {
+            eprintln!("* skipping \"{name}\" : \"{}\" {damage}", path.display());
+            None
+        }
+        Err(_) => None,
+    }
+}
+
 fn read_db_names(db_dir: &'static str) -> Option<impl Iterator<Item = String>> {
     match std::fs::read_dir(named_db_dir(db_dir)) {
         Ok(dir) => Some(dir.filter_map(|entry| {
@@ -2737,9 +6085,32 @@ fn read_db_names(db_dir: &'static str) -> Option<impl Iterator<Item = String>> {
 fn read_collected_dbs<C, D>(db_dir: &'static str) -> C
 where
     C: std::iter::FromIterator<(String, D)>,
-    D: DeserializeOwned,
+    D: DeserializeOwned + Send,
 {
-    read_named_dbs(db_dir).into_iter().flatten().collect()
+    use rayon::prelude::*;
+
+    // deserializing is the slow part, so read every database at once
+    // and collect them in the order of their encoded file names
+    // (rather than their names) regardless of which finishes first
+    let mut paths = match std::fs::read_dir(named_db_dir(db_dir)) {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>(),
+        Err(_) => return std::iter::empty().collect(),
+    };
+    paths.sort_unstable();
+
+    paths
+        .par_iter()
+        .filter_map(|path| read_named_db_file(path))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[inline]
+fn search_term(search: Option<String>, regex: bool) -> Result<Option<game::Search>, Error> {
+    Ok(search.map(|s| game::Search::new(s, regex)).transpose()?)
 }
 
 fn select_game_name(db: &game::GameDb) -> Result<&str, Error> {
@@ -2764,11 +6135,13 @@ fn select_game_name(db: &game::GameDb) -> Result<&str, Error> {
         .collect::<Vec<_>>();
     games.sort_unstable_by_key(|g| g.description);
 
-    inquire::Select::new("select game", games)
-        .with_page_size(terminal_height())
-        .prompt()
-        .map(|DbEntry { name, .. }| name)
-        .map_err(Error::Inquire)
+    selection::select(
+        &format!("game:{}", db.description()),
+        "select game",
+        games,
+        |g| g.name,
+    )
+    .map(|DbEntry { name, .. }| name)
 }
 
 fn select_game_names(db: &game::GameDb) -> Result<Vec<&str>, Error> {
@@ -2823,17 +6196,16 @@ fn select_software_list_and_name() -> Result<(game::GameDb, String), Error> {
     if software_lists.is_empty() {
         Err(Error::NoSoftwareLists)
     } else {
-        inquire::Select::new(
+        selection::select(
+            MESS,
             "select software list",
             software_lists
                 .into_iter()
                 .map(|(shortname, db)| DbEntry { shortname, db })
                 .collect(),
+            |entry| &entry.shortname,
         )
-        .with_page_size(terminal_height())
-        .prompt()
         .map(|DbEntry { db, shortname }| (db, shortname))
-        .map_err(Error::Inquire)
     }
 }
 
@@ -2854,17 +6226,15 @@ fn select_software_list_game(db: game::GameDb) -> Result<game::Game, Error> {
         }
     }
 
+    let key = format!("game:{}", db.description());
+
     let mut games = db
         .into_games()
         .map(|game| GameEntry { game })
         .collect::<Vec<_>>();
     games.sort_unstable_by(|x, y| x.game.description.cmp(&y.game.description));
 
-    inquire::Select::new("select game", games)
-        .with_page_size(terminal_height())
-        .prompt()
-        .map(|GameEntry { game }| game)
-        .map_err(Error::Inquire)
+    selection::select(&key, "select game", games, |g| &g.game.name).map(|GameEntry { game }| game)
 }
 
 fn select_datfile_game(dat: dat::DatFile) -> Result<game::GameParts, Error> {
@@ -2880,6 +6250,8 @@ fn select_datfile_game(dat: dat::DatFile) -> Result<game::GameParts, Error> {
         }
     }
 
+    let key = format!("game:{}", dat.name());
+
     let mut games = dat
         .into_game_parts()
         .map(|(name, game)| GameEntry { name, game })
@@ -2891,11 +6263,8 @@ fn select_datfile_game(dat: dat::DatFile) -> Result<game::GameParts, Error> {
         _ => {
             games.sort_unstable_by(|x, y| x.name.cmp(&y.name));
 
-            inquire::Select::new("select game", games)
-                .with_page_size(terminal_height())
-                .prompt()
+            selection::select(&key, "select game", games, |g| &g.name)
                 .map(|GameEntry { game, .. }| game)
-                .map_err(Error::Inquire)
         }
     }
 }
@@ -2944,6 +6313,7 @@ fn process_games<'g, I, P, E>(
     message: &'static str,
     root: P,
     games: I,
+    keep_extra: bool,
     handle_game: impl Fn(&'g game::Game, &Path, &ProgressBar) -> Result<Vec<game::VerifyFailure<'g>>, E>
         + Sync,
 ) -> Result<(), E>
@@ -2957,47 +6327,206 @@ where
 
     let total = games.len();
 
-    let pbar = ProgressBar::new(total.try_into().unwrap())
+    let pbar = progress::bar(total.try_into().unwrap())
         .with_style(game::verify_style())
         .with_message(message);
 
     let results = games
         .par_bridge()
         .progress_with(pbar.clone())
+        .filter(|_| !interrupt::requested())
         .map(|game| handle_game(game, root.as_ref(), &pbar))
+        // a game still in flight when interrupted may have been
+        // only partly processed, so it counts as neither tested nor OK
+        .filter(|result| result.is_err() || !interrupt::requested())
         .collect::<Result<Vec<_>, _>>()?;
 
     pbar.finish_and_clear();
 
-    let successes = results.iter().filter(|v| v.is_empty()).count();
+    // only those games handled before any interruption
+    let total = results.len();
+
+    let successes = results
+        .iter()
+        .filter(|v| v.iter().all(|f| f.is_ignored(keep_extra)))
+        .count();
 
     let mut failures = results.into_iter().flatten().collect::<Vec<_>>();
     failures.sort_unstable_by(|x, y| x.path().cmp(y.path()));
     failures.dedup_by(|x, y| x.path() == y.path());
 
-    for failure in failures {
-        println!("{failure}");
+    let (ignored, failures): (Vec<_>, Vec<_>) =
+        failures.into_iter().partition(|f| f.is_ignored(keep_extra));
+
+    let summary = game::VerifyResultsSummary { successes, total };
+
+    if output::is_json() {
+        let mut json = output::JsonResults::default();
+        json.add(
+            &root.as_ref().display().to_string(),
+            &summary,
+            &failures,
+            &ignored,
+        );
+        json.display(None);
+    } else {
+        for line in failure_lines(&failures, &ignored) {
+            println!("{line}");
+        }
     }
 
     eprintln!("{total} tested, {successes} OK");
+    notify::record(&root.as_ref().display().to_string(), &summary);
 
     Ok(())
 }
 
-fn verify<'g, I, P>(db: &'g game::GameDb, root: P, games: I)
-where
+fn verify<'g, I, P>(
+    db: &'g game::GameDb,
+    root: P,
+    games: I,
+    keep_extra: bool,
+    devices: bool,
+    needed: &game::NeededParts,
+    sets: &game::SetLists,
+) where
     P: AsRef<Path> + Sync,
     I: ExactSizeIterator<Item = &'g game::Game>,
     I: Send,
 {
-    process_games("verifying games", root, games, |game, root, _| {
-        Ok::<_, game::Never>(db.verify(root, game))
-    })
+    process_games(
+        "verifying games",
+        root,
+        games,
+        keep_extra,
+        |game, root, _| {
+            let failures = db.verify(root, game, devices);
+            // partly verified games belong in neither set list
+            if !interrupt::requested() {
+                needed.record(&failures);
+                sets.record(&game.name, &failures);
+            }
+            Ok::<_, game::Never>(failures)
+        },
+    )
     .unwrap()
 }
 
+// reports how many of each game's missing parts
+// the sources can supply, without repairing anything
+fn preflight_games<'g, I>(db: &'g game::GameDb, root: &Path, games: I, sources: &game::RomSources)
+where
+    I: ExactSizeIterator<Item = &'g game::Game> + Send,
+{
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
+
+    let pbar = progress::bar(games.len().try_into().unwrap())
+        .with_style(game::verify_style())
+        .with_message("checking sources");
+
+    let preflight = game::Preflight::default();
+
+    games
+        .par_bridge()
+        .progress_with(pbar.clone())
+        .for_each(|game| {
+            preflight.record(game.name.clone(), &db.verify(root, game, false), sources)
+        });
+
+    pbar.finish_and_clear();
+
+    preflight.display();
+}
+
+// like preflight_games, but for a DAT's games
+fn preflight_dat(datfile: &dat::DatFile, root: &Path, sources: &game::RomSources, archived: bool) {
+    let preflight = game::Preflight::default();
+    record_preflight(datfile, root, sources, archived, &preflight, None);
+    preflight.display();
+}
+
+fn record_preflight(
+    datfile: &dat::DatFile,
+    root: &Path,
+    sources: &game::RomSources,
+    archived: bool,
+    preflight: &game::Preflight,
+    dat: Option<&str>,
+) {
+    let pbar = datfile.progress_bar();
+    let results = match archived {
+        true => datfile.verify_archived(root, true, |_| pbar.inc(1)),
+        false => datfile.verify(root, true, &pbar),
+    };
+    pbar.finish_and_clear();
+
+    for failure in &results.failures {
+        if let Some((_, part)) = failure.needed() {
+            game::add_crc_source(sources, part);
+        }
+    }
+
+    datfile.preflight(root, &results.failures, sources, preflight, dat);
+}
+
+// like preflight_dat, but for every DAT with a directory,
+// reading each only as it's checked
+fn preflight_all_dat(
+    dirs: impl Iterator<Item = (String, PathBuf)>,
+    read_dat: impl Fn(&str) -> Result<dat::DatFile, Error>,
+    sources: &game::RomSources,
+) {
+    let preflight = game::Preflight::default();
+
+    for (name, dir) in dirs {
+        if let Ok(datfile) = read_dat(&name) {
+            record_preflight(
+                &datfile,
+                &dir,
+                sources,
+                datfile.layout() == dat::Layout::Archives,
+                &preflight,
+                Some(&name),
+            );
+        }
+    }
+
+    preflight.display();
+}
+
+// like preflight_all_dat, but for every installed software list
+fn preflight_all_mess(roms: Option<PathBuf>, filter: &OptNameFilter, sources: &game::RomSources) {
+    use rayon::prelude::*;
+
+    let roms_dir = dirs::mess_roms_all(roms);
+    let preflight = game::Preflight::default();
+
+    for (software_list, db) in read_collected_dbs::<BTreeMap<_, _>, game::GameDb>(DIR_SL)
+        .into_iter()
+        .filter(|(software_list, _)| filter.matches(software_list))
+    {
+        let root = roms_dir.as_ref().join(&software_list);
+
+        db.games_map().par_iter().for_each(|(_, game)| {
+            preflight.record(
+                format!("{software_list}/{}", game.name),
+                &db.verify(&root, game, false),
+                sources,
+            )
+        });
+    }
+
+    preflight.display();
+}
+
 #[inline]
-fn add_and_verify<'g, I, P>(roms: &mut game::RomSources, root: P, games: I) -> Result<(), Error>
+fn add_and_verify<'g, I, P>(
+    roms: &mut game::RomSources,
+    policy: &game::RepairPolicy,
+    root: P,
+    games: I,
+) -> Result<(), Error>
 where
     P: AsRef<Path> + Sync,
     I: ExactSizeIterator<Item = &'g game::Game>,
@@ -3007,9 +6536,10 @@ where
         "adding and verifying games",
         root,
         games,
+        policy.keep_extra,
         |game, root, pbar| {
-            game.add_and_verify(roms, root.as_ref(), |r| {
-                pbar.println(format!("{r}"));
+            game.add_and_verify(roms, policy, root.as_ref(), |r| {
+                pbar.log(format!("{r}"));
                 r.into_fixed_pathbuf()
             })
         },
@@ -3026,71 +6556,114 @@ fn process_all_mess<E>(
         ) -> Result<Vec<game::VerifyFailure<'g>>, E>
         + Sync,
     show_all: bool,
+    keep_extra: bool,
+    filter: &OptNameFilter,
+    jobs: usize,
 ) -> Result<(), E>
 where
     E: Send,
 {
-    use crate::game::verify_style;
-    use indicatif::{ParallelProgressIterator, ProgressDrawTarget, ProgressIterator};
+    use crate::game::{bytes_style, verify_style, Game, VerifyFailure};
+    use indicatif::ParallelProgressIterator;
+    use rayon::prelude::*;
 
     let roms_dir = dirs::mess_roms_all(roms);
     let mut total = game::VerifyResultsSummary::default();
     let mut table = init_dat_table();
-    let dbs = read_collected_dbs::<BTreeMap<_, _>, game::GameDb>(DIR_SL);
+    let mut json = output::JsonResults::default();
+    let dbs = read_collected_dbs::<BTreeMap<_, _>, game::GameDb>(DIR_SL)
+        .into_iter()
+        .filter(|(software_list, _)| filter.matches(software_list))
+        .collect::<Vec<_>>();
 
-    let mbar = MultiProgress::with_draw_target(ProgressDrawTarget::stderr_with_hz(2));
-    let pbar1 =
-        mbar.add(ProgressBar::new(dbs.len().try_into().unwrap()).with_style(verify_style()));
+    let mbar = progress::multi();
+    let pbar1 = mbar.add(
+        progress::bar(
+            dbs.iter()
+                .flat_map(|(_, db)| db.games_map().values())
+                .map(|game| game.parts.expected_size())
+                .sum(),
+        )
+        .with_style(bytes_style()),
+    );
     pbar1.set_message(message);
 
-    for (software_list, db) in dbs.into_iter().progress_with(pbar1.clone()) {
-        use crate::game::{Game, VerifyFailure};
-        use rayon::prelude::*;
+    // up to "jobs" software lists are processed at once
+    // while their output remains in software list order
+    for chunk in dbs.chunks(jobs.max(1)) {
+        // software lists not yet started are left for the next run
+        if interrupt::requested() {
+            break;
+        }
 
-        let pbar2 = mbar.insert_after(
-            &pbar1,
-            ProgressBar::new(db.len().try_into().unwrap()).with_style(verify_style()),
-        );
-        pbar2.set_message(software_list.clone());
+        let processed = chunk
+            .par_iter()
+            .map(|(software_list, db)| {
+                let pbar2 = mbar.insert_after(
+                    &pbar1,
+                    progress::bar(db.len().try_into().unwrap()).with_style(verify_style()),
+                );
+                pbar2.set_message(software_list.clone());
+
+                let db_root = roms_dir.as_ref().join(software_list);
+
+                let mut results = db
+                    .games_map()
+                    .par_iter()
+                    .progress_with(pbar2.clone())
+                    .filter(|_| !interrupt::requested())
+                    .map(|(_, Game { name, parts, .. })| {
+                        let failures = handle_parts(parts, &db_root.join(name), &mbar)?;
+                        pbar1.inc(parts.expected_size());
+                        Ok((name.as_str(), failures))
+                    })
+                    .collect::<Result<BTreeMap<&str, Vec<VerifyFailure>>, E>>()?;
 
-        let db_root = roms_dir.as_ref().join(&software_list);
+                mbar.remove(&pbar2);
 
-        let mut results = db
-            .games_map()
-            .par_iter()
-            .progress_with(pbar2.clone())
-            .map(|(_, Game { name, parts, .. })| {
-                Ok((
-                    name.as_str(),
-                    handle_parts(parts, &db_root.join(name), &mbar)?,
-                ))
-            })
-            .collect::<Result<BTreeMap<&str, Vec<VerifyFailure>>, E>>()?;
+                results
+                    .values_mut()
+                    .for_each(|v| v.sort_unstable_by(|x, y| x.path().cmp(y.path())));
 
-        results
-            .values_mut()
-            .for_each(|v| v.sort_unstable_by(|x, y| x.path().cmp(y.path())));
+                let db_total = game::VerifyResultsSummary {
+                    successes: results
+                        .values()
+                        .filter(|v| v.iter().all(|f| f.is_ignored(keep_extra)))
+                        .count(),
+                    total: results.len(),
+                };
 
-        let db_total = game::VerifyResultsSummary {
-            successes: results.values().filter(|v| v.is_empty()).count(),
-            total: db.len(),
-        };
+                let (ignored, failures): (Vec<_>, Vec<_>) = results
+                    .into_values()
+                    .flatten()
+                    .partition(|f| f.is_ignored(keep_extra));
+
+                Ok((software_list, db_total, failures, ignored))
+            })
+            .collect::<Result<Vec<_>, E>>()?;
 
-        for (_, failures) in results {
-            for failure in failures {
-                mbar.println(format!("{failure}")).unwrap();
+        for (software_list, db_total, failures, ignored) in processed {
+            if output::is_json() {
+                json.add(software_list, &db_total, &failures, &ignored);
+            } else {
+                for line in failure_lines(&failures, &ignored) {
+                    mbar.log(line);
+                }
             }
-        }
 
-        if show_all || (db_total.successes != db_total.total) {
-            table.add_row(db_total.row(&software_list));
+            notify::record(software_list, &db_total);
+            if show_all || (db_total.successes != db_total.total) {
+                table.add_row(db_total.row(software_list));
+            }
+            total += db_total;
         }
-        total += db_total;
-        mbar.remove(&pbar2);
     }
 
     mbar.clear().unwrap();
-    display_dat_table(table, Some(total));
+    match output::is_json() {
+        true => json.display(Some(&total)),
+        false => display_dat_table(table, Some(total)),
+    }
 
     Ok(())
 }
@@ -3104,106 +6677,339 @@ fn process_dat<E>(
 ) -> Result<(), E> {
     let mut table = init_dat_table();
     let pbar = datfile.progress_bar();
-    let dat::VerifyResults { failures, summary } = process(&datfile, &pbar)?;
+    let dat::VerifyResults {
+        failures,
+        ignored,
+        summary,
+    } = process(&datfile, &pbar)?;
     pbar.finish_and_clear();
-    for failure in failures {
-        println!("{failure}");
+    notify::record(datfile.name(), &summary);
+    if output::is_json() {
+        let mut json = output::JsonResults::default();
+        json.add(datfile.name(), &summary, &failures, &ignored);
+        json.display(None);
+    } else {
+        for line in failure_lines(&failures, &ignored) {
+            println!("{line}");
+        }
+        table.add_row(summary.row(datfile.name()));
+        display_dat_table(table, None);
     }
-    table.add_row(summary.row(datfile.name()));
-    display_dat_table(table, None);
 
     Ok(())
 }
 
+// processes every DAT with a directory, up to "jobs" DAT files at once,
+// while still reporting them in order
 fn process_all_dat<I, E>(
     message: &'static str,
     dirs: I,
-    read_named_db: impl Fn(&str) -> Result<dat::DatFile, Error>,
-    mut process_dat: impl for<'d> FnMut(
-        &'d dat::DatFile,
-        &Path,
-        &indicatif::ProgressBar,
-    ) -> Result<dat::VerifyResults<'d>, E>,
+    read_named_db: impl Fn(&str) -> Result<dat::DatFile, Error> + Sync,
+    process_dat: impl for<'d> Fn(
+            &'d dat::DatFile,
+            &Path,
+            &indicatif::ProgressBar,
+            &(dyn Fn(&game::Part) + Sync),
+        ) -> Result<dat::VerifyResults<'d>, E>
+        + Sync,
     show_all: bool,
+    jobs: usize,
 ) -> Result<(), E>
 where
-    I: ExactSizeIterator<Item = (String, PathBuf)>,
+    I: Iterator<Item = (String, PathBuf)>,
+    E: Send,
 {
-    use game::verify_style;
-    use indicatif::{ProgressDrawTarget, ProgressIterator};
+    use rayon::prelude::*;
 
-    let mbar = MultiProgress::with_draw_target(ProgressDrawTarget::stderr_with_hz(2));
-    let pbar1 =
-        mbar.add(ProgressBar::new(dirs.len().try_into().unwrap()).with_style(verify_style()));
+    let (dirs, expected_size) = sized_dirs(dirs, &read_named_db);
+
+    let mbar = progress::multi();
+    let pbar1 = mbar.add(progress::bar(expected_size).with_style(game::bytes_style()));
     pbar1.set_message(message);
 
     let mut table = init_dat_table();
+    let mut json = output::JsonResults::default();
     let mut total = game::VerifyResultsSummary::default();
-    for (name, dir) in dirs.progress_with(pbar1.clone()) {
-        if let Ok(datfile) = read_named_db(&name) {
-            let pbar2 = mbar.insert_after(&pbar1, datfile.progress_bar());
-            let dat::VerifyResults { failures, summary } = process_dat(&datfile, &dir, &pbar2)?;
-            pbar2.finish_and_clear();
-            for failure in failures {
-                mbar.println(format!("{}", failure)).unwrap();
+    for chunk in dirs.chunks(jobs.max(1)) {
+        // DAT files not yet started are left for the next run
+        if interrupt::requested() {
+            break;
+        }
+
+        // each chunk's DATs are read as they're needed, and dropped once done
+        let datfiles = chunk
+            .par_iter()
+            .filter_map(|(name, dir)| read_named_db(name).ok().map(|datfile| (datfile, dir)))
+            .collect::<Vec<_>>();
+
+        let processed = datfiles
+            .par_iter()
+            .map(|(datfile, dir)| {
+                let pbar2 = mbar.insert_after(&pbar1, datfile.progress_bar());
+                let results = process_dat(datfile, dir, &pbar2, &|part| {
+                    pbar2.inc(1);
+                    pbar1.inc(part.size().unwrap_or_default());
+                });
+                pbar2.finish_and_clear();
+                mbar.remove(&pbar2);
+                results.map(|results| (datfile, results))
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+
+        for (
+            datfile,
+            dat::VerifyResults {
+                failures,
+                ignored,
+                summary,
+            },
+        ) in processed
+        {
+            if output::is_json() {
+                json.add(datfile.name(), &summary, &failures, &ignored);
+            } else {
+                for line in failure_lines(&failures, &ignored) {
+                    mbar.log(line);
+                }
             }
+            notify::record(datfile.name(), &summary);
             if show_all || (summary.successes != summary.total) {
                 table.add_row(summary.row(datfile.name()));
             }
             total += summary;
-            mbar.remove(&pbar2);
         }
     }
-    display_dat_table(table, Some(total));
+    pbar1.finish_and_clear();
+    match output::is_json() {
+        true => json.display(Some(&total)),
+        false => display_dat_table(table, Some(total)),
+    }
 
     Ok(())
 }
 
+// the directories of DATs which can be read, along with the
+// expected size of all their parts for tracking a whole run's progress,
+// where each DAT is read only long enough to be measured
+// so that no more than a few are ever held at once
+fn sized_dirs<I>(
+    dirs: I,
+    read_named_db: &(impl Fn(&str) -> Result<dat::DatFile, Error> + Sync),
+) -> (Vec<(String, PathBuf)>, u64)
+where
+    I: Iterator<Item = (String, PathBuf)>,
+{
+    use rayon::prelude::*;
+
+    let sized = dirs
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .filter_map(|(name, dir)| {
+            let size = read_named_db(&name).ok()?.expected_size();
+            Some(((name, dir), size))
+        })
+        .collect::<Vec<_>>();
+
+    let expected_size = sized.iter().map(|(_, size)| size).sum();
+    (
+        sized.into_iter().map(|(dir, _)| dir).collect(),
+        expected_size,
+    )
+}
+
+// like process_all_dat, but only verifying
+fn verify_all_dat<I>(
+    message: &'static str,
+    dirs: I,
+    read_named_db: impl Fn(&str) -> Result<dat::DatFile, Error> + Sync,
+    verify_dat: impl for<'d> Fn(&'d dat::DatFile, &Path, &(dyn Fn(&game::Part) + Sync)) -> dat::VerifyResults<'d>
+        + Sync,
+    show_all: bool,
+    jobs: usize,
+) where
+    I: Iterator<Item = (String, PathBuf)>,
+{
+    use crate::game::Never;
+
+    process_all_dat(
+        message,
+        dirs,
+        read_named_db,
+        |datfile, dir, _, increment| Ok::<_, Never>(verify_dat(datfile, dir, increment)),
+        show_all,
+        jobs,
+    )
+    .unwrap()
+}
+
+static SUMMARY_ONLY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// whether to skip listing failures and display only summary counts,
+// such as for scheduled audits
+fn set_summary_only(summary_only: bool) {
+    SUMMARY_ONLY.store(summary_only, std::sync::atomic::Ordering::Relaxed);
+}
+
+// failures ignored by policy are listed separately after the real failures,
+// and nothing is listed at all if only summaries are wanted
+fn failure_lines<'f, F: fmt::Display>(
+    failures: &'f [F],
+    ignored: &'f [game::VerifyFailure],
+) -> impl Iterator<Item = String> + 'f {
+    let listed = !SUMMARY_ONLY.load(std::sync::atomic::Ordering::Relaxed);
+
+    failures
+        .iter()
+        .map(|failure| failure.to_string())
+        .chain(
+            (!ignored.is_empty())
+                .then(|| "ignored :".to_owned())
+                .into_iter()
+                .chain(ignored.iter().map(|failure| format!("  {failure}"))),
+        )
+        .filter(move |_| listed)
+}
+
+// reads checksum file entries along with a name for them
+// taken from the checksum file's own name
+fn read_checksums(file: Resource) -> Result<(String, Vec<(String, checksum::Expected)>), Error> {
+    let mut data = String::new();
+    file.open()?.read_to_string(&mut data)?;
+
+    let name = match &file {
+        Resource::File(path) => path.file_stem().map(|s| s.to_string_lossy().into_owned()),
+        Resource::Url(url) => url.rsplit('/').next().map(|s| s.to_owned()),
+    }
+    .unwrap_or_default();
+
+    match checksum::parse(&data) {
+        Ok(entries) => Ok((name, entries)),
+        Err(error) => Err(Error::InvalidChecksums(ResourceError { file, error })),
+    }
+}
+
+// Redump's DAT with .sbi files added to the games which need them
+fn read_redump_db(name: &str) -> Result<dat::DatFile, Error> {
+    let mut datfile = read_named_db(REDUMP, DIR_REDUMP, name)?;
+    sbi::SbiIndex::load()?.add_parts(&mut datfile);
+    Ok(datfile)
+}
+
+// adds any indexed .sbi files as sources, so missing ones can be repaired
+fn add_sbi_sources(rom_sources: game::RomSources<'_>) -> Result<game::RomSources<'_>, Error> {
+    Ok(match sbi::SbiIndex::load()?.source() {
+        Some(source) => {
+            let mbar = progress::multi();
+            let sources = merge_sources(rom_sources, game::file_rom_sources(source, &mbar));
+            mbar.clear().unwrap();
+            sources
+        }
+        None => rom_sources,
+    })
+}
+
+// combined disc images matching a split database entry
+// can supply their tracks to repairs directly
+fn add_redump_split_sources<F>(rom_sources: &game::RomSources, needed: F)
+where
+    F: Fn(&game::Part) -> bool + Sync,
+{
+    if let Ok(db) = read_game_db::<split::SplitDb>(REDUMP, DB_REDUMP_SPLIT) {
+        db.add_split_sources(rom_sources, needed);
+    }
+}
+
+#[derive(Args)]
+struct OptDirsFilter {
+    /// only entries whose DAT is initialized
+    #[clap(long = "initialized", conflicts_with = "orphaned")]
+    initialized: bool,
+
+    /// only entries whose directory exists
+    #[clap(long = "present")]
+    present: bool,
+
+    /// only entries whose DAT has been destroyed
+    #[clap(long = "orphaned")]
+    orphaned: bool,
+}
+
+impl OptDirsFilter {
+    fn matches(&self, initialized: bool, dir: &Path) -> bool {
+        (!self.initialized || initialized)
+            && (!self.orphaned || !initialized)
+            && (!self.present || dir.is_dir())
+    }
+}
+
 fn display_dirs<D>(
     dirs: D,
     db: BTreeMap<String, dat::DatFile>,
     search: Option<String>,
     sort_by_version: bool,
+    filter: OptDirsFilter,
 ) where
     D: Iterator<Item = (String, PathBuf)>,
 {
-    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-    use comfy_table::presets::UTF8_FULL_CONDENSED;
-    use comfy_table::Table;
-
-    let mut results: Vec<[String; 3]> = dirs
-        .filter_map(|(name, dir)| {
-            db.get(&name)
-                .filter(|dat| match &search {
-                    Some(search) => dat.version().contains(search) || dat.name().contains(search),
-                    None => true,
-                })
-                .map(|dat| {
-                    [
-                        dat.version().to_owned(),
-                        dat.name().to_owned(),
-                        dir.to_string_lossy().to_string(),
-                    ]
-                })
+    use comfy_table::{Cell, Color};
+
+    // entries whose DAT has been destroyed have only their names
+    let mut results: Vec<(Option<&dat::DatFile>, String, PathBuf)> = dirs
+        .map(|(name, dir)| (db.get(&name), name, dir))
+        .filter(|(dat, _, dir)| filter.matches(dat.is_some(), dir))
+        .filter(|(dat, name, _)| match (&search, dat) {
+            (Some(search), Some(dat)) => {
+                dat.version().contains(search) || dat.name().contains(search)
+            }
+            (Some(search), None) => name.contains(search),
+            (None, _) => true,
         })
         .collect();
 
     if sort_by_version {
-        results.sort_unstable_by(|x, y| x[0].cmp(&y[0]));
+        results.sort_by(|(x, _, _), (y, _, _)| x.map(|d| d.version()).cmp(&y.map(|d| d.version())));
     }
 
-    let mut table = Table::new();
-    table
-        .set_header(vec!["Version", "DAT Name", "Directory"])
-        .load_preset(UTF8_FULL_CONDENSED)
-        .apply_modifier(UTF8_ROUND_CORNERS);
-
-    for [version, name, dir] in results {
-        table.add_row(vec![version, name, dir]);
+    let mut table = crate::table::new();
+    table.set_header(vec!["Version", "DAT Name", "Imported", "Directory"]);
+
+    for (dat, name, dir) in results {
+        table.add_row(match dat {
+            Some(dat) => vec![
+                Cell::new(dat.version()),
+                Cell::new(dat.name()),
+                Cell::new(dat.imported()),
+                dir_cell(&dir),
+            ],
+            None => vec![
+                Cell::new(""),
+                Cell::new(format!("{name} (no DAT)")).fg(Color::Red),
+                Cell::new(""),
+                dir_cell(&dir),
+            ],
+        });
     }
     println!("{table}");
 }
 
+// flags directories which are missing or can't be read
+fn dir_cell(dir: &Path) -> comfy_table::Cell {
+    use comfy_table::{Cell, Color};
+    use std::io::ErrorKind;
+
+    let problem = match std::fs::read_dir(dir) {
+        Ok(_) => return Cell::new(dir.to_string_lossy()),
+        Err(err) => match err.kind() {
+            ErrorKind::NotFound => "missing".to_owned(),
+            ErrorKind::PermissionDenied => "unreadable".to_owned(),
+            ErrorKind::NotADirectory => "not a directory".to_owned(),
+            _ => err.to_string(),
+        },
+    };
+
+    Cell::new(format!("{} ({})", dir.display(), problem)).fg(Color::Red)
+}
+
 fn display_dir_sizes<D>(
     dirs: D,
     db: BTreeMap<String, dat::DatFile>,
@@ -3212,60 +7018,109 @@ fn display_dir_sizes<D>(
 ) where
     D: Iterator<Item = (String, PathBuf)>,
 {
-    use crate::game::FileSize;
-    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-    use comfy_table::presets::UTF8_FULL_CONDENSED;
-    use comfy_table::{Cell, CellAlignment, Table};
-
-    struct Size(u64);
-
-    impl fmt::Display for Size {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            const K: f64 = (1 << 10) as f64;
-            const M: f64 = (1 << 20) as f64;
-            const G: f64 = (1 << 30) as f64;
-            const T: f64 = (1u64 << 40) as f64;
-
-            match self.0 {
-                b if b < (1 << 10) => write!(f, "{:.2} B", b),
-                b if b < (1 << 20) => write!(f, "{:.2} KiB", b as f64 / K),
-                b if b < (1 << 30) => write!(f, "{:.2} MiB", b as f64 / M),
-                b if b < (1 << 40) => write!(f, "{:.2} GiB", b as f64 / G),
-                b => write!(f, "{:.2} TiB", b as f64 / T),
-            }
-        }
+    use crate::game::{FileSize, Size};
+    use comfy_table::{Cell, CellAlignment};
+
+    // the delta is against everything in the directory,
+    // so that extra files not in the DAT count as surplus
+    fn dir_len(dir: &Path) -> u64 {
+        crate::symlinks::walk(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.metadata().ok())
+            .map(|m| m.len())
+            .sum()
     }
 
-    let mut results: Vec<(FileSize, String, PathBuf)> = dirs
+    // every file in every DAT, so that files hardlinked
+    // between DATs only count once in the total
+    let mut paths = Vec::new();
+
+    let mut results: Vec<(FileSize, u64, i128, String, PathBuf)> = dirs
         .filter_map(|(name, dir)| {
             db.get(&name)
                 .filter(|dat| match &search {
                     Some(search) => dat.version().contains(search) || dat.name().contains(search),
                     None => true,
                 })
-                .map(|dat| (dat.size(&dir), dat.name().to_owned(), dir))
+                .map(|dat| {
+                    let expected = dat.expected_size();
+                    paths.extend(dat.paths(&dir));
+                    (
+                        dat.size(&dir),
+                        expected,
+                        i128::from(dir_len(&dir)) - i128::from(expected),
+                        dat.name().to_owned(),
+                        dir,
+                    )
+                })
         })
         .collect();
 
     if sort_by_size {
-        results.sort_unstable_by(|x, y| x.0.cmp(&y.0));
+        results.sort_unstable_by_key(|x| x.0);
     }
 
     if !results.is_empty() {
-        let total = results.iter().map(|(size, _, _)| *size).sum();
-        results.push((total, "Total".to_owned(), PathBuf::default()));
+        let total = FileSize::unique(paths);
+        let linked: FileSize = results.iter().map(|(size, _, _, _, _)| *size).sum();
+        let expected = results.iter().map(|(_, expected, _, _, _)| *expected).sum();
+        let delta = results.iter().map(|(_, _, delta, _, _)| *delta).sum();
+        results.push((
+            total,
+            expected,
+            delta,
+            "Total".to_owned(),
+            PathBuf::default(),
+        ));
+
+        // space saved by files hardlinked between DATs
+        if linked != total {
+            results.push((
+                FileSize {
+                    len: linked.len - total.len,
+                    real: linked.real - total.real,
+                },
+                0,
+                0,
+                "Shared".to_owned(),
+                PathBuf::default(),
+            ));
+        }
     }
 
-    let mut table = Table::new();
-    table
-        .set_header(vec!["Size", "Real Size", "DAT Name", "Directory"])
-        .load_preset(UTF8_FULL_CONDENSED)
-        .apply_modifier(UTF8_ROUND_CORNERS);
+    let mut table = crate::table::new();
+    table.set_header(vec![
+        "Size",
+        "Real Size",
+        "Expected",
+        "Delta",
+        "DAT Name",
+        "Directory",
+    ]);
+
+    for (FileSize { len, real }, expected, delta, name, dir) in results {
+        // a shortfall suggests missing files, a surplus extra files
+        let delta = match delta.cmp(&0) {
+            std::cmp::Ordering::Equal => Cell::new(""),
+            std::cmp::Ordering::Less => {
+                Cell::new(format!("-{}", Size(delta.unsigned_abs() as u64)))
+                    .fg(comfy_table::Color::Red)
+            }
+            std::cmp::Ordering::Greater => {
+                Cell::new(format!("+{}", Size(delta as u64))).fg(comfy_table::Color::Yellow)
+            }
+        };
 
-    for (FileSize { len, real }, name, dir) in results {
         table.add_row(vec![
             Cell::new(Size(len)).set_alignment(CellAlignment::Right),
             Cell::new(Size(real)).set_alignment(CellAlignment::Right),
+            match expected {
+                0 => Cell::new(""),
+                expected => Cell::new(Size(expected)).set_alignment(CellAlignment::Right),
+            },
+            delta.set_alignment(CellAlignment::Right),
             Cell::new(name),
             Cell::new(dir.to_string_lossy()),
         ]);
@@ -3274,19 +7129,14 @@ fn display_dir_sizes<D>(
 }
 
 fn init_dat_table() -> comfy_table::Table {
-    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-    use comfy_table::presets::UTF8_FULL_CONDENSED;
     use comfy_table::{Cell, CellAlignment};
 
-    let mut table = comfy_table::Table::new();
-    table
-        .set_header(vec![
-            Cell::new("Tested").set_alignment(CellAlignment::Right),
-            Cell::new("OK").set_alignment(CellAlignment::Right),
-            Cell::new(""),
-        ])
-        .load_preset(UTF8_FULL_CONDENSED)
-        .apply_modifier(UTF8_ROUND_CORNERS);
+    let mut table = crate::table::new();
+    table.set_header(vec![
+        Cell::new("Tested").set_alignment(CellAlignment::Right),
+        Cell::new("OK").set_alignment(CellAlignment::Right),
+        Cell::new(""),
+    ]);
 
     table
 }
@@ -3298,40 +7148,48 @@ fn display_dat_table(mut table: comfy_table::Table, summary: Option<game::Verify
     println!("{table}");
 }
 
-fn rom_sources(sources: &[Resource]) -> game::RomSources {
-    use indicatif::{ParallelProgressIterator, ProgressDrawTarget};
-    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-
-    fn merge_sources<'u>(
-        mut base: game::RomSources<'u>,
-        mut extend: game::RomSources<'u>,
-    ) -> game::RomSources<'u> {
-        use dashmap::mapref::entry::Entry;
+fn merge_sources<'u>(
+    mut base: game::RomSources<'u>,
+    mut extend: game::RomSources<'u>,
+) -> game::RomSources<'u> {
+    use dashmap::mapref::entry::Entry;
 
-        if extend.len() > base.len() {
-            std::mem::swap(&mut base, &mut extend);
-        }
+    if extend.len() > base.len() {
+        std::mem::swap(&mut base, &mut extend);
+    }
 
-        for (part, source) in extend {
-            match base.entry(part) {
-                Entry::Occupied(mut o) if source.more_local_than(o.get()) => {
-                    o.insert(source);
-                }
-                Entry::Occupied(_) => {}
-                Entry::Vacant(v) => {
-                    v.insert(source);
-                }
+    for (part, source) in extend {
+        match base.entry(part) {
+            Entry::Occupied(mut o) if source.more_local_than(o.get()) => {
+                tracing::trace!(preferred = %source, over = %o.get(), "choosing more local source");
+                o.insert(source);
+            }
+            Entry::Occupied(_) => {}
+            Entry::Vacant(v) => {
+                v.insert(source);
             }
         }
-
-        base
     }
 
-    let mbar = MultiProgress::with_draw_target(ProgressDrawTarget::stderr_with_hz(2));
-    let pbar1 = mbar
-        .add(ProgressBar::new(sources.len().try_into().unwrap()).with_style(game::verify_style()));
+    base
+}
+
+fn rom_sources(sources: &[Resource]) -> game::RomSources<'_> {
+    use indicatif::ParallelProgressIterator;
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+    let mbar = progress::multi();
+    let pbar1 =
+        mbar.add(progress::bar(sources.len().try_into().unwrap()).with_style(game::verify_style()));
     pbar1.set_message("retrieving ROMs");
 
+    if let Err(err) = http::DownloadQueue::enqueue(sources.iter().filter_map(|r| match r {
+        Resource::Url(url) => Some(url.as_str()),
+        Resource::File(_) => None,
+    })) {
+        tracing::warn!(%err, "unable to queue downloads");
+    }
+
     let results = sources
         .par_iter()
         .progress_with(pbar1)
@@ -3348,7 +7206,7 @@ fn sub_files(root: PathBuf) -> Box<dyn Iterator<Item = PathBuf>> {
         Box::new(std::iter::once(root))
     } else if root.is_dir() {
         Box::new(
-            walkdir::WalkDir::new(root)
+            crate::symlinks::walk(root)
                 .into_iter()
                 .filter_map(|e| e.ok())
                 .filter(|e| e.file_type().is_file())
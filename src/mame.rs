@@ -1,4 +1,4 @@
-use super::game::{Game, GameDb, Part, Status};
+use super::game::{parse_int, Game, GameDb, Part, Status};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -39,8 +39,8 @@ impl Machine {
         Game {
             name: self.name,
             description: self.description,
-            creator: self.manufacturer.unwrap_or_default(),
-            year: self.year.unwrap_or_default(),
+            creator: crate::game::intern(self.manufacturer.unwrap_or_default()),
+            year: crate::game::intern(self.year.unwrap_or_default()),
             status: self.driver.map(|d| d.status()).unwrap_or(Status::Working),
             is_device: matches!(self.isdevice.as_deref(), Some("yes")),
             parts: self
@@ -79,13 +79,18 @@ impl Driver {
 #[derive(Debug, Deserialize)]
 struct Rom {
     name: String,
+    size: Option<String>,
     sha1: Option<String>,
 }
 
 impl Rom {
     #[inline]
     fn into_part(self) -> Option<(String, Part)> {
-        Some((self.name, Part::new_rom(self.sha1.as_deref()?).ok()?))
+        let size = self.size.as_deref().and_then(|s| parse_int(s).ok());
+        Some((
+            self.name,
+            Part::new_rom(self.sha1.as_deref()?).ok()?.with_size(size),
+        ))
     }
 }
 
@@ -1,10 +1,10 @@
 use super::{
-    game::{Game, GameColumn, GameDb, GameParts, GameRow, Part as GamePart, Status},
+    game::{Game, GameColumn, GameDb, GameParts, GameRow, Part as GamePart, Search, Status},
     split::{SplitDb, SplitGame, SplitPart},
 };
 use crate::game::parse_int;
 use serde::Deserialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Debug, Deserialize)]
 pub struct Softwarelist {
@@ -38,7 +38,7 @@ impl Softwarelist {
             self.software
                 .iter()
                 .flatten()
-                .filter_map(|software| software.to_split_db()),
+                .filter_map(|software| software.to_split_db(&self.name)),
         )
     }
 }
@@ -58,8 +58,8 @@ impl Software {
         Game {
             name: self.name,
             description: self.description,
-            creator: self.publisher,
-            year: self.year,
+            creator: crate::game::intern(self.publisher),
+            year: crate::game::intern(self.year),
             status: match self.supported.as_deref() {
                 Some("partial") => Status::Partial,
                 Some("no") => Status::NotWorking,
@@ -80,7 +80,7 @@ impl Software {
         }
     }
 
-    fn to_split_db(&self) -> Option<(u64, SplitGame)> {
+    fn to_split_db(&self, software_list: &str) -> Option<(u64, SplitGame)> {
         let rom_sizes = self
             .part
             .iter()
@@ -100,7 +100,7 @@ impl Software {
         let total: u64 = rom_sizes.iter().map(|s| s.size).sum();
 
         let mut offset = 0;
-        let mut game = SplitGame::new(self.name.clone());
+        let mut game = SplitGame::with_software_list(self.name.clone(), software_list.to_owned());
 
         for RomSize { name, size, sha1 } in rom_sizes {
             game.push_track(SplitPart::new(name, offset, offset + size as usize, sha1));
@@ -157,11 +157,15 @@ pub struct Rom {
 impl Rom {
     #[inline]
     fn into_part(self) -> Option<(String, GamePart)> {
-        Some((self.name?, GamePart::new_rom(&self.sha1?).ok()?))
+        let size = self.size.as_deref().and_then(|s| parse_int(s).ok());
+        Some((
+            self.name?,
+            GamePart::new_rom(&self.sha1?).ok()?.with_size(size),
+        ))
     }
 
     #[inline]
-    fn to_size(&self) -> Option<RomSize> {
+    fn to_size(&self) -> Option<RomSize<'_>> {
         Some(RomSize {
             name: self.name.as_deref()?,
             size: parse_int(self.size.as_deref()?).ok()?,
@@ -205,12 +209,18 @@ impl Disk {
 
 pub type MessDb = BTreeMap<String, GameDb>;
 
-pub fn list(db: &MessDb, search: Option<&str>, sort: GameColumn, simple: bool) {
+pub fn list(
+    db: &MessDb,
+    search: Option<&Search>,
+    tagged: Option<&BTreeSet<String>>,
+    sort: &[GameColumn],
+    simple: bool,
+) {
     let mut results: Vec<(&str, GameRow)> = db
         .iter()
         .flat_map(|(name, game_db)| {
             game_db
-                .list_results(search, simple)
+                .list_results(search, tagged, simple)
                 .into_iter()
                 .map(move |row| (name.as_str(), row))
         })
@@ -222,15 +232,10 @@ pub fn list(db: &MessDb, search: Option<&str>, sort: GameColumn, simple: bool) {
 }
 
 pub fn display_results(results: &[(&str, GameRow)]) {
-    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-    use comfy_table::presets::UTF8_FULL_CONDENSED;
-    use comfy_table::{Cell, Color, Table};
+    use comfy_table::{Cell, Color};
 
-    let mut table = Table::new();
-    table
-        .set_header(vec!["Game", "Creator", "Year", "List", "Shortname"])
-        .load_preset(UTF8_FULL_CONDENSED)
-        .apply_modifier(UTF8_ROUND_CORNERS);
+    let mut table = crate::table::new();
+    table.set_header(vec!["Game", "Creator", "Year", "List", "Shortname"]);
 
     for (
         db_name,
@@ -260,15 +265,8 @@ pub fn display_results(results: &[(&str, GameRow)]) {
 }
 
 pub fn list_all(db: &MessDb) {
-    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-    use comfy_table::presets::UTF8_FULL_CONDENSED;
-    use comfy_table::Table;
-
-    let mut table = Table::new();
-    table
-        .set_header(vec!["Software List", "Shortname"])
-        .load_preset(UTF8_FULL_CONDENSED)
-        .apply_modifier(UTF8_ROUND_CORNERS);
+    let mut table = crate::table::new();
+    table.set_header(vec!["Software List", "Shortname"]);
 
     for (name, game_db) in db.iter() {
         table.add_row(vec![game_db.description(), name]);
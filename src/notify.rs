@@ -0,0 +1,315 @@
+use crate::game::VerifyResultsSummary;
+use crate::Error;
+use std::sync::Mutex;
+
+// every verified set's results, in the order verified,
+// so they can be passed along once the run ends
+static RESULTS: Mutex<Vec<(String, VerifyResultsSummary)>> = Mutex::new(Vec::new());
+
+pub fn record(name: &str, summary: &VerifyResultsSummary) {
+    RESULTS.lock().unwrap().push((
+        name.to_owned(),
+        VerifyResultsSummary {
+            successes: summary.successes,
+            total: summary.total,
+        },
+    ));
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WebhookFormat {
+    // the whole report as structured JSON
+    #[default]
+    Json,
+    // a Discord channel webhook
+    Discord,
+    // a Slack incoming webhook
+    Slack,
+}
+
+impl std::str::FromStr for WebhookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "discord" => Ok(Self::Discord),
+            "slack" => Ok(Self::Slack),
+            _ => Err(format!(
+                "unknown webhook format \"{s}\", use \"json\", \"discord\" or \"slack\""
+            )),
+        }
+    }
+}
+
+// where a run's results are sent once it ends,
+// as set by commands which verify anything
+pub struct Targets {
+    pub notify: bool,
+    pub on_complete: Option<String>,
+    pub webhook: Option<String>,
+    pub webhook_format: WebhookFormat,
+    pub mail_to: Option<String>,
+}
+
+static TARGETS: Mutex<Option<Targets>> = Mutex::new(None);
+
+pub fn set_targets(targets: Targets) {
+    *TARGETS.lock().unwrap() = Some(targets);
+}
+
+// passes the run's results along to any targets set
+pub fn complete(command: String, result: &Result<(), Error>) {
+    let Some(targets) = TARGETS.lock().unwrap().take() else {
+        return;
+    };
+
+    if !targets.notify
+        && targets.on_complete.is_none()
+        && targets.webhook.is_none()
+        && targets.mail_to.is_none()
+    {
+        return;
+    }
+
+    let completion = Completion::new(command, result);
+    if targets.notify {
+        completion.notify();
+    }
+    if let Some(command) = &targets.on_complete {
+        if let Err(err) = completion.run_hook(command) {
+            eprintln!("* {err}");
+        }
+    }
+    if let Some(url) = &targets.webhook {
+        if let Err(err) = completion.post_webhook(url, targets.webhook_format) {
+            eprintln!("* unable to post report : {err}");
+        }
+    }
+    if let Some(to) = &targets.mail_to {
+        if let Err(err) = completion.send_mail(to) {
+            eprintln!("* {err}");
+        }
+    }
+}
+
+// Discord rejects messages longer than this
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+// how a run ended, for notifications and completion hooks
+pub struct Completion {
+    pub command: String,
+    pub results: Vec<(String, VerifyResultsSummary)>,
+    pub total: VerifyResultsSummary,
+    pub error: Option<String>,
+    pub interrupted: bool,
+}
+
+impl Completion {
+    pub fn new(command: String, result: &Result<(), Error>) -> Self {
+        let results = std::mem::take(&mut *RESULTS.lock().unwrap());
+        let mut total = VerifyResultsSummary::default();
+        for (_, summary) in &results {
+            total.successes += summary.successes;
+            total.total += summary.total;
+        }
+
+        Self {
+            command,
+            results,
+            total,
+            error: result.as_ref().err().map(|err| err.to_string()),
+            interrupted: matches!(result, Err(Error::Interrupted)),
+        }
+    }
+
+    #[inline]
+    pub fn failures(&self) -> usize {
+        self.total.total - self.total.successes
+    }
+
+    pub fn status(&self) -> &'static str {
+        match &self.error {
+            Some(_) if self.interrupted => "interrupted",
+            Some(_) => "error",
+            None if self.failures() > 0 => "failed",
+            None => "ok",
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        match &self.error {
+            Some(err) if self.interrupted && !self.results.is_empty() => format!(
+                "emuman {} : {err}, {} tested, {} OK, {} failed",
+                self.command,
+                self.total.total,
+                self.total.successes,
+                self.failures()
+            ),
+            Some(err) => format!("emuman {} : {err}", self.command),
+            None if self.results.is_empty() => format!("emuman {} : finished", self.command),
+            None => format!(
+                "emuman {} : {} tested, {} OK, {} failed",
+                self.command,
+                self.total.total,
+                self.total.successes,
+                self.failures()
+            ),
+        }
+    }
+
+    // the summary followed by each verified set's counts,
+    // failing sets first
+    pub fn report(&self) -> String {
+        use std::fmt::Write;
+
+        let mut report = self.summary();
+        let mut results = self.results.iter().collect::<Vec<_>>();
+        results.sort_by_key(|(_, summary)| summary.successes == summary.total);
+        for (name, summary) in results {
+            let _ = write!(
+                report,
+                "\n{name} : {} tested, {} OK",
+                summary.total, summary.successes
+            );
+        }
+        report
+    }
+
+    fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "command": self.command,
+            "status": self.status(),
+            "summary": self.summary(),
+            "error": self.error,
+            "tested": self.total.total,
+            "ok": self.total.successes,
+            "failures": self.failures(),
+            "results": self.results.iter().map(|(name, summary)| serde_json::json!({
+                "name": name,
+                "tested": summary.total,
+                "ok": summary.successes,
+                "failures": summary.total - summary.successes,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn post_webhook(&self, url: &str, format: WebhookFormat) -> Result<(), Error> {
+        let body = match format {
+            WebhookFormat::Json => self.json(),
+            WebhookFormat::Discord => {
+                let mut report = self.report();
+                if report.len() > DISCORD_MAX_LENGTH {
+                    let mut end = DISCORD_MAX_LENGTH - 1;
+                    while !report.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    report.truncate(end);
+                    report.push('…');
+                }
+                serde_json::json!({ "content": report })
+            }
+            WebhookFormat::Slack => serde_json::json!({ "text": self.report() }),
+        };
+
+        tracing::debug!(url, ?format, "posting report");
+
+        match attohttpc::post(url)
+            .header(attohttpc::header::CONTENT_TYPE, "application/json")
+            .text(body.to_string())
+            .send()?
+        {
+            response if response.is_success() => Ok(()),
+            response => Err(Error::HttpCode(response.status())),
+        }
+    }
+
+    // hands the report to the system's sendmail,
+    // which relays it through whatever SMTP server it's set up for
+    pub fn send_mail(&self, to: &str) -> Result<(), Error> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut sendmail = Command::new("sendmail")
+            .arg("-t")
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = sendmail.stdin.take() {
+            write!(
+                stdin,
+                "To: {to}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+                self.summary(),
+                self.report().replace('\n', "\r\n"),
+            )?;
+        }
+
+        if sendmail.wait()?.success() {
+            Ok(())
+        } else {
+            Err(Error::MailFailed(to.to_owned()))
+        }
+    }
+
+    // a desktop notification, where failing to display one
+    // (such as on a headless machine) isn't an error
+    pub fn notify(&self) {
+        let summary = self.summary();
+
+        #[cfg(target_os = "macos")]
+        let status = std::process::Command::new("osascript")
+            .args([
+                "-e",
+                "on run argv",
+                "-e",
+                "display notification (item 1 of argv) with title \"emuman\"",
+                "-e",
+                "end run",
+            ])
+            .arg(&summary)
+            .status();
+
+        #[cfg(not(target_os = "macos"))]
+        let status = std::process::Command::new("notify-send")
+            .arg("emuman")
+            .arg(&summary)
+            .status();
+
+        if let Err(err) = status {
+            eprintln!("* unable to display notification : {err}");
+        }
+    }
+
+    // runs a command through the shell with the run's results
+    // in its environment, so follow-up steps can be chained
+    pub fn run_hook(&self, command: &str) -> Result<(), Error> {
+        #[cfg(windows)]
+        let mut hook = {
+            let mut hook = std::process::Command::new("cmd");
+            hook.arg("/C").arg(command);
+            hook
+        };
+
+        #[cfg(not(windows))]
+        let mut hook = {
+            let mut hook = std::process::Command::new("sh");
+            hook.arg("-c").arg(command);
+            hook
+        };
+
+        let status = hook
+            .env("EMUMAN_COMMAND", &self.command)
+            .env("EMUMAN_STATUS", self.status())
+            .env("EMUMAN_SUMMARY", self.summary())
+            .env("EMUMAN_TESTED", self.total.total.to_string())
+            .env("EMUMAN_OK", self.total.successes.to_string())
+            .env("EMUMAN_FAILURES", self.failures().to_string())
+            .status()?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::HookFailed(command.to_owned()))
+        }
+    }
+}
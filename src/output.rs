@@ -0,0 +1,72 @@
+use crate::game::{VerifyFailure, VerifyResultsSummary};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    // failure lines followed by a table of results
+    #[default]
+    Text,
+    // a single JSON document once everything is verified
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown output format \"{s}\", use \"text\" or \"json\""
+            )),
+        }
+    }
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// how verification results are written to standard output
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// each verified set's results, gathered until the run ends
+#[derive(Default)]
+pub struct JsonResults {
+    results: Vec<serde_json::Value>,
+}
+
+impl JsonResults {
+    pub fn add(
+        &mut self,
+        name: &str,
+        summary: &VerifyResultsSummary,
+        failures: &[VerifyFailure],
+        ignored: &[VerifyFailure],
+    ) {
+        self.results.push(serde_json::json!({
+            "name": name,
+            "tested": summary.total,
+            "ok": summary.successes,
+            "failures": failures.iter().map(VerifyFailure::json).collect::<Vec<_>>(),
+            "ignored": ignored.iter().map(VerifyFailure::json).collect::<Vec<_>>(),
+        }));
+    }
+
+    pub fn display(self, total: Option<&VerifyResultsSummary>) {
+        let mut report = serde_json::json!({ "results": self.results });
+
+        if let Some(total) = total {
+            report["tested"] = total.total.into();
+            report["ok"] = total.successes.into();
+        }
+
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+}
@@ -0,0 +1,63 @@
+use std::process::{Command, Stdio};
+
+// lowers the CPU and I/O priority of the process
+// (only the CPU priority on Windows),
+// which threads started afterward inherit,
+// so that long verifications and repairs yield to anything interactive
+pub fn lower() {
+    for mut command in commands(&std::process::id().to_string()) {
+        let program = command.get_program().to_string_lossy().into_owned();
+
+        match command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(status) if status.success() => {}
+            Ok(_) => eprintln!("* unable to lower priority : {program} failed"),
+            Err(err) => eprintln!("* unable to lower priority : {program} : {err}"),
+        }
+    }
+}
+
+// the idle I/O class only gets disk time when nothing else wants it
+#[cfg(target_os = "linux")]
+fn commands(pid: &str) -> Vec<Command> {
+    let mut renice = Command::new("renice");
+    renice.args(["-n", "19", "-p", pid]);
+
+    let mut ionice = Command::new("ionice");
+    ionice.args(["-c", "3", "-p", pid]);
+
+    vec![renice, ionice]
+}
+
+// the background QoS policy throttles both CPU and I/O
+#[cfg(target_os = "macos")]
+fn commands(pid: &str) -> Vec<Command> {
+    let mut taskpolicy = Command::new("taskpolicy");
+    taskpolicy.args(["-b", "-p", pid]);
+
+    vec![taskpolicy]
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn commands(pid: &str) -> Vec<Command> {
+    let mut renice = Command::new("renice");
+    renice.args(["-n", "20", "-p", pid]);
+
+    vec![renice]
+}
+
+#[cfg(windows)]
+fn commands(pid: &str) -> Vec<Command> {
+    let mut powershell = Command::new("powershell");
+    powershell.args([
+        "-NoProfile",
+        "-Command",
+        &format!("(Get-Process -Id {pid}).PriorityClass = 'Idle'"),
+    ]);
+
+    vec![powershell]
+}
+
+#[cfg(not(any(unix, windows)))]
+fn commands(_pid: &str) -> Vec<Command> {
+    vec![]
+}
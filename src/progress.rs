@@ -0,0 +1,112 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, WeakProgressBar};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// how often plain status lines are written in place of progress bars
+const STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+static REPORTED: Mutex<Vec<WeakProgressBar>> = Mutex::new(Vec::new());
+
+static REPORTER: OnceLock<()> = OnceLock::new();
+
+// replaces progress bars with occasional plain status lines,
+// for unattended runs whose output goes to a log file
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+#[inline]
+fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+pub fn bar(len: u64) -> ProgressBar {
+    if is_plain() {
+        reported(ProgressBar::with_draw_target(
+            Some(len),
+            ProgressDrawTarget::hidden(),
+        ))
+    } else {
+        ProgressBar::new(len)
+    }
+}
+
+pub fn spinner() -> ProgressBar {
+    if is_plain() {
+        reported(ProgressBar::with_draw_target(
+            None,
+            ProgressDrawTarget::hidden(),
+        ))
+    } else {
+        ProgressBar::new_spinner()
+    }
+}
+
+pub fn multi() -> MultiProgress {
+    MultiProgress::with_draw_target(if is_plain() {
+        ProgressDrawTarget::hidden()
+    } else {
+        ProgressDrawTarget::stderr_with_hz(2)
+    })
+}
+
+fn reported(pbar: ProgressBar) -> ProgressBar {
+    REPORTED.lock().unwrap().push(pbar.downgrade());
+
+    REPORTER.get_or_init(|| {
+        std::thread::spawn(|| loop {
+            std::thread::sleep(STATUS_INTERVAL);
+            report();
+        });
+    });
+
+    pbar
+}
+
+fn report() {
+    use indicatif::HumanDuration;
+
+    let mut reported = REPORTED.lock().unwrap();
+    reported.retain(|pbar| pbar.upgrade().is_some_and(|pbar| !pbar.is_finished()));
+
+    for pbar in reported.iter().filter_map(|pbar| pbar.upgrade()) {
+        let message = pbar.message();
+        let message = if message.is_empty() {
+            "working"
+        } else {
+            message.as_str()
+        };
+
+        match pbar.length() {
+            Some(len) if len > 0 => eprintln!(
+                "* {message} : {} / {len} ({}%, ETA {})",
+                pbar.position(),
+                pbar.position() * 100 / len,
+                HumanDuration(pbar.eta()),
+            ),
+            _ => eprintln!("* {message} : {}", pbar.position()),
+        }
+    }
+}
+
+// prints a line above any progress bars,
+// which still works when the bars are hidden
+pub trait Log {
+    fn log(&self, line: impl Display);
+}
+
+impl Log for ProgressBar {
+    fn log(&self, line: impl Display) {
+        self.suspend(|| eprintln!("{line}"))
+    }
+}
+
+impl Log for MultiProgress {
+    fn log(&self, line: impl Display) {
+        self.suspend(|| eprintln!("{line}"))
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// whether files which verify OK are made read-only
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// clears the write bits of a file which verified OK,
+// where failing to do so doesn't fail verification
+pub fn protect(path: &Path) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    match std::fs::metadata(path) {
+        // a hard-linked file's permissions are shared by all its links,
+        // which may be elsewhere and expected to stay writable
+        Ok(metadata) if is_linked(&metadata) => {
+            tracing::debug!(path = %path.display(), "left writable: multiple links");
+        }
+        Ok(metadata) if !metadata.permissions().readonly() => {
+            let mut permissions = metadata.permissions();
+            permissions.set_readonly(true);
+            if let Err(err) = std::fs::set_permissions(path, permissions) {
+                tracing::debug!(path = %path.display(), %err, "unable to protect file");
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(unix)]
+fn is_linked(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_linked(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+// restores the owner's write bit to a file
+// before it's intentionally replaced, renamed or deleted
+pub fn unprotect(path: &Path) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return;
+    };
+
+    let mut permissions = metadata.permissions();
+    if !permissions.readonly() {
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+
+    #[cfg(not(unix))]
+    permissions.set_readonly(false);
+
+    if let Err(err) = std::fs::set_permissions(path, permissions) {
+        tracing::debug!(path = %path.display(), %err, "unable to unprotect file");
+    }
+}
+
+// restores the write bits of every file beneath a directory,
+// such as an extra directory left for the user to remove
+pub fn unprotect_all(dir: &Path) {
+    crate::symlinks::walk(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .for_each(|entry| unprotect(entry.path()));
+}
@@ -0,0 +1,63 @@
+use std::path::{Component, Path, PathBuf};
+
+// where a file is kept within the quarantine directory,
+// beneath its whole original path so that files from
+// different collections never land on top of one another
+fn destination(dir: &Path, path: &Path) -> Result<PathBuf, std::io::Error> {
+    let mut destination = dir.to_owned();
+
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            Component::Prefix(prefix) => destination.push(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .replace(|c: char| !c.is_alphanumeric(), ""),
+            ),
+            Component::Normal(name) => destination.push(name),
+            Component::RootDir | Component::CurDir | Component::ParentDir => {}
+        }
+    }
+
+    // an earlier repair's copy is never replaced
+    if destination.exists() {
+        let mut name = destination.file_name().unwrap_or_default().to_owned();
+        name.push(".");
+        let numbered = (1..)
+            .map(|n| {
+                let mut name = name.clone();
+                name.push(n.to_string());
+                destination.with_file_name(name)
+            })
+            .find(|numbered| !numbered.exists())
+            .unwrap();
+        destination = numbered;
+    }
+
+    std::fs::create_dir_all(destination.parent().unwrap())?;
+
+    Ok(destination)
+}
+
+// moves a file being deleted or replaced into the quarantine directory,
+// copying it instead if the directory is on another filesystem
+pub fn store(dir: &Path, path: &Path) -> Result<(), std::io::Error> {
+    let destination = destination(dir, path)?;
+
+    tracing::debug!(
+        path = %path.display(),
+        destination = %destination.display(),
+        "quarantining file"
+    );
+
+    std::fs::rename(path, &destination).or_else(|err| {
+        tracing::debug!(%err, "unable to move file, copying instead");
+        std::fs::copy(path, &destination)?;
+        std::fs::remove_file(path)
+    })
+}
+
+// copies a file about to be modified in place into the quarantine directory
+pub fn preserve(dir: &Path, path: &Path) -> Result<(), std::io::Error> {
+    std::fs::copy(path, destination(dir, path)?).map(|_| ())
+}
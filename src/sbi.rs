@@ -0,0 +1,128 @@
+use crate::dat::DatFile;
+use crate::game::Part;
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+pub const SBI_INDEX_FILE: &str = "sbi.cbor";
+
+// the only system whose games libcrypt protects
+const LIBCRYPT_SYSTEM: &str = "Sony - PlayStation";
+
+// the subchannel data that PSX games protected by libcrypt
+// need beside their tracks, which Redump's DATs don't list,
+// indexed from a directory or Zip file of .sbi files
+#[derive(Default, Serialize, Deserialize)]
+pub struct SbiIndex {
+    source: Option<PathBuf>,
+    // each .sbi file by the game it's named after
+    files: BTreeMap<String, Part>,
+}
+
+impl SbiIndex {
+    pub fn load() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(SBI_INDEX_FILE))
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(SBI_INDEX_FILE), self)
+    }
+
+    #[inline]
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    // replaces the index with a fresh scan of the source,
+    // which may hold .sbi files loose, in Zip files,
+    // or in Zip files within Zip files (as Redump's packs do),
+    // returning the number of .sbi files found
+    pub fn index(&mut self, source: PathBuf) -> Result<usize, Error> {
+        let mut files = BTreeMap::default();
+
+        if !source.exists() {
+            return Err(Error::NoSuchDirectory(source));
+        }
+
+        for entry in crate::symlinks::walk(&source)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_type().is_dir())
+        {
+            let path = entry.path();
+            match sbi_game(path) {
+                Some(game) => {
+                    let f = std::fs::File::open(path)?;
+                    let size = f.metadata()?.len();
+                    let part = Part::from_reader(std::io::BufReader::new(f))?;
+                    files.insert(game.to_owned(), part.with_size(Some(size)));
+                }
+                None if is_zip(path) => {
+                    let f = std::io::BufReader::new(std::fs::File::open(path)?);
+                    if let Err(err) = index_zip(f, &mut files) {
+                        eprintln!("* {} : {err}", path.display());
+                    }
+                }
+                None => {}
+            }
+        }
+
+        self.source = Some(source.canonicalize()?);
+        self.files = files;
+
+        Ok(self.files.len())
+    }
+
+    // adds each game's .sbi file to its parts,
+    // where games without one are left as-is,
+    // and lets PlayStation games keep .sbi files which aren't indexed
+    pub fn add_parts(&self, datfile: &mut DatFile) {
+        if datfile.name() == LIBCRYPT_SYSTEM {
+            datfile.set_sidecar("sbi");
+        }
+
+        for (game, part) in &self.files {
+            datfile.add_part(game, format!("{game}.sbi"), part.clone());
+        }
+    }
+}
+
+fn index_zip<R: Read + Seek>(r: R, files: &mut BTreeMap<String, Part>) -> Result<(), Error> {
+    let mut zip = zip::ZipArchive::new(r)?;
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let name = Path::new(entry.name()).to_owned();
+
+        if let Some(game) = sbi_game(&name) {
+            let size = entry.size();
+            let part = Part::from_reader(&mut entry)?;
+            files.insert(game.to_owned(), part.with_size(Some(size)));
+        } else if is_zip(&name) {
+            // .sbi files are tiny, so nested Zip files are read into memory
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            index_zip(std::io::Cursor::new(data), files)?;
+        }
+    }
+
+    Ok(())
+}
+
+// the game a .sbi file belongs to, which Redump names them after
+fn sbi_game(path: &Path) -> Option<&str> {
+    path.extension()
+        .filter(|ext| ext.eq_ignore_ascii_case("sbi"))
+        .and(path.file_stem())
+        .and_then(|stem| stem.to_str())
+}
+
+#[inline]
+fn is_zip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
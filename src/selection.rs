@@ -0,0 +1,53 @@
+use crate::Error;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+const SELECTIONS_FILE: &str = "selections.toml";
+
+// the last item chosen from each kind of list,
+// so the next prompt can start there
+fn location() -> PathBuf {
+    crate::dirs::data_dir().join(SELECTIONS_FILE)
+}
+
+fn load() -> BTreeMap<String, String> {
+    std::fs::read_to_string(location())
+        .ok()
+        .and_then(|toml| toml::from_str(&toml).ok())
+        .unwrap_or_default()
+}
+
+// failing to remember a selection shouldn't fail the command
+fn remember(key: &str, choice: &str) {
+    let mut selections = load();
+    selections.insert(key.to_owned(), choice.to_owned());
+
+    if let Ok(toml) = toml::to_string(&selections) {
+        let _ = std::fs::create_dir_all(crate::dirs::data_dir());
+        let _ = std::fs::write(location(), toml);
+    }
+}
+
+// prompts for one of the given options, starting at
+// whichever was chosen the last time this key was used
+pub fn select<T: Display>(
+    key: &str,
+    prompt: &str,
+    options: Vec<T>,
+    id: impl Fn(&T) -> &str,
+) -> Result<T, Error> {
+    let cursor = load()
+        .get(key)
+        .and_then(|last| options.iter().position(|o| id(o) == last))
+        .unwrap_or_default();
+
+    let choice = inquire::Select::new(prompt, options)
+        .with_starting_cursor(cursor)
+        .with_page_size(crate::terminal_height())
+        .prompt()?;
+
+    remember(key, id(&choice));
+
+    Ok(choice)
+}
@@ -0,0 +1,152 @@
+use dashmap::DashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+// number of blocks which may be read ahead of the hasher
+const BLOCKS_AHEAD: usize = 4;
+
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+// one lock per device which needs sequential reads,
+// kept for the life of the program
+static DEVICES: OnceLock<DashMap<u64, Option<&'static Mutex<()>>>> = OnceLock::new();
+
+// reads files one at a time per device, even on non-rotational media
+pub fn set_forced(forced: bool) {
+    FORCED.store(forced, Ordering::Relaxed);
+}
+
+// if the file is on a device which seeks poorly, returns
+// a guard which serializes reads from that device
+pub fn device_guard(path: &Path) -> Option<MutexGuard<'static, ()>> {
+    let dev = crate::game::FileId::new(path).ok()?.dev;
+
+    let lock = *DEVICES
+        .get_or_init(DashMap::default)
+        .entry(dev)
+        .or_insert_with(|| {
+            (FORCED.load(Ordering::Relaxed) || is_rotational(dev))
+                .then(|| &*Box::leak(Box::new(Mutex::new(()))))
+        });
+
+    lock.map(|lock| lock.lock().unwrap_or_else(|err| err.into_inner()))
+}
+
+#[cfg(target_os = "linux")]
+fn sys_block(dev: u64) -> std::path::PathBuf {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    std::path::PathBuf::from(format!("/sys/dev/block/{major}:{minor}"))
+}
+
+#[cfg(target_os = "linux")]
+fn is_rotational(dev: u64) -> bool {
+    let sys = sys_block(dev);
+
+    // partitions keep their queue settings in their parent device
+    [
+        sys.join("queue/rotational"),
+        sys.join("../queue/rotational"),
+    ]
+    .iter()
+    .find_map(|path| std::fs::read_to_string(path).ok())
+    .is_some_and(|rotational| rotational.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_rotational(_dev: u64) -> bool {
+    false
+}
+
+// a device's name, such as "sda1", or its major:minor numbers
+// if it's something without a block device, like a network share
+#[cfg(target_os = "linux")]
+pub fn device_name(dev: u64) -> String {
+    let sys = sys_block(dev);
+
+    std::fs::read_link(&sys)
+        .ok()
+        .and_then(|link| {
+            link.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| {
+            sys.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn device_name(dev: u64) -> String {
+    format!("device {dev:x}")
+}
+
+// reads blocks from the file in another thread
+// so that hashing one block overlaps reading the next
+pub fn read_ahead<R, T>(reader: R, f: impl FnOnce(BlockReader) -> T) -> T
+where
+    R: Read + Send,
+{
+    use std::sync::mpsc::sync_channel;
+
+    let (sender, receiver) = sync_channel(BLOCKS_AHEAD);
+
+    std::thread::scope(|s| {
+        s.spawn(move || {
+            let mut reader = reader;
+
+            loop {
+                let mut block = vec![0; crate::game::buffer_size()];
+                let result = match reader.read(&mut block) {
+                    Ok(0) => break,
+                    Ok(bytes) => {
+                        block.truncate(bytes);
+                        Ok(block)
+                    }
+                    Err(err) => Err(err),
+                };
+                let failed = result.is_err();
+
+                // the receiver hangs up if it's seen enough
+                if sender.send(result).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        f(BlockReader {
+            receiver,
+            block: Vec::new(),
+            position: 0,
+        })
+    })
+}
+
+pub struct BlockReader {
+    receiver: std::sync::mpsc::Receiver<Result<Vec<u8>, std::io::Error>>,
+    block: Vec<u8>,
+    position: usize,
+}
+
+impl Read for BlockReader {
+    fn read(&mut self, data: &mut [u8]) -> Result<usize, std::io::Error> {
+        if self.position == self.block.len() {
+            match self.receiver.recv() {
+                Ok(block) => {
+                    self.block = block?;
+                    self.position = 0;
+                }
+                // the reader has finished
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let bytes = data.len().min(self.block.len() - self.position);
+        data[0..bytes].copy_from_slice(&self.block[self.position..self.position + bytes]);
+        self.position += bytes;
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Error;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// number of recently extracted-from archives kept decoded,
+// so parallel repairs drawing on the same few don't decode them again
+const CACHED_ARCHIVES: usize = 4;
+
+// a 7-Zip file can only be decoded from its start,
+// so rather than decode every earlier entry each time one is extracted,
+// the whole archive is decoded once into an unnamed temporary file
+// and each entry is read back from its byte range
+struct Decoded {
+    file: Mutex<File>,
+    entries: Vec<(u64, u64)>,
+}
+
+// an archive is identified by its path, size and modification time
+// in case it's replaced during the run
+type Key = (PathBuf, u64, Option<SystemTime>);
+
+type Slot = Arc<Mutex<Option<Arc<Decoded>>>>;
+
+static ARCHIVES: Mutex<VecDeque<(Key, Slot)>> = Mutex::new(VecDeque::new());
+
+// copies the nth entry of a 7-Zip file, in the order it's decoded,
+// returning the number of bytes written
+pub fn extract<W: Write>(path: &Path, index: usize, mut w: W) -> Result<u64, Error> {
+    let decoded = decoded(path)?;
+
+    let (start, len) = *decoded
+        .entries
+        .get(index)
+        .ok_or_else(|| Error::IO(std::io::ErrorKind::NotFound.into()))?;
+
+    let mut file = decoded.file.lock().unwrap_or_else(|err| err.into_inner());
+    file.seek(SeekFrom::Start(start))?;
+    std::io::copy(&mut (&mut *file).take(len), &mut w).map_err(Error::IO)
+}
+
+fn decoded(path: &Path) -> Result<Arc<Decoded>, Error> {
+    let metadata = path.metadata()?;
+    let key = (path.to_path_buf(), metadata.len(), metadata.modified().ok());
+
+    let slot = {
+        let mut archives = ARCHIVES.lock().unwrap_or_else(|err| err.into_inner());
+        let slot = match archives.iter().position(|(k, _)| *k == key) {
+            Some(pos) => archives.remove(pos).unwrap().1,
+            None => Slot::default(),
+        };
+        archives.push_front((key, Arc::clone(&slot)));
+        archives.truncate(CACHED_ARCHIVES);
+        slot
+    };
+
+    // others wanting the same archive wait for it to be decoded,
+    // while a failure to decode is left for the next attempt to retry
+    let mut slot = slot.lock().unwrap_or_else(|err| err.into_inner());
+
+    match &*slot {
+        Some(decoded) => Ok(Arc::clone(decoded)),
+        None => {
+            let decoded = Arc::new(decode(path)?);
+            *slot = Some(Arc::clone(&decoded));
+            Ok(decoded)
+        }
+    }
+}
+
+fn decode(path: &Path) -> Result<Decoded, Error> {
+    let mut spool = BufWriter::with_capacity(crate::game::buffer_size(), tempfile::tempfile()?);
+    let mut entries = Vec::new();
+    let mut start = 0;
+
+    sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())?.for_each_entries(
+        |_, r| {
+            let len = std::io::copy(r, &mut spool)?;
+            entries.push((start, len));
+            start += len;
+            Ok(true)
+        },
+    )?;
+
+    Ok(Decoded {
+        file: Mutex::new(spool.into_inner().map_err(|err| err.into_error())?),
+        entries,
+    })
+}
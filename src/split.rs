@@ -1,12 +1,26 @@
 use crate::dat::Datafile;
+use crate::game::{Compression, Part, RomSource, RomSources};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io;
 use std::path::Path;
+use std::sync::Arc;
+
+// two seconds of silence, the usual gap before an audio track,
+// which some images leave to their cue sheet instead of storing
+const STANDARD_PREGAP: usize = 150 * 2352;
+
+// audio tracks are always in raw 2352-byte sectors
+const SECTOR_SIZE: usize = 2352;
 
 #[derive(Serialize, Deserialize)]
 pub struct SplitDb {
+    // each game's tracks laid out end-to-end, by their combined size
     games: HashMap<u64, Vec<SplitGame>>,
+    // the combined sizes of games whose image may leave out
+    // the standard gap before each audio track, by that smaller size
+    #[serde(default)]
+    gapped: HashMap<u64, Vec<u64>>,
 }
 
 impl SplitDb {
@@ -14,38 +28,222 @@ impl SplitDb {
     pub fn new() -> Self {
         Self {
             games: HashMap::new(),
+            gapped: HashMap::new(),
         }
     }
 
     pub fn populate(&mut self, datafile: &Datafile) {
-        fn game_to_split(game: &crate::dat::Game) -> (u64, SplitGame) {
+        for game in datafile.games() {
             let mut offset = 0;
             let mut split_game = SplitGame::new(game.name().to_owned());
 
-            for rom in game.roms() {
-                if rom.name().ends_with(".bin") {
-                    let size = rom.size().unwrap() as usize;
-                    split_game.push_track(SplitPart::new(
-                        rom.name(),
-                        offset,
-                        offset + size,
-                        rom.sha1().unwrap(),
-                    ));
-                    offset += size;
+            for (index, rom) in game
+                .roms()
+                .filter(|rom| rom.name().ends_with(".bin"))
+                .enumerate()
+            {
+                let size = rom.size().unwrap() as usize;
+                let mut track =
+                    SplitPart::new(rom.name(), offset, offset + size, rom.sha1().unwrap());
+
+                // without a cue sheet, mixed-mode discs are presumed
+                // to hold their data on the first track and audio on all the rest
+                if index > 0 {
+                    track.track_type = TrackType::Audio;
                 }
+                offset = track.end;
+
+                split_game.push_track(track);
             }
 
-            (offset as u64, split_game)
+            if split_game.tracks.len() > 1 {
+                let total_size = offset as u64;
+                let gapped_size = split_game.with_standard_pregaps().total_size();
+                let gapped = self.gapped.entry(gapped_size).or_default();
+                if !gapped.contains(&total_size) {
+                    gapped.push(total_size);
+                }
+                self.games.entry(total_size).or_default().push(split_game);
+            }
         }
+    }
 
-        for game in datafile.games() {
-            let (total_size, split) = game_to_split(game);
-            if split.tracks.len() > 1 {
-                self.games.entry(total_size).or_default().push(split);
+    // drops the games which are about to be imported again,
+    // keeping those of everything else imported before
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&SplitGame) -> bool,
+    {
+        self.games.retain(|_, games| {
+            games.retain(&mut keep);
+            !games.is_empty()
+        });
+        self.gapped.retain(|_, totals| {
+            totals.retain(|total| self.games.contains_key(total));
+            !totals.is_empty()
+        });
+    }
+
+    // the ways a combined image of the given size may split into a game's tracks,
+    // where a cue sheet describing the image gives each track's gap and type
+    // while without one the image may store every gap,
+    // or leave out the standard gap before each audio track
+    pub fn layouts(&self, image_size: u64, cue: Option<&CueSheet>) -> Vec<SplitGame> {
+        match cue.and_then(|cue| cue.spans(image_size as usize)) {
+            Some(spans) => {
+                let total_size = spans
+                    .iter()
+                    .map(|span| (span.pregap + span.end - span.start) as u64)
+                    .sum();
+
+                self.possible_matches(total_size)
+                    .iter()
+                    .filter_map(|game| game.with_spans(&spans))
+                    .collect()
             }
+            None => self
+                .possible_matches(image_size)
+                .iter()
+                .cloned()
+                .chain(
+                    self.gapped
+                        .get(&image_size)
+                        .into_iter()
+                        .flatten()
+                        .flat_map(|total_size| self.possible_matches(*total_size))
+                        .map(|game| game.with_standard_pregaps())
+                        .filter(|game| game.total_size() == image_size),
+                )
+                .collect(),
         }
     }
 
+    #[inline]
+    pub fn games(&self) -> impl Iterator<Item = (u64, &SplitGame)> {
+        self.games
+            .iter()
+            .flat_map(|(size, games)| games.iter().map(move |game| (*size, game)))
+    }
+
+    pub fn display_games(&self) {
+        use crate::game::Size;
+        use comfy_table::{Cell, CellAlignment};
+
+        let mut games = self.games().collect::<Vec<_>>();
+        games.sort_unstable_by(|(_, x), (_, y)| x.name.cmp(&y.name));
+
+        let mut table = crate::table::new();
+        table.set_header(vec!["Game", "Tracks", "Total Size"]);
+
+        for (size, game) in games {
+            table.add_row(vec![
+                Cell::new(&game.name),
+                Cell::new(game.tracks.len()).set_alignment(CellAlignment::Right),
+                Cell::new(Size(size)).set_alignment(CellAlignment::Right),
+            ]);
+        }
+
+        println!("{table}");
+    }
+
+    pub fn display_tracks(&self, name: &str) -> Result<(), crate::Error> {
+        use comfy_table::{Attribute, Cell, CellAlignment};
+
+        let games = self
+            .games()
+            .filter(|(_, game)| game.name == name)
+            .collect::<Vec<_>>();
+
+        if games.is_empty() {
+            return Err(crate::Error::NoSuchSoftware(name.to_owned()));
+        }
+
+        let mut table = crate::table::new();
+        table.set_header(vec!["Track", "Type", "Start", "End", "Pregap", "SHA1 Hash"]);
+
+        for (size, game) in games {
+            table.add_row(vec![Cell::new(format!("{} ({} bytes)", game.name, size))
+                .set_alignment(CellAlignment::Center)
+                .add_attribute(Attribute::Bold)]);
+            for track in &game.tracks {
+                table.add_row(vec![
+                    Cell::new(&track.name),
+                    Cell::new(track.track_type),
+                    Cell::new(track.start).set_alignment(CellAlignment::Right),
+                    Cell::new(track.end).set_alignment(CellAlignment::Right),
+                    Cell::new(track.pregap).set_alignment(CellAlignment::Right),
+                    Cell::new(hex::encode(track.sha1)),
+                ]);
+            }
+        }
+
+        println!("{table}");
+        Ok(())
+    }
+
+    // adds the tracks of any plain files among the sources
+    // which match a splittable game as sources of their own,
+    // so that repairs can split them on the fly,
+    // where an image is only read if one of its tracks is needed
+    // and isn't already available from some other source
+    pub fn add_split_sources<F>(&self, sources: &RomSources<'_>, needed: F)
+    where
+        F: Fn(&Part) -> bool + Sync,
+    {
+        use rayon::prelude::*;
+
+        let candidates = sources
+            .iter()
+            .filter_map(|entry| match entry.value() {
+                RomSource::File {
+                    file, zip_parts, ..
+                } if zip_parts.is_empty() => Some(Arc::clone(file)),
+                _ => None,
+            })
+            .filter_map(|file| {
+                let layouts = self
+                    .layouts(
+                        file.metadata().ok()?.len(),
+                        CueSheet::beside(&file).as_ref(),
+                    )
+                    .into_iter()
+                    .filter(|game| {
+                        game.parts()
+                            .any(|part| needed(&part) && !sources.contains_key(&part))
+                    })
+                    .collect::<Vec<_>>();
+                (!layouts.is_empty()).then_some((file, layouts))
+            })
+            .collect::<Vec<_>>();
+
+        candidates.into_par_iter().for_each(|(file, layouts)| {
+            let Some(game) = layouts
+                .into_iter()
+                .find(|game| game.matches_file(&file).unwrap_or(false))
+            else {
+                return;
+            };
+
+            for (track, part) in game.tracks.iter().zip(game.parts()) {
+                sources.entry(part).or_insert_with(|| RomSource::File {
+                    file: Arc::clone(&file),
+                    has_xattr: false,
+                    zip_parts: vec![match track.pregap {
+                        0 => Compression::Range {
+                            start: track.start as u64,
+                            end: track.end as u64,
+                        },
+                        pregap => Compression::Gapped {
+                            pregap: pregap as u64,
+                            start: track.start as u64,
+                            end: track.end as u64,
+                        },
+                    }],
+                });
+            }
+        });
+    }
+
     #[inline]
     pub fn possible_matches(&self, total_size: u64) -> &[SplitGame] {
         self.games
@@ -66,10 +264,14 @@ impl Extend<(u64, SplitGame)> for SplitDb {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SplitGame {
     name: String,
     tracks: Vec<SplitPart>,
+    // the software list the game belongs to, if any,
+    // which databases from older versions don't record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    software_list: Option<String>,
 }
 
 impl SplitGame {
@@ -78,14 +280,127 @@ impl SplitGame {
         Self {
             name,
             tracks: Vec::new(),
+            software_list: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_software_list(name: String, software_list: String) -> Self {
+        Self {
+            software_list: Some(software_list),
+            ..Self::new(name)
         }
     }
 
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn software_list(&self) -> Option<&str> {
+        self.software_list.as_deref()
+    }
+
     #[inline]
     pub fn push_track(&mut self, track: SplitPart) {
         self.tracks.push(track)
     }
 
+    // the size of the image the tracks are laid out in
+    #[inline]
+    fn total_size(&self) -> u64 {
+        self.tracks
+            .iter()
+            .map(|track| (track.end - track.start) as u64)
+            .sum()
+    }
+
+    // each track's size as the DAT lists it, gap included
+    #[inline]
+    fn track_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.tracks
+            .iter()
+            .map(|track| track.pregap + track.end - track.start)
+    }
+
+    // the game's tracks in an image which leaves out
+    // the standard gap before each audio track
+    fn with_standard_pregaps(&self) -> Self {
+        let mut offset = 0;
+
+        Self {
+            tracks: self
+                .tracks
+                .iter()
+                .zip(self.track_sizes())
+                .map(|(track, size)| {
+                    let pregap = match track.track_type {
+                        TrackType::Audio => STANDARD_PREGAP.min(size),
+                        TrackType::Data => 0,
+                    };
+                    let start = offset;
+                    offset += size - pregap;
+                    SplitPart {
+                        start,
+                        end: offset,
+                        pregap,
+                        ..track.clone()
+                    }
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    // the game's tracks in an image laid out by its cue sheet,
+    // if each span is the size of the track it's to hold
+    fn with_spans(&self, spans: &[Span]) -> Option<Self> {
+        (spans.len() == self.tracks.len()
+            && spans
+                .iter()
+                .zip(self.track_sizes())
+                .all(|(span, size)| span.pregap + span.end - span.start == size))
+        .then(|| Self {
+            tracks: self
+                .tracks
+                .iter()
+                .zip(spans)
+                .map(|(track, span)| SplitPart {
+                    start: span.start,
+                    end: span.end,
+                    pregap: span.pregap,
+                    track_type: span.track_type,
+                    ..track.clone()
+                })
+                .collect(),
+            ..self.clone()
+        })
+    }
+
+    // each track as the part it splits into
+    fn parts(&self) -> impl Iterator<Item = Part> + '_ {
+        self.tracks.iter().map(|track| Part::Rom {
+            sha1: track.sha1,
+            size: Some((track.pregap + track.end - track.start) as u64),
+        })
+    }
+
+    // streams each track's range of the image through the hash,
+    // stopping at the first one which doesn't match
+    fn matches_file(&self, path: &Path) -> Result<bool, io::Error> {
+        let mut f = std::fs::File::open(path)?;
+        let mut buf = vec![0; 1 << 16];
+
+        for track in &self.tracks {
+            if !track.matches_reader(&mut f, &mut buf)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
     #[inline]
     pub fn matches(&self, data: &[u8]) -> bool {
         use rayon::prelude::*;
@@ -107,12 +422,35 @@ impl SplitGame {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrackType {
+    #[default]
+    Data,
+    Audio,
+}
+
+impl std::fmt::Display for TrackType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Data => "data".fmt(f),
+            Self::Audio => "audio".fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SplitPart {
     name: String,
+    // the track's range within the combined image
     start: usize,
     end: usize,
     sha1: [u8; 20],
+    #[serde(default)]
+    track_type: TrackType,
+    // bytes of silence which start the track
+    // but which the combined image leaves out
+    #[serde(default)]
+    pregap: usize,
 }
 
 impl SplitPart {
@@ -124,13 +462,47 @@ impl SplitPart {
             start,
             end,
             sha1: parse_sha1(sha1).unwrap(),
+            track_type: TrackType::default(),
+            pregap: 0,
         }
     }
 
     fn matches(&self, data: &[u8]) -> bool {
         use sha1_smol::Sha1;
 
-        Sha1::from(&data[self.start..self.end]).digest().bytes() == self.sha1
+        let mut sha1 = Sha1::new();
+        sha1.update(&vec![0; self.pregap]);
+        sha1.update(&data[self.start..self.end]);
+        sha1.digest().bytes() == self.sha1
+    }
+
+    fn matches_reader<R>(&self, r: &mut R, buf: &mut [u8]) -> Result<bool, io::Error>
+    where
+        R: io::Read + io::Seek,
+    {
+        use sha1_smol::Sha1;
+
+        let mut sha1 = Sha1::new();
+
+        buf.fill(0);
+        let mut pregap = self.pregap;
+        while pregap > 0 {
+            let zeros = pregap.min(buf.len());
+            sha1.update(&buf[..zeros]);
+            pregap -= zeros;
+        }
+
+        r.seek(io::SeekFrom::Start(self.start as u64))?;
+        let mut remaining = self.end - self.start;
+        while remaining > 0 {
+            let len = remaining.min(buf.len());
+            let chunk = &mut buf[..len];
+            r.read_exact(chunk)?;
+            sha1.update(chunk);
+            remaining -= chunk.len();
+        }
+
+        Ok(sha1.digest().bytes() == self.sha1)
     }
 
     fn extract(&self, root: &Path, data: &[u8]) -> Result<(), io::Error> {
@@ -138,7 +510,10 @@ impl SplitPart {
         use std::io::Write;
 
         let path = root.join(&self.name);
-        match File::create(&path).and_then(|mut f| f.write_all(&data[self.start..self.end])) {
+        match File::create(&path).and_then(|mut f| {
+            f.write_all(&vec![0; self.pregap])?;
+            f.write_all(&data[self.start..self.end])
+        }) {
             Ok(()) => {
                 println!("* {}", path.display());
                 Ok(())
@@ -147,3 +522,123 @@ impl SplitPart {
         }
     }
 }
+
+// a cue sheet describing a combined image in a single file,
+// which knows each track's type and where its gaps are
+pub struct CueSheet {
+    tracks: Vec<CueTrack>,
+}
+
+struct CueTrack {
+    track_type: TrackType,
+    sector_size: usize,
+    // sectors of silence the image leaves out before the track
+    pregap: usize,
+    // the sector the track starts at within the image,
+    // including any gap the image stores
+    start: usize,
+}
+
+// where a track lies within a combined image
+pub struct Span {
+    start: usize,
+    end: usize,
+    pregap: usize,
+    track_type: TrackType,
+}
+
+impl CueSheet {
+    // the cue sheet alongside an image, if it describes that image alone
+    pub fn beside(image: &Path) -> Option<Self> {
+        std::fs::read_to_string(image.with_extension("cue"))
+            .ok()
+            .and_then(|cue| Self::parse(&cue))
+    }
+
+    fn parse(cue: &str) -> Option<Self> {
+        // a position in minutes, seconds and frames of 1/75th second each
+        fn msf(msf: &str) -> Option<usize> {
+            let mut parts = msf.split(':').map(|part| part.parse::<usize>().ok());
+            match (parts.next()?, parts.next()?, parts.next()?, parts.next()) {
+                (Some(m), Some(s), Some(f), None) => Some((m * 60 + s) * 75 + f),
+                _ => None,
+            }
+        }
+
+        let mut files = 0;
+        let mut tracks = Vec::new();
+        let mut starts: Vec<(Option<usize>, Option<usize>)> = Vec::new();
+
+        for line in cue.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("FILE") => files += 1,
+                Some("TRACK") => {
+                    let mode = words.nth(1)?;
+                    tracks.push(CueTrack {
+                        track_type: match mode {
+                            "AUDIO" => TrackType::Audio,
+                            _ => TrackType::Data,
+                        },
+                        sector_size: match mode.split_once('/') {
+                            Some((_, size)) => size.parse().ok()?,
+                            None => SECTOR_SIZE,
+                        },
+                        pregap: 0,
+                        start: 0,
+                    });
+                    starts.push((None, None));
+                }
+                Some("PREGAP") => tracks.last_mut()?.pregap = msf(words.next()?)?,
+                Some("INDEX") => {
+                    let (index_0, index_1) = starts.last_mut()?;
+                    match (words.next()?.parse::<u8>().ok()?, msf(words.next()?)?) {
+                        (0, at) => *index_0 = Some(at),
+                        (1, at) => *index_1 = Some(at),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // every track needs to say where it starts
+        for (track, (index_0, index_1)) in tracks.iter_mut().zip(starts) {
+            track.start = index_0.or(index_1)?;
+        }
+
+        (files == 1 && !tracks.is_empty()).then_some(Self { tracks })
+    }
+
+    // each track's byte range within an image of the given size
+    fn spans(&self, image_size: usize) -> Option<Vec<Span>> {
+        let mut offset = 0;
+        let mut starts = Vec::with_capacity(self.tracks.len());
+        let mut previous: Option<&CueTrack> = None;
+
+        for track in &self.tracks {
+            if let Some(previous) = previous {
+                offset += track.start.checked_sub(previous.start)? * previous.sector_size;
+            } else {
+                offset = track.start * track.sector_size;
+            }
+            starts.push(offset);
+            previous = Some(track);
+        }
+
+        self.tracks
+            .iter()
+            .zip(&starts)
+            .enumerate()
+            .map(|(index, (track, start))| {
+                let end = starts.get(index + 1).copied().unwrap_or(image_size);
+                (*start <= end).then_some(Span {
+                    start: *start,
+                    end,
+                    pregap: track.pregap * track.sector_size,
+                    track_type: track.track_type,
+                })
+            })
+            .collect()
+    }
+}
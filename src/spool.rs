@@ -0,0 +1,107 @@
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+// nested files up to this size are kept in memory,
+// while anything larger overflows to a temporary file
+// so that a Zip of large Zips doesn't exhaust memory
+const IN_MEMORY_LIMIT: usize = 16 << 20;
+
+// a place to write data that's read back from one or more readers,
+// such as a Zip file within another Zip file
+pub enum Spool {
+    Memory(Vec<u8>),
+    File(tempfile::NamedTempFile),
+}
+
+impl Default for Spool {
+    #[inline]
+    fn default() -> Self {
+        Self::Memory(Vec::new())
+    }
+}
+
+impl Spool {
+    // copies all of a reader into a new spool
+    pub fn from_reader<R: Read>(mut r: R) -> std::io::Result<Spooled> {
+        let mut spool = Self::default();
+        std::io::copy(&mut r, &mut spool)?;
+        spool.finish()
+    }
+
+    pub fn finish(mut self) -> std::io::Result<Spooled> {
+        self.flush()?;
+        Ok(match self {
+            Self::Memory(data) => Spooled::Memory(Arc::from(data)),
+            Self::File(file) => Spooled::File(file),
+        })
+    }
+}
+
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Self::Memory(data) = self {
+            if data.len() + buf.len() > IN_MEMORY_LIMIT {
+                let mut file = tempfile::NamedTempFile::new()?;
+                file.write_all(data)?;
+                *self = Self::File(file);
+            }
+        }
+
+        match self {
+            Self::Memory(data) => data.write(buf),
+            Self::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::File(file) => file.flush(),
+        }
+    }
+}
+
+// a finished spool, whose temporary file (if any)
+// is removed once it's dropped
+pub enum Spooled {
+    Memory(Arc<[u8]>),
+    File(tempfile::NamedTempFile),
+}
+
+impl Spooled {
+    // each reader has its own position,
+    // so several may read the same spool at once
+    pub fn reader(&self) -> std::io::Result<SpooledReader> {
+        match self {
+            Self::Memory(data) => Ok(SpooledReader::Memory(Cursor::new(Arc::clone(data)))),
+            Self::File(file) => file.reopen().map(|f| {
+                SpooledReader::File(BufReader::with_capacity(crate::game::buffer_size(), f))
+            }),
+        }
+    }
+}
+
+pub enum SpooledReader {
+    Memory(Cursor<Arc<[u8]>>),
+    File(BufReader<std::fs::File>),
+}
+
+impl Read for SpooledReader {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Memory(r) => r.read(buf),
+            Self::File(r) => r.read(buf),
+        }
+    }
+}
+
+impl Seek for SpooledReader {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Self::Memory(r) => r.seek(pos),
+            Self::File(r) => r.seek(pos),
+        }
+    }
+}
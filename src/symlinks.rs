@@ -0,0 +1,34 @@
+use std::fs::{DirEntry, FileType};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FOLLOW: AtomicBool = AtomicBool::new(false);
+
+// whether directory scans descend into symlinked directories
+// and treat symlinked files as the files they point to
+pub fn set_follow(follow: bool) {
+    FOLLOW.store(follow, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn follow() -> bool {
+    FOLLOW.load(Ordering::Relaxed)
+}
+
+// a recursive scan of the directory using the current policy,
+// which reports symlink loops as errors rather than descending forever
+pub fn walk(root: impl AsRef<Path>) -> walkdir::WalkDir {
+    walkdir::WalkDir::new(root).follow_links(follow())
+}
+
+// the type of a directory entry using the current policy,
+// where a followed link takes the type of whatever it points to
+pub fn file_type(entry: &DirEntry) -> std::io::Result<FileType> {
+    let file_type = entry.file_type()?;
+
+    if file_type.is_symlink() && follow() {
+        std::fs::metadata(entry.path()).map(|m| m.file_type())
+    } else {
+        Ok(file_type)
+    }
+}
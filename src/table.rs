@@ -0,0 +1,114 @@
+use comfy_table::Table;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static STYLE: AtomicU8 = AtomicU8::new(TableStyle::Utf8 as u8);
+static COLOR: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    // columns separated by whitespace only
+    Plain,
+    // borders drawn with ASCII characters
+    Ascii,
+    // borders drawn with UTF-8 box characters
+    #[default]
+    Utf8,
+}
+
+impl std::str::FromStr for TableStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "ascii" => Ok(Self::Ascii),
+            "utf8" => Ok(Self::Utf8),
+            _ => Err(format!(
+                "unknown table style \"{s}\", use \"plain\", \"ascii\" or \"utf8\""
+            )),
+        }
+    }
+}
+
+pub fn set_style(style: TableStyle) {
+    STYLE.store(style as u8, Ordering::Relaxed);
+}
+
+fn style() -> TableStyle {
+    match STYLE.load(Ordering::Relaxed) {
+        s if s == TableStyle::Plain as u8 => TableStyle::Plain,
+        s if s == TableStyle::Ascii as u8 => TableStyle::Ascii,
+        _ => TableStyle::Utf8,
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    // colors when writing to a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    // colors even when output is captured
+    Always,
+    // no colors at all
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "unknown color choice \"{s}\", use \"auto\", \"always\" or \"never\""
+            )),
+        }
+    }
+}
+
+pub fn set_color(color: ColorChoice) {
+    COLOR.store(color as u8, Ordering::Relaxed);
+}
+
+fn color() -> ColorChoice {
+    match COLOR.load(Ordering::Relaxed) {
+        c if c == ColorChoice::Always as u8 => ColorChoice::Always,
+        c if c == ColorChoice::Never as u8 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+// a new table in the current style, whose colors
+// follow --color, or NO_COLOR if set to anything non-empty
+pub fn new() -> Table {
+    use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+    use comfy_table::presets::{ASCII_FULL_CONDENSED, NOTHING, UTF8_FULL_CONDENSED};
+
+    let mut table = Table::new();
+
+    match style() {
+        TableStyle::Plain => table.load_preset(NOTHING),
+        TableStyle::Ascii => table.load_preset(ASCII_FULL_CONDENSED),
+        TableStyle::Utf8 => table
+            .load_preset(UTF8_FULL_CONDENSED)
+            .apply_modifier(UTF8_ROUND_CORNERS),
+    };
+
+    match color() {
+        ColorChoice::Always => {
+            table.enforce_styling();
+        }
+        ColorChoice::Never => {
+            table.force_no_tty();
+        }
+        ColorChoice::Auto => {
+            if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                table.force_no_tty();
+            }
+        }
+    }
+
+    table
+}
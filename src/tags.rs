@@ -0,0 +1,77 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const TAGS_FILE: &str = "tags.cbor";
+
+// user-defined tags, such as "favorites" or "kids",
+// each holding the short names of the games tagged with it
+// whether they're MAME machines or software list entries
+#[derive(Default, Serialize, Deserialize)]
+pub struct Tags(BTreeMap<String, BTreeSet<String>>);
+
+impl Tags {
+    pub fn load() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(TAGS_FILE))
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(TAGS_FILE), self)
+    }
+
+    // returns the number of games newly tagged
+    pub fn add<I>(&mut self, tag: &str, games: I) -> usize
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let tagged = self.0.entry(tag.to_owned()).or_default();
+        games
+            .into_iter()
+            .filter(|game| tagged.insert(game.clone()))
+            .count()
+    }
+
+    // returns the number of games untagged,
+    // where removing no games removes the whole tag
+    pub fn remove(&mut self, tag: &str, games: &[String]) -> Result<usize, Error> {
+        let tagged = self
+            .0
+            .get_mut(tag)
+            .ok_or_else(|| Error::NoSuchTag(tag.to_owned()))?;
+
+        let removed = match games {
+            [] => std::mem::take(tagged).len(),
+            games => games.iter().filter(|game| tagged.remove(*game)).count(),
+        };
+
+        if tagged.is_empty() {
+            self.0.remove(tag);
+        }
+
+        Ok(removed)
+    }
+
+    pub fn games(&self, tag: &str) -> Result<&BTreeSet<String>, Error> {
+        self.0
+            .get(tag)
+            .ok_or_else(|| Error::NoSuchTag(tag.to_owned()))
+    }
+
+    pub fn list(&self) {
+        let mut table = crate::table::new();
+        table.set_header(vec!["Tag", "Games"]);
+        for (tag, games) in &self.0 {
+            table.add_row(vec![tag.clone(), games.len().to_string()]);
+        }
+        println!("{table}");
+    }
+
+    pub fn list_games(&self, tag: &str) -> Result<(), Error> {
+        for game in self.games(tag)? {
+            println!("{game}");
+        }
+        Ok(())
+    }
+}
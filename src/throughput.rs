@@ -0,0 +1,158 @@
+use crate::game::Size;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static FILES: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static DOWNLOADED: AtomicU64 = AtomicU64::new(0);
+static WRITTEN: AtomicU64 = AtomicU64::new(0);
+
+// bytes read from and written to each device, by device number
+static DEVICES: Mutex<BTreeMap<u64, Transferred>> = Mutex::new(BTreeMap::new());
+
+#[derive(Default)]
+struct Transferred {
+    read: u64,
+    written: u64,
+}
+
+// a file (or archive member) has been read and hashed
+pub fn hashed(bytes: u64) {
+    FILES.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+// a file's hash cache has been consulted
+pub fn cached(hit: bool) {
+    if hit { &HITS } else { &MISSES }.fetch_add(1, Ordering::Relaxed);
+}
+
+// bytes have arrived from a URL
+pub fn downloaded(bytes: u64) {
+    DOWNLOADED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+// a file has been written in full, such as by a repair
+pub fn written(path: &Path) {
+    let Ok(size) = path.metadata().map(|m| m.len()) else {
+        return;
+    };
+
+    WRITTEN.fetch_add(size, Ordering::Relaxed);
+    if let Ok(id) = crate::game::FileId::new(path) {
+        DEVICES.lock().unwrap().entry(id.dev).or_default().written += size;
+    }
+}
+
+// counts the bytes actually read from a file on disk,
+// which are credited to its device once the reader is dropped
+pub struct DeviceReader<R> {
+    reader: R,
+    dev: Option<u64>,
+    read: u64,
+}
+
+impl<R> DeviceReader<R> {
+    pub fn new(path: &Path, reader: R) -> Self {
+        Self {
+            reader,
+            dev: crate::game::FileId::new(path).ok().map(|id| id.dev),
+            read: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for DeviceReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for DeviceReader<R> {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl<R> Drop for DeviceReader<R> {
+    fn drop(&mut self) {
+        if let Some(dev) = self.dev.filter(|_| self.read > 0) {
+            DEVICES.lock().unwrap().entry(dev).or_default().read += self.read;
+        }
+    }
+}
+
+// a timed run whose hashing is summarized once finished
+pub struct Run {
+    started: Instant,
+}
+
+pub fn start() -> Run {
+    for counter in [&FILES, &BYTES, &HITS, &MISSES, &DOWNLOADED, &WRITTEN] {
+        counter.store(0, Ordering::Relaxed);
+    }
+    DEVICES.lock().unwrap().clear();
+
+    Run {
+        started: Instant::now(),
+    }
+}
+
+impl Run {
+    pub fn display(self) {
+        let elapsed = self.started.elapsed();
+        let bytes = BYTES.load(Ordering::Relaxed);
+
+        eprintln!(
+            "* {} files hashed, {} in {:.2?} ({}/s), {} cache hits, {} cache misses",
+            FILES.load(Ordering::Relaxed),
+            Size(bytes),
+            elapsed,
+            rate(bytes, elapsed),
+            HITS.load(Ordering::Relaxed),
+            MISSES.load(Ordering::Relaxed),
+        );
+
+        let downloaded = DOWNLOADED.load(Ordering::Relaxed);
+        let written = WRITTEN.load(Ordering::Relaxed);
+        if downloaded > 0 || written > 0 {
+            eprintln!(
+                "* {} downloaded ({}/s), {} written ({}/s)",
+                Size(downloaded),
+                rate(downloaded, elapsed),
+                Size(written),
+                rate(written, elapsed),
+            );
+        }
+
+        // a breakdown is only useful when more than one device is involved
+        let devices = std::mem::take(&mut *DEVICES.lock().unwrap());
+        if devices.len() > 1 {
+            for (dev, Transferred { read, written }) in devices {
+                eprintln!(
+                    "* {} : {} read ({}/s), {} written ({}/s)",
+                    crate::sequential::device_name(dev),
+                    Size(read),
+                    rate(read, elapsed),
+                    Size(written),
+                    rate(written, elapsed),
+                );
+            }
+        }
+    }
+}
+
+// the effective rate over the whole run
+fn rate(bytes: u64, elapsed: Duration) -> Size {
+    Size((bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)) as u64)
+}
@@ -0,0 +1,254 @@
+use crate::checksum::Failure;
+use crate::game::VerifyResultsSummary;
+use serde_derive::Deserialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// the most memory reserved up front for a piece,
+// which is only a hint since the torrent's piece length
+// isn't to be trusted before any of its data is read
+const PIECE_RESERVE: u64 = 16 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct Torrent {
+    info: Info,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: u64,
+    pieces: serde_bytes::ByteBuf,
+    // single-file torrents have a length, multi-file torrents have files
+    length: Option<u64>,
+    files: Option<Vec<InfoFile>>,
+}
+
+#[derive(Deserialize)]
+struct InfoFile {
+    length: u64,
+    path: Vec<String>,
+    // padding files, marked with "p", are never written to disk
+    attr: Option<String>,
+}
+
+// a file's place in the torrent's contiguous stream of data
+struct Span {
+    path: PathBuf,
+    start: u64,
+    length: u64,
+    padding: bool,
+}
+
+impl Span {
+    #[inline]
+    fn end(&self) -> u64 {
+        self.start + self.length
+    }
+}
+
+impl Torrent {
+    pub fn parse(data: &[u8]) -> Result<Self, serde_bencode::Error> {
+        use serde::de::Error;
+
+        let torrent: Self = serde_bencode::from_bytes(data)?;
+
+        // every file must stay within the directory being verified
+        let names = std::iter::once(&torrent.info.name).chain(
+            torrent
+                .info
+                .files
+                .iter()
+                .flatten()
+                .flat_map(|file| file.path.iter()),
+        );
+        for name in names {
+            if !is_plain_name(name) {
+                return Err(serde_bencode::Error::custom(format!(
+                    "invalid path component \"{name}\""
+                )));
+            }
+        }
+        if torrent
+            .info
+            .files
+            .iter()
+            .flatten()
+            .any(|file| file.path.is_empty())
+        {
+            return Err(serde_bencode::Error::custom("empty file path"));
+        }
+        if torrent
+            .info
+            .files
+            .iter()
+            .flatten()
+            .try_fold(0u64, |total, file| total.checked_add(file.length))
+            .is_none()
+        {
+            return Err(serde_bencode::Error::custom("files too large"));
+        }
+
+        Ok(torrent)
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    // files in torrent order, relative to the torrent's root
+    fn spans(&self, root: &Path) -> Vec<Span> {
+        let mut start = 0;
+
+        match &self.info.files {
+            None => vec![Span {
+                path: root.join(&self.info.name),
+                start,
+                length: self.info.length.unwrap_or_default(),
+                padding: false,
+            }],
+            Some(files) => files
+                .iter()
+                .map(|file| {
+                    let span = Span {
+                        path: file
+                            .path
+                            .iter()
+                            .fold(root.to_owned(), |path, c| path.join(c)),
+                        start,
+                        length: file.length,
+                        padding: file.attr.as_deref().is_some_and(|a| a.contains('p')),
+                    };
+                    start += file.length;
+                    span
+                })
+                .collect(),
+        }
+    }
+
+    fn piece_hashes(&self) -> impl Iterator<Item = &[u8]> {
+        self.info.pieces.chunks_exact(20)
+    }
+
+    // multi-file torrents keep their files in a directory
+    // named after the torrent, which may or may not
+    // be the directory we've been given
+    fn root(&self, dir: &Path) -> PathBuf {
+        match &self.info.files {
+            Some(_) if dir.join(&self.info.name).is_dir() => dir.join(&self.info.name),
+            _ => dir.to_owned(),
+        }
+    }
+
+    // checks files by the torrent's piece hashes, where a file is only
+    // OK if every piece it contributes to matches
+    pub fn verify(&self, dir: &Path) -> (Vec<Failure>, VerifyResultsSummary) {
+        use indicatif::ParallelProgressIterator;
+        use rayon::prelude::*;
+
+        let spans = self.spans(&self.root(dir));
+        let piece_length = self.info.piece_length.max(1);
+        let hashes = self.piece_hashes().collect::<Vec<_>>();
+
+        let pbar = crate::progress::bar(hashes.len() as u64)
+            .with_style(crate::game::verify_style())
+            .with_message("verifying pieces");
+
+        let bad_pieces = hashes
+            .par_iter()
+            .enumerate()
+            .progress_with(pbar.clone())
+            .filter(|(index, hash)| {
+                let start = *index as u64 * piece_length;
+                !matches!(read_piece(&spans, start, piece_length), Ok(data)
+                    if crate::game::slice_sha1(&data) == **hash)
+            })
+            .map(|(index, _)| index as u64)
+            .collect::<Vec<_>>();
+
+        pbar.finish_and_clear();
+
+        let mut summary = VerifyResultsSummary::default();
+        let mut failures = Vec::new();
+
+        for span in spans.into_iter().filter(|span| !span.padding) {
+            summary.total += 1;
+
+            let first = span.start / piece_length;
+            let last = span.end().saturating_sub(1) / piece_length;
+            let is_bad = span.length > 0
+                && bad_pieces
+                    .binary_search_by(|piece| {
+                        if *piece < first {
+                            std::cmp::Ordering::Less
+                        } else if *piece > last {
+                            std::cmp::Ordering::Greater
+                        } else {
+                            std::cmp::Ordering::Equal
+                        }
+                    })
+                    .is_ok();
+
+            match span.path.metadata() {
+                Err(_) => failures.push(Failure::Missing { path: span.path }),
+                Ok(m) if !m.is_file() => failures.push(Failure::Missing { path: span.path }),
+                Ok(m) if m.len() != span.length => failures.push(Failure::Error {
+                    path: span.path,
+                    err: std::io::Error::other(format!(
+                        "expected {} bytes, found {}",
+                        span.length,
+                        m.len()
+                    )),
+                }),
+                Ok(_) if is_bad => failures.push(Failure::Bad { path: span.path }),
+                Ok(_) => summary.successes += 1,
+            }
+        }
+
+        (failures, summary)
+    }
+}
+
+// whether the name is a single ordinary path component,
+// rather than something like "..", "/" or "a/b"
+fn is_plain_name(name: &str) -> bool {
+    use std::path::Component;
+
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) && !name.contains(['/', '\\'])
+}
+
+// reads one piece from however many files it spans
+fn read_piece(spans: &[Span], start: u64, piece_length: u64) -> Result<Vec<u8>, std::io::Error> {
+    let end = start.saturating_add(piece_length);
+    let total = spans.last().map_or(0, Span::end);
+    let mut data =
+        Vec::with_capacity(end.min(total).saturating_sub(start).min(PIECE_RESERVE) as usize);
+
+    for span in spans
+        .iter()
+        .skip_while(|span| span.end() <= start)
+        .take_while(|span| span.start < end)
+    {
+        let from = start.max(span.start) - span.start;
+        let to = end.min(span.end()) - span.start;
+
+        if span.padding {
+            data.resize(data.len() + (to - from) as usize, 0);
+        } else {
+            let mut f = std::fs::File::open(&span.path)?;
+            f.seek(SeekFrom::Start(from))?;
+            let read = f.take(to - from).read_to_end(&mut data)?;
+            if read as u64 != to - from {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+
+    Ok(data)
+}
@@ -0,0 +1,48 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+pub const UPDATE_SOURCES_FILE: &str = "update-sources.cbor";
+
+// where MAME's databases were last initialized from,
+// so they can be initialized from there again,
+// while DATs already record where they were imported from
+#[derive(Default, Serialize, Deserialize)]
+pub struct UpdateSources {
+    pub mame: Option<String>,
+    pub sl: BTreeSet<PathBuf>,
+}
+
+impl UpdateSources {
+    pub fn load() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(UPDATE_SOURCES_FILE))
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(UPDATE_SOURCES_FILE), self)
+    }
+
+    pub fn set_mame(source: String) -> Result<(), Error> {
+        let mut sources = Self::load()?;
+        sources.mame = Some(source);
+        sources.save()
+    }
+
+    // software lists are kept by absolute path,
+    // since later runs may be from somewhere else
+    pub fn add_sl<'p, I>(paths: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'p PathBuf>,
+    {
+        let mut sources = Self::load()?;
+        sources.sl.extend(
+            paths
+                .into_iter()
+                .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone())),
+        );
+        sources.save()
+    }
+}
@@ -0,0 +1,99 @@
+use crate::game::{GameParts, Part, RomSources};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const WISHLIST_FILE: &str = "wishlist.cbor";
+
+// a game being hunted for, along with the parts it needs
+// so that they can be recognized in any incoming files
+#[derive(Serialize, Deserialize)]
+pub struct Wish {
+    source: String,
+    game: String,
+    parts: GameParts,
+}
+
+impl std::fmt::Display for Wish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.source, self.game)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Wishlist(Vec<Wish>);
+
+impl Wishlist {
+    pub fn load() -> Result<Self, Error> {
+        crate::dbfile::read_or_default(&crate::dirs::data_dir().join(WISHLIST_FILE))
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let dir = crate::dirs::data_dir();
+        std::fs::create_dir_all(&dir)?;
+        crate::dbfile::write(&dir.join(WISHLIST_FILE), self)
+    }
+
+    // replaces any earlier wish for the same game
+    pub fn add(&mut self, source: String, game: String, parts: GameParts) {
+        self.0
+            .retain(|wish| !(wish.source == source && wish.game == game));
+        self.0.push(Wish {
+            source,
+            game,
+            parts,
+        });
+    }
+
+    pub fn remove(&mut self, source: &str, game: &str) -> Result<(), Error> {
+        match self
+            .0
+            .iter()
+            .position(|wish| wish.source == source && wish.game == game)
+        {
+            Some(index) => {
+                self.0.remove(index);
+                Ok(())
+            }
+            None => Err(Error::NoSuchSoftware(game.to_owned())),
+        }
+    }
+
+    pub fn list(&self) {
+        let mut table = crate::table::new();
+        table.set_header(vec!["Source", "Game", "Parts"]);
+        for wish in &self.0 {
+            table.add_row(vec![
+                wish.source.clone(),
+                wish.game.clone(),
+                wish.parts.len().to_string(),
+            ]);
+        }
+        println!("{table}");
+    }
+
+    // each wished-for part, along with the wishes it satisfies
+    // and what the part is named in each
+    pub fn lookup(&self) -> HashMap<&Part, Vec<(&Wish, &str)>> {
+        let mut lookup: HashMap<&Part, Vec<(&Wish, &str)>> = HashMap::default();
+        for wish in &self.0 {
+            for (name, part) in wish.parts.iter() {
+                if !part.is_placeholder() {
+                    lookup.entry(part).or_default().push((wish, name.as_str()));
+                }
+            }
+        }
+        lookup
+    }
+
+    // notes every wished-for part found among the sources
+    pub fn report(&self, sources: &RomSources) {
+        for wish in &self.0 {
+            for (name, part) in wish.parts.iter() {
+                if let Some(source) = sources.get(part).filter(|_| !part.is_placeholder()) {
+                    eprintln!("* wished for {wish} : {name} found in {}", source.value());
+                }
+            }
+        }
+    }
+}